@@ -0,0 +1,203 @@
+//! Kill banners announcing the destruction of significant enemies
+//! and fading titles announcing waves.
+
+use hecs::{CommandBuffer, Entity, World};
+use macroquad::prelude::*;
+
+use crate::{
+    basic::{FadeOutOnExpiry, Health, Lifetime, Position},
+    locale::tr,
+    menu::{Title, UiOwner},
+    persist::Persistent,
+    xp::BurstXpOnDeath,
+    SPACE_HEIGHT, SPACE_WIDTH,
+};
+
+/// Max amount of banners that can be shown at once.
+pub const MAX_BANNERS: usize = 3;
+/// Time a kill banner is shown for.
+const BANNER_TIME: f32 = 1.5;
+/// Time it takes the banner to slide in and out.
+const BANNER_SLIDE_TIME: f32 = 0.25;
+/// Vertical position of the first slot.
+/// It is below the score display and boss health bars.
+const BANNER_TOP: f32 = 100.0;
+/// Vertical distance between two slots.
+const BANNER_SPACING: f32 = 40.0;
+/// Size of the banner's text.
+const BANNER_SIZE: f32 = 30.0;
+
+/// Time a fading title is shown for.
+const FADING_TITLE_TIME: f32 = 2.0;
+/// Time over which a fading title fades out.
+const FADING_TITLE_FADE: f32 = 1.0;
+/// Size of the fading title's text.
+const FADING_TITLE_SIZE: f32 = 60.0;
+
+/// Banner sliding from the top of the screen.
+/// Its text is stored in [Title].
+#[derive(Clone, Copy, Debug)]
+pub struct Banner {
+    /// Time before the banner disappears.
+    pub ttl: f32,
+    /// Time the banner is shown for in total.
+    pub time: f32,
+    /// Slot the banner is stacked in.
+    /// Lower slots are closer to the top.
+    pub slot: usize,
+}
+
+/// Marker of enemies that announce their death with a [Banner].
+#[derive(Clone, Copy, Debug)]
+pub struct KillBanner {
+    /// Locale key of the enemy's name shown on the banner.
+    pub name: &'static str,
+}
+
+/// Banner slots, each holding the banner in it and its remaining time.
+pub type BannerSlots = [Option<(Entity, f32)>; MAX_BANNERS];
+
+//-----------------------------------------------------------------------------
+//ENTITY CREATION
+//-----------------------------------------------------------------------------
+
+/// Returns the slots taken by existing banners.
+pub fn banner_slots(world: &World) -> BannerSlots {
+    let mut slots = [None; MAX_BANNERS];
+    for (id, banner) in world.query::<&Banner>().iter() {
+        slots[banner.slot] = Some((id, banner.ttl));
+    }
+    slots
+}
+
+/// Spawns a banner into a free slot.
+/// When all slots are taken, the oldest banner is replaced.
+/// # Arguments
+/// * `slots` - slots taken so far, updated with the new banner
+/// * `text` - text of the banner
+/// * `color` - color of the text
+/// * `time` - time the banner is shown for
+pub fn push_banner(
+    world: &World,
+    cmd: &mut CommandBuffer,
+    slots: &mut BannerSlots,
+    text: String,
+    color: Color,
+    time: f32,
+) {
+    //get free slot or replace the oldest banner
+    let slot = match slots.iter().position(Option::is_none) {
+        Some(slot) => slot,
+        None => {
+            let (slot, (oldest, _)) = slots
+                .iter()
+                .map(|slot| slot.unwrap())
+                .enumerate()
+                .min_by(|a, b| a.1 .1.total_cmp(&b.1 .1))
+                .unwrap();
+            cmd.despawn(oldest);
+            slot
+        }
+    };
+    //spawn the banner
+    let banner_id = world.reserve_entity();
+    cmd.insert(
+        banner_id,
+        (
+            Position {
+                x: SPACE_WIDTH / 2.0,
+                y: -BANNER_SIZE,
+            },
+            Title {
+                text,
+                font: "main_font",
+                size: BANNER_SIZE,
+                color,
+                max_width: None,
+            },
+            UiOwner::Always,
+            Banner {
+                ttl: time,
+                time,
+                slot,
+            },
+        ),
+    );
+    slots[slot] = Some((banner_id, time));
+}
+
+/// Spawns a title in the middle of the screen, fading out over time.
+/// # Arguments
+/// * `text` - text of the title
+/// * `color` - color of the text
+/// * `owner` - owner of the title, see [UiOwner]
+pub fn push_fading_title(cmd: &mut CommandBuffer, text: String, color: Color, owner: UiOwner) {
+    cmd.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: SPACE_HEIGHT / 3.0,
+        },
+        Title {
+            text,
+            font: "main_font",
+            size: FADING_TITLE_SIZE,
+            color,
+            max_width: None,
+        },
+        owner,
+        Lifetime::new(FADING_TITLE_TIME),
+        FadeOutOnExpiry {
+            seconds: FADING_TITLE_FADE,
+        },
+    ));
+}
+
+//-----------------------------------------------------------------------------
+//SYSTEM PART
+//-----------------------------------------------------------------------------
+
+/// Spawns banners for dead (hp <= 0.0) [KillBanner] entities.
+pub fn kill_banners(world: &mut World, cmd: &mut CommandBuffer, persist: &Persistent) {
+    //are banners enabled?
+    if !persist.settings.kill_banners {
+        return;
+    }
+    let mut slots = banner_slots(world);
+
+    for (_, (kill, health, burst)) in world
+        .query::<(&KillBanner, &Health, Option<&BurstXpOnDeath>)>()
+        .into_iter()
+    {
+        //is the entity dead?
+        if health.hp > 0.0 {
+            continue;
+        }
+        let reward = burst.map(|burst| burst.amount).unwrap_or(0) * 10;
+        push_banner(
+            world,
+            cmd,
+            &mut slots,
+            tr!("banner.destroyed", name = tr!(kill.name), reward = reward),
+            GOLD,
+            BANNER_TIME,
+        );
+    }
+}
+
+/// Animates banners sliding in and out and despawns expired ones.
+pub fn banners(world: &mut World, cmd: &mut CommandBuffer, dt: f32) {
+    for (id, (banner, pos)) in world.query_mut::<(&mut Banner, &mut Position)>() {
+        banner.ttl -= dt;
+        if banner.ttl <= 0.0 {
+            cmd.despawn(id);
+            continue;
+        }
+        //ease out when sliding in, ease in when sliding out
+        let time = banner.time - banner.ttl;
+        let progress = (time.min(banner.ttl) / BANNER_SLIDE_TIME).min(1.0);
+        let eased = 1.0 - (1.0 - progress).powi(3);
+
+        let target = BANNER_TOP + banner.slot as f32 * BANNER_SPACING;
+        pos.y = -BANNER_SIZE + (target + BANNER_SIZE) * eased;
+    }
+}
@@ -2,16 +2,22 @@
 use hecs::{CommandBuffer, World};
 use macroquad::prelude::*;
 
+pub mod arena;
+pub mod audio;
+pub mod clock;
+pub mod embed;
 pub mod fx;
+pub mod grid;
 pub mod health;
 pub mod motion;
 pub mod render;
+pub mod rumble;
 
 pub use health::*;
 
-use crate::{SPACE_HEIGHT, SPACE_WIDTH};
+use crate::menu::Title;
 
-use self::render::{AssetManager, Sprite};
+use self::render::{AssetManager, Circle, Sprite};
 
 //-----------------------------------------------------------------------------
 //UTILS PART
@@ -21,17 +27,114 @@ use self::render::{AssetManager, Sprite};
 ///This determines hurting between entities.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
 pub enum Team {
+    /// Hazards and decorations.
+    /// Hurts everyone, but cannot be hurt by anyone.
     #[default]
     Neutral,
+    /// The player and everything that belongs to them.
     Player,
+    /// Enemies of the player.
     Enemy,
 }
 
 impl Team {
     /// Can the `self` team hurt the `other` team.
+    ///
+    /// Uses an explicit interaction matrix of (attacker, victim) pairs.
     #[inline]
     pub fn can_hurt(&self, other: &Team) -> bool {
-        self != other
+        match (self, other) {
+            //nobody hurts neutral hazards and decorations
+            (_, Team::Neutral) => false,
+            //neutral hazards hurt everyone else
+            (Team::Neutral, _) => true,
+            //player's side against the enemies
+            (Team::Player, Team::Enemy) => true,
+            (Team::Enemy, Team::Player) => true,
+            //no friendly fire
+            (Team::Player, Team::Player) => false,
+            (Team::Enemy, Team::Enemy) => false,
+        }
+    }
+}
+
+/// Timer counting time up to its duration.
+///
+/// The timer is finished when the elapsed time reaches its duration.
+/// Repeating timers start over by themselves, others have to be [reset](Timer::reset).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Timer {
+    /// Time it takes the timer to finish.
+    pub duration: f32,
+    /// Time elapsed since the timer started.
+    pub elapsed: f32,
+    /// Does the timer start over when finished?
+    pub repeating: bool,
+    /// Did the timer finish during the last tick?
+    just_finished: bool,
+}
+
+impl Timer {
+    /// Creates a timer that has just started.
+    /// # Arguments
+    /// * `duration` - time it takes the timer to finish
+    /// * `repeating` - does the timer start over when finished
+    pub fn new(duration: f32, repeating: bool) -> Self {
+        Self {
+            duration,
+            elapsed: 0.0,
+            repeating,
+            just_finished: false,
+        }
+    }
+
+    /// Creates a non-repeating timer that is already finished.
+    /// # Arguments
+    /// * `duration` - time it takes the timer to finish after reset
+    pub fn new_finished(duration: f32) -> Self {
+        Self {
+            duration,
+            elapsed: duration,
+            repeating: false,
+            just_finished: false,
+        }
+    }
+
+    /// Advances the timer.
+    /// # Arguments
+    /// * `dt` - delta time
+    pub fn tick(&mut self, dt: f32) {
+        let was_finished = self.finished();
+        self.elapsed += dt;
+        self.just_finished = !was_finished && self.finished();
+        //start over
+        if self.repeating && self.finished() {
+            self.elapsed = 0.0;
+        }
+    }
+
+    /// Has the timer reached its duration?
+    #[inline]
+    pub fn finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Did the timer finish during the last tick?
+    #[inline]
+    pub fn just_finished(&self) -> bool {
+        self.just_finished
+    }
+
+    /// Time left before the timer finishes.
+    #[inline]
+    pub fn remaining(&self) -> f32 {
+        (self.duration - self.elapsed).max(0.0)
+    }
+
+    /// Starts the timer over.
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+        self.just_finished = false;
     }
 }
 
@@ -61,6 +164,63 @@ pub struct Wrapped;
 #[derive(Clone, Copy, Debug, Default)]
 pub struct DeleteOnWarp;
 
+/// Time an entity lives for before it is despawned.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Lifetime {
+    /// Time left before the entity is despawned.
+    pub remaining: f32,
+    /// Time the entity lives for in total.
+    pub total: f32,
+    /// Time the entity has lived so far.
+    pub elapsed: f32,
+}
+
+impl Lifetime {
+    /// Creates a lifetime of an entity living for `seconds`.
+    pub fn new(seconds: f32) -> Self {
+        Self {
+            remaining: seconds,
+            total: seconds,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Creates a lifetime of an entity which is never despawned,
+    /// used to time its [FadeInOnSpawn].
+    pub fn forever() -> Self {
+        Self::new(f32::INFINITY)
+    }
+}
+
+/// Marker of entities with a [Lifetime] that fade in after they are spawned.
+///
+/// Fades the [Title], [Sprite] and [Circle] of the entity.
+#[derive(Clone, Copy, Debug)]
+pub struct FadeInOnSpawn {
+    /// Time after the spawn over which the entity fades in.
+    pub seconds: f32,
+}
+
+/// Marker of entities with a [Lifetime] that fade out before they are despawned.
+///
+/// Fades the [Title], [Sprite] and [Circle] of the entity.
+#[derive(Clone, Copy, Debug)]
+pub struct FadeOutOnExpiry {
+    /// Time before the despawn over which the entity fades out.
+    pub seconds: f32,
+}
+
+impl Default for FadeOutOnExpiry {
+    fn default() -> Self {
+        Self {
+            seconds: LIFETIME_FADE,
+        }
+    }
+}
+
+/// Default time over which entities marked by [FadeOutOnExpiry] fade out.
+const LIFETIME_FADE: f32 = 0.3;
+
 //-----------------------------------------------------------------------------
 //EVENTS
 //-----------------------------------------------------------------------------
@@ -69,24 +229,64 @@ pub struct DeleteOnWarp;
 //SYSTEM PART
 //-----------------------------------------------------------------------------
 
+/// Counts down [Lifetime]s and despawns entities whose time ran out.
+/// Fades in entities marked by [FadeInOnSpawn] right after their spawn
+/// and fades out entities marked by [FadeOutOnExpiry] right before their despawn.
+pub fn tick_lifetimes(world: &mut World, cmd: &mut CommandBuffer, dt: f32) {
+    for (id, lifetime) in world.query_mut::<&mut Lifetime>() {
+        lifetime.remaining -= dt;
+        lifetime.elapsed += dt;
+        if lifetime.remaining <= 0.0 {
+            cmd.despawn(id);
+        }
+    }
+
+    //fade in and out
+    for (_, (lifetime, fade_in, fade_out, title, sprite, circle)) in world.query_mut::<(
+        &Lifetime,
+        Option<&FadeInOnSpawn>,
+        Option<&FadeOutOnExpiry>,
+        Option<&mut Title>,
+        Option<&mut Sprite>,
+        Option<&mut Circle>,
+    )>() {
+        if fade_in.is_none() && fade_out.is_none() {
+            continue;
+        }
+        let faded_in = fade_in.map_or(1.0, |fade| lifetime.elapsed / fade.seconds);
+        let faded_out = fade_out.map_or(1.0, |fade| lifetime.remaining / fade.seconds);
+        let alpha = faded_in.min(faded_out).clamp(0.0, 1.0);
+        if let Some(title) = title {
+            title.color.a = alpha;
+        }
+        if let Some(sprite) = sprite {
+            sprite.color.a = alpha;
+        }
+        if let Some(circle) = circle {
+            circle.color.a = alpha;
+        }
+    }
+}
+
 /// Handles the wrapping and deletion of entities marked by [Wrapped] or [DeleteOnWarp].
 pub fn ensure_wrapping(world: &mut World, cmd: &mut CommandBuffer, assets: &AssetManager) {
+    let arena = arena::arena(world);
     //handle Wrapped wraping
     for (_, pos) in world.query_mut::<&mut Position>().with::<&Wrapped>() {
         //if outside of screen tp them back
         //assumes position is center
-        if pos.x > SPACE_WIDTH {
+        if pos.x > arena.width {
             pos.x = 0.0;
         }
         if pos.x < 0.0 {
-            pos.x = SPACE_WIDTH;
+            pos.x = arena.width;
         }
 
-        if pos.y > SPACE_HEIGHT {
+        if pos.y > arena.height {
             pos.y = 0.0;
         }
         if pos.y < 0.0 {
-            pos.y = SPACE_HEIGHT;
+            pos.y = arena.height;
         }
     }
 
@@ -112,14 +312,14 @@ pub fn ensure_wrapping(world: &mut World, cmd: &mut CommandBuffer, assets: &Asse
         };
         //if outside of screen tp delete them
         //assumes position is center
-        if pos.x > SPACE_WIDTH + pushback {
+        if pos.x > arena.width + pushback {
             cmd.despawn(id);
         }
         if pos.x < -pushback {
             cmd.despawn(id);
         }
 
-        if pos.y > SPACE_HEIGHT + pushback {
+        if pos.y > arena.height + pushback {
             cmd.despawn(id);
         }
         if pos.y < -pushback {
@@ -127,3 +327,149 @@ pub fn ensure_wrapping(world: &mut World, cmd: &mut CommandBuffer, assets: &Asse
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{basic::clock::FIXED_DT, enemy::mine::MINE_DETONATION_TIMER, player};
+
+    #[test]
+    fn timer_finishes_at_duration() {
+        let mut timer = Timer::new(1.0, false);
+        assert!(!timer.finished());
+        assert_eq!(timer.remaining(), 1.0);
+
+        timer.tick(0.5);
+        assert!(!timer.finished());
+        assert!(!timer.just_finished());
+        assert_eq!(timer.remaining(), 0.5);
+
+        timer.tick(0.5);
+        assert!(timer.finished());
+        assert!(timer.just_finished());
+        assert_eq!(timer.remaining(), 0.0);
+    }
+
+    #[test]
+    fn timer_just_finished_once() {
+        let mut timer = Timer::new(0.25, false);
+        timer.tick(0.3);
+        assert!(timer.just_finished());
+        timer.tick(0.3);
+        assert!(timer.finished());
+        assert!(!timer.just_finished());
+        //never below zero
+        assert_eq!(timer.remaining(), 0.0);
+    }
+
+    #[test]
+    fn timer_new_finished() {
+        let mut timer = Timer::new_finished(2.0);
+        assert!(timer.finished());
+        assert!(!timer.just_finished());
+
+        timer.tick(1.0);
+        assert!(!timer.just_finished());
+
+        timer.reset();
+        assert!(!timer.finished());
+        assert_eq!(timer.remaining(), 2.0);
+    }
+
+    #[test]
+    fn timer_reset() {
+        let mut timer = Timer::new(1.0, false);
+        timer.tick(1.5);
+        timer.reset();
+        assert!(!timer.finished());
+        assert!(!timer.just_finished());
+        assert_eq!(timer.elapsed, 0.0);
+    }
+
+    #[test]
+    fn timer_repeating() {
+        let mut timer = Timer::new(1.0, true);
+        let mut finishes = 0;
+        for _ in 0..40 {
+            timer.tick(0.25);
+            if timer.just_finished() {
+                finishes += 1;
+                //starts over right away
+                assert!(!timer.finished());
+                assert_eq!(timer.elapsed, 0.0);
+            }
+        }
+        assert_eq!(finishes, 10);
+    }
+
+    #[test]
+    fn fire_cadence_matches_countdown() {
+        //the cooldown counted down by hand before the timer replaced it
+        let mut countdown = 0.0;
+        let mut timer = Timer::new_finished(player::PLAYER_FIRE_COOLDOWN);
+        let mut shots = 0;
+        for step in 0..(10.0 / FIXED_DT) as u32 {
+            countdown -= FIXED_DT;
+            let fired_countdown = countdown <= 0.0;
+            if fired_countdown {
+                countdown = player::PLAYER_FIRE_COOLDOWN;
+            }
+
+            timer.tick(FIXED_DT);
+            let fired_timer = timer.finished();
+            if fired_timer {
+                timer.reset();
+                shots += 1;
+            }
+            assert_eq!(fired_countdown, fired_timer, "shots differ at step {step}");
+        }
+        //one shot every cooldown, rounded up to whole steps
+        let steps_per_shot = (player::PLAYER_FIRE_COOLDOWN / FIXED_DT).round() as u32;
+        assert!(shots >= (10.0 / FIXED_DT) as u32 / (steps_per_shot + 1));
+        assert!(shots <= (10.0 / FIXED_DT) as u32 / steps_per_shot + 1);
+    }
+
+    #[test]
+    fn mine_detonation_matches_countdown() {
+        //the detonation counted down by hand before the timer replaced it
+        let mut countdown = MINE_DETONATION_TIMER;
+        let countdown_step = (1..).find(|_| {
+            countdown -= FIXED_DT;
+            countdown <= 0.0
+        });
+
+        let mut timer = Timer::new(MINE_DETONATION_TIMER, false);
+        let timer_step = (1..).find(|_| {
+            timer.tick(FIXED_DT);
+            timer.finished()
+        });
+
+        assert_eq!(countdown_step, timer_step);
+        let expected = (MINE_DETONATION_TIMER / FIXED_DT).round() as i32;
+        assert!((timer_step.unwrap() - expected).abs() <= 1);
+    }
+
+    #[test]
+    fn team_interaction_matrix() {
+        use Team::*;
+        //(attacker, victim, can hurt)
+        let matrix = [
+            (Neutral, Neutral, false),
+            (Neutral, Player, true),
+            (Neutral, Enemy, true),
+            (Player, Neutral, false),
+            (Player, Player, false),
+            (Player, Enemy, true),
+            (Enemy, Neutral, false),
+            (Enemy, Player, true),
+            (Enemy, Enemy, false),
+        ];
+        for (attacker, victim, expected) in matrix {
+            assert_eq!(
+                attacker.can_hurt(&victim),
+                expected,
+                "{attacker:?} hurting {victim:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! Play space of a run and the camera looking at it.
+
+use hecs::World;
+use macroquad::prelude::*;
+
+use crate::{player::Player, SPACE_HEIGHT, SPACE_WIDTH};
+
+use super::Position;
+
+/// Resource describing the play space of the current run.
+///
+/// The visible part of the arena is always [SPACE_WIDTH] x [SPACE_HEIGHT],
+/// arenas larger than that are looked at by a camera following the player.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Arena {
+    /// Width of the play space.
+    pub width: f32,
+    /// Height of the play space.
+    pub height: f32,
+    /// Should the camera follow the player?
+    pub follow: bool,
+    /// Top left corner of the visible part of the arena.
+    pub camera: Vec2,
+}
+
+impl Arena {
+    /// Arena exactly the size of the screen.
+    pub const STANDARD: Arena = Arena {
+        width: SPACE_WIDTH,
+        height: SPACE_HEIGHT,
+        follow: false,
+        camera: Vec2::ZERO,
+    };
+
+    /// Widescreen arena the camera follows the player in.
+    pub const LARGE: Arena = Arena {
+        width: 1920.0,
+        height: 1080.0,
+        follow: true,
+        camera: Vec2::ZERO,
+    };
+
+    /// Returns the center of the arena.
+    pub fn center(&self) -> Vec2 {
+        vec2(self.width / 2.0, self.height / 2.0)
+    }
+
+    /// Returns the visible part of the arena.
+    pub fn view_rect(&self) -> Rect {
+        Rect {
+            x: self.camera.x,
+            y: self.camera.y,
+            w: SPACE_WIDTH,
+            h: SPACE_HEIGHT,
+        }
+    }
+
+    /// Returns the camera mapping the visible part of the arena onto the screen.
+    pub fn world_camera(&self) -> Camera2D {
+        rect_camera(self.view_rect())
+    }
+
+    /// Returns the position of the mouse in arena coordinates.
+    pub fn mouse_pos(&self) -> Vec2 {
+        let (mx, my) = mouse_position();
+        self.world_camera().screen_to_world(vec2(mx, my))
+    }
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+/// Resource replacing the visible part of the arena,
+/// e.g. while spectating after the player's death.
+#[derive(Clone, Copy, Debug)]
+pub struct CameraOverride {
+    /// Part of the world shown on the screen.
+    pub rect: Rect,
+}
+
+/// Returns `rect` grown to the aspect ratio of the window.
+/// `rect` stays centered and whole, the rest is covered by letterbox bars.
+pub fn letterbox(rect: Rect) -> Rect {
+    //nothing to fit into when minimized
+    if screen_width() < 1.0 || screen_height() < 1.0 {
+        return rect;
+    }
+    let aspect = screen_width() / screen_height();
+    if aspect > rect.w / rect.h {
+        //bars on the sides
+        let width = rect.h * aspect;
+        Rect::new(rect.x - (width - rect.w) / 2.0, rect.y, width, rect.h)
+    } else {
+        //bars on the top and bottom
+        let height = rect.w / aspect;
+        Rect::new(rect.x, rect.y - (height - rect.h) / 2.0, rect.w, height)
+    }
+}
+
+/// Returns the camera mapping `rect` of the world onto the screen.
+/// The aspect ratio of `rect` is kept, see [letterbox].
+pub fn rect_camera(rect: Rect) -> Camera2D {
+    target_camera(letterbox(rect))
+}
+
+/// Returns the camera mapping `rect` of the world onto the whole render target.
+/// Used for render targets of the same aspect ratio as `rect`.
+pub fn target_camera(rect: Rect) -> Camera2D {
+    Camera2D::from_display_rect(Rect {
+        x: rect.x,
+        y: rect.y + rect.h,
+        w: rect.w,
+        h: -rect.h,
+    })
+}
+
+//-----------------------------------------------------------------------------
+//SYSTEM PART
+//-----------------------------------------------------------------------------
+
+/// Returns the [Arena] of the world.
+/// Worlds without one are of the standard size.
+pub fn arena(world: &World) -> Arena {
+    world
+        .query::<&Arena>()
+        .iter()
+        .next()
+        .map(|(_, arena)| *arena)
+        .unwrap_or_default()
+}
+
+/// Returns the part of the world shown on the screen.
+/// [CameraOverride] takes precedence over the camera of the [Arena].
+pub fn view_rect(world: &World) -> Rect {
+    world
+        .query::<&CameraOverride>()
+        .iter()
+        .next()
+        .map(|(_, view)| view.rect)
+        .unwrap_or_else(|| arena(world).view_rect())
+}
+
+/// Returns the camera mapping [view_rect] onto the screen, letterboxed.
+pub fn view_camera(world: &World) -> Camera2D {
+    rect_camera(view_rect(world))
+}
+
+/// Centers the camera of following arenas on the player.
+/// The camera never looks outside of the arena.
+pub fn follow_camera(world: &mut World) {
+    let Some((_, &player_pos)) = world
+        .query_mut::<&Position>()
+        .with::<&Player>()
+        .into_iter()
+        .next()
+    else {
+        return;
+    };
+
+    for (_, arena) in world.query_mut::<&mut Arena>() {
+        if !arena.follow {
+            continue;
+        }
+        arena.camera = vec2(
+            (player_pos.x - SPACE_WIDTH / 2.0).clamp(0.0, arena.width - SPACE_WIDTH),
+            (player_pos.y - SPACE_HEIGHT / 2.0).clamp(0.0, arena.height - SPACE_HEIGHT),
+        );
+    }
+}
@@ -0,0 +1,239 @@
+//! Playing of sounds, limited so that bursts of events do not clip.
+//!
+//! Sounds of events in the world fade with their distance from the listener.
+//! Macroquad's sounds cannot be panned, so they all play centered.
+
+use std::cell::{Cell, RefCell};
+
+use fnv::{FnvHashMap, FnvHashSet};
+use macroquad::{
+    audio::{play_sound, set_sound_volume, stop_sound, PlaySoundParams, Sound},
+    prelude::*,
+};
+
+use hecs::World;
+
+use crate::{persist::Settings, player::Player, SPACE_HEIGHT, SPACE_WIDTH};
+
+use super::Position;
+
+/// Distance from the listener up to which sounds play at full volume.
+const FULL_VOLUME_DISTANCE: f32 = 200.0;
+/// Distance from the listener at which sounds fade out completely.
+const SILENT_DISTANCE: f32 = 1000.0;
+
+/// Limits of a sound played by [AudioManager::play].
+#[derive(Clone, Copy, Debug)]
+pub struct SoundLimits {
+    /// Minimal time between two plays of the sound.
+    pub min_interval: f64,
+    /// Max amount of instances of the sound playing at once.
+    pub max_instances: usize,
+    /// Time an instance of the sound is considered playing for.
+    pub length: f64,
+}
+
+/// Limits of sounds without their own entry in [SOUND_LIMITS].
+const DEFAULT_LIMITS: SoundLimits = SoundLimits {
+    min_interval: 0.05,
+    max_instances: 4,
+    length: 0.5,
+};
+
+/// Limits of individual sounds, by their ids.
+const SOUND_LIMITS: [(&str, SoundLimits); 2] = [
+    //grinding bodies knock each other back many times a second
+    (
+        "knockback",
+        SoundLimits {
+            min_interval: 0.08,
+            max_instances: 3,
+            length: 0.4,
+        },
+    ),
+    (
+        "pew_pew",
+        SoundLimits {
+            min_interval: 0.04,
+            max_instances: 4,
+            length: 0.3,
+        },
+    ),
+];
+
+/// Instances of a sound played recently.
+#[derive(Clone, Debug)]
+struct Channel {
+    /// Time the sound was last played at.
+    last: f64,
+    /// Times the playing instances end at.
+    ends: Vec<f64>,
+}
+
+/// Manager of all the sounds.
+///
+/// Limits how often and how many times at once every sound plays,
+/// and keeps track of looping sounds.
+#[derive(Debug)]
+pub struct AudioManager {
+    /// Sound storage.
+    sounds: FnvHashMap<&'static str, Sound>,
+    /// Multiplier of the volume of all played sounds.
+    volume: f32,
+    /// Recently played instances of every sound.
+    channels: RefCell<FnvHashMap<&'static str, Channel>>,
+    /// Looping sounds currently playing.
+    loops: RefCell<FnvHashSet<&'static str>>,
+    /// Position positional sounds are heard from.
+    listener: Cell<Vec2>,
+}
+
+impl Default for AudioManager {
+    fn default() -> Self {
+        Self {
+            sounds: Default::default(),
+            volume: 1.0,
+            channels: Default::default(),
+            loops: Default::default(),
+            listener: Cell::new(vec2(SPACE_WIDTH / 2.0, SPACE_HEIGHT / 2.0)),
+        }
+    }
+}
+
+impl AudioManager {
+    /// Adds a loaded sound to the manager.
+    /// # Arguments
+    /// * `id` - id using which the sound can be played
+    /// * `sound` - the loaded sound
+    pub fn insert(&mut self, id: &'static str, sound: Sound) {
+        self.sounds.insert(id, sound);
+    }
+
+    /// Gets a sound from storage.
+    ///
+    /// Returns [None] if the sound is not present.
+    /// # Arguments
+    /// * `id` - id passed when loading the sound
+    pub fn get(&self, id: &'static str) -> Option<&Sound> {
+        self.sounds.get(id)
+    }
+
+    /// Applies the user's volume preferences.
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.volume = settings.master_volume * settings.sfx_volume;
+    }
+
+    /// Plays a sound once, scaled by the user's volume preferences.
+    ///
+    /// Silent sounds are not played at all,
+    /// neither are sounds played too recently or too many times at once.
+    /// # Arguments
+    /// * `id` - id passed when loading the sound
+    /// * `volume` - volume of the sound before scaling
+    pub fn play(&self, id: &'static str, volume: f32) {
+        let volume = volume * self.volume;
+        if volume <= 0.0 {
+            return;
+        }
+        let Some(sound) = self.sounds.get(id) else {
+            return;
+        };
+        let limits = SOUND_LIMITS
+            .iter()
+            .find(|(limited, _)| *limited == id)
+            .map_or(DEFAULT_LIMITS, |(_, limits)| *limits);
+
+        //respect the limits
+        let now = get_time();
+        let mut channels = self.channels.borrow_mut();
+        let channel = channels.entry(id).or_insert(Channel {
+            last: f64::NEG_INFINITY,
+            ends: Vec::new(),
+        });
+        channel.ends.retain(|end| *end > now);
+        if channel.ends.len() >= limits.max_instances || now - channel.last < limits.min_interval {
+            return;
+        }
+        channel.last = now;
+        channel.ends.push(now + limits.length);
+
+        play_sound(
+            sound,
+            PlaySoundParams {
+                looped: false,
+                volume,
+            },
+        );
+    }
+
+    /// Plays a sound of an event at `pos` once, quieter the further it is from the listener.
+    /// Limited the same way as [AudioManager::play].
+    /// # Arguments
+    /// * `id` - id passed when loading the sound
+    /// * `volume` - volume of the sound right at the listener
+    /// * `pos` - position of the event in the world
+    pub fn play_at(&self, id: &'static str, volume: f32, pos: Vec2) {
+        let distance = pos.distance(self.listener.get());
+        let falloff = 1.0
+            - ((distance - FULL_VOLUME_DISTANCE) / (SILENT_DISTANCE - FULL_VOLUME_DISTANCE))
+                .clamp(0.0, 1.0);
+        self.play(id, volume * falloff);
+    }
+
+    /// Starts looping a sound, or updates its volume if it already loops.
+    /// Silent sounds are stopped instead.
+    /// # Arguments
+    /// * `id` - id passed when loading the sound
+    /// * `volume` - volume of the sound before scaling
+    pub fn start_loop(&self, id: &'static str, volume: f32) {
+        let volume = volume * self.volume;
+        if volume <= 0.0 {
+            self.stop_loop(id);
+            return;
+        }
+        let Some(sound) = self.sounds.get(id) else {
+            return;
+        };
+        if self.loops.borrow_mut().insert(id) {
+            play_sound(
+                sound,
+                PlaySoundParams {
+                    looped: true,
+                    volume,
+                },
+            );
+        } else {
+            set_sound_volume(sound, volume);
+        }
+    }
+
+    /// Stops a looping sound, if it loops.
+    /// # Arguments
+    /// * `id` - id passed when loading the sound
+    pub fn stop_loop(&self, id: &'static str) {
+        if !self.loops.borrow_mut().remove(id) {
+            return;
+        }
+        if let Some(sound) = self.sounds.get(id) {
+            stop_sound(sound);
+        }
+    }
+}
+
+//-----------------------------------------------------------------------------
+//SYSTEM PART
+//-----------------------------------------------------------------------------
+
+/// Places the listener of positional sounds at the player,
+/// or at the center of the space when there is none, e.g. in menus.
+pub fn place_listener(world: &mut World, audio: &AudioManager) {
+    let listener = world
+        .query_mut::<&Position>()
+        .with::<&Player>()
+        .into_iter()
+        .next()
+        .map_or(vec2(SPACE_WIDTH / 2.0, SPACE_HEIGHT / 2.0), |(_, pos)| {
+            vec2(pos.x, pos.y)
+        });
+    audio.listener.set(listener);
+}
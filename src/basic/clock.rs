@@ -0,0 +1,46 @@
+//! Fixed timestep of the simulation.
+
+/// Duration of a single simulation step, 120 steps per second.
+pub const FIXED_DT: f32 = 1.0 / 120.0;
+/// Most steps simulated in a single frame.
+/// Longer hitches are dropped instead of catching up.
+const MAX_STEPS: u32 = 8;
+
+/// Accumulates the real time and splits it into fixed simulation steps.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FixedClock {
+    /// Real time not simulated yet.
+    /// Negative when a step was simulated early.
+    accumulator: f32,
+}
+
+impl FixedClock {
+    /// Adds the time of a frame and returns how many steps should be simulated.
+    /// # Arguments
+    /// * `dt` - real time of the frame
+    /// * `early` - should a step be simulated even when not enough time accumulated?
+    ///   Used so that inputs pressed this frame are not lost, the time is borrowed from the next frame.
+    pub fn advance(&mut self, dt: f32, early: bool) -> u32 {
+        self.accumulator += dt;
+        let mut steps = (self.accumulator / FIXED_DT).max(0.0) as u32;
+        if steps == 0 && early {
+            steps = 1;
+        }
+        if steps > MAX_STEPS {
+            self.accumulator = 0.0;
+            return MAX_STEPS;
+        }
+        self.accumulator -= steps as f32 * FIXED_DT;
+        steps
+    }
+
+    /// Returns how far between the last two steps the current frame is, 0.0 to 1.0.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / FIXED_DT).clamp(0.0, 1.0)
+    }
+
+    /// Drops the accumulated time, e.g. when the simulation stops.
+    pub fn reset(&mut self) {
+        self.accumulator = 0.0;
+    }
+}
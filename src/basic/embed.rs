@@ -0,0 +1,80 @@
+//! Assets embedded into the binary, so that the game runs without the `res/` folder.
+//!
+//! Enabled by the `embed-assets` cargo feature:
+//! ```text
+//! cargo build --release --features embed-assets
+//! ```
+//! The [AssetManager](super::render::AssetManager) falls back to the embedded files
+//! only when they are missing on disk, so the files in `res/` can still override them
+//! without rebuilding.
+
+/// Builds the archive of the files at `paths`, relative to the crate root.
+#[cfg(feature = "embed-assets")]
+macro_rules! archive {
+    ($($path:literal),* $(,)?) => {
+        &[$((
+            $path,
+            include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/", $path)) as &[u8],
+        )),*]
+    };
+}
+
+/// Embedded files by their paths.
+/// It must contain every texture, sound, music track and font loaded at startup.
+#[cfg(feature = "embed-assets")]
+const ARCHIVE: &[(&str, &[u8])] = archive![
+    //textures
+    "res/asteroid.png",
+    "res/asteroid_plus.png",
+    "res/asteroid_minus.png",
+    "res/asteroid_outline.png",
+    "res/asteroid_big_plus.png",
+    "res/asteroid_big_minus.png",
+    "res/player_plus.png",
+    "res/player_minus.png",
+    "res/thruster_flame.png",
+    "res/smal_proj_minus.png",
+    "res/smal_proj_plus.png",
+    "res/medium_proj_neutral.png",
+    "res/medium_proj_minus.png",
+    "res/medium_proj_plus.png",
+    "res/saw_blade.png",
+    "res/saw_blade_plus.png",
+    "res/saw_blade_minus.png",
+    "res/mine_neutral.png",
+    "res/mine_plus.png",
+    "res/mine_minus.png",
+    "res/mine_light.png",
+    "res/asteroid_big.png",
+    //sounds
+    "res/sound/movement.wav",
+    "res/sound/boing.wav",
+    "res/sound/pew_pew.wav",
+    "res/sound/heartbeat.wav",
+    //music
+    "res/music/menu.wav",
+    "res/music/combat.wav",
+    "res/music/game_over.wav",
+    //fonts
+    "res/NotoSans-Regular.ttf",
+];
+
+/// Returns the embedded file at `path`.
+///
+/// Returns [None] if the file is not embedded or the `embed-assets` feature is disabled.
+/// # Arguments
+/// * `path` - path of the file, as passed to the loaders
+pub fn get(path: &str) -> Option<&'static [u8]> {
+    #[cfg(feature = "embed-assets")]
+    {
+        ARCHIVE
+            .iter()
+            .find(|(embedded, _)| *embedded == path)
+            .map(|(_, bytes)| *bytes)
+    }
+    #[cfg(not(feature = "embed-assets"))]
+    {
+        let _ = path;
+        None
+    }
+}
@@ -4,8 +4,15 @@ use std::collections::VecDeque;
 
 use macroquad::prelude::*;
 
+use crate::persist::Settings;
+
+/// Multiplier of emitted particles when reduced motion is on.
+const REDUCED_MOTION_EMISSION: f32 = 0.5;
+/// Most particles emitted by a single burst when flashing is reduced.
+const REDUCED_FLASHING_MAX_BURST: usize = 6;
+
 /// Particle to render
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Default)]
 pub struct Particle {
     /// Position of the particle.
     pub pos: Vec2,
@@ -22,6 +29,13 @@ pub struct Particle {
     pub max_size: f32,
     /// Color of the particle.
     pub color: Color,
+    /// Acceleration applied to the velocity every second.
+    pub accel: Vec2,
+    /// Fraction of the velocity lost every second.
+    pub drag: f32,
+    /// Color the particle fades into when `life` is zero.
+    /// The color stays the same if [None].
+    pub end_color: Option<Color>,
 }
 
 /// Manager of all the particles.
@@ -31,6 +45,12 @@ pub struct FxManager {
     particles: VecDeque<Particle>,
     /// Max particles that can be spawned at once.
     pub max_particles: usize,
+    /// Multiplier of the amount of particles emitted by bursts.
+    /// Derived from user's settings in [FxManager::apply_settings].
+    pub emission_mult: f32,
+    /// Most particles emitted by a single burst.
+    /// Derived from user's settings in [FxManager::apply_settings].
+    pub max_burst: usize,
 }
 
 impl FxManager {
@@ -41,9 +61,33 @@ impl FxManager {
         Self {
             particles: VecDeque::with_capacity(max_particles),
             max_particles,
+            emission_mult: 1.0,
+            max_burst: usize::MAX,
         }
     }
 
+    /// Returns the number of live particles.
+    pub fn particle_count(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// Derives emission of particles from user's settings.
+    ///
+    /// All settings affecting the amount of particles are combined here,
+    /// so that they are applied exactly once.
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.emission_mult = if settings.reduced_motion {
+            REDUCED_MOTION_EMISSION
+        } else {
+            1.0
+        };
+        self.max_burst = if settings.reduce_flashing {
+            REDUCED_FLASHING_MAX_BURST
+        } else {
+            usize::MAX
+        };
+    }
+
     /// Adds a particle to the manager.
     /// Removes the oldest particle if space is not available.
     /// # Arguments
@@ -63,18 +107,44 @@ impl FxManager {
     /// * `base` - base particle to add
     /// * `vel_deviation` - random change in the base velocitie's length
     /// * `angle_deviation` - random change in the base velocitie's direction, in radians
-    /// * `count` - how many particles should it spawn
+    /// * `count` - how many particles should it spawn, scaled by `emission_mult` and capped by `max_burst`
     pub fn burst_particles(
         &mut self,
         base: Particle,
         vel_deviation: f32,
         angle_deviation: f32,
         count: usize,
+    ) {
+        self.burst_with(base, vel_deviation, angle_deviation, count, |_| {});
+    }
+
+    /// Spawns many particles with different velocities, each changed by `randomize`.
+    /// Removes old particles if space is not enough.
+    /// # Arguments
+    /// * `base` - base particle to add
+    /// * `vel_deviation` - random change in the base velocitie's length
+    /// * `angle_deviation` - random change in the base velocitie's direction, in radians
+    /// * `count` - how many particles should it spawn, scaled by `emission_mult` and capped by `max_burst`
+    /// * `randomize` - changes every particle right before it is added
+    pub fn burst_with(
+        &mut self,
+        base: Particle,
+        vel_deviation: f32,
+        angle_deviation: f32,
+        count: usize,
+        mut randomize: impl FnMut(&mut Particle),
     ) {
         //base velocity information to construct new velocity vectors
         let vel_normal = base.vel.normalize_or_zero();
         let vel_length = base.vel.length();
 
+        //scale the count, but keep at least one particle
+        let count = if count > 0 {
+            ((count as f32 * self.emission_mult).round() as usize).clamp(1, self.max_burst)
+        } else {
+            0
+        };
+
         //spawn `count` particles
         for _ in 0..count {
             //apply angle deviation
@@ -84,6 +154,7 @@ impl FxManager {
             //spawn it
             let mut particle = base;
             particle.vel = vel;
+            randomize(&mut particle);
             self.add_particle(particle);
         }
     }
@@ -98,6 +169,8 @@ impl FxManager {
     /// * `dt` - delta time  
     pub fn update_particles(&mut self, dt: f32) {
         for particle in &mut self.particles {
+            particle.vel += particle.accel * dt;
+            particle.vel *= (-particle.drag * dt).exp();
             particle.pos += particle.vel * dt;
             particle.life -= dt;
         }
@@ -112,12 +185,21 @@ impl FxManager {
             let size = (particle.life / particle.max_life)
                 * (particle.max_size - particle.min_size)
                 + particle.min_size;
+            //fade into the end color over the particle's life
+            let color = match particle.end_color {
+                Some(end_color) => Color::from_vec(
+                    end_color
+                        .to_vec()
+                        .lerp(particle.color.to_vec(), particle.life / particle.max_life),
+                ),
+                None => particle.color,
+            };
             draw_rectangle(
                 particle.pos.x - size / 2.0,
                 particle.pos.y - size / 2.0,
                 size,
                 size,
-                particle.color,
+                color,
             );
         }
     }
@@ -0,0 +1,68 @@
+//! Uniform spatial hash used to find nearby entities quickly.
+
+use fnv::FnvHashMap;
+use macroquad::math::Vec2;
+
+/// Uniform grid of square cells storing indices of items in them.
+///
+/// Cells are hashed, so items outside of the logical space are stored as well.
+#[derive(Clone, Debug)]
+pub struct SpatialGrid {
+    /// Side of a single cell.
+    cell_size: f32,
+    /// Indices of items in every non-empty cell.
+    cells: FnvHashMap<(i32, i32), Vec<usize>>,
+}
+
+impl SpatialGrid {
+    /// Creates an empty grid.
+    /// # Arguments
+    /// * `cell_size` - side of a single cell, should be around the size of the items
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size: cell_size.max(1.0),
+            cells: FnvHashMap::default(),
+        }
+    }
+
+    /// Returns the cell containing `pos`.
+    fn cell(&self, pos: Vec2) -> (i32, i32) {
+        (
+            (pos.x / self.cell_size).floor() as i32,
+            (pos.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// Inserts the item `index` at `pos`.
+    pub fn insert(&mut self, pos: Vec2, index: usize) {
+        let cell = self.cell(pos);
+        self.cells.entry(cell).or_default().push(index);
+    }
+
+    /// Collects indices of all items that can be closer than `radius` to `pos`
+    /// into `out`, sorted and without duplicates.
+    ///
+    /// Items further away may be returned as well, the exact distance must be checked.
+    pub fn query(&self, pos: Vec2, radius: f32, out: &mut Vec<usize>) {
+        self.query_unsorted(pos, radius, out);
+        out.sort_unstable();
+        out.dedup();
+    }
+
+    /// Collects indices of all items that can be closer than `radius` to `pos` into `out`,
+    /// cell by cell in a fixed order.
+    ///
+    /// Cheaper than [SpatialGrid::query], for callers that do not depend on the order.
+    pub fn query_unsorted(&self, pos: Vec2, radius: f32, out: &mut Vec<usize>) {
+        out.clear();
+        let (min_x, min_y) = self.cell(pos - Vec2::splat(radius));
+        let (max_x, max_y) = self.cell(pos + Vec2::splat(radius));
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                if let Some(items) = self.cells.get(&(x, y)) {
+                    out.extend_from_slice(items);
+                }
+            }
+        }
+    }
+}
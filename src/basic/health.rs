@@ -1,10 +1,57 @@
 //! Health, Damage and Collision handling systems and structs.
-use hecs::{Entity, World};
-use macroquad::{color::Color, shapes::draw_rectangle};
+use std::f32::consts::PI;
 
-use crate::basic::Position;
+use fnv::{FnvHashMap, FnvHashSet};
+use hecs::{CommandBuffer, Entity, EntityBuilder, World};
+use macroquad::prelude::*;
 
-use super::Team;
+use crate::{basic::Position, input::Binding, SPACE_WIDTH};
+
+use super::{
+    fx::{FxManager, Particle},
+    grid::SpatialGrid,
+    motion::{ChargeSender, PreviousTransform},
+    render::AssetManager,
+    Team, Timer,
+};
+
+/// Time newly spawned entities ignore collisions with other enemies.
+pub const SPAWN_GRACE_TIME: f32 = 0.75;
+/// Time after which a lasting contact produces another [HitEvent].
+pub const CONTACT_COOLDOWN: f32 = 0.5;
+
+/// Vertical position of boss health bars.
+/// It is below the score display.
+const BOSS_BAR_Y: f32 = 62.0;
+/// Width of boss health bars.
+const BOSS_BAR_WIDTH: f32 = 600.0;
+/// Height of boss health bars.
+const BOSS_BAR_HEIGHT: f32 = 10.0;
+/// Size of the boss's name above its health bar.
+const BOSS_BAR_NAME_SIZE: f32 = 20.0;
+/// Longest move of a [SweptCollider] that is swept.
+/// Longer moves are teleports, e.g. wrapping around the arena, which crosses most of it.
+const MAX_SWEEP_DISTANCE: f32 = 300.0;
+
+/// Key showing and hiding the [CollisionDebug] drawing.
+const COLLISION_DEBUG_KEY: KeyCode = KeyCode::F4;
+/// Color of [HitBox] outlines.
+const HITBOX_COLOR: Color = GREEN;
+/// Color of [HurtBox] outlines.
+const HURTBOX_COLOR: Color = RED;
+/// Color of colliders hit in the current frame.
+const CONTACT_COLOR: Color = YELLOW;
+/// Color of [ChargeSender] radii.
+const CHARGE_RADIUS_COLOR: Color = Color::new(0.5, 0.5, 1.0, 0.25);
+/// Times per second colliders hit in the current frame flash.
+const CONTACT_FLASH_RATE: f64 = 10.0;
+
+/// Time the damage ghost of a health bar takes to shrink to the current health.
+const GHOST_SHRINK_TIME: f32 = 0.5;
+/// Color of the damage ghost of health bars.
+const GHOST_COLOR: Color = YELLOW;
+/// Amount of shards the bar shatters into.
+const BOSS_BAR_SHARDS: usize = 24;
 
 //-----------------------------------------------------------------------------
 //EVENT PART
@@ -23,6 +70,17 @@ pub struct HitEvent {
     pub can_hurt: bool,
 }
 
+/// Event representing damage dealt to an entity by a single hit.
+#[derive(Clone, Copy, Debug)]
+pub struct DamageEvent {
+    /// Entity id of the damaged entity.
+    pub who: Entity,
+    /// Entity id of the entity that dealt the damage.
+    pub by: Entity,
+    /// Amount of damage dealt.
+    pub amount: f32,
+}
+
 //-----------------------------------------------------------------------------
 //COMPONENT PART
 //-----------------------------------------------------------------------------
@@ -67,6 +125,100 @@ pub struct HitBox {
     pub radius: f32,
 }
 
+/// Grace period of freshly spawned entities.
+/// While it lasts, the entity does not collide with other [Team::Enemy] entities.
+/// Collisions with other teams are unaffected.
+#[derive(Clone, Copy, Debug)]
+pub struct SpawnGrace {
+    /// Time before the grace period ends.
+    pub timer: Timer,
+}
+
+impl Default for SpawnGrace {
+    fn default() -> Self {
+        Self {
+            timer: Timer::new(SPAWN_GRACE_TIME, false),
+        }
+    }
+}
+
+/// Makes [ensure_damage] test the whole path the entity's [HurtBox] moved along
+/// during the last step, not just where it ended up.
+///
+/// Keeps fast entities from skipping over small [HitBox]es.
+/// Requires [PreviousTransform] to know where the entity moved from.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SweptCollider;
+
+/// State of a contact between two colliding entities.
+#[derive(Clone, Copy, Debug)]
+struct Contact {
+    /// Time since the contact last produced a [HitEvent].
+    age: f32,
+    /// Are the entities touching this frame?
+    touching: bool,
+}
+
+/// Resource remembering pairs of colliding entities.
+///
+/// A pair only produces a [HitEvent] when it starts touching,
+/// and again every `cooldown` while the contact lasts.
+#[derive(Clone, Debug)]
+pub struct ContactMemory {
+    /// Time after which a lasting contact produces another [HitEvent].
+    pub cooldown: f32,
+    /// Contacts keyed by the hit and the hurting entity.
+    contacts: FnvHashMap<(Entity, Entity), Contact>,
+}
+
+impl ContactMemory {
+    /// Ages all contacts, call before reporting the contacts of a frame.
+    fn begin(&mut self, dt: f32) {
+        for contact in self.contacts.values_mut() {
+            contact.age += dt;
+            contact.touching = false;
+        }
+    }
+
+    /// Reports a contact between `who` and `by`.
+    /// Returns whether it should produce a [HitEvent].
+    fn touch(&mut self, who: Entity, by: Entity) -> bool {
+        let cooldown = self.cooldown;
+        let contact = self.contacts.entry((who, by)).or_insert(Contact {
+            age: cooldown,
+            touching: false,
+        });
+        contact.touching = true;
+        if contact.age >= cooldown {
+            contact.age = 0.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Forgets separated pairs, call after reporting the contacts of a frame.
+    fn end(&mut self) {
+        self.contacts.retain(|_, contact| contact.touching);
+    }
+}
+
+impl Default for ContactMemory {
+    fn default() -> Self {
+        Self {
+            cooldown: CONTACT_COOLDOWN,
+            contacts: FnvHashMap::default(),
+        }
+    }
+}
+
+/// Resource remembering whether colliders are drawn for debugging.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CollisionDebug {
+    /// Are the colliders drawn?
+    pub shown: bool,
+}
+
 /// Component that shows a health bar that represents the entity's health
 /// stored in `Health`.
 #[derive(Clone, Copy, Debug)]
@@ -85,12 +237,212 @@ pub struct HealthDisplay {
     /// Background shows the max health the entity can have
     /// (According to its [Health] component).
     pub max_color: Color,
+    /// Segment lagging behind the health after damage.
+    pub ghost: DamageGhost,
+}
+
+/// Health fraction lagging behind the real one after damage,
+/// so that bursts of damage stay readable.
+///
+/// Shrinks to the health over [GHOST_SHRINK_TIME] after every hit,
+/// snaps to it when the health rises.
+#[derive(Clone, Copy, Debug)]
+pub struct DamageGhost {
+    /// Health fraction shown by the ghost.
+    pub fraction: f32,
+    /// Health fraction seen by the previous update.
+    health: f32,
+    /// Time left until the ghost reaches the health.
+    remaining: f32,
+}
+
+impl Default for DamageGhost {
+    fn default() -> Self {
+        Self {
+            fraction: 1.0,
+            health: 1.0,
+            remaining: 0.0,
+        }
+    }
+}
+
+impl DamageGhost {
+    /// Moves the ghost towards the current health fraction.
+    /// # Arguments
+    /// * `health` - current health fraction
+    /// * `dt` - delta time
+    pub fn update(&mut self, health: f32, dt: f32) {
+        //fresh damage restarts the shrinking
+        if health < self.health {
+            self.remaining = GHOST_SHRINK_TIME;
+        }
+        self.health = health;
+
+        if health >= self.fraction || self.remaining <= dt {
+            self.fraction = health;
+            self.remaining = 0.0;
+        } else {
+            self.fraction -= (self.fraction - health) * dt / self.remaining;
+            self.remaining -= dt;
+        }
+    }
+}
+
+/// Wide health bar docked at the top of the screen for boss encounters.
+///
+/// Shows the boss's name, phase markers and a [DamageGhost].
+#[derive(Clone, Debug)]
+pub struct BossHealthBar {
+    /// Boss whose [Health] is being shown.
+    pub target: Entity,
+    /// Name of the boss shown above the bar.
+    pub name: String,
+    /// Health fractions (0.0 to 1.0) at which the boss changes its behaviour.
+    pub phases: Vec<f32>,
+    /// Segment lagging behind the health after damage.
+    pub ghost: DamageGhost,
+}
+
+//-----------------------------------------------------------------------------
+//ENTITY CREATION
+//-----------------------------------------------------------------------------
+
+/// Creates a boss health bar entity.
+/// # Arguments
+/// * `target` - entity id of the boss
+/// * `name` - name of the boss
+/// * `phases` - health fractions at which the boss changes phases
+pub fn create_boss_health_bar(target: Entity, name: &str, phases: Vec<f32>) -> EntityBuilder {
+    let mut builder = EntityBuilder::new();
+
+    builder.add(Position {
+        x: SPACE_WIDTH / 2.0,
+        y: BOSS_BAR_Y,
+    });
+
+    builder.add(BossHealthBar {
+        target,
+        name: name.to_string(),
+        phases,
+        ghost: DamageGhost::default(),
+    });
+
+    builder
 }
 
 //-----------------------------------------------------------------------------
 //SYSTEM PART
 //-----------------------------------------------------------------------------
 
+/// Shrinks the damage ghosts of [BossHealthBar]s.
+/// Shatters and despawns bars whose boss is dead or despawned.
+pub fn boss_bars(world: &mut World, cmd: &mut CommandBuffer, fx: &mut FxManager, dt: f32) {
+    for (bar_id, (bar, pos)) in world.query::<(&mut BossHealthBar, &Position)>().into_iter() {
+        //get boss's health, tolerating its disappearance
+        let fraction = world
+            .get::<&Health>(bar.target)
+            .map(|health| health.hp / health.max_hp)
+            .unwrap_or(0.0)
+            .max(0.0);
+
+        //shatter when dead
+        if fraction <= 0.0 {
+            for i in 0..BOSS_BAR_SHARDS {
+                let x = pos.x - BOSS_BAR_WIDTH / 2.0
+                    + BOSS_BAR_WIDTH * (i as f32 + 0.5) / BOSS_BAR_SHARDS as f32;
+                fx.burst_particles(
+                    Particle {
+                        pos: vec2(x, pos.y),
+                        vel: vec2(0.0, 60.0),
+                        life: 0.8,
+                        max_life: 0.8,
+                        min_size: 0.0,
+                        max_size: BOSS_BAR_HEIGHT,
+                        color: RED,
+                        ..Default::default()
+                    },
+                    30.0,
+                    PI / 2.0,
+                    2,
+                );
+            }
+            cmd.despawn(bar_id);
+            continue;
+        }
+
+        bar.ghost.update(fraction.min(1.0), dt);
+    }
+}
+
+/// Renders [BossHealthBar]s.
+pub fn render_boss_bars(world: &mut World, assets: &AssetManager) {
+    for (_, (bar, pos)) in world.query::<(&BossHealthBar, &Position)>().into_iter() {
+        //get boss's health, tolerating its disappearance
+        let fraction = world
+            .get::<&Health>(bar.target)
+            .map(|health| health.hp / health.max_hp)
+            .unwrap_or(0.0)
+            .clamp(0.0, 1.0);
+
+        let left = pos.x - BOSS_BAR_WIDTH / 2.0;
+        let top = pos.y - BOSS_BAR_HEIGHT / 2.0;
+        //draw background
+        draw_rectangle(
+            left,
+            top,
+            BOSS_BAR_WIDTH,
+            BOSS_BAR_HEIGHT,
+            Color::new(0.4, 0.0, 0.0, 1.0),
+        );
+        //draw damage ghost
+        draw_rectangle(
+            left,
+            top,
+            BOSS_BAR_WIDTH * bar.ghost.fraction,
+            BOSS_BAR_HEIGHT,
+            GHOST_COLOR,
+        );
+        //draw actual health
+        draw_rectangle(left, top, BOSS_BAR_WIDTH * fraction, BOSS_BAR_HEIGHT, RED);
+        //draw phase markers
+        for phase in &bar.phases {
+            draw_rectangle(
+                left + BOSS_BAR_WIDTH * phase.clamp(0.0, 1.0) - 1.0,
+                top - 2.0,
+                2.0,
+                BOSS_BAR_HEIGHT + 4.0,
+                WHITE,
+            );
+        }
+        //draw name above the bar
+        let font = assets.get_font("main_font");
+        let dimensions = measure_text(&bar.name, font, BOSS_BAR_NAME_SIZE as u16, 1.0);
+        draw_text_ex(
+            &bar.name,
+            pos.x - dimensions.width / 2.0,
+            top - 6.0,
+            TextParams {
+                font,
+                font_size: BOSS_BAR_NAME_SIZE as u16 * 2,
+                font_scale: 0.5,
+                color: WHITE,
+                ..Default::default()
+            },
+        );
+    }
+}
+
+/// Shrinks the damage ghosts of `HealthDisplay`s.
+pub fn update_displays(world: &mut World, dt: f32) {
+    for (_, display) in world.query::<&mut HealthDisplay>().into_iter() {
+        //get the entity of the health to display
+        let mut target = world.query_one::<&Health>(display.target).unwrap();
+        let target_hp = target.get().unwrap();
+        let fraction = (target_hp.hp / target_hp.max_hp).clamp(0.0, 1.0);
+        display.ghost.update(fraction, dt);
+    }
+}
+
 /// Renders `HealthDisplay`s
 pub fn render_displays(world: &mut World) {
     //iterate over all displays
@@ -109,6 +461,14 @@ pub fn render_displays(world: &mut World) {
             display.height,
             display.max_color,
         );
+        //draw damage ghost
+        draw_rectangle(
+            pos.x - display.max_width / 2.0,
+            pos.y - display.height / 2.0,
+            display.ghost.fraction * display.max_width,
+            display.height,
+            GHOST_COLOR,
+        );
         //draw actual health
         draw_rectangle(
             pos.x - display.max_width / 2.0,
@@ -121,30 +481,408 @@ pub fn render_displays(world: &mut World) {
 }
 
 /// Handles collision detection between [HitBox]es and [HurtBox]es.
-pub fn ensure_damage(world: &mut World, events: &mut World) {
+///
+/// Only pairs in neighbouring cells of a [SpatialGrid] are tested,
+/// the events are the same and in the same order as when testing all pairs.
+///
+/// With a [ContactMemory] in the world, a lasting contact only produces
+/// an event once per its cooldown.
+///
+/// [SweptCollider]s that moved further than their radius and hit nothing
+/// they can hurt are swept along their move, hitting the first [HitBox] on the way.
+pub fn ensure_damage(world: &mut World, events: &mut World, dt: f32) {
+    //remember contacts of previous frames
+    let mut memory_query = world.query::<&mut ContactMemory>();
+    let mut memory = memory_query.iter().next().map(|(_, memory)| memory);
+    if let Some(memory) = memory.as_deref_mut() {
+        memory.begin(dt);
+    }
+
+    //gather all hurting entities
+    let mut hurt_query = world.query::<(&Position, &HurtBox, &Team, Option<&SpawnGrace>)>();
+    let hurters: Vec<_> = hurt_query.iter().collect();
+    //put them into the grid
+    let max_radius = hurters
+        .iter()
+        .map(|(_, (_, hurt_box, _, _))| hurt_box.radius)
+        .fold(0.0, f32::max);
+    let mut grid = SpatialGrid::new(max_radius * 2.0);
+    for (index, (_, (pos, _, _, _))) in hurters.iter().enumerate() {
+        grid.insert(vec2(pos.x, pos.y), index);
+    }
+
+    //gather all hitable entities
+    let mut hit_query = world.query::<(&Position, &HitBox, &Team, Option<&SpawnGrace>)>();
+    let hittables: Vec<_> = hit_query.iter().collect();
+
+    //hurting entities that already hit something they can hurt
+    let mut landed = FnvHashSet::default();
+    let mut candidates = Vec::new();
     //iterate through all hitable entities
-    for (hit_id, (hit_pos, hit_box, hit_team)) in
-        world.query::<(&Position, &HitBox, &Team)>().into_iter()
-    {
-        //iterate through all hurting entities
-        for (hurt_id, (hurt_pos, hurt_box, hurt_team)) in
-            world.query::<(&Position, &HurtBox, &Team)>().into_iter()
-        {
+    for &(hit_id, (hit_pos, hit_box, hit_team, hit_grace)) in &hittables {
+        //iterate through nearby hurting entities
+        grid.query(
+            vec2(hit_pos.x, hit_pos.y),
+            hit_box.radius + max_radius,
+            &mut candidates,
+        );
+        for &index in &candidates {
+            let (hurt_id, (hurt_pos, hurt_box, hurt_team, hurt_grace)) = hurters[index];
             //ignore self collisions
             if hurt_id == hit_id {
                 continue;
             }
+            //ignore enemies still in their spawn grace
+            if (hit_grace.is_some() || hurt_grace.is_some())
+                && *hit_team == Team::Enemy
+                && *hurt_team == Team::Enemy
+            {
+                continue;
+            }
             //are they touching?
             let dx = hit_pos.x - hurt_pos.x;
             let dy = hit_pos.y - hurt_pos.y;
             if dx * dx + dy * dy < (hurt_box.radius + hit_box.radius).powi(2) {
+                //ignore lasting contacts
+                if let Some(memory) = memory.as_deref_mut() {
+                    if !memory.touch(hit_id, hurt_id) {
+                        continue;
+                    }
+                }
                 //add hit event
+                let can_hurt = hurt_team.can_hurt(hit_team);
+                if can_hurt {
+                    landed.insert(hurt_id);
+                }
                 events.spawn((HitEvent {
                     who: hit_id,
                     by: hurt_id,
-                    can_hurt: hurt_team.can_hurt(hit_team),
+                    can_hurt,
                 },));
             }
         }
     }
+
+    //put hitable entities into a grid for sweeping
+    let max_hit_radius = hittables
+        .iter()
+        .map(|(_, (_, hit_box, _, _))| hit_box.radius)
+        .fold(0.0, f32::max);
+    let mut hit_grid = SpatialGrid::new(max_hit_radius * 2.0);
+    for (index, (_, (pos, _, _, _))) in hittables.iter().enumerate() {
+        hit_grid.insert(vec2(pos.x, pos.y), index);
+    }
+
+    //sweep fast entities along their move
+    for (hurt_id, (hurt_pos, previous, hurt_box, hurt_team)) in world
+        .query::<(&Position, &PreviousTransform, &HurtBox, &Team)>()
+        .with::<&SweptCollider>()
+        .into_iter()
+    {
+        //already hit something at the end of the move
+        if landed.contains(&hurt_id) {
+            continue;
+        }
+        //slow enough to be caught by the regular test or teleported
+        let from = previous.pos;
+        let to = vec2(hurt_pos.x, hurt_pos.y);
+        let distance = from.distance(to);
+        if distance <= hurt_box.radius || distance > MAX_SWEEP_DISTANCE {
+            continue;
+        }
+
+        //find the earliest contact along the move
+        hit_grid.query(
+            (from + to) / 2.0,
+            distance / 2.0 + hurt_box.radius + max_hit_radius,
+            &mut candidates,
+        );
+        let earliest = candidates
+            .iter()
+            .filter_map(|&index| {
+                let (hit_id, (hit_pos, hit_box, hit_team, _)) = hittables[index];
+                if hit_id == hurt_id || !hurt_team.can_hurt(hit_team) {
+                    return None;
+                }
+                sweep_circle(
+                    from,
+                    to,
+                    vec2(hit_pos.x, hit_pos.y),
+                    hit_box.radius + hurt_box.radius,
+                )
+                .map(|time| (time, hit_id))
+            })
+            .min_by(|(a, _), (b, _)| a.total_cmp(b));
+        let Some((_, hit_id)) = earliest else {
+            continue;
+        };
+
+        //ignore lasting contacts
+        if let Some(memory) = memory.as_deref_mut() {
+            if !memory.touch(hit_id, hurt_id) {
+                continue;
+            }
+        }
+        //add hit event
+        events.spawn((HitEvent {
+            who: hit_id,
+            by: hurt_id,
+            can_hurt: true,
+        },));
+    }
+
+    //forget separated pairs
+    if let Some(memory) = memory {
+        memory.end();
+    }
+}
+
+/// Returns the earliest portion (0.0 to 1.0) of the move from `from` to `to`
+/// at which it is closer than `radius` to `center`, [None] when it never is.
+fn sweep_circle(from: Vec2, to: Vec2, center: Vec2, radius: f32) -> Option<f32> {
+    //solve |from + t * dir - center| = radius for t
+    let dir = to - from;
+    let offset = from - center;
+    let a = dir.dot(dir);
+    let b = 2.0 * offset.dot(dir);
+    let c = offset.dot(offset) - radius * radius;
+    //starting inside
+    if c < 0.0 {
+        return Some(0.0);
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if a <= 0.0 || discriminant < 0.0 {
+        return None;
+    }
+    let time = (-b - discriminant.sqrt()) / (2.0 * a);
+    (0.0..=1.0).contains(&time).then_some(time)
+}
+
+/// Ticks [SpawnGrace]s and removes the finished ones.
+pub fn spawn_grace(world: &mut World, cmd: &mut CommandBuffer, dt: f32) {
+    for (id, grace) in world.query_mut::<&mut SpawnGrace>() {
+        grace.timer.tick(dt);
+        if grace.timer.finished() {
+            cmd.remove_one::<SpawnGrace>(id);
+        }
+    }
+}
+
+/// Shows and hides the [CollisionDebug] drawing when its key is pressed.
+/// Runs every simulation step, only the first step of a frame sees the press.
+pub fn toggle_collision_debug(world: &mut World) {
+    if !Binding::key(COLLISION_DEBUG_KEY).is_pressed() {
+        return;
+    }
+    for (_, debug) in world.query_mut::<&mut CollisionDebug>() {
+        debug.shown = !debug.shown;
+    }
+}
+
+/// Draws outlines of every [HitBox] and [HurtBox] when the [CollisionDebug] is shown.
+/// Colliders of the pairs hit in the current frame flash yellow,
+/// [ChargeSender] radii are drawn dimmer behind them.
+/// Must be rendered with the world camera, above the sprites.
+pub fn debug_draw_collision(world: &mut World, events: &World) {
+    let shown = world
+        .query_mut::<&CollisionDebug>()
+        .into_iter()
+        .any(|(_, debug)| debug.shown);
+    if !shown {
+        return;
+    }
+
+    //charge radii behind the colliders
+    for (_, (sender, pos)) in world.query_mut::<(&ChargeSender, &Position)>() {
+        draw_circle_lines(pos.x, pos.y, sender.full_radius, 1.0, CHARGE_RADIUS_COLOR);
+        draw_circle_lines(pos.x, pos.y, sender.no_radius, 1.0, CHARGE_RADIUS_COLOR);
+    }
+
+    //pairs hit in the current frame
+    let mut hit = FnvHashSet::default();
+    for (_, event) in events.query::<&HitEvent>().iter() {
+        hit.insert(event.who);
+        hit.insert(event.by);
+    }
+    let flash = ((get_time() * CONTACT_FLASH_RATE) as u64).is_multiple_of(2);
+    let color = |id: Entity, color: Color| {
+        if flash && hit.contains(&id) {
+            CONTACT_COLOR
+        } else {
+            color
+        }
+    };
+
+    for (id, (hit_box, pos)) in world.query_mut::<(&HitBox, &Position)>() {
+        draw_circle_lines(pos.x, pos.y, hit_box.radius, 1.0, color(id, HITBOX_COLOR));
+    }
+    for (id, (hurt_box, pos)) in world.query_mut::<(&HurtBox, &Position)>() {
+        draw_circle_lines(pos.x, pos.y, hurt_box.radius, 1.0, color(id, HURTBOX_COLOR));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{basic::motion::PreviousTransform, input};
+
+    #[test]
+    fn sweep_finds_earliest_contact() {
+        let time = sweep_circle(vec2(-100.0, 0.0), vec2(100.0, 0.0), Vec2::ZERO, 10.0);
+        assert_eq!(time, Some(0.45));
+    }
+
+    #[test]
+    fn sweep_misses() {
+        //passing by
+        let time = sweep_circle(vec2(-100.0, 20.0), vec2(100.0, 20.0), Vec2::ZERO, 10.0);
+        assert_eq!(time, None);
+        //stopping short
+        let time = sweep_circle(vec2(-100.0, 0.0), vec2(-50.0, 0.0), Vec2::ZERO, 10.0);
+        assert_eq!(time, None);
+        //moving away
+        let time = sweep_circle(vec2(50.0, 0.0), vec2(100.0, 0.0), Vec2::ZERO, 10.0);
+        assert_eq!(time, None);
+        //not moving at all
+        let time = sweep_circle(vec2(50.0, 0.0), vec2(50.0, 0.0), Vec2::ZERO, 10.0);
+        assert_eq!(time, None);
+    }
+
+    #[test]
+    fn sweep_starting_inside() {
+        let time = sweep_circle(vec2(5.0, 0.0), vec2(100.0, 0.0), Vec2::ZERO, 10.0);
+        assert_eq!(time, Some(0.0));
+    }
+
+    #[test]
+    fn fast_projectile_hits_small_hitbox() {
+        let mut world = World::new();
+        let mut events = World::new();
+        let target = world.spawn((
+            Position { x: 0.0, y: 0.0 },
+            HitBox { radius: 5.0 },
+            Team::Enemy,
+        ));
+        //moves 200 units in one step, from one side of the target to the other
+        let projectile = world.spawn((
+            Position { x: 100.0, y: 0.0 },
+            PreviousTransform {
+                pos: vec2(-100.0, 0.0),
+                angle: None,
+            },
+            HurtBox { radius: 2.0 },
+            Team::Player,
+            SweptCollider,
+        ));
+
+        ensure_damage(&mut world, &mut events, 1.0 / 120.0);
+
+        let hits: Vec<_> = events
+            .query_mut::<&HitEvent>()
+            .into_iter()
+            .map(|(_, hit)| (hit.who, hit.by, hit.can_hurt))
+            .collect();
+        assert_eq!(hits, [(target, projectile, true)]);
+    }
+
+    #[test]
+    fn grid_hits_match_brute_force() {
+        let mut rng = fastrand::Rng::with_seed(1503);
+        let teams = [Team::Neutral, Team::Player, Team::Enemy];
+        for _ in 0..50 {
+            let mut world = World::new();
+            for _ in 0..rng.usize(1..80) {
+                let entity = world.spawn((
+                    Position {
+                        x: rng.f32() * 1280.0,
+                        y: rng.f32() * 720.0,
+                    },
+                    teams[rng.usize(..teams.len())],
+                ));
+                //colliders of all sizes, some entities have both
+                if rng.bool() {
+                    let radius = 1.0 + rng.f32() * 60.0;
+                    world.insert_one(entity, HurtBox { radius }).unwrap();
+                }
+                if rng.bool() {
+                    let radius = 1.0 + rng.f32() * 60.0;
+                    world.insert_one(entity, HitBox { radius }).unwrap();
+                }
+            }
+
+            //test every pair
+            let mut expected = Vec::new();
+            for (hit_id, (hit_pos, hit_box, hit_team)) in
+                world.query::<(&Position, &HitBox, &Team)>().iter()
+            {
+                for (hurt_id, (hurt_pos, hurt_box, hurt_team)) in
+                    world.query::<(&Position, &HurtBox, &Team)>().iter()
+                {
+                    let distance = vec2(hit_pos.x - hurt_pos.x, hit_pos.y - hurt_pos.y).length();
+                    if hit_id != hurt_id && distance < hit_box.radius + hurt_box.radius {
+                        expected.push((hit_id, hurt_id, hurt_team.can_hurt(hit_team)));
+                    }
+                }
+            }
+
+            let mut events = World::new();
+            ensure_damage(&mut world, &mut events, 1.0 / 120.0);
+            let hits: Vec<_> = events
+                .query_mut::<&HitEvent>()
+                .into_iter()
+                .map(|(_, hit)| (hit.who, hit.by, hit.can_hurt))
+                .collect();
+            assert_eq!(hits, expected);
+        }
+    }
+
+    #[test]
+    fn wrapping_projectile_is_not_swept() {
+        let mut world = World::new();
+        let mut events = World::new();
+        world.spawn((
+            Position { x: 0.0, y: 0.0 },
+            HitBox { radius: 5.0 },
+            Team::Enemy,
+        ));
+        //wrapped around the arena, it never crossed the target
+        world.spawn((
+            Position { x: 600.0, y: 0.0 },
+            PreviousTransform {
+                pos: vec2(-600.0, 0.0),
+                angle: None,
+            },
+            HurtBox { radius: 2.0 },
+            Team::Player,
+            SweptCollider,
+        ));
+
+        ensure_damage(&mut world, &mut events, 1.0 / 120.0);
+
+        assert_eq!(events.query_mut::<&HitEvent>().into_iter().count(), 0);
+    }
+
+    #[test]
+    fn collision_debug_toggles_once_per_frame() {
+        let mut world = World::new();
+        world.spawn((CollisionDebug::default(),));
+        let shown = |world: &mut World| {
+            world
+                .query_mut::<&CollisionDebug>()
+                .into_iter()
+                .any(|(_, debug)| debug.shown)
+        };
+        //a frame of two steps, like at 60 fps
+        input::begin_frame();
+        input::press_key(COLLISION_DEBUG_KEY);
+        for _ in 0..2 {
+            toggle_collision_debug(&mut world);
+            input::mark_presses_handled();
+        }
+        assert!(shown(&mut world));
+        //the next frame without a press keeps it shown
+        input::begin_frame();
+        toggle_collision_debug(&mut world);
+        assert!(shown(&mut world));
+    }
 }
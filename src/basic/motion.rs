@@ -1,11 +1,92 @@
 //! Motion and physics components and systems.
-use hecs::World;
-use macroquad::{
-    audio::{self, PlaySoundParams},
-    math::{vec2, Vec2},
+use std::f32::consts::PI;
+
+use fnv::FnvHashMap;
+use hecs::{CommandBuffer, Entity, World};
+use macroquad::math::{vec2, Vec2};
+
+use crate::{heatmap::ForceHeatmap, persist::Settings};
+
+use super::{
+    grid::SpatialGrid, render::AssetManager, HitBox, HitEvent, Position, Rotation, SpawnGrace,
+    Timer,
 };
 
-use super::{render::AssetManager, HitEvent, Position, Rotation};
+/// Multiplier of [LinearTorgue] speed when reduced motion is on.
+const REDUCED_MOTION_TORGUE: f32 = 0.2;
+/// Default portion of the approaching speed kept after a collision of two bodies.
+pub const BODY_RESTITUTION: f32 = 0.8;
+/// Time after a knockback during which it can be cancelled.
+pub const KNOCKBACK_CANCEL_WINDOW: f32 = 0.2;
+/// Time before the same dealer can knock back the same victim again.
+/// Matches the player's invulnerability after a hit.
+pub const KNOCKBACK_COOLDOWN: f32 = 1.0;
+/// Distance moved in a single step above which the entity is drawn without interpolation.
+/// Such moves are teleports, e.g. wrapping around the arena.
+const MAX_INTERPOLATED_DISTANCE: f32 = 100.0;
+
+/// Position and rotation of a moving entity at the start of the last simulation step.
+/// Rendering interpolates between them and the current ones.
+#[derive(Clone, Copy, Debug)]
+pub struct PreviousTransform {
+    /// Position at the start of the step.
+    pub pos: Vec2,
+    /// Rotation at the start of the step, if the entity has any.
+    pub angle: Option<f32>,
+}
+
+/// Current transforms of entities drawn interpolated.
+/// Returned by [interpolate_transforms] to be put back by [restore_transforms].
+pub type TransformStash = Vec<(Entity, Position, Option<Rotation>)>;
+
+/// Velocity an entity recently received from a knockback.
+/// Removed once older than [KNOCKBACK_CANCEL_WINDOW].
+#[derive(Clone, Copy, Debug)]
+pub struct RecentKnockback {
+    /// Velocity added by the knockback.
+    pub vel: Vec2,
+    /// Time since the knockback.
+    pub since: f32,
+}
+
+/// Resource remembering recent knockbacks between pairs of entities.
+///
+/// The same dealer can only knock back the same victim once per `cooldown`,
+/// so lasting contacts cannot pin the victim with repeated impulses.
+#[derive(Clone, Debug)]
+pub struct KnockbackMemory {
+    /// Time before the same dealer can knock back the same victim again.
+    pub cooldown: f32,
+    /// Time since the last knockback, keyed by the victim and the dealer.
+    pairs: FnvHashMap<(Entity, Entity), f32>,
+}
+
+impl KnockbackMemory {
+    /// Reports a knockback of `victim` by `dealer`.
+    /// Returns whether it should be applied.
+    fn knock(&mut self, victim: Entity, dealer: Entity) -> bool {
+        let since = self.pairs.entry((victim, dealer)).or_insert(self.cooldown);
+        if *since >= self.cooldown {
+            *since = 0.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl Default for KnockbackMemory {
+    fn default() -> Self {
+        Self {
+            cooldown: KNOCKBACK_COOLDOWN,
+            pairs: FnvHashMap::default(),
+        }
+    }
+}
+
+/// Reflects knockback dealt to the entity back onto the dealer.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct KnockbackReflect;
 
 /// Moves an entity in a linear way.
 /// It does not accelerate, decelerate, change directions
@@ -90,11 +171,37 @@ pub struct ChargeReceiver {
     pub multiplier: f32,
 }
 
+/// Charge force currently applied on the entity.
+/// Cleared and summed up by [apply_physics] every step.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NetForce {
+    /// Sum of the charge forces applied during the last step.
+    pub force: Vec2,
+}
+
 /// Makes an entity temporalily immune to charge forces.
 #[derive(Clone, Copy, Debug, Default)]
 pub struct ChargeDisable {
     /// Time before the entity becomes affected by charges.
-    pub timer: f32,
+    pub timer: Timer,
+}
+
+/// Resource configuring collisions of physics bodies.
+/// Bodies are entities with [PhysicsMotion] and [HitBox].
+#[derive(Clone, Copy, Debug)]
+pub struct BodyCollisions {
+    /// Portion of the approaching speed kept after a collision.
+    /// 1.0 => perfectly elastic
+    /// 0.0 => perfectly inelastic
+    pub restitution: f32,
+}
+
+impl Default for BodyCollisions {
+    fn default() -> Self {
+        Self {
+            restitution: BODY_RESTITUTION,
+        }
+    }
 }
 
 /// Makes an entity deal knockback to other entities.
@@ -110,9 +217,79 @@ pub struct KnockbackDealer {
 //SYSTEM PART
 //-----------------------------------------------------------------------------
 
+/// Remembers the transforms of moving entities as their [PreviousTransform].
+/// Must run at the start of every simulation step.
+pub fn remember_transforms(world: &mut World, cmd: &mut CommandBuffer) {
+    for (entity, (pos, rotation, previous)) in world
+        .query_mut::<(&Position, Option<&Rotation>, Option<&mut PreviousTransform>)>()
+        .with::<hecs::Or<&LinearMotion, &PhysicsMotion>>()
+    {
+        let transform = PreviousTransform {
+            pos: vec2(pos.x, pos.y),
+            angle: rotation.map(|rotation| rotation.angle),
+        };
+        match previous {
+            Some(previous) => *previous = transform,
+            None => cmd.insert_one(entity, transform),
+        }
+    }
+}
+
+/// Moves entities to where they were `alpha` of the way through the last step.
+///
+/// Returns their current transforms, which must be put back by [restore_transforms] after rendering.
+/// Teleported entities are left where they are.
+pub fn interpolate_transforms(world: &mut World, alpha: f32) -> TransformStash {
+    let mut stash = Vec::new();
+    if alpha >= 1.0 {
+        return stash;
+    }
+    for (entity, (pos, rotation, previous)) in
+        world.query_mut::<(&mut Position, Option<&mut Rotation>, &PreviousTransform)>()
+    {
+        let current = vec2(pos.x, pos.y);
+        if current.distance(previous.pos) > MAX_INTERPOLATED_DISTANCE {
+            continue;
+        }
+        stash.push((entity, *pos, rotation.as_deref().copied()));
+
+        let lerped = previous.pos.lerp(current, alpha);
+        pos.x = lerped.x;
+        pos.y = lerped.y;
+        if let (Some(rotation), Some(angle)) = (rotation, previous.angle) {
+            //angles wrapping around are not interpolated
+            if (rotation.angle - angle).abs() < PI {
+                rotation.angle = angle + (rotation.angle - angle) * alpha;
+            }
+        }
+    }
+    stash
+}
+
+/// Puts back the current transforms taken by [interpolate_transforms].
+pub fn restore_transforms(world: &mut World, stash: TransformStash) {
+    for (entity, pos, rotation) in stash {
+        if let Ok(mut current) = world.get::<&mut Position>(entity) {
+            *current = pos;
+        }
+        if let (Some(rotation), Ok(mut current)) = (rotation, world.get::<&mut Rotation>(entity)) {
+            *current = rotation;
+        }
+    }
+}
+
 /// Add [LinearMotion], [LinearTorgue] and [PhysicsMotion]
 /// velocities to entities' positions and/or rotations.
-pub fn apply_motion(world: &mut World, dt: f32) {
+///
+/// [LinearTorgue] is purely cosmetic, so it is slowed down when
+/// the user prefers reduced motion.
+pub fn apply_motion(world: &mut World, settings: &Settings, dt: f32) {
+    let torgue_mult = if settings.reduced_motion {
+        REDUCED_MOTION_TORGUE
+    } else {
+        1.0
+    };
+
     //apply linear motion
     for (_, (linear, pos)) in world.query_mut::<(&LinearMotion, &mut Position)>() {
         pos.x += linear.vel.x * dt;
@@ -121,7 +298,7 @@ pub fn apply_motion(world: &mut World, dt: f32) {
 
     //apply linear torgue
     for (_, (torgue, rotation)) in world.query_mut::<(&LinearTorgue, &mut Rotation)>() {
-        rotation.angle += torgue.speed * dt;
+        rotation.angle += torgue.speed * torgue_mult * dt;
     }
 
     //apply physics motion
@@ -133,7 +310,10 @@ pub fn apply_motion(world: &mut World, dt: f32) {
 
 /// Advance physics simulation.
 /// Handles the logic of [PhysicsDamping], [MaxVelocity] and charges.
-pub fn apply_physics(world: &mut World, dt: f32) {
+/// Charge forces applied on entities with [NetForce] are summed up into it.
+/// # Arguments
+/// * `heatmap` - records charge forces applied to its target, if present
+pub fn apply_physics(world: &mut World, dt: f32, mut heatmap: Option<&mut ForceHeatmap>) {
     //apply damping
     for (_, (physics, damping)) in world.query_mut::<(&mut PhysicsMotion, &PhysicsDamping)>() {
         //first mul factor
@@ -153,27 +333,51 @@ pub fn apply_physics(world: &mut World, dt: f32) {
         }
     }
 
-    //apply all charges O(n^2)
+    //gather all charge sources into a grid
+    //only sources whose `no_radius` reaches the receiver are tested
+    let senders: Vec<_> = world
+        .query::<(&ChargeSender, &Position)>()
+        .iter()
+        .map(|(id, (charge, pos))| (id, *charge, *pos))
+        .collect();
+    let max_radius = senders
+        .iter()
+        .map(|(_, charge, _)| charge.no_radius)
+        .fold(0.0, f32::max);
+    let mut grid = SpatialGrid::new(max_radius);
+    for (index, (_, _, pos)) in senders.iter().enumerate() {
+        grid.insert(vec2(pos.x, pos.y), index);
+    }
+
+    //forget forces of the previous step
+    for (_, net) in world.query_mut::<&mut NetForce>() {
+        net.force = Vec2::ZERO;
+    }
+
+    let mut candidates = Vec::new();
     //iterate through all charge receivers
-    for (a_ind, (a_charge, a_physics, a_pos, a_disable)) in world
+    for (a_ind, (a_charge, a_physics, a_pos, a_disable, mut a_net)) in world
         .query::<(
             &ChargeReceiver,
             &mut PhysicsMotion,
             &Position,
             Option<&mut ChargeDisable>,
+            Option<&mut NetForce>,
         )>()
         .into_iter()
     {
         //is charge receiving disabled?
         if let Some(disabler) = a_disable {
-            disabler.timer -= dt;
-            if disabler.timer > 0.0 {
+            disabler.timer.tick(dt);
+            if !disabler.timer.finished() {
                 continue;
             }
         }
 
-        //apply all charge sources
-        for (b_ind, (b_charge, b_pos)) in world.query::<(&ChargeSender, &Position)>().into_iter() {
+        //apply all nearby charge sources, forces are summed so their order does not matter
+        grid.query_unsorted(vec2(a_pos.x, a_pos.y), max_radius, &mut candidates);
+        for &index in &candidates {
+            let (b_ind, b_charge, b_pos) = senders[index];
             //ignore same entities
             if a_ind == b_ind {
                 continue;
@@ -199,6 +403,15 @@ pub fn apply_physics(world: &mut World, dt: f32) {
             //apply force
             let normal = vec2(a_pos.x - b_pos.x, a_pos.y - b_pos.y) / distance;
             a_physics.apply_force(a_charge.multiplier * force * normal, dt);
+            if let Some(net) = a_net.as_deref_mut() {
+                net.force += a_charge.multiplier * force * normal;
+            }
+            //report forces for balancing
+            if let Some(heatmap) = heatmap.as_deref_mut() {
+                if heatmap.target == Some(a_ind) {
+                    heatmap.record(vec2(a_pos.x, a_pos.y), a_charge.multiplier * force);
+                }
+            }
         }
     }
 }
@@ -206,7 +419,13 @@ pub fn apply_physics(world: &mut World, dt: f32) {
 /// Applies knockback dealt by [KnockbackDealer].
 ///
 /// Only affects entities with [PhysicsMotion].
+/// Victims with [KnockbackReflect] push the dealer away instead.
+/// With a [KnockbackMemory] in the world, the same pair is only knocked back once per its cooldown.
 pub fn apply_knockback(world: &mut World, event: &mut World, assets: &AssetManager) {
+    let mut recent = Vec::new();
+    //remember recent knockbacks
+    let mut memory_query = world.query::<&mut KnockbackMemory>();
+    let mut memory = memory_query.iter().next().map(|(_, memory)| memory);
     //for all events
     for (_, event) in event.query_mut::<&HitEvent>() {
         //is the producer equal to the consumer?
@@ -231,24 +450,297 @@ pub fn apply_knockback(world: &mut World, event: &mut World, assets: &AssetManag
             continue;
         };
 
-        //get required components from the victim
-        let Some(mut victim_vel) = victim_ent.get::<&mut PhysicsMotion>() else {
+        let Some(victim_pos) = victim_ent.get::<&Position>() else {
             continue;
         };
+        //ignore pairs knocked back recently
+        if let Some(memory) = memory.as_deref_mut() {
+            if !memory.knock(event.who, event.by) {
+                continue;
+            }
+        }
+        //reflect it onto the dealer
+        if victim_ent.has::<KnockbackReflect>() {
+            let Some(mut deal_vel) = deal_ent.get::<&mut PhysicsMotion>() else {
+                continue;
+            };
+            let normal =
+                vec2(deal_pos.x - victim_pos.x, deal_pos.y - victim_pos.y).normalize_or_zero();
+            deal_vel.apply_force(normal * deal.force, 1.0);
+            assets
+                .audio()
+                .play_at("knockback", 0.5, vec2(victim_pos.x, victim_pos.y));
+            continue;
+        }
 
-        let Some(victim_pos) = victim_ent.get::<&Position>() else {
+        //get required components from the victim
+        let Some(mut victim_vel) = victim_ent.get::<&mut PhysicsMotion>() else {
             continue;
         };
         //deal force
         let normal = vec2(victim_pos.x - deal_pos.x, victim_pos.y - deal_pos.y).normalize_or_zero();
+        let before = victim_vel.vel;
         victim_vel.apply_force(normal * deal.force, 1.0);
+        recent.push((event.who, victim_vel.vel - before));
         //play sound to knockback
-        audio::play_sound(
-            assets.get_sound("knockback").unwrap(),
-            PlaySoundParams {
-                looped: false,
-                volume: 0.5,
-            },
+        assets
+            .audio()
+            .play_at("knockback", 0.5, vec2(victim_pos.x, victim_pos.y));
+    }
+    drop(memory_query);
+    //remember it, so that it can be cancelled
+    for (victim, vel) in recent {
+        let _ = world.insert_one(victim, RecentKnockback { vel, since: 0.0 });
+    }
+}
+
+/// Ages [RecentKnockback]s and removes those that can no longer be cancelled.
+/// Forgets pairs of [KnockbackMemory] whose cooldown is over or whose entities despawned.
+pub fn age_knockbacks(world: &mut World, cmd: &mut CommandBuffer, dt: f32) {
+    for (id, recent) in world.query_mut::<&mut RecentKnockback>() {
+        recent.since += dt;
+        if recent.since > KNOCKBACK_CANCEL_WINDOW {
+            cmd.remove_one::<RecentKnockback>(id);
+        }
+    }
+
+    for (_, memory) in world.query::<&mut KnockbackMemory>().into_iter() {
+        let cooldown = memory.cooldown;
+        memory.pairs.retain(|&(victim, dealer), since| {
+            *since += dt;
+            *since < cooldown && world.contains(victim) && world.contains(dealer)
+        });
+    }
+}
+
+/// Resolves collisions between physics bodies (entities with [PhysicsMotion] and [HitBox]).
+///
+/// Overlapping bodies are separated along the contact normal and exchange momentum
+/// according to their masses and the restitution of [BodyCollisions].
+/// Entities moving by [LinearMotion] and entities in [SpawnGrace] are ignored.
+pub fn resolve_body_collisions(world: &mut World) {
+    //get restitution
+    let restitution = world
+        .query::<&BodyCollisions>()
+        .iter()
+        .next()
+        .map(|(_, config)| config.restitution)
+        .unwrap_or(BODY_RESTITUTION);
+
+    //gather all bodies
+    let mut query = world
+        .query::<(&mut Position, &mut PhysicsMotion, &HitBox)>()
+        .without::<&LinearMotion>()
+        .without::<&SpawnGrace>();
+    let mut bodies: Vec<_> = query.iter().map(|(_, body)| body).collect();
+    //put them into the grid
+    let max_radius = bodies
+        .iter()
+        .map(|(_, _, hit_box)| hit_box.radius)
+        .fold(0.0, f32::max);
+    let mut grid = SpatialGrid::new(max_radius * 2.0);
+    for (index, (pos, _, _)) in bodies.iter().enumerate() {
+        grid.insert(vec2(pos.x, pos.y), index);
+    }
+
+    let mut candidates = Vec::new();
+    for a_ind in 0..bodies.len() {
+        let a_center = vec2(bodies[a_ind].0.x, bodies[a_ind].0.y);
+        grid.query(
+            a_center,
+            bodies[a_ind].2.radius + max_radius,
+            &mut candidates,
         );
+        for &b_ind in &candidates {
+            //resolve every pair only once
+            if b_ind <= a_ind {
+                continue;
+            }
+            let (head, tail) = bodies.split_at_mut(b_ind);
+            let (a_pos, a_physics, a_box) = &mut head[a_ind];
+            let (b_pos, b_physics, b_box) = &mut tail[0];
+
+            //are they overlapping?
+            let delta = vec2(b_pos.x - a_pos.x, b_pos.y - a_pos.y);
+            let distance = delta.length();
+            let overlap = a_box.radius + b_box.radius - distance;
+            //distance to small to safely get normal
+            if overlap <= 0.0 || distance <= 0.1 {
+                continue;
+            }
+            let normal = delta / distance;
+
+            //lighter bodies move more
+            let a_inv_mass = 1.0 / a_physics.mass;
+            let b_inv_mass = 1.0 / b_physics.mass;
+            let inv_mass = a_inv_mass + b_inv_mass;
+            if !inv_mass.is_finite() || inv_mass <= 0.0 {
+                continue;
+            }
+
+            //separate them
+            let a_shift = normal * overlap * a_inv_mass / inv_mass;
+            let b_shift = normal * overlap * b_inv_mass / inv_mass;
+            a_pos.x -= a_shift.x;
+            a_pos.y -= a_shift.y;
+            b_pos.x += b_shift.x;
+            b_pos.y += b_shift.y;
+
+            //exchange momentum, unless already moving apart
+            let approach = (b_physics.vel - a_physics.vel).dot(normal);
+            if approach >= 0.0 {
+                continue;
+            }
+            let impulse = -(1.0 + restitution) * approach / inv_mass;
+            a_physics.vel -= normal * impulse * a_inv_mass;
+            b_physics.vel += normal * impulse * b_inv_mass;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic::arena::Arena;
+
+    /// Returns the charge force `sender` at `sender_pos` applies on a receiver at `pos`,
+    /// tested against every sender without the grid.
+    fn brute_force(pos: Vec2, sender_pos: Vec2, sender: &ChargeSender) -> Vec2 {
+        let distance = pos.distance(sender_pos);
+        if distance <= 0.1 || distance >= sender.no_radius {
+            return Vec2::ZERO;
+        }
+        let force = if distance > sender.full_radius {
+            (sender.no_radius - distance) / (sender.no_radius - sender.full_radius) * sender.force
+        } else {
+            sender.force
+        };
+        (pos - sender_pos) / distance * force
+    }
+
+    #[test]
+    fn grid_forces_match_brute_force() {
+        let mut rng = fastrand::Rng::with_seed(1503);
+        for _ in 0..50 {
+            let mut world = World::new();
+            let mut senders = Vec::new();
+            let mut receivers = Vec::new();
+            for _ in 0..rng.usize(1..80) {
+                let pos = vec2(rng.f32() * 1280.0, rng.f32() * 720.0);
+                let entity = world.spawn((Position { x: pos.x, y: pos.y },));
+                //senders of all sizes, some of them receivers too
+                if rng.bool() {
+                    let full_radius = rng.f32() * 100.0;
+                    let sender = ChargeSender {
+                        force: rng.f32() * 400.0 - 200.0,
+                        full_radius,
+                        no_radius: full_radius + 1.0 + rng.f32() * 300.0,
+                    };
+                    world.insert_one(entity, sender).unwrap();
+                    senders.push((entity, pos, sender));
+                }
+                if rng.bool() {
+                    let receiver = ChargeReceiver {
+                        multiplier: rng.f32() * 2.0 - 1.0,
+                    };
+                    world
+                        .insert(
+                            entity,
+                            (
+                                receiver,
+                                PhysicsMotion {
+                                    vel: Vec2::ZERO,
+                                    mass: 1.0,
+                                },
+                                NetForce::default(),
+                            ),
+                        )
+                        .unwrap();
+                    receivers.push((entity, pos, receiver));
+                }
+            }
+
+            apply_physics(&mut world, 1.0 / 120.0, None);
+
+            for (entity, pos, receiver) in receivers {
+                let expected = senders
+                    .iter()
+                    .filter(|(sender_id, _, _)| *sender_id != entity)
+                    .map(|(_, sender_pos, sender)| brute_force(pos, *sender_pos, sender))
+                    .fold(Vec2::ZERO, |sum, force| sum + force)
+                    * receiver.multiplier;
+                let net = world.get::<&NetForce>(entity).unwrap().force;
+                assert!(
+                    net.distance(expected) <= 1e-3 * expected.length().max(1.0),
+                    "grid force {net} differs from brute force {expected}"
+                );
+            }
+        }
+    }
+
+    /// Times the charge forces of 200 charged asteroids, with the grid and without it.
+    /// Run with `cargo test --release -- --ignored --nocapture charge_grid_benchmark`.
+    #[test]
+    #[ignore]
+    fn charge_grid_benchmark() {
+        const STEPS: u32 = 500;
+        let sender = ChargeSender {
+            force: 750.0,
+            full_radius: 200.0,
+            no_radius: 350.0,
+        };
+        for arena in [Arena::STANDARD, Arena::LARGE] {
+            let mut rng = fastrand::Rng::with_seed(1504);
+            let mut world = World::new();
+            let positions: Vec<_> = (0..200)
+                .map(|_| vec2(rng.f32() * arena.width, rng.f32() * arena.height))
+                .collect();
+            for pos in &positions {
+                world.spawn((
+                    Position { x: pos.x, y: pos.y },
+                    sender,
+                    ChargeReceiver { multiplier: 1.0 },
+                    PhysicsMotion {
+                        vel: Vec2::ZERO,
+                        mass: 1.0,
+                    },
+                ));
+            }
+
+            let start = std::time::Instant::now();
+            for _ in 0..STEPS {
+                apply_physics(&mut world, 1.0 / 120.0, None);
+            }
+            let grid = start.elapsed().as_secs_f64() / STEPS as f64;
+
+            //every sender tested against every receiver, as before the grid
+            let start = std::time::Instant::now();
+            for _ in 0..STEPS {
+                let senders: Vec<_> = world
+                    .query::<(&ChargeSender, &Position)>()
+                    .iter()
+                    .map(|(id, (charge, pos))| (id, *charge, vec2(pos.x, pos.y)))
+                    .collect();
+                for (id, (receiver, physics, pos)) in
+                    world.query_mut::<(&ChargeReceiver, &mut PhysicsMotion, &Position)>()
+                {
+                    for (sender_id, sender, sender_pos) in &senders {
+                        if *sender_id != id {
+                            let force = brute_force(vec2(pos.x, pos.y), *sender_pos, sender);
+                            physics.apply_force(receiver.multiplier * force, 1.0 / 120.0);
+                        }
+                    }
+                }
+            }
+            let brute = start.elapsed().as_secs_f64() / STEPS as f64;
+
+            println!(
+                "{}x{} arena: grid {:.1} us, brute force {:.1} us per step",
+                arena.width,
+                arena.height,
+                grid * 1e6,
+                brute * 1e6
+            );
+        }
     }
 }
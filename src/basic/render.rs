@@ -1,13 +1,59 @@
 //! Rendering objects and logic.
 
 use enum_dispatch::enum_dispatch;
-use hecs::World;
+use hecs::{CommandBuffer, World};
 use macroquad::{
-    audio::{load_sound, Sound},
+    audio::{load_sound, load_sound_from_bytes},
     prelude::*,
 };
 
-use super::{Position, Rotation};
+use crate::{persist::Settings, world_camera, SPACE_HEIGHT, SPACE_WIDTH};
+
+use super::{
+    arena::{letterbox, rect_camera, target_camera},
+    audio::AudioManager,
+    embed,
+    motion::ChargeSender,
+    HitBox, Position, Rotation, Timer,
+};
+
+/// Color of the background of the world.
+pub const BACKGROUND_COLOR: Color = Color::new(0.0, 0.05, 0.1, 1.0);
+
+/// Color of positive charges.
+const POSITIVE_COLOR: Color = RED;
+/// Color of negative charges.
+const NEGATIVE_COLOR: Color = Color::new(0.0, 1.0, 1.0, 1.0);
+/// Color of neutral charges.
+const NEUTRAL_COLOR: Color = GREEN;
+/// Color of positive charges in colorblind mode.
+const COLORBLIND_POSITIVE_COLOR: Color = Color::new(0.9, 0.6, 0.0, 1.0);
+/// Color of negative charges in colorblind mode.
+const COLORBLIND_NEGATIVE_COLOR: Color = Color::new(0.35, 0.7, 0.9, 1.0);
+/// Half of the length of the strokes of charge glyphs.
+const GLYPH_SIZE: f32 = 5.0;
+/// Thickness of the strokes of charge glyphs.
+const GLYPH_THICKNESS: f32 = 2.0;
+/// Gap between an entity's [HitBox] and its charge glyph.
+const GLYPH_MARGIN: f32 = 6.0;
+/// Radius used for entities without a [HitBox] when placing their charge glyph.
+const GLYPH_DEFAULT_RADIUS: f32 = 10.0;
+
+/// Distinct texture IDs requested by [Sprite]s which were never loaded.
+/// Only tracked in debug builds.
+static MISSING_TEXTURES: std::sync::Mutex<Vec<&'static str>> = std::sync::Mutex::new(Vec::new());
+
+/// Seconds between checks of the texture files for changes, only in debug builds.
+const TEXTURE_POLL_INTERVAL: f64 = 1.0;
+
+/// File a texture was loaded from, to hot-reload it when it changes.
+#[derive(Clone, Debug)]
+struct TextureSource {
+    /// Path of the texture file.
+    path: String,
+    /// Modification time of the file when it was loaded, [None] if unknown.
+    modified: Option<std::time::SystemTime>,
+}
 
 /// Manager of all the used assets.
 /// Stores textures, fonts and sounds in one place so that they
@@ -18,12 +64,42 @@ pub struct AssetManager {
     textures: fnv::FnvHashMap<&'static str, Texture2D>,
     /// Font storage
     fonts: fnv::FnvHashMap<&'static str, Font>,
-    /// Sound storage
-    sound: fnv::FnvHashMap<&'static str, Sound>,
+    /// Sound storage and playback
+    audio: AudioManager,
+    /// Material drawing [HitFlash]es, [None] if it could not be created.
+    flash: Option<Material>,
+    /// Files of the textures by their ids, only tracked in debug builds.
+    texture_sources: fnv::FnvHashMap<&'static str, TextureSource>,
+    /// Time of the last check of the texture files for changes.
+    last_texture_poll: f64,
 }
 
 impl AssetManager {
+    /// Applies the user's volume preferences.
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.audio.apply_settings(settings);
+    }
+
+    /// Creates the material drawing [HitFlash]es.
+    /// Without it, hit flashes are not shown.
+    pub fn load_flash_material(&mut self) {
+        self.flash = load_material(
+            ShaderSource::Glsl {
+                vertex: CRT_VERTEX_SHADER,
+                fragment: FLASH_FRAGMENT_SHADER,
+            },
+            MaterialParams::default(),
+        )
+        .ok();
+    }
+
+    /// Returns the manager playing the loaded sounds.
+    pub fn audio(&self) -> &AudioManager {
+        &self.audio
+    }
+
     /// Loads a texture from texture file (.png,...) into [AssetManager].
+    /// Falls back to the [embed]ded texture when the file is missing.
     ///
     /// Returns an error when something went bad during loading.
     /// # Arguments
@@ -34,13 +110,59 @@ impl AssetManager {
         id: &'static str,
         path: &str,
     ) -> Result<(), macroquad::Error> {
-        //load it
-        let texture = load_texture(path).await?;
+        //load it, the file on disk takes priority
+        let texture = match (load_texture(path).await, embed::get(path)) {
+            (Ok(texture), _) => texture,
+            (Err(_), Some(bytes)) => Texture2D::from_file_with_format(bytes, None),
+            (Err(err), None) => return Err(err),
+        };
+        //remember its file to hot-reload it
+        if cfg!(debug_assertions) {
+            self.texture_sources.insert(
+                id,
+                TextureSource {
+                    path: path.to_owned(),
+                    modified: modified_time(path),
+                },
+            );
+        }
         //save it
         self.textures.insert(id, texture);
         Ok(())
     }
 
+    /// Reloads the textures whose files changed since they were loaded,
+    /// so that sprites show the new pixels without restarting.
+    /// The files are checked every [TEXTURE_POLL_INTERVAL] seconds,
+    /// pressing `reload_key` reloads all the textures at once.
+    /// Does nothing in release builds.
+    /// # Arguments
+    /// * `reload_key` - key forcing the reload
+    pub async fn reload_dirty(&mut self, reload_key: KeyCode) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+        let forced = is_key_pressed(reload_key);
+        let now = get_time();
+        if !forced && now - self.last_texture_poll < TEXTURE_POLL_INTERVAL {
+            return;
+        }
+        self.last_texture_poll = now;
+
+        let dirty: Vec<_> = self
+            .texture_sources
+            .iter()
+            .filter(|(_, source)| forced || modified_time(&source.path) != source.modified)
+            .map(|(id, source)| (*id, source.path.clone()))
+            .collect();
+        for (id, path) in dirty {
+            //the old texture is kept when the new one is broken
+            if let Err(err) = self.load_texture(id, &path).await {
+                eprintln!("Texture `{path}` could not be reloaded: {err}");
+            }
+        }
+    }
+
     /// Gets a texture from storage.
     ///
     /// Returns [None] if the texture is not present.
@@ -50,7 +172,18 @@ impl AssetManager {
         self.textures.get(id)
     }
 
+    /// Returns all the texture IDs from `ids` which are not loaded.
+    /// # Arguments
+    /// * `ids` - texture ids to verify
+    pub fn missing_textures<'a>(
+        &'a self,
+        ids: impl IntoIterator<Item = &'static str> + 'a,
+    ) -> impl Iterator<Item = &'static str> + 'a {
+        ids.into_iter().filter(|id| !self.textures.contains_key(id))
+    }
+
     /// Loads a font from font file (.ttf) into [AssetManager].
+    /// Falls back to the [embed]ded font when the file is missing.
     ///
     /// Returns an error when something went bad during loading.
     /// # Arguments
@@ -61,8 +194,12 @@ impl AssetManager {
         id: &'static str,
         path: &str,
     ) -> Result<(), macroquad::Error> {
-        //load it
-        let font = load_ttf_font(path).await?;
+        //load it, the file on disk takes priority
+        let font = match (load_ttf_font(path).await, embed::get(path)) {
+            (Ok(font), _) => font,
+            (Err(_), Some(bytes)) => load_ttf_font_from_bytes(bytes)?,
+            (Err(err), None) => return Err(err),
+        };
         //save it
         self.fonts.insert(id, font);
         Ok(())
@@ -78,6 +215,7 @@ impl AssetManager {
     }
 
     /// Loads a sound from sound file (.wav,...) into [AssetManager].
+    /// Falls back to the [embed]ded sound when the file is missing.
     ///
     /// Returns an error when something went bad during loading.
     /// # Arguments
@@ -88,20 +226,202 @@ impl AssetManager {
         id: &'static str,
         path: &str,
     ) -> Result<(), macroquad::Error> {
-        //load it
-        let sound = load_sound(path).await?;
+        //load it, the file on disk takes priority
+        let sound = match (load_sound(path).await, embed::get(path)) {
+            (Ok(sound), _) => sound,
+            (Err(_), Some(bytes)) => load_sound_from_bytes(bytes).await?,
+            (Err(err), None) => return Err(err),
+        };
         //save it
-        self.sound.insert(id, sound);
+        self.audio.insert(id, sound);
         Ok(())
     }
+}
 
-    /// Gets a sound from storage.
-    ///
-    /// Returns [None] if the sound is not present.
+/// Vertex shader of the CRT filter and the [HitFlash].
+const CRT_VERTEX_SHADER: &str = "#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+
+varying lowp vec2 uv;
+varying lowp vec4 color;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    color = color0 / 255.0;
+    uv = texcoord;
+}
+";
+
+/// Fragment shader of the CRT filter.
+/// Adds scanlines, a slight vignette and a mild chromatic offset.
+const CRT_FRAGMENT_SHADER: &str = "#version 100
+precision mediump float;
+
+varying vec4 color;
+varying vec2 uv;
+
+uniform sampler2D Texture;
+
+void main() {
+    //chromatic offset growing towards the edges
+    vec2 offset = (uv - 0.5) * 0.004;
+    vec3 res = vec3(
+        texture2D(Texture, uv + offset).r,
+        texture2D(Texture, uv).g,
+        texture2D(Texture, uv - offset).b
+    ) * color.rgb;
+    //scanlines
+    res *= 0.9 + 0.1 * cos(uv.y * 720.0 * 3.14159);
+    //vignette
+    float vignette = uv.x * uv.y * (1.0 - uv.x) * (1.0 - uv.y);
+    res *= clamp(pow(16.0 * vignette, 0.25), 0.0, 1.0);
+    gl_FragColor = vec4(res, 1.0);
+}
+";
+
+/// Fragment shader of the [HitFlash].
+/// Fills the texture's silhouette with the tint.
+const FLASH_FRAGMENT_SHADER: &str = "#version 100
+precision mediump float;
+
+varying vec4 color;
+varying vec2 uv;
+
+uniform sampler2D Texture;
+
+void main() {
+    gl_FragColor = vec4(color.rgb, color.a * texture2D(Texture, uv).a);
+}
+";
+
+/// Time a [HitFlash] lasts.
+const HIT_FLASH_TIME: f32 = 0.1;
+
+/// Flashes the entity's [Sprite] after it got hit.
+///
+/// The flash is drawn over the sprite when rendering,
+/// so that the tint set by other systems stays untouched.
+#[derive(Clone, Copy, Debug)]
+pub struct HitFlash {
+    /// Time before the flash fades out.
+    pub timer: Timer,
+    /// Color of the flash.
+    pub color: Color,
+}
+
+impl HitFlash {
+    /// Creates a flash that has just started.
     /// # Arguments
-    /// * `id` - id passed when loading the sound
-    pub fn get_sound(&self, id: &'static str) -> Option<&Sound> {
-        self.sound.get(id)
+    /// * `color` - color of the flash
+    pub fn new(color: Color) -> Self {
+        Self {
+            timer: Timer::new(HIT_FLASH_TIME, false),
+            color,
+        }
+    }
+
+    /// Returns the strength of the flash, from 1.0 right after the hit down to 0.0.
+    pub fn strength(&self) -> f32 {
+        self.timer.remaining() / self.timer.duration
+    }
+}
+
+/// Canvas the world is rendered into at a reduced resolution
+/// or with the CRT filter.
+/// It is then stretched over the whole screen.
+#[derive(Default)]
+pub struct WorldCanvas {
+    /// Render target of the scaled resolution.
+    target: Option<RenderTarget>,
+    /// Scale of the current render target.
+    scale: f32,
+    /// Is the world currently rendered into the canvas?
+    active: bool,
+    /// Material of the CRT filter, created on first use.
+    crt: Option<Material>,
+    /// Has creating the CRT material failed?
+    crt_failed: bool,
+    /// Is the CRT filter applied this frame?
+    crt_active: bool,
+}
+
+impl WorldCanvas {
+    /// Can the CRT filter be used on this platform?
+    /// Creates its material on the first call.
+    pub fn crt_supported(&mut self) -> bool {
+        if self.crt.is_none() && !self.crt_failed {
+            match load_material(
+                ShaderSource::Glsl {
+                    vertex: CRT_VERTEX_SHADER,
+                    fragment: CRT_FRAGMENT_SHADER,
+                },
+                MaterialParams::default(),
+            ) {
+                Ok(material) => self.crt = Some(material),
+                Err(_) => self.crt_failed = true,
+            }
+        }
+        self.crt.is_some()
+    }
+
+    /// Redirects all rendering of `view` into the canvas.
+    /// Scales of 1.0 and more without the CRT filter render directly onto the screen.
+    /// # Arguments
+    /// * `scale` - scale of the resolution
+    /// * `crt` - should the CRT filter be applied, ignored when unsupported
+    /// * `view` - part of the world to render
+    pub fn begin(&mut self, scale: f32, crt: bool, view: Rect) {
+        self.crt_active = crt && self.crt_supported();
+        let scale = scale.min(1.0);
+        if scale >= 1.0 && !self.crt_active {
+            set_camera(&rect_camera(view));
+            return;
+        }
+        //recreate the target on scale change
+        if self.target.is_none() || self.scale != scale {
+            let target = render_target((SPACE_WIDTH * scale) as u32, (SPACE_HEIGHT * scale) as u32);
+            target.texture.set_filter(FilterMode::Linear);
+            self.target = Some(target);
+            self.scale = scale;
+        }
+        //render into it, the target is of the same aspect ratio as the space
+        let mut camera = target_camera(view);
+        camera.render_target = self.target.clone();
+        set_camera(&camera);
+        clear_background(BACKGROUND_COLOR);
+        self.active = true;
+    }
+
+    /// Stops rendering into the canvas and stretches it over the screen.
+    /// Restores the screen camera for rendering UI.
+    pub fn end(&mut self) {
+        set_camera(&world_camera());
+        if !self.active {
+            return;
+        }
+        self.active = false;
+        //draw it onto the screen, filtered when asked to
+        if let Some(target) = &self.target {
+            if let (true, Some(material)) = (self.crt_active, &self.crt) {
+                gl_use_material(material);
+            }
+            draw_texture_ex(
+                &target.texture,
+                0.0,
+                0.0,
+                WHITE,
+                DrawTextureParams {
+                    dest_size: Some(vec2(SPACE_WIDTH, SPACE_HEIGHT)),
+                    ..Default::default()
+                },
+            );
+            gl_use_default_material();
+        }
     }
 }
 
@@ -192,6 +512,7 @@ impl Renderable for Sprite {
     fn render(&self, pos: &Position, rotation: Option<&Rotation>, assets: &AssetManager) {
         //fetch texture
         let Some(texture) = assets.get_texture(self.texture) else {
+            report_missing_texture(self.texture);
             return;
         };
         //render itself
@@ -216,6 +537,92 @@ impl Renderable for Sprite {
     }
 }
 
+/// Renders a frame of a texture atlas centered at entity's position.
+/// Frames are laid out horizontally in the atlas and cycle on their own.
+#[derive(Clone, Debug)]
+pub struct AnimatedSprite {
+    /// Texture ID of the atlas to render.
+    pub texture: &'static str,
+    /// Size of a single frame in the atlas.
+    pub frame_size: Vec2,
+    /// Amount of frames in the atlas.
+    pub frames: u32,
+    /// Frames shown per second.
+    pub fps: f32,
+    /// Time the animation has been playing for.
+    pub time: f32,
+    /// Scale of the frame.
+    pub scale: f32,
+    /// Tint of the frame.
+    /// This color gets multiplied with texture's.
+    pub color: Color,
+    /// Z index the frame should be rendered at.
+    pub z_index: i16,
+}
+
+impl AnimatedSprite {
+    /// Returns the index of the currently shown frame.
+    pub fn frame(&self) -> u32 {
+        (self.time * self.fps) as u32 % self.frames.max(1)
+    }
+}
+
+impl Renderable for AnimatedSprite {
+    fn render(&self, pos: &Position, rotation: Option<&Rotation>, assets: &AssetManager) {
+        //fetch texture
+        let Some(texture) = assets.get_texture(self.texture) else {
+            report_missing_texture(self.texture);
+            return;
+        };
+        //render the current frame
+        let size = self.frame_size * self.scale;
+        let source = Rect::new(
+            self.frame() as f32 * self.frame_size.x,
+            0.0,
+            self.frame_size.x,
+            self.frame_size.y,
+        );
+
+        draw_texture_ex(
+            texture,
+            pos.x - size.x / 2.0,
+            pos.y - size.y / 2.0,
+            self.color,
+            DrawTextureParams {
+                dest_size: Some(size),
+                source: Some(source),
+                rotation: rotation.map(|rot| rot.angle).unwrap_or(0.0),
+                ..Default::default()
+            },
+        );
+    }
+
+    fn z_index(&self) -> i16 {
+        self.z_index
+    }
+}
+
+/// Returns the modification time of the file at `path`, [None] if it is unknown.
+fn modified_time(path: &str) -> Option<std::time::SystemTime> {
+    std::fs::metadata(path).ok()?.modified().ok()
+}
+
+/// Reports a texture that is not loaded.
+/// Every texture is reported once and only in debug builds.
+fn report_missing_texture(texture: &'static str) {
+    if cfg!(debug_assertions) {
+        let mut missing = MISSING_TEXTURES.lock().unwrap();
+        if !missing.contains(&texture) {
+            missing.push(texture);
+            eprintln!(
+                "Sprite texture `{}` is not loaded ({} missing in total)",
+                texture,
+                missing.len()
+            );
+        }
+    }
+}
+
 //-----------------------------------------------------------------------------
 //TRAIT PART
 //-----------------------------------------------------------------------------
@@ -236,6 +643,34 @@ trait Renderable {
     }
 }
 
+/// Silhouette of a [Sprite] filled with the color of its [HitFlash].
+#[derive(Clone, Debug)]
+struct SpriteFlash {
+    /// Sprite the flash is drawn over.
+    sprite: Sprite,
+    /// Color of the flash, its alpha is the strength.
+    color: Color,
+}
+
+impl Renderable for SpriteFlash {
+    fn render(&self, pos: &Position, rotation: Option<&Rotation>, assets: &AssetManager) {
+        let Some(material) = &assets.flash else {
+            return;
+        };
+        gl_use_material(material);
+        Sprite {
+            color: self.color,
+            ..self.sprite.clone()
+        }
+        .render(pos, rotation, assets);
+        gl_use_default_material();
+    }
+
+    fn z_index(&self) -> i16 {
+        self.sprite.z_index
+    }
+}
+
 /// Enumerator of all the possible Renderable objects this application wants
 /// to render.
 #[enum_dispatch(Renderable)]
@@ -243,13 +678,50 @@ enum RenderJobs {
     Rectangle,
     Circle,
     Sprite,
+    AnimatedSprite,
+    SpriteFlash,
+}
+
+/// Returns the color representing `charge`.
+/// # Arguments
+/// * `charge` - sign of the charge, 1, 0 or -1
+/// * `colorblind` - should the colorblind-safe palette be used?
+pub fn charge_color(charge: i8, colorblind: bool) -> Color {
+    match (charge.signum(), colorblind) {
+        (1, false) => POSITIVE_COLOR,
+        (1, true) => COLORBLIND_POSITIVE_COLOR,
+        (-1, false) => NEGATIVE_COLOR,
+        (-1, true) => COLORBLIND_NEGATIVE_COLOR,
+        _ => NEUTRAL_COLOR,
+    }
 }
 
 //-----------------------------------------------------------------------------
 //SYSTEM PART
 //-----------------------------------------------------------------------------
 
-/// Renders [Rectangle]s, [Circle]s and [Sprite]s on the screen.
+/// Fades out [HitFlash]es and removes the finished ones.
+pub fn tick_hit_flashes(world: &mut World, cmd: &mut CommandBuffer, dt: f32) {
+    for (id, flash) in world.query_mut::<&mut HitFlash>() {
+        flash.timer.tick(dt);
+        if flash.timer.finished() {
+            cmd.remove_one::<HitFlash>(id);
+        }
+    }
+}
+
+/// Advances the animations of [AnimatedSprite]s.
+pub fn advance_animations(world: &mut World, dt: f32) {
+    for (_, sprite) in world.query_mut::<&mut AnimatedSprite>() {
+        sprite.time += dt;
+        //keep the time within one cycle
+        if sprite.fps > 0.0 {
+            sprite.time %= sprite.frames as f32 / sprite.fps;
+        }
+    }
+}
+
+/// Renders [Rectangle]s, [Circle]s, [Sprite]s and [AnimatedSprite]s on the screen.
 pub fn render_all(world: &mut World, assets: &AssetManager) {
     //gather all render jobs
     //circles
@@ -265,17 +737,83 @@ pub fn render_all(world: &mut World, assets: &AssetManager) {
             .into_iter()
             .map(|(_, (c, p, r))| (Into::<RenderJobs>::into(*c), *p, r.copied())),
     );
-    //sprites
+    //sprites, flashed ones are followed by their flash
+    for (_, (sprite, pos, rot, flash)) in
+        world.query_mut::<(&Sprite, &Position, Option<&Rotation>, Option<&HitFlash>)>()
+    {
+        jobs.push((sprite.clone().into(), *pos, rot.copied()));
+        if let Some(flash) = flash {
+            let mut color = flash.color;
+            color.a *= flash.strength();
+            let flash = SpriteFlash {
+                sprite: sprite.clone(),
+                color,
+            };
+            jobs.push((flash.into(), *pos, rot.copied()));
+        }
+    }
+    //animated sprites
     jobs.extend(
         world
-            .query_mut::<(&Sprite, &Position, Option<&Rotation>)>()
+            .query_mut::<(&AnimatedSprite, &Position, Option<&Rotation>)>()
             .into_iter()
             .map(|(_, (c, p, r))| (Into::<RenderJobs>::into(c.clone()), *p, r.copied())),
     );
-    //sort them by z_index
-    jobs.sort_unstable_by_key(|a| a.0.z_index());
+    //sort them by z_index, keeping flashes above their sprites
+    jobs.sort_by_key(|a| a.0.z_index());
     //render all of them
     for job in jobs {
         job.0.render(&job.1, job.2.as_ref(), assets);
     }
 }
+
+/// Draws a plus or minus glyph above every charged entity with a [ChargeSender].
+///
+/// Should be called after [render_all], so that no sprite hides the glyphs.
+pub fn render_charge_glyphs(world: &mut World) {
+    for (_, (sender, pos, hit_box)) in
+        world.query_mut::<(&ChargeSender, &Position, Option<&HitBox>)>()
+    {
+        if sender.force == 0.0 {
+            continue;
+        }
+        let radius = hit_box.map_or(GLYPH_DEFAULT_RADIUS, |hit_box| hit_box.radius);
+        let center = vec2(pos.x, pos.y - radius - GLYPH_MARGIN - GLYPH_SIZE);
+        //outline first, then the glyph itself
+        for (color, extra) in [(BLACK, GLYPH_THICKNESS), (WHITE, 0.0)] {
+            let size = GLYPH_SIZE + extra / 2.0;
+            let thickness = GLYPH_THICKNESS + extra;
+            draw_line(
+                center.x - size,
+                center.y,
+                center.x + size,
+                center.y,
+                thickness,
+                color,
+            );
+            if sender.force > 0.0 {
+                draw_line(
+                    center.x,
+                    center.y - size,
+                    center.x,
+                    center.y + size,
+                    thickness,
+                    color,
+                );
+            }
+        }
+    }
+}
+
+/// Covers everything outside of the space with black bars.
+/// Expects the [world_camera] to be set.
+pub fn render_letterbox() {
+    let space = Rect::new(0.0, 0.0, SPACE_WIDTH, SPACE_HEIGHT);
+    let visible = letterbox(space);
+    //left and right
+    draw_rectangle(visible.x, visible.y, -visible.x, visible.h, BLACK);
+    draw_rectangle(SPACE_WIDTH, visible.y, -visible.x, visible.h, BLACK);
+    //top and bottom
+    draw_rectangle(visible.x, visible.y, visible.w, -visible.y, BLACK);
+    draw_rectangle(visible.x, SPACE_HEIGHT, visible.w, -visible.y, BLACK);
+}
@@ -0,0 +1,103 @@
+//! Gamepad rumble feedback.
+
+use hecs::World;
+
+use crate::persist::Settings;
+
+/// Single rumble effect waiting to be played.
+#[derive(Clone, Copy, Debug)]
+pub struct RumbleEffect {
+    /// Strength of the rumble, 0.0 to 1.0.
+    pub strength: f32,
+    /// Time before the effect ends.
+    pub duration: f32,
+}
+
+/// Resource collecting rumble effects requested by the game.
+#[derive(Clone, Debug, Default)]
+pub struct Rumble {
+    /// Queued effects that are still playing.
+    effects: Vec<RumbleEffect>,
+    /// Strength requested only for the current frame.
+    held: f32,
+}
+
+impl Rumble {
+    /// Queues a rumble effect.
+    /// # Arguments
+    /// * `strength` - strength of the rumble, 0.0 to 1.0
+    /// * `duration` - how long should the rumble last
+    pub fn add(&mut self, strength: f32, duration: f32) {
+        self.effects.push(RumbleEffect { strength, duration });
+    }
+
+    /// Requests a rumble of `strength` for the current frame only.
+    /// Used by continuous effects, stronger request wins.
+    pub fn hold(&mut self, strength: f32) {
+        self.held = self.held.max(strength);
+    }
+
+    /// Returns the combined strength of all effects.
+    /// It never exceeds full strength.
+    pub fn strength(&self) -> f32 {
+        let queued: f32 = self.effects.iter().map(|effect| effect.strength).sum();
+        (queued + self.held).clamp(0.0, 1.0)
+    }
+
+    /// Advances all effects and drops the finished ones.
+    /// # Arguments
+    /// * `dt` - delta time
+    pub fn update(&mut self, dt: f32) {
+        for effect in &mut self.effects {
+            effect.duration -= dt;
+        }
+        self.effects.retain(|effect| effect.duration > 0.0);
+        self.held = 0.0;
+    }
+}
+
+/// Platform layer able to play rumble.
+pub trait RumbleBackend {
+    /// Sets the motors' strength, 0.0 stops them.
+    fn set_rumble(&mut self, strength: f32);
+}
+
+/// Backend of platforms without rumble support.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoRumble;
+
+impl RumbleBackend for NoRumble {
+    fn set_rumble(&mut self, _strength: f32) {}
+}
+
+//-----------------------------------------------------------------------------
+//SYSTEM PART
+//-----------------------------------------------------------------------------
+
+/// Queues a rumble effect into the [Rumble] resource, if there is any.
+pub fn queue_rumble(world: &World, strength: f32, duration: f32) {
+    for (_, rumble) in world.query::<&mut Rumble>().into_iter() {
+        rumble.add(strength, duration);
+    }
+}
+
+/// Plays the [Rumble] on the `backend` respecting user's settings.
+/// Stops the rumble when there is no [Rumble] resource.
+pub fn play_rumble(
+    world: &mut World,
+    backend: &mut dyn RumbleBackend,
+    settings: &Settings,
+    dt: f32,
+) {
+    let mut strength = 0.0;
+    for (_, rumble) in world.query_mut::<&mut Rumble>() {
+        strength = rumble.strength();
+        rumble.update(dt);
+    }
+
+    if settings.rumble {
+        backend.set_rumble(strength * settings.rumble_intensity.clamp(0.0, 1.0));
+    } else {
+        backend.set_rumble(0.0);
+    }
+}
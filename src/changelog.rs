@@ -0,0 +1,199 @@
+//! Changelog shown on the "What's new" screen.
+
+use hecs::World;
+use macroquad::prelude::*;
+
+use crate::{basic::render::AssetManager, menu, persist::Persistent};
+
+/// Version of the running game.
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Changes of a single release.
+#[derive(Clone, Copy, Debug)]
+pub struct ChangelogEntry {
+    /// Version of the release.
+    pub version: &'static str,
+    /// Date of the release.
+    pub date: &'static str,
+    /// List of changes.
+    pub changes: &'static [&'static str],
+}
+
+/// All releases, the newest first.
+/// Adding a release only requires adding an entry here.
+pub const CHANGELOG: &[ChangelogEntry] = &[ChangelogEntry {
+    version: "0.1.0",
+    date: "2026-10-16",
+    changes: &[
+        "Banners announce the destruction of big asteroids.",
+        "Titles announce the start and end of every wave.",
+        "Practice mode lets you start at any wave.",
+        "Hold space to grapple towards large xp orbs.",
+        "Enemies slung into each other by your charge count as engineered kills and drop more xp.",
+        "Your best run is replayed as a faint ghost.",
+        "New large arena with a camera following the ship.",
+        "Reduced motion, render scale and rumble settings.",
+        "Gamepad controls for flying, shooting and switching polarity.",
+        "Minimap showing incoming enemies, toggled with M.",
+        "Settings screen with volume sliders and an optional CRT filter.",
+        "New splitter asteroid that breaks into smaller pieces on every hit.",
+        "New turret that anchors in the arena and fires aimed bursts.",
+        "New flipper that switches its polarity every few seconds, flashing right before.",
+        "After death the camera drifts towards the remaining enemies, toggled with C.",
+        "Every fifth wave ends with a boss fight.",
+        "Hints explain the basics during your first run.",
+        "Collected xp levels you up, pick one of three weapon upgrades on every level.",
+        "Xp orbs of charged enemies carry their charge, flip polarity to pull them in.",
+        "Dash with left shift to dodge through enemies and knock them away.",
+        "Enemies grow in numbers and strength the longer a run lasts, shown as the threat level.",
+        "Animated thruster flame on the ship and blinking lights on mines.",
+        "Asteroid debris slows down and fades out instead of popping.",
+        "Press F11 to toggle fullscreen, the choice is remembered.",
+        "Arrows at the edge of the screen point at enemies outside of it.",
+        "Quick kills build up a combo multiplying collected xp, getting hit breaks it.",
+        "Stats screen with lifetime play time, collected xp and kills of every enemy type.",
+        "Saves are stored in the user's data directory, or the browser's storage on the web.",
+        "Leaderboard of the ten best runs, entered with your initials.",
+        "Run timer on the HUD, with the survived and best time on the game over screen.",
+        "Enemies no longer spawn right next to a player hugging the edge.",
+        "Seeded runs, started from the practice menu with the seed shown on the game over screen.",
+        "Daily challenge, the same seeded run for everyone each day with its own records.",
+        "Runs are simulated at a fixed rate, so they play the same on any monitor.",
+        "Fast shots no longer pass through small enemies.",
+        "Enemies rarely drop health pickups, collect them before they fade away.",
+        "Enemies can no longer pin the player with constant knockback.",
+        "The wreck of the player no longer collides with anything or pulls orbs.",
+        "Deaths play out in slow motion with the camera zooming in.",
+        "Press tab to show charge fields and the force pulling on you.",
+        "A dial next to the health bar shows the charge force pulling on you.",
+        "A tutorial teaches flying, polarity and shooting, find it under HOW TO PLAY.",
+        "Charged asteroids can now crunch into the center from all sides or come in triple volleys.",
+        "New formations: orbiting dipole pairs, walls of mines and big asteroid sandwiches.",
+        "Hold fire to charge a piercing railgun shot, release to fire it.",
+        "Sustained fire loses accuracy, the STEADY AIM upgrade keeps it tighter.",
+        "A bigger cursor shows your polarity and pulses when the weapon is ready.",
+        "Sounds now fade with their distance from your ship, and no longer pile up into a din.",
+        "Enemies flash white when damaged, your ship flashes red.",
+        "Low health is hard to miss: a red pulse at the screen edges, a heartbeat and a blinking health bar.",
+        "Health bars leave a yellow trail after hits, so bursts of damage are readable.",
+        "Reduce flashing setting: mines keep a steady tint and particle bursts are capped.",
+        "Colorblind mode: charges are marked by plus and minus glyphs and use an orange and blue palette.",
+        "The menus, settings, scores and game over screen can be shown in Czech.",
+        "Menus can be navigated with the arrow keys or W and S, and Enter presses the underlined button.",
+        "The pause menu can resume the run and the game over screen can start another one.",
+        "Daily challenges ignore the config file, so that everyone plays the same run.",
+        "Runs can be quit from the pause menu, after confirming to abandon them.",
+        "A loading screen shows the progress of loading, and lists the files which are missing.",
+        "Music plays in the menus and during runs, with its own volume slider.",
+    ],
+}];
+
+/// Left edge of the changelog panel.
+const PANEL_X: f32 = 240.0;
+/// Top edge of the changelog panel.
+const PANEL_Y: f32 = 180.0;
+/// Width of the changelog panel.
+const PANEL_WIDTH: f32 = 800.0;
+/// Height of the changelog panel.
+const PANEL_HEIGHT: f32 = 400.0;
+/// Size of the changelog's text.
+const TEXT_SIZE: f32 = 22.0;
+/// Distance between two lines of text.
+const LINE_HEIGHT: f32 = 30.0;
+/// Scrolling speed of the arrow keys.
+const SCROLL_SPEED: f32 = 300.0;
+/// Scrolled distance per mouse wheel step.
+const WHEEL_STEP: f32 = 40.0;
+
+/// Scrollable panel listing the [CHANGELOG].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ChangelogPanel {
+    /// How far the text is scrolled down.
+    pub scroll: f32,
+    /// Height of all the text, known after the first render.
+    pub content_height: f32,
+}
+
+/// Has the user not seen the changelog of the current version yet?
+pub fn is_unseen(persist: &Persistent) -> bool {
+    persist.last_seen_version != CURRENT_VERSION
+}
+
+/// Remembers that the user saw the changelog of the current version.
+pub fn mark_seen(persist: &mut Persistent) {
+    persist.last_seen_version = CURRENT_VERSION.into();
+    let _ = persist.save();
+}
+
+//-----------------------------------------------------------------------------
+//SYSTEM PART
+//-----------------------------------------------------------------------------
+
+/// Scrolls [ChangelogPanel]s with the mouse wheel and the arrow keys.
+pub fn scroll_changelog(world: &mut World, dt: f32) {
+    let wheel = mouse_wheel().1;
+    let mut delta = if wheel != 0.0 {
+        -wheel.signum() * WHEEL_STEP
+    } else {
+        0.0
+    };
+    if is_key_down(KeyCode::Down) {
+        delta += SCROLL_SPEED * dt;
+    }
+    if is_key_down(KeyCode::Up) {
+        delta -= SCROLL_SPEED * dt;
+    }
+
+    for (_, panel) in world.query_mut::<&mut ChangelogPanel>() {
+        let max_scroll = (panel.content_height - PANEL_HEIGHT).max(0.0);
+        panel.scroll = (panel.scroll + delta).clamp(0.0, max_scroll);
+    }
+}
+
+/// Renders the [CHANGELOG] into [ChangelogPanel]s.
+/// Lines outside of the panel are not rendered.
+pub fn render_changelog(world: &mut World, assets: &AssetManager) {
+    let font = assets.get_font("main_font");
+
+    for (_, panel) in world.query_mut::<&mut ChangelogPanel>() {
+        //panel background
+        draw_rectangle(
+            PANEL_X - 10.0,
+            PANEL_Y - 10.0,
+            PANEL_WIDTH + 20.0,
+            PANEL_HEIGHT + 20.0,
+            Color::new(0.0, 0.0, 0.0, 0.5),
+        );
+
+        //lay out all the lines
+        let mut lines = Vec::new();
+        for entry in CHANGELOG {
+            lines.push((format!("{} - {}", entry.version, entry.date), GOLD));
+            for change in entry.changes {
+                for line in menu::wrap_text(&format!("- {change}"), font, TEXT_SIZE, PANEL_WIDTH) {
+                    lines.push((line, WHITE));
+                }
+            }
+        }
+        panel.content_height = lines.len() as f32 * LINE_HEIGHT;
+
+        for (i, (line, color)) in lines.iter().enumerate() {
+            let y = PANEL_Y + i as f32 * LINE_HEIGHT - panel.scroll;
+            if y < PANEL_Y || y + LINE_HEIGHT > PANEL_Y + PANEL_HEIGHT {
+                continue;
+            }
+            draw_text_ex(
+                line,
+                PANEL_X,
+                y + TEXT_SIZE,
+                TextParams {
+                    font,
+                    font_size: TEXT_SIZE as u16 * 2,
+                    font_scale: 0.5,
+                    color: *color,
+                    ..Default::default()
+                },
+            );
+        }
+    }
+}
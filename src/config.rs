@@ -0,0 +1,385 @@
+//! Tuning values loaded from a data file, so that balancing does not need recompiling.
+//!
+//! The file is TOML with one section per group of values, e.g.
+//! ```toml
+//! [player]
+//! fire_cooldown = 0.1
+//!
+//! [spawn_mines]
+//! weight = 50
+//! ```
+//! Missing keys keep their compiled defaults.
+
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use macroquad::prelude::*;
+use nanoserde::{Toml, TomlParser};
+
+use crate::{
+    enemy::{
+        asteroid::{
+            ASTEROID_DMG, ASTEROID_HEALTH, ASTEROID_SPEED, BIG_ASTEROID_DMG, BIG_ASTEROID_HEALTH,
+            BIG_ASTEROID_SPEED,
+        },
+        boss::{BOSS_DMG, BOSS_HEALTH, BOSS_SPEED},
+        flipper::{FLIPPER_DMG, FLIPPER_HEALTH, FLIPPER_SPEED},
+        follower::{FOLLOWER_DMG, FOLLOWER_HEALTH, FOLLOWER_SPEED},
+        mine::{MINE_DMG, MINE_HEALTH, MINE_SPEED},
+        splitter::{SPLITTER_DMG, SPLITTER_HEALTH, SPLITTER_SPEED},
+        turret::{TURRET_DMG, TURRET_HEALTH, TURRET_SPEED},
+    },
+    game, player,
+};
+
+/// Path of the config file.
+const CONFIG_PATH: &str = "res/config.toml";
+/// Key reloading the config file and the textures, only in debug builds.
+pub const RELOAD_KEY: KeyCode = KeyCode::F5;
+/// Prefix of the sections tuning the enemy spawns.
+const SPAWN_PREFIX: &str = "spawn_";
+
+thread_local! {
+    /// Config currently in use.
+    static CONFIG: RefCell<Rc<GameConfig>> = RefCell::new(Rc::new(GameConfig::default()));
+    /// Compiled defaults, used instead of [CONFIG] while [DEFAULTS_ONLY] is set.
+    static DEFAULTS: Rc<GameConfig> = Rc::new(GameConfig::default());
+    /// Are the loaded values ignored?
+    static DEFAULTS_ONLY: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Implements setting the fields of a config section by their names.
+macro_rules! section {
+    ($section:ty { $($field:ident),* $(,)? }) => {
+        impl $section {
+            /// Sets the field called `name` to `num`.
+            /// Returns whether there is such field.
+            fn set(&mut self, name: &str, num: f64) -> bool {
+                match name {
+                    $(stringify!($field) => self.$field = num as _,)*
+                    _ => return false,
+                }
+                true
+            }
+        }
+    };
+}
+
+/// All tuning values of the game.
+#[derive(Clone, Debug)]
+pub struct GameConfig {
+    /// Tuning of the player.
+    pub player: PlayerConfig,
+    /// Tuning of the enemies.
+    pub enemies: EnemyConfig,
+    /// Tuning of the enemy spawner.
+    pub spawner: SpawnerConfig,
+    /// Tuning of every possible enemy spawn, in the order of the spawn table.
+    pub spawns: Vec<SpawnTuning>,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            player: PlayerConfig::default(),
+            enemies: EnemyConfig::default(),
+            spawner: SpawnerConfig::default(),
+            spawns: game::default_spawn_tunings(),
+        }
+    }
+}
+
+/// Tuning of the player.
+#[derive(Clone, Copy, Debug)]
+pub struct PlayerConfig {
+    /// Acceleration while thrusting.
+    pub accel: f32,
+    /// Mass of the player.
+    pub mass: f32,
+    /// Charge force of the player's field.
+    pub charge_force: f32,
+    /// Max health without upgrades.
+    pub max_hp: f32,
+    /// Health regenerated every second without upgrades.
+    pub hp_regen: f32,
+    /// Time between shots without upgrades.
+    pub fire_cooldown: f32,
+    /// Time of invulnerability after getting hit.
+    pub invul_cooldown: f32,
+    /// Force pushing the player back with every shot.
+    pub recoil: f32,
+    /// Impulse of a dash.
+    pub dash_impulse: f32,
+    /// Time a dash lasts.
+    pub dash_time: f32,
+    /// Time between dashes.
+    pub dash_cooldown: f32,
+}
+
+section!(PlayerConfig {
+    accel,
+    mass,
+    charge_force,
+    max_hp,
+    hp_regen,
+    fire_cooldown,
+    invul_cooldown,
+    recoil,
+    dash_impulse,
+    dash_time,
+    dash_cooldown,
+});
+
+impl Default for PlayerConfig {
+    fn default() -> Self {
+        Self {
+            accel: player::PLAYER_ACCEL,
+            mass: player::PLAYER_MASS,
+            charge_force: player::PLAYER_CHARGE_FORCE,
+            max_hp: player::PLAYER_MAX_BASE_HP,
+            hp_regen: player::PLAYER_BASE_HP_REGEN,
+            fire_cooldown: player::PLAYER_FIRE_COOLDOWN,
+            invul_cooldown: player::PLAYER_INVUL_COOLDOWN,
+            recoil: player::PLAYER_RECOIL,
+            dash_impulse: player::PLAYER_DASH_IMPULSE,
+            dash_time: player::PLAYER_DASH_TIME,
+            dash_cooldown: player::PLAYER_DASH_COOLDOWN,
+        }
+    }
+}
+
+/// Basic stats of an enemy type.
+#[derive(Clone, Copy, Debug)]
+pub struct EnemyStats {
+    /// Health of a fresh enemy, before difficulty scaling.
+    pub health: f32,
+    /// Speed the enemy spawns with.
+    pub speed: f32,
+    /// Damage dealt by touching the enemy, before difficulty scaling.
+    pub dmg: f32,
+}
+
+section!(EnemyStats { health, speed, dmg });
+
+impl EnemyStats {
+    /// Creates stats of an enemy type.
+    const fn new(health: f32, speed: f32, dmg: f32) -> Self {
+        Self { health, speed, dmg }
+    }
+}
+
+/// Tuning of the enemies, each type has its own section.
+#[derive(Clone, Copy, Debug)]
+pub struct EnemyConfig {
+    /// Stats of asteroids and charged asteroids.
+    pub asteroid: EnemyStats,
+    /// Stats of big asteroids.
+    pub big_asteroid: EnemyStats,
+    /// Stats of saw blades.
+    pub follower: EnemyStats,
+    /// Stats of mines.
+    pub mine: EnemyStats,
+    /// Stats of the largest splitter asteroids, smaller ones are scaled down.
+    pub splitter: EnemyStats,
+    /// Stats of turrets.
+    pub turret: EnemyStats,
+    /// Stats of flippers.
+    pub flipper: EnemyStats,
+    /// Stats of the boss.
+    pub boss: EnemyStats,
+}
+
+impl EnemyConfig {
+    /// Returns the stats of the enemy type called `name`.
+    fn stats_mut(&mut self, name: &str) -> Option<&mut EnemyStats> {
+        match name {
+            "asteroid" => Some(&mut self.asteroid),
+            "big_asteroid" => Some(&mut self.big_asteroid),
+            "follower" => Some(&mut self.follower),
+            "mine" => Some(&mut self.mine),
+            "splitter" => Some(&mut self.splitter),
+            "turret" => Some(&mut self.turret),
+            "flipper" => Some(&mut self.flipper),
+            "boss" => Some(&mut self.boss),
+            _ => None,
+        }
+    }
+}
+
+impl Default for EnemyConfig {
+    fn default() -> Self {
+        Self {
+            asteroid: EnemyStats::new(ASTEROID_HEALTH, ASTEROID_SPEED, ASTEROID_DMG),
+            big_asteroid: EnemyStats::new(
+                BIG_ASTEROID_HEALTH,
+                BIG_ASTEROID_SPEED,
+                BIG_ASTEROID_DMG,
+            ),
+            follower: EnemyStats::new(FOLLOWER_HEALTH, FOLLOWER_SPEED, FOLLOWER_DMG),
+            mine: EnemyStats::new(MINE_HEALTH, MINE_SPEED, MINE_DMG),
+            splitter: EnemyStats::new(SPLITTER_HEALTH, SPLITTER_SPEED, SPLITTER_DMG),
+            turret: EnemyStats::new(TURRET_HEALTH, TURRET_SPEED, TURRET_DMG),
+            flipper: EnemyStats::new(FLIPPER_HEALTH, FLIPPER_SPEED, FLIPPER_DMG),
+            boss: EnemyStats::new(BOSS_HEALTH, BOSS_SPEED, BOSS_DMG),
+        }
+    }
+}
+
+/// Tuning of the enemy spawner.
+#[derive(Clone, Copy, Debug)]
+pub struct SpawnerConfig {
+    /// Credits the spawner starts with.
+    pub init_credits: f32,
+    /// Credits the spawner gets every second.
+    pub credits_per_sec: f32,
+    /// Cooldown before the first spawn.
+    pub init_cooldown: f32,
+    /// Minimal cooldown between individual enemy spawns.
+    pub min_spawn_cooldown: f32,
+    /// Maximal cooldown between individual enemy spawns.
+    pub max_spawn_cooldown: f32,
+    /// Minimal break time between waves.
+    pub min_break_cooldown: f32,
+    /// Maximal break time between waves.
+    pub max_break_cooldown: f32,
+    /// Minimal amount of spawns before a wave ends.
+    pub min_spawns_before_break: u32,
+    /// Maximal amount of spawns before a wave ends.
+    pub max_spawns_before_break: u32,
+    /// Max sum of the threat of enemies alive at once, before difficulty scaling.
+    pub max_active_threat: f32,
+}
+
+section!(SpawnerConfig {
+    init_credits,
+    credits_per_sec,
+    init_cooldown,
+    min_spawn_cooldown,
+    max_spawn_cooldown,
+    min_break_cooldown,
+    max_break_cooldown,
+    min_spawns_before_break,
+    max_spawns_before_break,
+    max_active_threat,
+});
+
+impl Default for SpawnerConfig {
+    fn default() -> Self {
+        game::default_spawner_config()
+    }
+}
+
+/// Tuning of an enemy spawn of the spawn table.
+#[derive(Clone, Copy, Debug)]
+pub struct SpawnTuning {
+    /// Name of the spawn, its section is the name prefixed by `spawn_`.
+    pub name: &'static str,
+    /// Cost of the spawn.
+    pub cost: f32,
+    /// Credits the spawner gets after paying for the spawn.
+    pub gain: f32,
+    /// Weight of the spawn when choosing one.
+    pub weight: u32,
+    /// Weight added while the player camps a corner.
+    pub camp_weight: u32,
+    /// Threat each enemy of the spawn poses while alive.
+    pub threat: f32,
+}
+
+section!(SpawnTuning {
+    cost,
+    gain,
+    weight,
+    camp_weight,
+    threat,
+});
+
+impl GameConfig {
+    /// Creates the default config with the values of `text` applied.
+    ///
+    /// Unknown keys and values other than numbers are skipped with a warning.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let values = TomlParser::parse(text).map_err(|err| format!("{err:?}"))?;
+        let mut config = Self::default();
+        for (key, value) in values {
+            let Toml::Num(num) = value else {
+                eprintln!("Config key `{key}` is not a number, it is skipped!");
+                continue;
+            };
+            if !config.set(&key, num) {
+                eprintln!("Config key `{key}` is unknown, it is skipped!");
+            }
+        }
+        Ok(config)
+    }
+
+    /// Sets the value of `key`, in the `section.name` form, to `num`.
+    /// Returns whether there is such key.
+    fn set(&mut self, key: &str, num: f64) -> bool {
+        let Some((section, name)) = key.split_once('.') else {
+            return false;
+        };
+        if let Some(spawn) = section.strip_prefix(SPAWN_PREFIX) {
+            return self
+                .spawns
+                .iter_mut()
+                .find(|tuning| tuning.name == spawn)
+                .is_some_and(|tuning| tuning.set(name, num));
+        }
+        match section {
+            "player" => self.player.set(name, num),
+            "spawner" => self.spawner.set(name, num),
+            _ => self
+                .enemies
+                .stats_mut(section)
+                .is_some_and(|stats| stats.set(name, num)),
+        }
+    }
+}
+
+//-----------------------------------------------------------------------------
+//SYSTEM PART
+//-----------------------------------------------------------------------------
+
+/// Returns the config currently in use.
+pub fn get() -> Rc<GameConfig> {
+    if DEFAULTS_ONLY.get() {
+        DEFAULTS.with(Rc::clone)
+    } else {
+        CONFIG.with_borrow(Rc::clone)
+    }
+}
+
+/// Sets whether [get] ignores the config file and returns the compiled defaults.
+///
+/// Runs shared between players, like the daily challenge, must not depend on local tuning.
+/// # Arguments
+/// * `defaults_only` - are the loaded values ignored
+pub fn use_defaults_only(defaults_only: bool) {
+    DEFAULTS_ONLY.set(defaults_only);
+}
+
+/// Loads the config file and puts it to use.
+///
+/// A missing file uses the defaults,
+/// a malformed one uses them too with a warning.
+pub async fn load() {
+    let config = match load_string(CONFIG_PATH).await {
+        Ok(text) => GameConfig::parse(&text).unwrap_or_else(|err| {
+            eprintln!("Config `{CONFIG_PATH}` is malformed, defaults are used: {err}");
+            GameConfig::default()
+        }),
+        Err(_) => GameConfig::default(),
+    };
+    CONFIG.set(Rc::new(config));
+}
+
+/// Reloads the config file when its key is pressed, for tuning mid-run.
+/// Does nothing in release builds.
+pub async fn hot_reload() {
+    if cfg!(debug_assertions) && is_key_pressed(RELOAD_KEY) {
+        load().await;
+    }
+}
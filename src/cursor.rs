@@ -0,0 +1,122 @@
+//! Game cursor replacing the OS cursor.
+//!
+//! The OS cursor is shown again while the game is paused
+//! or when the user is likely away from the window, e.g. after alt-tabbing.
+
+use std::f32::consts::TAU;
+
+use hecs::World;
+use macroquad::prelude::*;
+
+use crate::{
+    game::state::GameState,
+    player::{Dead, Player},
+    world_mouse_pos,
+};
+
+/// Radius of the cursor's circle.
+const CURSOR_RADIUS: f32 = 8.0;
+/// Length of the crosshair lines sticking out of the cursor's circle.
+const CURSOR_LINE: f32 = 5.0;
+/// Thickness of the cursor's lines.
+const CURSOR_THICKNESS: f32 = 2.0;
+/// Color of the cursor outside of runs.
+const CURSOR_COLOR: Color = WHITE;
+/// Color of the cursor while the player is positive.
+const CURSOR_POSITIVE_COLOR: Color = Color::new(1.0, 0.4, 0.4, 1.0);
+/// Color of the cursor while the player is negative.
+const CURSOR_NEGATIVE_COLOR: Color = Color::new(0.3, 1.0, 1.0, 1.0);
+/// Pulses per second of the cursor while the weapon is ready.
+const CURSOR_PULSE_FREQ: f32 = 3.0;
+/// Relative change of the cursor's size while pulsing.
+const CURSOR_PULSE_SIZE: f32 = 0.2;
+
+/// Tracks whether the game cursor or the OS cursor is shown.
+#[derive(Clone, Copy, Debug)]
+pub struct Cursor {
+    /// Is the OS cursor shown instead of the game one?
+    os_shown: bool,
+    /// Is the user likely away from the window?
+    /// Set when the game gets suspended, cleared once the mouse moves.
+    away: bool,
+    /// Position of the mouse in the previous frame, in screen coordinates.
+    last_mouse: Vec2,
+}
+
+impl Default for Cursor {
+    fn default() -> Self {
+        //the OS cursor is shown until the first update
+        Self {
+            os_shown: true,
+            away: false,
+            last_mouse: Vec2::ZERO,
+        }
+    }
+}
+
+impl Cursor {
+    /// Chooses between the game and the OS cursor.
+    /// Should be called exactly once per frame.
+    /// # Arguments
+    /// * `state` - current game state
+    /// * `suspended` - has the window just been hidden or the game suspended?
+    pub fn update(&mut self, state: GameState, suspended: bool) {
+        let (mx, my) = mouse_position();
+        let mouse = vec2(mx, my);
+        if suspended {
+            self.away = true;
+        } else if mouse != self.last_mouse {
+            self.away = false;
+        }
+        self.last_mouse = mouse;
+
+        let outside = mx <= 0.0 || my <= 0.0 || mx >= screen_width() || my >= screen_height();
+        let os_shown = self.away || outside || state == GameState::Paused;
+        if os_shown != self.os_shown {
+            show_mouse(os_shown);
+            self.os_shown = os_shown;
+        }
+    }
+
+    /// Renders the game cursor above everything else, unless the OS cursor is shown.
+    ///
+    /// Its color follows the player's polarity and it pulses while the weapon is ready.
+    pub fn render(&self, world: &mut World) {
+        if self.os_shown {
+            return;
+        }
+        let player = world
+            .query_mut::<&Player>()
+            .without::<&Dead>()
+            .into_iter()
+            .next()
+            .map(|(_, player)| (player.polarity(), player.can_fire()));
+        let (color, pulse) = match player {
+            Some((polarity, ready)) => (
+                if polarity > 0 {
+                    CURSOR_POSITIVE_COLOR
+                } else {
+                    CURSOR_NEGATIVE_COLOR
+                },
+                ready,
+            ),
+            None => (CURSOR_COLOR, false),
+        };
+        let scale = if pulse {
+            1.0 + CURSOR_PULSE_SIZE * (get_time() as f32 * CURSOR_PULSE_FREQ * TAU).sin()
+        } else {
+            1.0
+        };
+
+        let pos = world_mouse_pos();
+        let radius = CURSOR_RADIUS * scale;
+        draw_circle_lines(pos.x, pos.y, radius, CURSOR_THICKNESS, color);
+        //crosshair sticking out of the circle
+        for dir in [Vec2::X, Vec2::Y, -Vec2::X, -Vec2::Y] {
+            let from = pos + dir * (radius - CURSOR_LINE / 2.0);
+            let to = pos + dir * (radius + CURSOR_LINE);
+            draw_line(from.x, from.y, to.x, to.y, CURSOR_THICKNESS, color);
+        }
+        draw_circle(pos.x, pos.y, CURSOR_THICKNESS / 2.0, color);
+    }
+}
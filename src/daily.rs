@@ -0,0 +1,68 @@
+//! Daily challenge, a seeded run shared by everyone on the same day.
+
+use std::hash::Hasher;
+
+use hecs::World;
+use macroquad::miniquad;
+use nanoserde::{DeBin, SerBin};
+
+/// Seconds in a day.
+const SECS_PER_DAY: f64 = 86400.0;
+
+/// Marker of daily challenge runs.
+#[derive(Clone, Debug)]
+pub struct DailyRun {
+    /// Date of the challenge, as returned by [today].
+    pub date: String,
+    /// Is it not the first attempt of the day?
+    pub improvement: bool,
+}
+
+/// Results of a single day's challenge.
+#[derive(Clone, Copy, Debug, Default, DeBin, SerBin)]
+pub struct DailyRecord {
+    /// Highest score reached that day.
+    pub best: u32,
+    /// Amount of finished attempts.
+    pub attempts: u32,
+}
+
+/// Returns the current UTC date as `YYYY-MM-DD`.
+pub fn today() -> String {
+    let days = (miniquad::date::now() / SECS_PER_DAY).floor() as i64;
+    let (year, month, day) = civil_from_days(days);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Returns the seed of the challenge of `date`.
+pub fn daily_seed(date: &str) -> u64 {
+    let mut hasher = fnv::FnvHasher::default();
+    hasher.write(date.as_bytes());
+    hasher.finish()
+}
+
+/// Returns the [DailyRun] marker of the current run, if it is one.
+pub fn daily_run(world: &mut World) -> Option<DailyRun> {
+    world
+        .query_mut::<&DailyRun>()
+        .into_iter()
+        .next()
+        .map(|(_, daily)| daily.clone())
+}
+
+/// Converts days since the unix epoch into a (year, month, day) date.
+///
+/// Uses the proleptic gregorian calendar, see
+/// <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -0,0 +1,394 @@
+//! Debug overlay with performance stats and an entity inspector.
+//! Stats are available in all builds, the inspector only in debug builds.
+
+use hecs::{Entity, World};
+use macroquad::prelude::*;
+
+use crate::{
+    basic::{
+        arena,
+        fx::FxManager,
+        motion::{
+            ChargeDisable, ChargeReceiver, ChargeSender, KnockbackDealer, LinearMotion,
+            LinearTorgue, MaxVelocity, PhysicsMotion,
+        },
+        render::{AnimatedSprite, AssetManager, Sprite},
+        DamageDealer, Health, HitBox, HitEvent, HurtBox, Lifetime, Position, Rotation, SpawnGrace,
+        Team,
+    },
+    enemy::{
+        asteroid::BigAsteroid, boss::Boss, charged::ChargedAsteroid, flipper::Flipper,
+        follower::Follower, mine::Mine, splitter::SplitterAsteroid, turret::Turret, Enemy,
+        LastDamagedBy, StaggerConfig, Stunned,
+    },
+    game::EnemySpawner,
+    persist::Settings,
+    projectile::Projectile,
+    upgrade::PlayerUpgrades,
+    xp::{BurstXpOnDeath, XpOrb},
+    SPACE_HEIGHT,
+};
+
+/// Key toggling the debug overlay.
+const OVERLAY_KEY: KeyCode = KeyCode::F3;
+/// Key freezing the selected entity.
+const FREEZE_KEY: KeyCode = KeyCode::F;
+/// Max distance of a click from an entity to select it.
+const SELECT_RADIUS: f32 = 20.0;
+/// Max distance from the selected entity to cycle to other entities.
+const CYCLE_RADIUS: f32 = 150.0;
+/// Size of the inspector's text.
+const TEXT_SIZE: f32 = 16.0;
+/// Left edge of the stats panel, right of the inspector panel.
+const STATS_X: f32 = 440.0;
+/// Width of the stats panel.
+const STATS_WIDTH: f32 = 300.0;
+
+/// Debug overlay showing performance stats, able to inspect and freeze entities.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DebugOverlay {
+    /// Is the overlay shown?
+    pub active: bool,
+    /// Entity being inspected.
+    pub selected: Option<Entity>,
+    /// Hit events generated so far in the current frame.
+    hits: usize,
+    /// Hit events generated in the last frame.
+    last_hits: usize,
+}
+
+impl DebugOverlay {
+    /// Counts the hit events of a step, must be called before the `events` are cleared.
+    pub fn record_events(&mut self, events: &World) {
+        self.hits += events.query::<&HitEvent>().iter().count();
+    }
+
+    /// Handles toggling, selecting and freezing of entities.
+    /// Must be called once per frame, after all steps are recorded.
+    /// Selecting and freezing does nothing in release builds.
+    pub fn update(&mut self, world: &mut World) {
+        self.last_hits = std::mem::take(&mut self.hits);
+        if is_key_pressed(OVERLAY_KEY) {
+            self.active = !self.active;
+        }
+        if !cfg!(debug_assertions) {
+            return;
+        }
+        //forget despawned entities
+        if self
+            .selected
+            .is_some_and(|selected| !world.contains(selected))
+        {
+            self.selected = None;
+        }
+        if !self.active {
+            return;
+        }
+
+        //select by clicking
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let mouse_pos = arena::arena(world).mouse_pos();
+            self.selected = nearby(world, mouse_pos, SELECT_RADIUS)
+                .into_iter()
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(entity, _)| entity);
+        }
+
+        //cycle between nearby entities
+        let step = match (
+            is_key_pressed(KeyCode::Right),
+            is_key_pressed(KeyCode::Left),
+        ) {
+            (true, false) => 1,
+            (false, true) => -1,
+            _ => 0,
+        };
+        if let (Some(selected), true) = (self.selected, step != 0) {
+            let center = position(world, selected);
+            let mut candidates: Vec<Entity> = nearby(world, center, CYCLE_RADIUS)
+                .into_iter()
+                .map(|(entity, _)| entity)
+                .collect();
+            candidates.sort_by_key(|entity| entity.id());
+            if let Some(index) = candidates.iter().position(|entity| *entity == selected) {
+                let next = (index as i32 + step).rem_euclid(candidates.len() as i32) as usize;
+                self.selected = Some(candidates[next]);
+            }
+        }
+
+        //freeze the selected entity
+        if let (Some(selected), true) = (self.selected, is_key_pressed(FREEZE_KEY)) {
+            if world.satisfies::<&Stunned>(selected).unwrap_or(false) {
+                let _ = world.remove_one::<Stunned>(selected);
+            } else {
+                let _ = world.insert_one(selected, Stunned);
+            }
+        }
+    }
+
+    /// Renders the stats panel, the outline of the selected entity and the inspector panel.
+    /// The inspector is not rendered in release builds.
+    pub fn render(
+        &self,
+        world: &mut World,
+        assets: &AssetManager,
+        fx: &FxManager,
+        settings: &Settings,
+    ) {
+        if !self.active {
+            return;
+        }
+        let font = assets.get_font("main_font");
+        let params = TextParams {
+            font,
+            font_size: TEXT_SIZE as u16 * 2,
+            font_scale: 0.5,
+            color: YELLOW,
+            ..Default::default()
+        };
+        draw_text_ex("DEBUG (F3)", 10.0, 20.0, params.clone());
+        self.render_stats(world, fx, settings, &params);
+        if !cfg!(debug_assertions) {
+            return;
+        }
+        render_camping(world, &params);
+
+        let Some(selected) = self.selected else {
+            return;
+        };
+
+        //outline the entity in the world
+        let pos = position(world, selected);
+        set_camera(&arena::view_camera(world));
+        draw_circle_lines(pos.x, pos.y, SELECT_RADIUS, 2.0, YELLOW);
+        set_camera(&crate::world_camera());
+
+        //list its components
+        let lines = inspect(world, selected);
+        let height = (lines.len() + 1) as f32 * (TEXT_SIZE + 4.0) + 10.0;
+        draw_rectangle(10.0, 30.0, 420.0, height, Color::new(0.0, 0.0, 0.0, 0.7));
+        draw_text_ex(&format!("{selected:?}"), 20.0, 50.0, params.clone());
+        for (i, line) in lines.iter().enumerate() {
+            draw_text_ex(
+                line,
+                20.0,
+                50.0 + (i + 1) as f32 * (TEXT_SIZE + 4.0),
+                TextParams {
+                    color: WHITE,
+                    ..params.clone()
+                },
+            );
+        }
+    }
+}
+
+impl DebugOverlay {
+    /// Renders frame timing, render scale, entity counts,
+    /// the [EnemySpawner], the [StaggerConfig] and the [SystemTimings].
+    fn render_stats(
+        &self,
+        world: &World,
+        fx: &FxManager,
+        settings: &Settings,
+        params: &TextParams,
+    ) {
+        let mut lines = vec![
+            format!("{} FPS, {:.2} ms", get_fps(), get_frame_time() * 1000.0),
+            format!("render scale {:.0} %", settings.render_scale * 100.0),
+            format!(
+                "entities {}: enemies {}, projectiles {}, orbs {}",
+                world.len(),
+                world.query::<&Enemy>().iter().count(),
+                world.query::<&Projectile>().iter().count(),
+                world.query::<&XpOrb>().iter().count(),
+            ),
+            format!("particles {}", fx.particle_count()),
+            format!("hit events {}", self.last_hits),
+        ];
+        if let Some((_, spawner)) = world.query::<&EnemySpawner>().iter().next() {
+            lines.push(format!(
+                "credits {:.1}, cooldown {:.2}, before break {}",
+                spawner.credits, spawner.cooldown, spawner.before_break
+            ));
+        }
+        if let Some((_, stagger)) = world.query::<&StaggerConfig>().iter().next() {
+            let slices = stagger.slices.max(1);
+            let thinking = world
+                .query::<()>()
+                .with::<&Enemy>()
+                .iter()
+                .filter(|(id, _)| stagger.should_think(*id))
+                .count();
+            lines.push(format!(
+                "ai slice {} of {slices}, {thinking} enemies thinking",
+                stagger.frame % slices + 1
+            ));
+        }
+        //slowest systems first
+        if let Some((_, timings)) = world.query::<&SystemTimings>().iter().next() {
+            let mut laps = timings.laps.clone();
+            laps.sort_by(|a, b| b.1.total_cmp(&a.1));
+            lines.extend(
+                laps.iter()
+                    .map(|(name, time)| format!("{name} {:.3} ms", time * 1000.0)),
+            );
+        }
+
+        let height = lines.len() as f32 * (TEXT_SIZE + 4.0) + 10.0;
+        draw_rectangle(
+            STATS_X,
+            30.0,
+            STATS_WIDTH,
+            height,
+            Color::new(0.0, 0.0, 0.0, 0.7),
+        );
+        for (i, line) in lines.iter().enumerate() {
+            draw_text_ex(
+                line,
+                STATS_X + 10.0,
+                50.0 + i as f32 * (TEXT_SIZE + 4.0),
+                TextParams {
+                    color: WHITE,
+                    ..params.clone()
+                },
+            );
+        }
+    }
+}
+
+/// Resource with the time the systems of the last step took.
+#[derive(Clone, Debug, Default)]
+pub struct SystemTimings {
+    /// Name of every timed group of systems with its time in seconds.
+    laps: Vec<(&'static str, f64)>,
+    /// Time the current lap started at.
+    start: f64,
+}
+
+impl SystemTimings {
+    /// Starts timing the systems of a step.
+    pub fn start() -> Self {
+        Self {
+            laps: Vec::new(),
+            start: get_time(),
+        }
+    }
+
+    /// Records the time since the previous lap under `name`.
+    pub fn lap(&mut self, name: &'static str) {
+        let now = get_time();
+        self.laps.push((name, now - self.start));
+        self.start = now;
+    }
+
+    /// Replaces the [SystemTimings] resource of `world` with these, if there is one.
+    pub fn store(self, world: &mut World) {
+        if let Some((_, timings)) = world.query_mut::<&mut SystemTimings>().into_iter().next() {
+            *timings = self;
+        }
+    }
+}
+
+/// Renders the camping detection of the [EnemySpawner], when there is one.
+fn render_camping(world: &World, params: &TextParams) {
+    let Some(camp) = world
+        .query::<&EnemySpawner>()
+        .iter()
+        .next()
+        .map(|(_, spawner)| spawner.camp)
+    else {
+        return;
+    };
+    let arena = arena::arena(world);
+    let corner = camp.camped_corner(&arena);
+
+    //average position in the world
+    let average = camp.average();
+    set_camera(&arena::view_camera(world));
+    draw_circle_lines(average.x, average.y, 6.0, 1.0, ORANGE);
+    set_camera(&crate::world_camera());
+
+    let text = match corner {
+        Some(corner) => format!(
+            "CAMPING ({:.0}, {:.0}) for {:.1}s",
+            corner.x, corner.y, camp.corner_time
+        ),
+        None => format!("not camping, near corner for {:.1}s", camp.corner_time),
+    };
+    draw_text_ex(
+        &text,
+        10.0,
+        SPACE_HEIGHT - 10.0,
+        TextParams {
+            color: if corner.is_some() { ORANGE } else { YELLOW },
+            ..params.clone()
+        },
+    );
+}
+
+/// Returns the position of `entity`, or zero when it has none.
+fn position(world: &World, entity: Entity) -> Vec2 {
+    world
+        .get::<&Position>(entity)
+        .map(|pos| vec2(pos.x, pos.y))
+        .unwrap_or(Vec2::ZERO)
+}
+
+/// Returns all entities closer than `radius` to `point` with their distances.
+fn nearby(world: &World, point: Vec2, radius: f32) -> Vec<(Entity, f32)> {
+    world
+        .query::<&Position>()
+        .iter()
+        .map(|(entity, pos)| (entity, vec2(pos.x, pos.y).distance(point)))
+        .filter(|(_, distance)| *distance <= radius)
+        .collect()
+}
+
+/// Formats all inspectable components of `entity`, one per line.
+fn inspect(world: &World, entity: Entity) -> Vec<String> {
+    let mut lines = Vec::new();
+    //registry of inspectable components
+    macro_rules! inspect {
+        ($($component:ty),* $(,)?) => {
+            $(
+                if let Ok(component) = world.get::<&$component>(entity) {
+                    lines.push(format!("{:?}", *component));
+                }
+            )*
+        };
+    }
+    inspect!(
+        Position,
+        Rotation,
+        LinearMotion,
+        LinearTorgue,
+        PhysicsMotion,
+        MaxVelocity,
+        Health,
+        Team,
+        HitBox,
+        HurtBox,
+        DamageDealer,
+        KnockbackDealer,
+        ChargeSender,
+        ChargeReceiver,
+        ChargeDisable,
+        SpawnGrace,
+        Lifetime,
+        LastDamagedBy,
+        BurstXpOnDeath,
+        PlayerUpgrades,
+        BigAsteroid,
+        Boss,
+        ChargedAsteroid,
+        Flipper,
+        Follower,
+        Mine,
+        SplitterAsteroid,
+        Turret,
+        Stunned,
+        Sprite,
+        AnimatedSprite,
+    );
+    lines
+}
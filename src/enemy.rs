@@ -1,28 +1,262 @@
 //! Enemy components and AI.
 
 pub mod asteroid;
+pub mod boss;
 pub mod charged;
+pub mod flipper;
 pub mod follower;
 pub mod mine;
+pub mod splitter;
+pub mod turret;
 
 pub use asteroid::*;
 
-use hecs::{CommandBuffer, World};
+use hecs::{CommandBuffer, Entity, EntityRef, World};
+use macroquad::{
+    color::{Color, WHITE},
+    math::{vec2, Vec2},
+};
+use nanoserde::{DeBin, SerBin};
 
-use crate::basic::{DamageDealer, Health, HitEvent};
+use crate::{
+    basic::{
+        motion::{KnockbackDealer, PhysicsMotion},
+        render::{AssetManager, HitFlash},
+        DamageDealer, DamageEvent, Health, HitEvent, Position, Team,
+    },
+    game::run_rng,
+    pickup::{self, create_health_pickup},
+    projectile::{Piercing, Projectile},
+    score::Combo,
+    xp::BurstXpOnDeath,
+};
+
+/// Relative speed two enemies must collide at to hurt each other.
+const FRIENDLY_IMPACT_SPEED: f32 = 120.0;
+/// Multiplier of damage dealt by enemies colliding with each other.
+const FRIENDLY_IMPACT_MULT: f32 = 0.5;
+/// Time before the same enemy can hurt another one by collision again.
+const FRIENDLY_IMPACT_COOLDOWN: f32 = 0.5;
+/// Time before the same piercing projectile can hurt an enemy again.
+const PIERCE_REHIT_COOLDOWN: f32 = 0.5;
+/// Multiplier of xp dropped by enemies killed by engineered collisions.
+const ENGINEERED_XP_MULT: f32 = 1.5;
+
+/// Color enemies flash with when damaged.
+const HIT_FLASH_COLOR: Color = WHITE;
+/// Volume of the cue of a dying enemy.
+const DEATH_CUE_VOLUME: f32 = 0.4;
+/// Volume of the cue of a detonating mine.
+const MINE_CUE_VOLUME: f32 = 0.8;
+
+/// Amount of slices AI decisions are split into by default.
+/// At 60 FPS every enemy decides 15 times per second.
+const DEFAULT_AI_SLICES: u32 = 4;
 
 ///Marker of enemy entities.
 ///Every enemy should have this marker.
 #[derive(Clone, Copy, Debug, Default)]
 pub struct Enemy;
 
+/// Stops the entity's AI and motion.
+/// Used by the debug overlay to inspect entities.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Stunned;
+
+/// Way an enemy was damaged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DamageKind {
+    /// Hit by the player's projectile.
+    PlayerProjectile,
+    /// Collided with the player or their entities.
+    PlayerCollision,
+    /// Collided with another enemy at high speed.
+    /// Usually engineered by the player's charge field.
+    FriendlyFire,
+    /// Hurt by anything else.
+    Environment,
+}
+
+/// Remembers who damaged the enemy last.
+/// Used to attribute the enemy's kill.
+#[derive(Clone, Copy, Debug)]
+pub struct LastDamagedBy {
+    /// Entity that dealt the damage.
+    pub entity: Entity,
+    /// Team of the entity that dealt the damage.
+    pub team: Team,
+    /// How the damage was dealt.
+    pub kind: DamageKind,
+    /// Time since the damage was dealt.
+    pub since: f32,
+}
+
+/// Statistics of the current run.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RunStats {
+    /// Enemies killed by the player's projectiles.
+    pub projectile_kills: u32,
+    /// Enemies killed by colliding with the player's entities.
+    pub collision_kills: u32,
+    /// Enemies killed by colliding with other enemies.
+    pub engineered_kills: u32,
+    /// Enemies killed by anything else.
+    pub environment_kills: u32,
+    /// Time the player survived.
+    pub time: f32,
+    /// Time the player spent with positive polarity.
+    pub positive_time: f32,
+    /// Time the player spent with negative polarity.
+    pub negative_time: f32,
+    /// Enemies killed, by their type.
+    pub kills: KillCounts,
+}
+
+/// Amounts of killed enemies, by their type.
+#[derive(Clone, Copy, Debug, Default, DeBin, SerBin)]
+pub struct KillCounts {
+    /// Asteroids, charged or not.
+    pub asteroid: u32,
+    /// Big asteroids.
+    pub big_asteroid: u32,
+    /// Supercharged asteroids.
+    pub charged: u32,
+    /// Splitter asteroids.
+    pub splitter: u32,
+    /// Saw blades following the player.
+    pub follower: u32,
+    /// Mines.
+    pub mine: u32,
+    /// Turrets.
+    pub turret: u32,
+    /// Flippers.
+    pub flipper: u32,
+    /// Bosses.
+    pub boss: u32,
+}
+
+impl KillCounts {
+    /// Counts the kill of `enemy` by its type.
+    pub fn count(&mut self, enemy: EntityRef) {
+        let counter = if enemy.has::<boss::Boss>() {
+            &mut self.boss
+        } else if enemy.has::<BigAsteroid>() {
+            &mut self.big_asteroid
+        } else if enemy.has::<charged::ChargedAsteroid>() {
+            &mut self.charged
+        } else if enemy.has::<splitter::SplitterAsteroid>() {
+            &mut self.splitter
+        } else if enemy.has::<follower::Follower>() {
+            &mut self.follower
+        } else if enemy.has::<mine::Mine>() {
+            &mut self.mine
+        } else if enemy.has::<turret::Turret>() {
+            &mut self.turret
+        } else if enemy.has::<flipper::Flipper>() {
+            &mut self.flipper
+        } else {
+            &mut self.asteroid
+        };
+        *counter += 1;
+    }
+
+    /// Adds up kills of both counts.
+    pub fn add(&mut self, other: &KillCounts) {
+        self.asteroid += other.asteroid;
+        self.big_asteroid += other.big_asteroid;
+        self.charged += other.charged;
+        self.splitter += other.splitter;
+        self.follower += other.follower;
+        self.mine += other.mine;
+        self.turret += other.turret;
+        self.flipper += other.flipper;
+        self.boss += other.boss;
+    }
+
+    /// Returns the locale key of every type's name with its amount of kills.
+    pub fn by_type(&self) -> [(&'static str, u32); 9] {
+        [
+            ("kills.asteroid", self.asteroid),
+            ("kills.big_asteroid", self.big_asteroid),
+            ("kills.charged", self.charged),
+            ("kills.splitter", self.splitter),
+            ("kills.follower", self.follower),
+            ("kills.mine", self.mine),
+            ("kills.turret", self.turret),
+            ("kills.flipper", self.flipper),
+            ("kills.boss", self.boss),
+        ]
+    }
+
+    /// Returns the amount of all kills.
+    pub fn total(&self) -> u32 {
+        self.by_type().iter().map(|(_, kills)| kills).sum()
+    }
+}
+
+/// Resource that spreads expensive AI decisions across multiple frames.
+///
+/// Every frame only the enemies in the current slice (entity id modulo `slices`)
+/// make their decisions, while the cheap integration runs every frame.
+#[derive(Clone, Copy, Debug)]
+pub struct StaggerConfig {
+    /// Amount of slices the enemies are split into.
+    pub slices: u32,
+    /// Current frame number, wraps around.
+    pub frame: u32,
+}
+
+impl StaggerConfig {
+    /// Should `entity` make its decisions this frame?
+    #[inline]
+    pub fn should_think(&self, entity: Entity) -> bool {
+        let slices = self.slices.max(1);
+        entity.id() % slices == self.frame % slices
+    }
+}
+
+impl Default for StaggerConfig {
+    fn default() -> Self {
+        Self {
+            slices: DEFAULT_AI_SLICES,
+            frame: 0,
+        }
+    }
+}
+
 //------------------------------------------------------------------------------
 //SYSTEM PART
 //------------------------------------------------------------------------------
 
-/// Handles hurting of enemies by hostile hurt events.
+/// Returns the current [StaggerConfig].
+pub fn stagger(world: &mut World) -> StaggerConfig {
+    world
+        .query_mut::<&StaggerConfig>()
+        .into_iter()
+        .next()
+        .map(|(_, stagger)| *stagger)
+        .unwrap_or_default()
+}
+
+/// Advances [StaggerConfig] to the next slice.
+/// Should run after all AI systems.
+pub fn advance_stagger(world: &mut World) {
+    for (_, stagger) in world.query_mut::<&mut StaggerConfig>() {
+        stagger.frame = stagger.frame.wrapping_add(1);
+    }
+}
+
+/// Handles hurting of enemies by hostile hurt events and high speed
+/// collisions with other enemies.
 /// Calculates resulting health and despawns dead (hp <= 0.0) enemies.
-pub fn health(world: &mut World, events: &mut World, cmd: &mut CommandBuffer) {
+/// Dead enemies have a chance to drop a health pickup.
+pub fn health(world: &mut World, events: &mut World, cmd: &mut CommandBuffer, dt: f32) {
+    //age last damage
+    for (_, last) in world.query_mut::<&mut LastDamagedBy>() {
+        last.since += dt;
+    }
+
+    let mut damaged_by = Vec::new();
     {
         //get enemy view
         let enemy_query = &mut world.query::<&mut Health>().with::<&Enemy>();
@@ -30,10 +264,6 @@ pub fn health(world: &mut World, events: &mut World, cmd: &mut CommandBuffer) {
         //get events concerning the player
         let hit_events = events.query_mut::<&HitEvent>().into_iter();
         for (_, event) in hit_events {
-            //can be hurt by it?
-            if !event.can_hurt {
-                continue;
-            }
             //get the enemy
             let Some(enemy_hp) = enemy_view.get_mut(event.who) else {
                 continue;
@@ -42,15 +272,255 @@ pub fn health(world: &mut World, events: &mut World, cmd: &mut CommandBuffer) {
             let Ok(damage) = world.get::<&DamageDealer>(event.by) else {
                 continue;
             };
+            //piercing projectiles hurt each enemy once
+            if is_repeated_pierce(world, event) {
+                continue;
+            }
+            let team = world
+                .get::<&Team>(event.by)
+                .map(|team| *team)
+                .unwrap_or_default();
+            //classify the damage
+            let (kind, dmg) = if event.can_hurt {
+                let kind = match team {
+                    Team::Player if world.satisfies::<&Projectile>(event.by).unwrap_or(false) => {
+                        DamageKind::PlayerProjectile
+                    }
+                    Team::Player => DamageKind::PlayerCollision,
+                    _ => DamageKind::Environment,
+                };
+                (kind, damage.dmg)
+            } else if team == Team::Enemy && is_friendly_impact(world, event) {
+                (DamageKind::FriendlyFire, damage.dmg * FRIENDLY_IMPACT_MULT)
+            } else {
+                continue;
+            };
             //apply it
-            enemy_hp.hp -= damage.dmg;
+            enemy_hp.hp -= dmg;
+            damaged_by.push((
+                event.who,
+                dmg,
+                LastDamagedBy {
+                    entity: event.by,
+                    team,
+                    kind,
+                    since: 0.0,
+                },
+            ));
         }
     }
+    //remember who did it
+    for (enemy, amount, last) in damaged_by {
+        events.spawn((DamageEvent {
+            who: enemy,
+            by: last.entity,
+            amount,
+        },));
+        let _ = world.insert_one(enemy, last);
+        let _ = world.insert_one(enemy, HitFlash::new(HIT_FLASH_COLOR));
+    }
 
     //despawn dead enemies
-    for (enemy_id, health) in world.query_mut::<&Health>().with::<&Enemy>() {
+    let drop_chance = pickup::drop_chance(world);
+    let mut rng = run_rng(world);
+    for (enemy_id, (health, pos)) in world
+        .query_mut::<(&Health, Option<&Position>)>()
+        .with::<&Enemy>()
+    {
         if health.hp <= 0.0 {
             cmd.despawn(enemy_id);
+            //rarely drop health
+            let Some(pos) = pos else {
+                continue;
+            };
+            if rng.f32() < drop_chance {
+                cmd.spawn(create_health_pickup(vec2(pos.x, pos.y)).build());
+            }
+        }
+    }
+}
+
+/// Did the piercing projectile of `event` already hurt the enemy recently?
+fn is_repeated_pierce(world: &World, event: &HitEvent) -> bool {
+    world.satisfies::<&Piercing>(event.by).unwrap_or(false)
+        && world
+            .get::<&LastDamagedBy>(event.who)
+            .is_ok_and(|last| last.entity == event.by && last.since < PIERCE_REHIT_COOLDOWN)
+}
+
+/// Is the collision described by `event` a high speed impact between two enemies,
+/// which did not already hurt the enemy recently?
+fn is_friendly_impact(world: &World, event: &HitEvent) -> bool {
+    //only bodies collide, not projectiles
+    if !world
+        .satisfies::<&KnockbackDealer>(event.by)
+        .unwrap_or(false)
+    {
+        return false;
+    }
+    //the same enemy cannot hurt again immediately
+    if let Ok(last) = world.get::<&LastDamagedBy>(event.who) {
+        if last.entity == event.by && last.since < FRIENDLY_IMPACT_COOLDOWN {
+            return false;
+        }
+    }
+    //are they fast enough?
+    let velocity = |entity| {
+        world
+            .get::<&PhysicsMotion>(entity)
+            .map(|physics| physics.vel)
+            .unwrap_or(Vec2::ZERO)
+    };
+    (velocity(event.who) - velocity(event.by)).length() >= FRIENDLY_IMPACT_SPEED
+}
+
+/// Plays the death cues of dead (hp <= 0.0) enemies where they died.
+/// Mines detonate louder.
+pub fn death_cues(world: &mut World, assets: &AssetManager) {
+    for (_, (health, pos, mine)) in world
+        .query_mut::<(&Health, &Position, Option<&mine::Mine>)>()
+        .with::<&Enemy>()
+    {
+        if health.hp > 0.0 {
+            continue;
+        }
+        let volume = if mine.is_some() {
+            MINE_CUE_VOLUME
+        } else {
+            DEATH_CUE_VOLUME
+        };
+        assets
+            .audio()
+            .play_at("knockback", volume, vec2(pos.x, pos.y));
+    }
+}
+
+/// Attributes kills of dead (hp <= 0.0) enemies into [RunStats].
+/// Enemies killed by engineered collisions drop more xp.
+pub fn kill_attribution(world: &mut World) {
+    let mut stats = RunStats::default();
+    let mut dead = Vec::new();
+    for (id, (health, last, burst)) in world
+        .query_mut::<(&Health, Option<&LastDamagedBy>, Option<&mut BurstXpOnDeath>)>()
+        .with::<&Enemy>()
+    {
+        if health.hp > 0.0 {
+            continue;
+        }
+        dead.push(id);
+        match last.map(|last| last.kind) {
+            Some(DamageKind::PlayerProjectile) => stats.projectile_kills += 1,
+            Some(DamageKind::PlayerCollision) => stats.collision_kills += 1,
+            Some(DamageKind::FriendlyFire) => {
+                stats.engineered_kills += 1;
+                //reward the engineered kill
+                if let Some(burst) = burst {
+                    burst.amount = (burst.amount as f32 * ENGINEERED_XP_MULT) as u32;
+                }
+            }
+            Some(DamageKind::Environment) | None => stats.environment_kills += 1,
+        }
+    }
+
+    //count kills by the type of the enemy
+    for id in dead {
+        if let Ok(enemy) = world.entity(id) {
+            stats.kills.count(enemy);
+        }
+    }
+
+    //kills of the player keep the combo going
+    let player_kills = stats.projectile_kills + stats.collision_kills + stats.engineered_kills;
+    for (_, combo) in world.query_mut::<&mut Combo>() {
+        combo.add_kills(player_kills);
+    }
+
+    for (_, run_stats) in world.query_mut::<&mut RunStats>() {
+        run_stats.projectile_kills += stats.projectile_kills;
+        run_stats.collision_kills += stats.collision_kills;
+        run_stats.engineered_kills += stats.engineered_kills;
+        run_stats.environment_kills += stats.environment_kills;
+        run_stats.kills.add(&stats.kills);
+    }
+}
+
+/// Keeps [Stunned] entities in place.
+pub fn stunned(world: &mut World) {
+    for (_, physics) in world.query_mut::<&mut PhysicsMotion>().with::<&Stunned>() {
+        physics.vel = Vec2::ZERO;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fastrand::Rng;
+
+    use super::*;
+    use crate::{basic::arena::Arena, player};
+
+    /// Spawns the player and 100 sawblades around it, returning the sawblades.
+    fn crowded_world(slices: u32) -> (World, Vec<Entity>) {
+        let mut world = World::new();
+        let mut rng = Rng::with_seed(1486);
+        world.spawn((StaggerConfig { slices, frame: 0 },));
+        world.spawn(player::new_entity(&Arena::STANDARD));
+        let enemies = (0..100)
+            .map(|i| {
+                let pos = Vec2::from_angle(i as f32 * 0.1) * (200.0 + i as f32);
+                world.spawn(follower::create_follower(pos, Vec2::X, 0, &mut rng).build())
+            })
+            .collect();
+        (world, enemies)
+    }
+
+    #[test]
+    fn every_enemy_thinks_once_per_cycle() {
+        for slices in [1, 2, 4, 8] {
+            let (mut world, enemies) = crowded_world(slices);
+            let mut thoughts = vec![0; enemies.len()];
+            for _ in 0..slices {
+                let stagger = stagger(&mut world);
+                for (thought, enemy) in thoughts.iter_mut().zip(&enemies) {
+                    *thought += stagger.should_think(*enemy) as u32;
+                }
+                advance_stagger(&mut world);
+            }
+            assert!(thoughts.iter().all(|thoughts| *thoughts == 1));
+        }
+    }
+
+    #[test]
+    fn thinking_enemies_drop_with_slices() {
+        for slices in [1, 2, 4, 8] {
+            let (mut world, enemies) = crowded_world(slices);
+            let stagger = stagger(&mut world);
+            let thinking = enemies
+                .iter()
+                .filter(|enemy| stagger.should_think(**enemy))
+                .count();
+            let expected = 100 / slices as usize;
+            assert!(
+                thinking.abs_diff(expected) <= 1,
+                "{thinking} of 100 enemies think with {slices} slices"
+            );
+        }
+    }
+
+    /// Times the sawblade AI of a 100 enemy world for every slice count.
+    /// Run with `cargo test --release -- --ignored --nocapture stagger_benchmark`.
+    #[test]
+    #[ignore]
+    fn stagger_benchmark() {
+        const FRAMES: u32 = 20_000;
+        for slices in [1, 2, 4, 8] {
+            let (mut world, _) = crowded_world(slices);
+            let start = std::time::Instant::now();
+            for _ in 0..FRAMES {
+                follower::follower_ai(&mut world, 1.0 / 120.0);
+                advance_stagger(&mut world);
+            }
+            let per_frame = start.elapsed().as_secs_f64() / FRAMES as f64;
+            println!("{slices} slices: {:.2} us per frame", per_frame * 1e6);
         }
     }
 }
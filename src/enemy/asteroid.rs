@@ -1,33 +1,38 @@
 //! Asteroid, charged and big asteroid logic.
 use std::f32::consts::PI;
 
+use fastrand::Rng;
 use hecs::{CommandBuffer, EntityBuilder, World};
 use macroquad::prelude::*;
 
 use crate::{
+    banner::KillBanner,
     basic::{
         fx::{FxManager, Particle},
         motion::{
             ChargeReceiver, ChargeSender, KnockbackDealer, LinearMotion, LinearTorgue, MaxVelocity,
             PhysicsMotion,
         },
-        render::Sprite,
-        DamageDealer, DeleteOnWarp, Health, HitBox, HurtBox, Position, Rotation, Team,
+        render::{AssetManager, Sprite},
+        DamageDealer, DamageEvent, DeleteOnWarp, Health, HitBox, HurtBox, Position, Rotation,
+        SpawnGrace, Team,
     },
+    config,
+    game::run_rng,
     player::Player,
     xp::BurstXpOnDeath,
 };
 
-use super::{charged::create_supercharged_asteroid, Enemy};
+use super::{charged::create_supercharged_asteroid, stagger, Enemy, Stunned};
 
 //ASTEROID STATS
 
 /// Health of an asteroid.
-pub(super) const ASTEROID_HEALTH: f32 = 1.0;
+pub(crate) const ASTEROID_HEALTH: f32 = 1.0;
 /// Speed of an asteroid.
-pub(super) const ASTEROID_SPEED: f32 = 50.0;
+pub(crate) const ASTEROID_SPEED: f32 = 50.0;
 /// Mass of an asteroid.
-pub(super) const ASTEROID_MASS: f32 = 18.0;
+pub(crate) const ASTEROID_MASS: f32 = 18.0;
 
 /// Size of an asteroid.
 /// Also affects Hit/HurtBox sizes.
@@ -36,7 +41,7 @@ pub(super) const ASTEROID_SIZE: f32 = 50.0;
 pub(super) const ASTEROID_SCALE: f32 = ASTEROID_SIZE / 512.0;
 
 /// Dmg an asteroid does while hitting something.
-pub(super) const ASTEROID_DMG: f32 = 2.0;
+pub(crate) const ASTEROID_DMG: f32 = 2.0;
 
 /// Texture ID of neutral asteroid.
 pub const ASTEROID_TEX_NEUTRAL: &str = "asteroid";
@@ -46,7 +51,7 @@ pub const ASTEROID_TEX_POSITIVE: &str = "asteroid_plus";
 pub const ASTEROID_TEX_NEGATIVE: &str = "asteroid_negative";
 
 /// Charge force of a charged asteroid.
-pub(super) const ASTEROID_FORCE: f32 = 750.0;
+pub(crate) const ASTEROID_FORCE: f32 = 750.0;
 /// Full radius of charge field of a charged asteroid.
 pub(super) const ASTEROID_FORCE_F_RADIUS: f32 = 200.0;
 /// Zero radius of charge field of a charged asteroid.
@@ -61,9 +66,9 @@ const ASTEROID_XP: u32 = 10;
 //BIG ASTEROID STATS
 
 /// Health of a big asteroid.
-const BIG_ASTEROID_HEALTH: f32 = 2.0;
+pub(crate) const BIG_ASTEROID_HEALTH: f32 = 2.0;
 /// Speed of a big asteroid.
-const BIG_ASTEROID_SPEED: f32 = 45.0;
+pub(crate) const BIG_ASTEROID_SPEED: f32 = 45.0;
 /// Mass of a big asteroid.
 const BIG_ASTEROID_MASS: f32 = 30.0;
 
@@ -74,13 +79,22 @@ const BIG_ASTEROID_SIZE: f32 = 200.0;
 const BIG_ASTEROID_SCALE: f32 = BIG_ASTEROID_SIZE / 512.0;
 
 /// Dmg a big asteroid does while hitting something.
-const BIG_ASTEROID_DMG: f32 = 3.0;
+pub(crate) const BIG_ASTEROID_DMG: f32 = 3.0;
 
 /// Texture ID of positively charged asteroid.
 pub const BIG_ASTEROID_TEX_POSITIVE: &str = "asteroid_big_plus";
 /// Texture ID of negatively charged asteroid.
 pub const BIG_ASTEROID_TEX_NEGATIVE: &str = "asteroid__big_minus";
 
+/// All texture IDs used by asteroids' sprites.
+pub const ASTEROID_TEXTURES: [&str; 5] = [
+    ASTEROID_TEX_NEUTRAL,
+    ASTEROID_TEX_POSITIVE,
+    ASTEROID_TEX_NEGATIVE,
+    BIG_ASTEROID_TEX_POSITIVE,
+    BIG_ASTEROID_TEX_NEGATIVE,
+];
+
 /// Charge force of a big asteroid.
 const BIG_ASTEROID_FORCE: f32 = 950.0;
 /// Full radius of charge field of a big asteroid.
@@ -94,16 +108,32 @@ const BIG_ASTEROID_KNOCKBACK: f32 = 700.0;
 /// Xp dropped by a big asteroid on death.
 const BIG_ASTEROID_XP: u32 = 20;
 
+/// Damage of a single hit that chips a fragment off a big asteroid.
+const BIG_ASTEROID_CHIP_DMG: f32 = 1.0;
+/// Max amount of fragments chipped off a single big asteroid.
+const BIG_ASTEROID_MAX_CHIPS: u8 = 3;
+/// Xp dropped by a fragment chipped off a big asteroid.
+const BIG_ASTEROID_CHIP_XP: u32 = 3;
+
 /// Acceleration towards player applied to big asteroids.
 const BIG_ASTEROID_FOLLOW: f32 = 20.0;
 
+/// Color debris of destroyed asteroids fades into.
+const DEBRIS_END_COLOR: Color = Color::new(0.3, 0.3, 0.3, 0.0);
+
 /// Marker of an asteroid.
 #[derive(Clone, Copy, Debug)]
 pub struct Asteroid;
 
-/// Marker of a big asteroid.
+/// Handles big asteroid's logic.
 #[derive(Clone, Copy, Debug)]
-pub struct BigAsteroid;
+pub struct BigAsteroid {
+    /// Direction towards the player.
+    /// Recomputed only on the asteroid's AI slice.
+    pub target_dir: Vec2,
+    /// Amount of fragments already chipped off.
+    pub chips: u8,
+}
 
 //------------------------------------------------------------------------------
 //ENTITY CREATION
@@ -114,13 +144,14 @@ pub struct BigAsteroid;
 /// * `pos` - position of the asteroid
 /// * `dir` - direction the asteroid is heading
 pub fn create_asteroid(pos: Vec2, dir: Vec2) -> EntityBuilder {
+    let stats = config::get().enemies.asteroid;
     let mut builder = EntityBuilder::new();
     builder.add_bundle((
         Enemy,
         Asteroid,
         Position { x: pos.x, y: pos.y },
         LinearMotion {
-            vel: dir * ASTEROID_SPEED,
+            vel: dir * stats.speed,
         },
         Sprite {
             texture: ASTEROID_TEX_NEUTRAL,
@@ -135,10 +166,10 @@ pub fn create_asteroid(pos: Vec2, dir: Vec2) -> EntityBuilder {
             radius: ASTEROID_SIZE / 2.0 - 8.0,
         },
         Health {
-            max_hp: ASTEROID_HEALTH,
-            hp: ASTEROID_HEALTH,
+            max_hp: stats.health,
+            hp: stats.health,
         },
-        DamageDealer { dmg: ASTEROID_DMG },
+        DamageDealer { dmg: stats.dmg },
         Team::Enemy,
         DeleteOnWarp,
         KnockbackDealer {
@@ -159,7 +190,9 @@ pub fn create_asteroid(pos: Vec2, dir: Vec2) -> EntityBuilder {
 ///     - x > 0 -> positively charged asteroid
 ///     - x < 0 -> negatively charged asteroid
 ///     - x = 0 -> undefined behaviour
-pub fn create_charged_asteroid(pos: Vec2, dir: Vec2, charge: i8) -> EntityBuilder {
+/// * `rng` - generator of the randomized rotation
+pub fn create_charged_asteroid(pos: Vec2, dir: Vec2, charge: i8, rng: &mut Rng) -> EntityBuilder {
+    let stats = config::get().enemies.asteroid;
     let texture = if charge > 0 {
         ASTEROID_TEX_POSITIVE
     } else {
@@ -173,13 +206,13 @@ pub fn create_charged_asteroid(pos: Vec2, dir: Vec2, charge: i8) -> EntityBuilde
         Asteroid,
         Position { x: pos.x, y: pos.y },
         Rotation {
-            angle: fastrand::f32() * 2.0 * PI,
+            angle: rng.f32() * 2.0 * PI,
         },
         LinearTorgue {
-            speed: fastrand::f32() * 1.0 - 0.50,
+            speed: rng.f32() * 1.0 - 0.50,
         },
         PhysicsMotion {
-            vel: dir * ASTEROID_SPEED,
+            vel: dir * stats.speed,
             mass: ASTEROID_MASS,
         },
         Sprite {
@@ -197,10 +230,10 @@ pub fn create_charged_asteroid(pos: Vec2, dir: Vec2, charge: i8) -> EntityBuilde
             radius: ASTEROID_SIZE / 2.0,
         },
         Health {
-            max_hp: ASTEROID_HEALTH,
-            hp: ASTEROID_HEALTH,
+            max_hp: stats.health,
+            hp: stats.health,
         },
-        DamageDealer { dmg: ASTEROID_DMG },
+        DamageDealer { dmg: stats.dmg },
         Team::Enemy,
         DeleteOnWarp,
         ChargeSender {
@@ -218,7 +251,7 @@ pub fn create_charged_asteroid(pos: Vec2, dir: Vec2, charge: i8) -> EntityBuilde
             amount: ASTEROID_XP,
         },
         MaxVelocity {
-            max_velocity: ASTEROID_SPEED * 2.0,
+            max_velocity: stats.speed * 2.0,
         },
     ));
     builder
@@ -232,7 +265,9 @@ pub fn create_charged_asteroid(pos: Vec2, dir: Vec2, charge: i8) -> EntityBuilde
 ///     - x > 0 -> positively charged asteroid
 ///     - x < 0 -> negatively charged asteroid
 ///     - x = 0 -> undefined behaviour
-pub fn create_big_asteroid(pos: Vec2, dir: Vec2, charge: i8) -> EntityBuilder {
+/// * `rng` - generator of the randomized rotation
+pub fn create_big_asteroid(pos: Vec2, dir: Vec2, charge: i8, rng: &mut Rng) -> EntityBuilder {
+    let stats = config::get().enemies.big_asteroid;
     let texture = if charge > 0 {
         BIG_ASTEROID_TEX_POSITIVE
     } else {
@@ -242,16 +277,19 @@ pub fn create_big_asteroid(pos: Vec2, dir: Vec2, charge: i8) -> EntityBuilder {
     let mut builder = EntityBuilder::default();
     builder.add_bundle((
         Enemy,
-        BigAsteroid,
+        BigAsteroid {
+            target_dir: dir,
+            chips: 0,
+        },
         Position { x: pos.x, y: pos.y },
         Rotation {
-            angle: fastrand::f32() * 2.0 * PI,
+            angle: rng.f32() * 2.0 * PI,
         },
         LinearTorgue {
-            speed: fastrand::f32() * 1.0 - 0.50,
+            speed: rng.f32() * 1.0 - 0.50,
         },
         PhysicsMotion {
-            vel: dir * BIG_ASTEROID_SPEED,
+            vel: dir * stats.speed,
             mass: BIG_ASTEROID_MASS,
         },
         Sprite {
@@ -267,12 +305,10 @@ pub fn create_big_asteroid(pos: Vec2, dir: Vec2, charge: i8) -> EntityBuilder {
             radius: BIG_ASTEROID_SIZE / 2.0 - 15.0,
         },
         Health {
-            max_hp: BIG_ASTEROID_HEALTH,
-            hp: BIG_ASTEROID_HEALTH,
-        },
-        DamageDealer {
-            dmg: BIG_ASTEROID_DMG,
+            max_hp: stats.health,
+            hp: stats.health,
         },
+        DamageDealer { dmg: stats.dmg },
         Team::Enemy,
         DeleteOnWarp,
     ));
@@ -292,7 +328,10 @@ pub fn create_big_asteroid(pos: Vec2, dir: Vec2, charge: i8) -> EntityBuilder {
             amount: BIG_ASTEROID_XP,
         },
         MaxVelocity {
-            max_velocity: BIG_ASTEROID_SPEED * 2.0,
+            max_velocity: stats.speed * 2.0,
+        },
+        KillBanner {
+            name: "enemy.big_asteroid",
         },
     ));
     builder
@@ -306,21 +345,26 @@ pub fn create_big_asteroid(pos: Vec2, dir: Vec2, charge: i8) -> EntityBuilder {
 /// Currently only makes the asteroid attracted to player.
 pub fn big_asteroid_ai(world: &mut World, dt: f32) {
     //get player's position
-    let (_, &player_pos) = world
+    let Some((_, &player_pos)) = world
         .query_mut::<&Position>()
         .with::<&Player>()
         .into_iter()
         .next()
-        .unwrap();
+    else {
+        return;
+    };
+    let stagger = stagger(world);
     //update velocity
-    for (_, (pos, vel)) in world
-        .query_mut::<(&Position, &mut PhysicsMotion)>()
-        .with::<&BigAsteroid>()
+    for (id, (pos, vel, big)) in world
+        .query_mut::<(&Position, &mut PhysicsMotion, &mut BigAsteroid)>()
+        .without::<&Stunned>()
     {
+        //retarget on its own slice
+        if stagger.should_think(id) {
+            big.target_dir = vec2(player_pos.x - pos.x, player_pos.y - pos.y).normalize_or_zero();
+        }
         //speed up towards player
-        let acceleration = vec2(player_pos.x - pos.x, player_pos.y - pos.y).normalize_or_zero()
-            * BIG_ASTEROID_FOLLOW
-            * dt;
+        let acceleration = big.target_dir * BIG_ASTEROID_FOLLOW * dt;
         vel.vel += acceleration;
     }
 }
@@ -333,29 +377,104 @@ pub fn asteroid_death(world: &mut World, fx: &mut FxManager) {
     {
         //check if it is dead
         if health.hp <= 0.0 {
-            //spawn random particles on destroy
+            //spawn random debris on destroy, slowing down and darkening
             for i in 1..=2 {
-                fx.burst_particles(
+                fx.burst_with(
                     Particle {
                         pos: vec2(pos.x, pos.y),
-                        vel: vec2(30.0 * i as f32, 0.0),
+                        vel: vec2(60.0 * i as f32, 0.0),
                         life: 1.0,
                         max_life: 1.0,
                         min_size: 0.0,
                         max_size: 12.0,
                         color: LIGHTGRAY,
+                        drag: 2.0,
+                        end_color: Some(DEBRIS_END_COLOR),
+                        ..Default::default()
                     },
-                    14.0,
+                    28.0,
                     2.0 * PI,
                     4 * i,
+                    |particle| particle.life *= 0.6 + fastrand::f32() * 0.4,
                 );
             }
         }
     }
 }
 
+/// Chips fragments off big asteroids hit by heavy hits.
+///
+/// The fragment flies off the hit side of the asteroid.
+/// Only [BIG_ASTEROID_MAX_CHIPS] fragments can be chipped off a single big asteroid.
+pub fn big_asteroid_chips(
+    world: &mut World,
+    events: &mut World,
+    cmd: &mut CommandBuffer,
+    fx: &mut FxManager,
+    assets: &AssetManager,
+) {
+    let mut rng = run_rng(world);
+    for (_, event) in events.query_mut::<&DamageEvent>() {
+        //is the hit heavy enough?
+        if event.amount < BIG_ASTEROID_CHIP_DMG {
+            continue;
+        }
+        //get the big asteroid
+        let Ok(mut query) = world.query_one::<(&mut BigAsteroid, &Health, &Position)>(event.who)
+        else {
+            continue;
+        };
+        let Some((big, health, pos)) = query.get() else {
+            continue;
+        };
+        //dead ones split instead
+        if health.hp <= 0.0 || big.chips >= BIG_ASTEROID_MAX_CHIPS {
+            continue;
+        }
+        big.chips += 1;
+        //find the hit side
+        let center = vec2(pos.x, pos.y);
+        let impactor = world
+            .get::<&Position>(event.by)
+            .map(|pos| vec2(pos.x, pos.y))
+            .unwrap_or(center);
+        let normal = (impactor - center)
+            .try_normalize()
+            .unwrap_or_else(|| Vec2::from_angle(rng.f32() * 2.0 * PI));
+        let impact = center + normal * BIG_ASTEROID_SIZE / 2.0;
+        //chip off the fragment
+        cmd.spawn(
+            create_asteroid(impact, normal)
+                .add(BurstXpOnDeath {
+                    amount: BIG_ASTEROID_CHIP_XP,
+                })
+                .add(SpawnGrace::default())
+                .build(),
+        );
+        //crunch
+        fx.burst_particles(
+            Particle {
+                pos: impact,
+                vel: normal * 60.0,
+                life: 0.6,
+                max_life: 0.6,
+                min_size: 0.0,
+                max_size: 8.0,
+                color: LIGHTGRAY,
+                ..Default::default()
+            },
+            20.0,
+            PI / 3.0,
+            10,
+        );
+        assets.audio().play_at("knockback", 0.3, impact);
+    }
+}
+
 /// Spawns asteroids and particles on big asteroid's death.
 pub fn big_asteroid_death(world: &mut World, cmd: &mut CommandBuffer, fx: &mut FxManager) {
+    let stats = config::get().enemies.big_asteroid;
+    let mut rng = run_rng(world);
     for (_, (health, pos, phys, charge)) in world
         .query::<(&Health, &Position, &PhysicsMotion, &ChargeSender)>()
         .with::<&BigAsteroid>()
@@ -375,37 +494,49 @@ pub fn big_asteroid_death(world: &mut World, cmd: &mut CommandBuffer, fx: &mut F
                 let dir =
                     Vec2::from_angle(PI / 2.0 * (i as f32) + if i >= 4 { PI / 4.0 } else { 0.0 })
                         .rotate(Vec2::X)
-                        + phys.vel / BIG_ASTEROID_SPEED;
+                        + phys.vel / stats.speed;
 
                 //let charge = big_charge.force.signum() as i8;
                 let charge = if i >= 4 { -1 } else { 1 } * charge.force.signum() as i8;
 
                 if i < 4 {
-                    create_supercharged_asteroid(vec2(off.x + pos.x, off.y + pos.y), dir, charge)(
-                        world, cmd,
-                    );
+                    create_supercharged_asteroid(
+                        vec2(off.x + pos.x, off.y + pos.y),
+                        dir,
+                        charge,
+                        &mut rng,
+                    )(world, cmd);
                 } else {
                     cmd.spawn(
-                        create_charged_asteroid(vec2(off.x + pos.x, off.y + pos.y), dir, charge)
-                            .build(),
+                        create_charged_asteroid(
+                            vec2(off.x + pos.x, off.y + pos.y),
+                            dir,
+                            charge,
+                            &mut rng,
+                        )
+                        .build(),
                     );
                 }
             }
-            //spawn random particles on destroy
+            //spawn random debris on destroy, slowing down and darkening
             for i in 1..5 {
-                fx.burst_particles(
+                fx.burst_with(
                     Particle {
                         pos: vec2(pos.x, pos.y),
-                        vel: vec2(45.0 * i as f32, 0.0),
+                        vel: vec2(90.0 * i as f32, 0.0),
                         life: 1.0,
                         max_life: 1.0,
                         min_size: 0.0,
                         max_size: 20.0,
                         color: LIGHTGRAY,
+                        drag: 2.0,
+                        end_color: Some(DEBRIS_END_COLOR),
+                        ..Default::default()
                     },
-                    30.0,
+                    60.0,
                     2.0 * PI,
                     8 * i,
+                    |particle| particle.life *= 0.6 + fastrand::f32() * 0.4,
                 );
             }
         }
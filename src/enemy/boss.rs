@@ -0,0 +1,291 @@
+//! Boss logic.
+use std::f32::consts::PI;
+
+use fastrand::Rng;
+use hecs::{CommandBuffer, EntityBuilder, World};
+use macroquad::prelude::*;
+
+use crate::{
+    banner::KillBanner,
+    basic::{
+        fx::{FxManager, Particle},
+        health::create_boss_health_bar,
+        motion::{ChargeSender, KnockbackDealer, LinearTorgue, MaxVelocity, PhysicsMotion},
+        render::Sprite,
+        DamageDealer, Health, HitBox, HurtBox, Position, Rotation, SpawnGrace, Team, Timer,
+        Wrapped,
+    },
+    config,
+    game::run_rng,
+    locale::tr,
+    player::Player,
+    projectile::{self, ProjectileType},
+    xp::BurstXpOnDeath,
+};
+
+use super::{follower::create_follower, Enemy, Stunned};
+
+/// Health of the boss.
+/// A player's shot deals 0.2 damage, so it takes 300 hits.
+pub(crate) const BOSS_HEALTH: f32 = 60.0;
+/// Speed of the boss.
+pub(crate) const BOSS_SPEED: f32 = 30.0;
+/// Acceleration towards the player applied to the boss.
+const BOSS_FOLLOW: f32 = 10.0;
+/// Mass of the boss.
+const BOSS_MASS: f32 = 120.0;
+
+/// Size of the boss.
+/// Also affects Hit/HurtBox sizes.
+pub const BOSS_SIZE: f32 = 240.0;
+/// Scale of the texture of the boss.
+const BOSS_SCALE: f32 = BOSS_SIZE / 512.0;
+
+/// Dmg the boss does while hitting something.
+pub(crate) const BOSS_DMG: f32 = 3.0;
+/// Knockback dealt by the boss collision.
+const BOSS_KNOCKBACK: f32 = 900.0;
+
+/// Texture ID of the boss.
+pub const BOSS_TEX: &str = "asteroid_big";
+/// All texture IDs used by the boss's sprites.
+pub const BOSS_TEXTURES: [&str; 1] = [BOSS_TEX];
+/// Locale key of the boss's name shown on its health bar and banner.
+const BOSS_NAME: &str = "enemy.boss";
+
+/// Health fraction below which the boss enters its second phase.
+const BOSS_PHASE_2: f32 = 0.5;
+
+/// Time between ring bursts in the first phase.
+const BOSS_RING_COOLDOWN: f32 = 3.0;
+/// Amount of projectiles in a ring burst.
+const BOSS_RING_PROJS: u32 = 12;
+/// Speed of projectiles of a ring burst.
+const BOSS_PROJ_SPEED: f32 = 150.0;
+/// Damage of projectiles of a ring burst.
+const BOSS_PROJ_DMG: f32 = 1.5;
+
+/// Time between follower spawns in the second phase.
+const BOSS_SPAWN_COOLDOWN: f32 = 5.0;
+/// Amount of sawblades spawned at once in the second phase.
+const BOSS_SPAWN_COUNT: u32 = 2;
+/// Charge force the boss gains in the second phase.
+const BOSS_FORCE: f32 = 1800.0;
+/// Full radius of charge field of the boss.
+const BOSS_FORCE_F_RADIUS: f32 = 300.0;
+/// Zero radius of charge field of the boss.
+const BOSS_FORCE_RADIUS: f32 = 500.0;
+
+/// Xp dropped by the boss on death.
+const BOSS_XP: u32 = 200;
+
+/// Handles the boss's logic.
+#[derive(Clone, Copy, Debug)]
+pub struct Boss {
+    /// Current phase, starting at 1.
+    pub phase: u8,
+    /// Charge of the boss in the second phase.
+    pub charge: i8,
+    /// Time before the next ring burst or follower spawn.
+    pub attack_timer: Timer,
+}
+
+//-----------------------------------------------------------------------------
+//ENTITY CREATION
+//-----------------------------------------------------------------------------
+
+/// Returns a function that can be used to spawn the boss together
+/// with its health bar.
+/// # Arguments
+/// * `pos` - position of the boss
+/// * `dir` - direction the boss is heading
+/// * `charge` - charge the boss gains in its second phase, same as asteroids
+/// * `rng` - generator of the randomized rotation
+pub fn create_boss(
+    pos: Vec2,
+    dir: Vec2,
+    charge: i8,
+    rng: &mut Rng,
+) -> impl FnOnce(&World, &mut CommandBuffer) {
+    let stats = config::get().enemies.boss;
+    let mut builder = EntityBuilder::default();
+    builder.add_bundle((
+        Enemy,
+        Boss {
+            phase: 1,
+            charge,
+            attack_timer: Timer::new(BOSS_RING_COOLDOWN, true),
+        },
+        Position { x: pos.x, y: pos.y },
+        Rotation {
+            angle: rng.f32() * 2.0 * PI,
+        },
+        LinearTorgue {
+            speed: rng.f32() * 0.5 - 0.25,
+        },
+        PhysicsMotion {
+            vel: dir * stats.speed,
+            mass: BOSS_MASS,
+        },
+        Sprite {
+            texture: BOSS_TEX,
+            scale: BOSS_SCALE,
+            color: WHITE,
+            z_index: 0,
+        },
+        HitBox {
+            radius: BOSS_SIZE / 2.0 - 20.0,
+        },
+        HurtBox {
+            radius: BOSS_SIZE / 2.0 - 20.0,
+        },
+        Health {
+            max_hp: stats.health,
+            hp: stats.health,
+        },
+        DamageDealer { dmg: stats.dmg },
+        Team::Enemy,
+        Wrapped,
+    ));
+    builder.add_bundle((
+        KnockbackDealer {
+            force: BOSS_KNOCKBACK,
+        },
+        BurstXpOnDeath { amount: BOSS_XP },
+        MaxVelocity {
+            max_velocity: stats.speed * 2.0,
+        },
+        KillBanner { name: BOSS_NAME },
+        SpawnGrace::default(),
+    ));
+
+    move |world, cmd| {
+        let boss_id = world.reserve_entity();
+        cmd.insert(boss_id, builder.build());
+        cmd.spawn(create_boss_health_bar(boss_id, &tr!(BOSS_NAME), vec![BOSS_PHASE_2]).build());
+    }
+}
+
+//-----------------------------------------------------------------------------
+//SYSTEM PART
+//-----------------------------------------------------------------------------
+
+/// AI of the boss.
+///
+/// In the first phase it fires rings of projectiles.
+/// Below [BOSS_PHASE_2] of its health it gains a strong charge and spawns sawblades instead.
+pub fn boss_ai(world: &mut World, cmd: &mut CommandBuffer, dt: f32) {
+    //get player's position
+    let Some(player_pos) = world
+        .query_mut::<&Position>()
+        .with::<&Player>()
+        .into_iter()
+        .next()
+        .map(|(_, pos)| vec2(pos.x, pos.y))
+    else {
+        return;
+    };
+    let mut rng = run_rng(world);
+
+    for (id, (boss, pos, vel, health, sprite)) in world
+        .query::<(
+            &mut Boss,
+            &Position,
+            &mut PhysicsMotion,
+            &Health,
+            &mut Sprite,
+        )>()
+        .without::<&Stunned>()
+        .into_iter()
+    {
+        let pos = vec2(pos.x, pos.y);
+        //drift towards the player
+        vel.vel += (player_pos - pos).normalize_or_zero() * BOSS_FOLLOW * dt;
+
+        //enter the second phase
+        if boss.phase == 1 && health.hp / health.max_hp < BOSS_PHASE_2 {
+            boss.phase = 2;
+            boss.attack_timer = Timer::new(BOSS_SPAWN_COOLDOWN, true);
+            sprite.color = if boss.charge > 0 {
+                Color::new(1.0, 0.6, 0.6, 1.0)
+            } else {
+                Color::new(0.6, 1.0, 1.0, 1.0)
+            };
+            cmd.insert_one(
+                id,
+                ChargeSender {
+                    force: BOSS_FORCE * boss.charge as f32,
+                    full_radius: BOSS_FORCE_F_RADIUS,
+                    no_radius: BOSS_FORCE_RADIUS,
+                },
+            );
+        }
+
+        boss.attack_timer.tick(dt);
+        if !boss.attack_timer.just_finished() {
+            continue;
+        }
+        match boss.phase {
+            //ring burst
+            1 => {
+                let granted = projectile::take_enemy_projectiles(world, BOSS_RING_PROJS);
+                let offset = rng.f32() * 2.0 * PI;
+                for i in 0..granted {
+                    let dir = Vec2::from_angle(offset + 2.0 * PI * i as f32 / granted as f32)
+                        .rotate(Vec2::X);
+                    projectile::queue_projectile(
+                        world,
+                        cmd,
+                        projectile::create_projectile(
+                            pos + dir * BOSS_SIZE / 2.0,
+                            dir * BOSS_PROJ_SPEED,
+                            BOSS_PROJ_DMG,
+                            Team::Enemy,
+                            ProjectileType::Medium {
+                                charge: if i % 2 == 0 { 1 } else { -1 },
+                            },
+                        ),
+                    );
+                }
+            }
+            //sawblade spawns
+            _ => {
+                for _ in 0..BOSS_SPAWN_COUNT {
+                    let dir = Vec2::from_angle(rng.f32() * 2.0 * PI).rotate(Vec2::X);
+                    cmd.spawn(
+                        create_follower(pos + dir * BOSS_SIZE / 2.0, dir, boss.charge, &mut rng)
+                            .add(SpawnGrace::default())
+                            .build(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Spawns particles on the boss's death.
+pub fn boss_death(world: &mut World, fx: &mut FxManager) {
+    for (_, (health, pos)) in world.query_mut::<(&Health, &Position)>().with::<&Boss>() {
+        if health.hp > 0.0 {
+            continue;
+        }
+        //spawn random particles on destroy
+        for i in 1..6 {
+            fx.burst_particles(
+                Particle {
+                    pos: vec2(pos.x, pos.y),
+                    vel: vec2(50.0 * i as f32, 0.0),
+                    life: 1.2,
+                    max_life: 1.2,
+                    min_size: 0.0,
+                    max_size: 24.0,
+                    color: LIGHTGRAY,
+                    ..Default::default()
+                },
+                40.0,
+                2.0 * PI,
+                10 * i,
+            );
+        }
+    }
+}
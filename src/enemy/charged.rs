@@ -1,6 +1,7 @@
 //! Supercharged (glowing) asteroid logic.
 use std::f32::consts::PI;
 
+use fastrand::Rng;
 use hecs::{CommandBuffer, Entity, EntityBuilder, World};
 use macroquad::prelude::*;
 
@@ -10,19 +11,27 @@ use crate::{
         motion::{
             ChargeReceiver, ChargeSender, KnockbackDealer, LinearTorgue, MaxVelocity, PhysicsMotion,
         },
-        render::Sprite,
-        DamageDealer, DeleteOnWarp, Health, HitBox, HurtBox, Position, Rotation, Team,
+        render::{self, Sprite},
+        DamageDealer, DeleteOnWarp, Health, HitBox, HurtBox, Position, Rotation, SpawnGrace, Team,
+        Timer,
     },
+    config,
     player::Player,
     projectile::{self, ProjectileType},
     xp::BurstXpOnDeath,
 };
 
 use super::asteroid::*;
-use super::{Enemy, ASTEROID_TEX_NEGATIVE, ASTEROID_TEX_POSITIVE};
+use super::{Enemy, Stunned, ASTEROID_TEX_NEGATIVE, ASTEROID_TEX_POSITIVE};
 
 /// Texture ID of a supercharged asteroid.
 pub const ASTEROID_OUTLINE_TEX: &str = "asteroid_outline";
+/// All texture IDs used by supercharged asteroids' sprites.
+pub const CHARGED_TEXTURES: [&str; 3] = [
+    ASTEROID_TEX_POSITIVE,
+    ASTEROID_TEX_NEGATIVE,
+    ASTEROID_OUTLINE_TEX,
+];
 /// Scale of the texture of an outline of a supercharged asteroid.
 const ASTEROID_OUTLINE_SCALE: f32 = ASTEROID_SIZE / 544.0;
 
@@ -40,7 +49,7 @@ const ASTEROID_CHARGED_XP: u32 = 15;
 #[derive(Clone, Copy, Debug)]
 pub struct ChargedAsteroid {
     /// Time before the next shot.
-    pub cooldown: f32,
+    pub cooldown: Timer,
     /// Reference to the entity making the outline.
     pub outline: Entity,
     /// Charge of the supercharged asteroid.
@@ -58,18 +67,21 @@ pub struct ChargedAsteroid {
 /// * `pos` - position of the supercharged asteroid
 /// * `dir` - direction it is heading
 /// * `charge` - its charge, same as regular asteroid
+/// * `rng` - generator of the randomized rotation
 pub fn create_supercharged_asteroid(
     pos: Vec2,
     dir: Vec2,
     charge: i8,
+    rng: &mut Rng,
 ) -> impl FnOnce(&World, &mut CommandBuffer) {
+    let stats = config::get().enemies.asteroid;
     let texture = if charge > 0 {
         ASTEROID_TEX_POSITIVE
     } else {
         ASTEROID_TEX_NEGATIVE
     };
 
-    let angle = fastrand::f32() * 2.0 * PI;
+    let angle = rng.f32() * 2.0 * PI;
 
     let mut charged_builder = EntityBuilder::default();
 
@@ -78,10 +90,10 @@ pub fn create_supercharged_asteroid(
         Position { x: pos.x, y: pos.y },
         Rotation { angle },
         LinearTorgue {
-            speed: fastrand::f32() * 1.0 - 0.50,
+            speed: rng.f32() * 1.0 - 0.50,
         },
         PhysicsMotion {
-            vel: dir * ASTEROID_SPEED,
+            vel: dir * stats.speed,
             mass: ASTEROID_MASS,
         },
         Sprite {
@@ -99,10 +111,10 @@ pub fn create_supercharged_asteroid(
             radius: ASTEROID_SIZE / 2.0,
         },
         Health {
-            max_hp: ASTEROID_HEALTH,
-            hp: ASTEROID_HEALTH,
+            max_hp: stats.health,
+            hp: stats.health,
         },
-        DamageDealer { dmg: ASTEROID_DMG },
+        DamageDealer { dmg: stats.dmg },
         Team::Enemy,
         DeleteOnWarp,
         ChargeSender {
@@ -120,8 +132,9 @@ pub fn create_supercharged_asteroid(
             amount: ASTEROID_CHARGED_XP,
         },
         MaxVelocity {
-            max_velocity: ASTEROID_SPEED * 2.0,
+            max_velocity: stats.speed * 2.0,
         },
+        SpawnGrace::default(),
     ));
 
     move |world, cmd| {
@@ -129,7 +142,7 @@ pub fn create_supercharged_asteroid(
         let outline_id = world.reserve_entity();
         //embed into charged asteroid
         charged_builder.add(ChargedAsteroid {
-            cooldown: ASTEROID_CHARGED_FIRE_COOLDOWN,
+            cooldown: Timer::new(ASTEROID_CHARGED_FIRE_COOLDOWN, true),
             outline: outline_id,
             charge,
         });
@@ -161,32 +174,41 @@ pub fn create_supercharged_asteroid(
 /// Makes them shoot projectiles periodically.
 pub fn supercharged_asteroid_ai(world: &mut World, cmd: &mut CommandBuffer, dt: f32) {
     //get player pos
-    let (_, &player_pos) = world
+    let Some((_, &player_pos)) = world
         .query_mut::<&Position>()
         .with::<&Player>()
         .into_iter()
         .next()
-        .unwrap();
+    else {
+        return;
+    };
 
-    for (_, (charged, pos)) in world.query_mut::<(&mut ChargedAsteroid, &Position)>() {
+    for (_, (charged, pos)) in world
+        .query::<(&mut ChargedAsteroid, &Position)>()
+        .without::<&Stunned>()
+        .into_iter()
+    {
         //fire logic
-        charged.cooldown -= dt;
-        if charged.cooldown <= 0.0 {
-            charged.cooldown = ASTEROID_CHARGED_FIRE_COOLDOWN;
-
+        charged.cooldown.tick(dt);
+        //skip the shot when there are too many projectiles
+        if charged.cooldown.just_finished() && projectile::take_enemy_projectiles(world, 1) == 1 {
             let delta_x = player_pos.x - pos.x;
             let delta_y = player_pos.y - pos.y;
             let delta = vec2(delta_x, delta_y).normalize_or_zero();
 
-            cmd.spawn(projectile::create_projectile(
-                vec2(pos.x, pos.y),
-                delta * ASTEROID_CHARGED_PROJ_SPEED,
-                ASTEROID_CHARGED_PROJ_DMG,
-                Team::Enemy,
-                ProjectileType::Medium {
-                    charge: charged.charge,
-                },
-            ));
+            projectile::queue_projectile(
+                world,
+                cmd,
+                projectile::create_projectile(
+                    vec2(pos.x, pos.y),
+                    delta * ASTEROID_CHARGED_PROJ_SPEED,
+                    ASTEROID_CHARGED_PROJ_DMG,
+                    Team::Enemy,
+                    ProjectileType::Medium {
+                        charge: charged.charge,
+                    },
+                ),
+            );
         }
     }
 }
@@ -202,7 +224,8 @@ pub fn supercharged_asteroid_death(world: &mut World, cmd: &mut CommandBuffer) {
 
 /// Synchronizes outline with the supercharged asteroid and spawns particles
 /// on its death.
-pub fn supercharged_asteroid_visual(world: &mut World, fx: &mut FxManager) {
+/// With `colorblind` the outline is colored by the colorblind-safe palette.
+pub fn supercharged_asteroid_visual(world: &mut World, fx: &mut FxManager, colorblind: bool) {
     //CHARGING OUTLINE
     for (_, (charged, pos, angle)) in world
         .query::<(&ChargedAsteroid, &Position, &Rotation)>()
@@ -221,20 +244,14 @@ pub fn supercharged_asteroid_visual(world: &mut World, fx: &mut FxManager) {
 
         outline_angle.angle = angle.angle;
 
-        let color_unit = (1.0 - charged.cooldown / ASTEROID_CHARGED_FIRE_COOLDOWN).min(1.0);
-        outline_sprite.color = if charged.charge > 0 {
-            Color {
-                r: color_unit,
-                a: 1.0,
-                ..Default::default()
-            }
-        } else {
-            Color {
-                r: 0.0,
-                g: color_unit,
-                b: color_unit,
-                a: 1.0,
-            }
+        let color_unit =
+            (1.0 - charged.cooldown.remaining() / ASTEROID_CHARGED_FIRE_COOLDOWN).min(1.0);
+        let color = render::charge_color(charged.charge, colorblind);
+        outline_sprite.color = Color {
+            r: color.r * color_unit,
+            g: color.g * color_unit,
+            b: color.b * color_unit,
+            a: 1.0,
         }
     }
     //DEATH PARTICLES
@@ -255,6 +272,7 @@ pub fn supercharged_asteroid_visual(world: &mut World, fx: &mut FxManager) {
                         min_size: 0.0,
                         max_size: 12.0,
                         color: LIGHTGRAY,
+                        ..Default::default()
                     },
                     14.0,
                     2.0 * PI,
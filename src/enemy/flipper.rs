@@ -0,0 +1,241 @@
+//! Charge-flipping trickster logic.
+use std::f32::consts::PI;
+
+use fastrand::Rng;
+use hecs::{EntityBuilder, World};
+use macroquad::prelude::*;
+
+use crate::{
+    basic::{
+        fx::{FxManager, Particle},
+        motion::{
+            ChargeReceiver, ChargeSender, KnockbackDealer, LinearTorgue, MaxVelocity, PhysicsMotion,
+        },
+        render::Sprite,
+        DamageDealer, DeleteOnWarp, Health, HitBox, HurtBox, Position, Rotation, Team, Timer,
+    },
+    config,
+    xp::BurstXpOnDeath,
+};
+
+use super::{Enemy, Stunned, ASTEROID_TEX_NEGATIVE, ASTEROID_TEX_POSITIVE};
+
+/// Health of a flipper.
+pub(crate) const FLIPPER_HEALTH: f32 = 1.0;
+/// Speed of a flipper.
+pub(crate) const FLIPPER_SPEED: f32 = 60.0;
+/// Mass of a flipper.
+const FLIPPER_MASS: f32 = 12.0;
+
+/// Size of a flipper.
+/// Also affects Hit/HurtBox sizes.
+const FLIPPER_SIZE: f32 = 45.0;
+/// Scale of the texture of a flipper.
+const FLIPPER_SCALE: f32 = FLIPPER_SIZE / 512.0;
+
+/// Dmg a flipper does while hitting something.
+pub(crate) const FLIPPER_DMG: f32 = 1.5;
+/// Knockback dealt by a flipper collision.
+const FLIPPER_KNOCKBACK: f32 = 400.0;
+
+/// Charge force of a flipper.
+const FLIPPER_FORCE: f32 = 700.0;
+/// Full radius of charge field of a flipper.
+const FLIPPER_FORCE_F_RADIUS: f32 = 180.0;
+/// Zero radius of charge field of a flipper.
+const FLIPPER_FORCE_RADIUS: f32 = 320.0;
+
+/// Average time between polarity flips.
+const FLIPPER_FLIP_TIME: f32 = 4.0;
+/// Max relative deviation of the flip time of a single flipper.
+/// Keeps groups of flippers from flipping in sync.
+const FLIPPER_FLIP_JITTER: f32 = 0.2;
+/// Time before a flip during which the flipper flashes.
+const FLIPPER_TELEGRAPH_TIME: f32 = 0.5;
+/// Blinks per second of the flashing flipper.
+const FLIPPER_BLINK_RATE: f32 = 12.0;
+
+/// Xp dropped by a flipper on death.
+const FLIPPER_XP: u32 = 15;
+
+/// Handles flipper's logic.
+#[derive(Clone, Copy, Debug)]
+pub struct Flipper {
+    /// Current charge of the flipper.
+    /// 1 => positive
+    /// -1 => negative
+    pub charge: i8,
+    /// Time before the next flip.
+    pub flip_timer: Timer,
+    /// Was the coming flip already telegraphed by a flash?
+    pub flashed: bool,
+}
+
+//-----------------------------------------------------------------------------
+//ENTITY CREATION
+//-----------------------------------------------------------------------------
+
+/// Creates a flipper.
+/// # Arguments
+/// * `pos` - position of the flipper
+/// * `dir` - direction the flipper is heading
+/// * `charge` - initial charge of the flipper, same as charged asteroids
+/// * `rng` - generator of the randomized rotation and flip time
+pub fn create_flipper(pos: Vec2, dir: Vec2, charge: i8, rng: &mut Rng) -> EntityBuilder {
+    let stats = config::get().enemies.flipper;
+    let texture = if charge > 0 {
+        ASTEROID_TEX_POSITIVE
+    } else {
+        ASTEROID_TEX_NEGATIVE
+    };
+    let flip_time = FLIPPER_FLIP_TIME * (1.0 + FLIPPER_FLIP_JITTER * (2.0 * rng.f32() - 1.0));
+
+    let mut builder = EntityBuilder::default();
+    builder.add_bundle((
+        Enemy,
+        Flipper {
+            charge,
+            flip_timer: Timer::new(flip_time, true),
+            flashed: false,
+        },
+        Position { x: pos.x, y: pos.y },
+        Rotation {
+            angle: rng.f32() * 2.0 * PI,
+        },
+        LinearTorgue {
+            speed: rng.f32() * 2.0 - 1.0,
+        },
+        PhysicsMotion {
+            vel: dir * stats.speed,
+            mass: FLIPPER_MASS,
+        },
+        Sprite {
+            texture,
+            scale: FLIPPER_SCALE,
+            color: WHITE,
+            z_index: 0,
+        },
+        HitBox {
+            radius: FLIPPER_SIZE / 2.0,
+        },
+        HurtBox {
+            radius: FLIPPER_SIZE / 2.0,
+        },
+        Health {
+            max_hp: stats.health,
+            hp: stats.health,
+        },
+        DamageDealer { dmg: stats.dmg },
+        Team::Enemy,
+    ));
+    builder.add_bundle((
+        DeleteOnWarp,
+        ChargeSender {
+            force: FLIPPER_FORCE * charge as f32,
+            full_radius: FLIPPER_FORCE_F_RADIUS,
+            no_radius: FLIPPER_FORCE_RADIUS,
+        },
+        ChargeReceiver {
+            multiplier: charge as f32,
+        },
+        KnockbackDealer {
+            force: FLIPPER_KNOCKBACK,
+        },
+        BurstXpOnDeath { amount: FLIPPER_XP },
+        MaxVelocity {
+            max_velocity: stats.speed * 2.0,
+        },
+    ));
+    builder
+}
+
+//-----------------------------------------------------------------------------
+//SYSTEM PART
+//-----------------------------------------------------------------------------
+
+/// AI of flippers.
+/// Flips their polarity when their timer runs out.
+pub fn flipper_ai(world: &mut World, dt: f32) {
+    for (_, (flipper, sender, receiver)) in world
+        .query_mut::<(&mut Flipper, &mut ChargeSender, &mut ChargeReceiver)>()
+        .without::<&Stunned>()
+    {
+        flipper.flip_timer.tick(dt);
+        if flipper.flip_timer.just_finished() {
+            flipper.charge = -flipper.charge;
+            flipper.flashed = false;
+            //change charge
+            sender.force = FLIPPER_FORCE * flipper.charge as f32;
+            receiver.multiplier = flipper.charge as f32;
+        }
+    }
+}
+
+/// Changes flippers' textures based on their polarity and
+/// telegraphs coming flips by flashing.
+pub fn flipper_visual(world: &mut World, fx: &mut FxManager) {
+    for (_, (flipper, sprite, pos)) in world.query_mut::<(&mut Flipper, &mut Sprite, &Position)>() {
+        //change texture based on polarity
+        sprite.texture = if flipper.charge > 0 {
+            ASTEROID_TEX_POSITIVE
+        } else {
+            ASTEROID_TEX_NEGATIVE
+        };
+
+        let remaining = flipper.flip_timer.remaining();
+        if remaining > FLIPPER_TELEGRAPH_TIME {
+            sprite.color = WHITE;
+            continue;
+        }
+        //white flash when the telegraph starts
+        if !flipper.flashed {
+            flipper.flashed = true;
+            fx.add_particle(Particle {
+                pos: vec2(pos.x, pos.y),
+                vel: Vec2::ZERO,
+                life: 0.15,
+                max_life: 0.15,
+                min_size: FLIPPER_SIZE / 4.0,
+                max_size: FLIPPER_SIZE / 2.0,
+                color: WHITE,
+                ..Default::default()
+            });
+        }
+        //then blink until the flip
+        let blink = ((remaining * FLIPPER_BLINK_RATE) as u32).is_multiple_of(2);
+        sprite.color = if blink {
+            WHITE
+        } else {
+            Color::new(1.0, 1.0, 1.0, 0.4)
+        };
+    }
+}
+
+/// Spawns particles on flipper's death.
+pub fn flipper_death(world: &mut World, fx: &mut FxManager) {
+    for (_, (flipper, health, pos)) in world.query_mut::<(&Flipper, &Health, &Position)>() {
+        if health.hp > 0.0 {
+            continue;
+        }
+        //spawn random particles on destroy
+        fx.burst_particles(
+            Particle {
+                pos: vec2(pos.x, pos.y),
+                vel: vec2(40.0, 0.0),
+                life: 1.0,
+                max_life: 1.0,
+                min_size: 0.0,
+                max_size: 12.0,
+                color: if flipper.charge > 0 {
+                    RED
+                } else {
+                    Color::new(0.0, 1.0, 1.0, 1.0)
+                },
+                ..Default::default()
+            },
+            20.0,
+            2.0 * PI,
+            12,
+        );
+    }
+}
@@ -1,6 +1,7 @@
 //! Sawblade logic.
 use std::f32::consts::PI;
 
+use fastrand::Rng;
 use hecs::{EntityBuilder, World};
 use macroquad::prelude::*;
 
@@ -8,19 +9,20 @@ use crate::{
     basic::{
         fx::{FxManager, Particle},
         motion::{ChargeReceiver, KnockbackDealer, LinearTorgue, MaxVelocity, PhysicsMotion},
-        render::Sprite,
+        render::{self, Sprite},
         DamageDealer, Health, HitBox, HurtBox, Position, Rotation, Team,
     },
+    config,
     player::Player,
     xp::BurstXpOnDeath,
 };
 
-use super::Enemy;
+use super::{stagger, Enemy, Stunned};
 
 /// Health of a sawblade.
-const FOLLOWER_HEALTH: f32 = 0.8;
+pub(crate) const FOLLOWER_HEALTH: f32 = 0.8;
 /// Speed of a sawblade.
-const FOLLOWER_SPEED: f32 = 240.0;
+pub(crate) const FOLLOWER_SPEED: f32 = 240.0;
 /// Acceleration towards player of a sawblade.
 const FOLLOWER_SPEED_CHANGE: f32 = 400.0;
 /// Mass of a sawblade.
@@ -31,7 +33,7 @@ const FOLLOWER_MASS: f32 = 4.0;
 const FOLLOWER_SIZE: f32 = 40.0;
 
 /// Damage sawblade does on hit.
-const FOLLOWER_DMG: f32 = 2.0;
+pub(crate) const FOLLOWER_DMG: f32 = 2.0;
 
 /// Texture ID of neutral sawblade.
 pub const FOLLOWER_TEX_NEUTRAL: &str = "follower";
@@ -39,6 +41,12 @@ pub const FOLLOWER_TEX_NEUTRAL: &str = "follower";
 pub const FOLLOWER_TEX_POSITIVE: &str = "follower_plus";
 /// Texture ID of negatively charged sawblade.
 pub const FOLLOWER_TEX_NEGATIVE: &str = "follower_negative";
+/// All texture IDs used by sawblades' sprites.
+pub const FOLLOWER_TEXTURES: [&str; 3] = [
+    FOLLOWER_TEX_NEUTRAL,
+    FOLLOWER_TEX_POSITIVE,
+    FOLLOWER_TEX_NEGATIVE,
+];
 
 /// Knockback force dealt on hit by a sawblade.
 const FOLLOWER_KNOCKBACK: f32 = 150.0;
@@ -54,6 +62,9 @@ pub struct Follower {
     /// 0 => neutral
     /// -1 => negative
     pub charge: i8,
+    /// Direction towards the player.
+    /// Recomputed only on the sawblade's AI slice.
+    pub target_dir: Vec2,
 }
 
 //-----------------------------------------------------------------------------
@@ -65,20 +76,25 @@ pub struct Follower {
 /// * `pos` - position of the sawblade
 /// * `dir` - direction the sawblade is initially heading
 /// * `charge` - charge of the sawblade, same as asteroids
-pub fn create_follower(pos: Vec2, dir: Vec2, charge: i8) -> EntityBuilder {
+/// * `rng` - generator of the randomized rotation
+pub fn create_follower(pos: Vec2, dir: Vec2, charge: i8, rng: &mut Rng) -> EntityBuilder {
+    let stats = config::get().enemies.follower;
     let mut builder = EntityBuilder::default();
     builder.add_bundle((
         Enemy,
-        Follower { charge },
+        Follower {
+            charge,
+            target_dir: dir,
+        },
         Position { x: pos.x, y: pos.y },
         Rotation {
-            angle: fastrand::f32() * 2.0 * PI,
+            angle: rng.f32() * 2.0 * PI,
         },
         LinearTorgue {
-            speed: fastrand::f32() * 30.0 - 15.0,
+            speed: rng.f32() * 30.0 - 15.0,
         },
         PhysicsMotion {
-            vel: dir * FOLLOWER_SPEED,
+            vel: dir * stats.speed,
             mass: FOLLOWER_MASS,
         },
         Sprite {
@@ -102,16 +118,16 @@ pub fn create_follower(pos: Vec2, dir: Vec2, charge: i8) -> EntityBuilder {
         KnockbackDealer {
             force: FOLLOWER_KNOCKBACK,
         },
-        DamageDealer { dmg: FOLLOWER_DMG },
+        DamageDealer { dmg: stats.dmg },
         Health {
-            max_hp: FOLLOWER_HEALTH,
-            hp: FOLLOWER_HEALTH,
+            max_hp: stats.health,
+            hp: stats.health,
         },
         BurstXpOnDeath {
             amount: FOLLOWER_XP,
         },
         MaxVelocity {
-            max_velocity: FOLLOWER_SPEED * 2.0,
+            max_velocity: stats.speed * 2.0,
         },
     ));
 
@@ -132,32 +148,40 @@ pub fn create_follower(pos: Vec2, dir: Vec2, charge: i8) -> EntityBuilder {
 ///
 /// Makes the sawblade attracted to the player.
 pub fn follower_ai(world: &mut World, dt: f32) {
+    let stats = config::get().enemies.follower;
     //get player's position
-    let (_, &player_pos) = world
+    let Some((_, &player_pos)) = world
         .query_mut::<&Position>()
         .with::<&Player>()
         .into_iter()
         .next()
-        .unwrap();
+    else {
+        return;
+    };
+    let stagger = stagger(world);
     //update velocity
-    for (_, (pos, vel)) in world
-        .query_mut::<(&Position, &mut PhysicsMotion)>()
-        .with::<&Follower>()
+    for (id, (pos, vel, follower)) in world
+        .query_mut::<(&Position, &mut PhysicsMotion, &mut Follower)>()
+        .without::<&Stunned>()
     {
+        //retarget on its own slice
+        if stagger.should_think(id) {
+            follower.target_dir =
+                vec2(player_pos.x - pos.x, player_pos.y - pos.y).normalize_or_zero();
+        }
         //speed up towards player
-        let acceleration = vec2(player_pos.x - pos.x, player_pos.y - pos.y).normalize_or_zero()
-            * FOLLOWER_SPEED_CHANGE
-            * dt;
+        let acceleration = follower.target_dir * FOLLOWER_SPEED_CHANGE * dt;
         vel.vel += acceleration;
         //clamp speed
-        if vel.vel.length() > FOLLOWER_SPEED {
-            vel.vel = vel.vel.normalize_or_zero() * FOLLOWER_SPEED;
+        if vel.vel.length() > stats.speed {
+            vel.vel = vel.vel.normalize_or_zero() * stats.speed;
         }
     }
 }
 
 /// Spawns sawblade's trail.
-pub fn follower_fx(world: &mut World, fx: &mut FxManager) {
+/// With `colorblind` its color comes from the colorblind-safe palette.
+pub fn follower_fx(world: &mut World, fx: &mut FxManager, colorblind: bool) {
     for (_, (follower, pos)) in world.query_mut::<(&Follower, &Position)>() {
         fx.burst_particles(
             Particle {
@@ -167,14 +191,8 @@ pub fn follower_fx(world: &mut World, fx: &mut FxManager) {
                 max_life: 0.4,
                 min_size: 0.0,
                 max_size: 4.0,
-                color: match follower.charge {
-                    1 => RED,
-                    0 => GREEN,
-                    -1 => Color::new(0.0, 1.0, 1.0, 1.0),
-                    _ => {
-                        unimplemented!("Followers do not support charges different than 0,1,-1")
-                    }
-                },
+                color: render::charge_color(follower.charge, colorblind),
+                ..Default::default()
             },
             0.0,
             0.0,
@@ -198,6 +216,7 @@ pub fn follower_death(world: &mut World, fx: &mut FxManager) {
                         min_size: 0.0,
                         max_size: 12.0,
                         color: LIGHTGRAY,
+                        ..Default::default()
                     },
                     14.0,
                     2.0 * PI,
@@ -220,6 +239,7 @@ pub fn follower_death(world: &mut World, fx: &mut FxManager) {
                             unimplemented!("Followers do not support charges different than 0,1,-1")
                         }
                     },
+                    ..Default::default()
                 },
                 5.0,
                 2.0 * PI,
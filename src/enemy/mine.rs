@@ -2,6 +2,7 @@
 
 use std::f32::consts::PI;
 
+use fastrand::Rng;
 use hecs::{CommandBuffer, EntityBuilder, World};
 use macroquad::prelude::*;
 
@@ -11,19 +12,22 @@ use crate::{
         motion::{
             ChargeReceiver, ChargeSender, KnockbackDealer, LinearTorgue, MaxVelocity, PhysicsMotion,
         },
-        render::Sprite,
-        DamageDealer, DeleteOnWarp, Health, HitBox, HurtBox, Position, Rotation, Team,
+        render::{self, AnimatedSprite, Sprite},
+        rumble::Rumble,
+        DamageDealer, DeleteOnWarp, Health, HitBox, HurtBox, Position, Rotation, Team, Timer,
     },
+    config,
+    player::Player,
     projectile::ProjectileType,
     xp::BurstXpOnDeath,
 };
 
-use super::Enemy;
+use super::{Enemy, Stunned};
 
 /// Health of a mine.
-const MINE_HEALTH: f32 = 0.5;
+pub(crate) const MINE_HEALTH: f32 = 0.5;
 /// Speed of a mine.
-const MINE_SPEED: f32 = 60.0;
+pub(crate) const MINE_SPEED: f32 = 60.0;
 /// Mass of a mine.
 const MINE_MASS: f32 = 4.0;
 
@@ -32,7 +36,7 @@ const MINE_MASS: f32 = 4.0;
 const MINE_SIZE: f32 = 60.0;
 
 /// Damage the mine deals on collision.
-const MINE_DMG: f32 = 1.5;
+pub(crate) const MINE_DMG: f32 = 1.5;
 
 /// Texture ID of neutral mine.
 pub const MINE_TEX_NEUTRAL: &str = "mine";
@@ -40,6 +44,26 @@ pub const MINE_TEX_NEUTRAL: &str = "mine";
 pub const MINE_TEX_POSITIVE: &str = "mine_plus";
 /// Texture ID of negatively charged mine.
 pub const MINE_TEX_NEGATIVE: &str = "mine_negative";
+/// Texture ID of the atlas of mine's blinking light.
+pub const MINE_LIGHT_TEX: &str = "mine_light";
+/// All texture IDs used by mines' sprites.
+pub const MINE_TEXTURES: [&str; 4] = [
+    MINE_TEX_NEUTRAL,
+    MINE_TEX_POSITIVE,
+    MINE_TEX_NEGATIVE,
+    MINE_LIGHT_TEX,
+];
+
+/// Size of a single frame of the light's atlas.
+const MINE_LIGHT_FRAME: Vec2 = vec2(16.0, 16.0);
+/// Amount of frames of the light, off and on.
+const MINE_LIGHT_FRAMES: u32 = 2;
+/// Frames per second of the light right after the mine is spawned.
+const MINE_LIGHT_MIN_FPS: f32 = 2.0;
+/// Frames per second of the light right before the detonation.
+const MINE_LIGHT_MAX_FPS: f32 = 24.0;
+/// Green and blue of the tint of a growing mine when flashing is reduced.
+const MINE_STEADY_TINT: f32 = 0.4;
 
 /// Charge force of a mine.
 const MINE_FORCE: f32 = 200.0;
@@ -52,22 +76,33 @@ const MINE_FORCE_RADIUS: f32 = 200.0;
 const MINE_KNOCKBACK: f32 = 250.0;
 
 /// Time before the mine detonates by itself.
-const MINE_DETONATION_TIMER: f32 = 4.0;
+pub(crate) const MINE_DETONATION_TIMER: f32 = 4.0;
 /// Time before detonation after which the mine starts to grow in size.
 const MINE_DETONATION_GROWING_TIMER: f32 = 1.0;
 
 /// Speed of the projectiles created by the mine.
 const MINE_PROJ_SPEED: f32 = 200.0;
+/// Time fragments of a mine live for.
+/// Shorter than player's shots, the burst only threatens the area around the mine.
+const MINE_PROJ_LIFETIME: f32 = 1.5;
 /// Damage of the projectiles created by the mine.
 const MINE_PROJ_DMG: f32 = 2.0;
 
+/// Distance from the player at which growing mines rumble.
+const MINE_RUMBLE_RADIUS: f32 = 150.0;
+
 /// Xp dropped by the mine on death.
 const MINE_XP: u32 = 20;
 
 /// Handles all of Mine AI.
 #[derive(Clone, Copy, Debug, Default)]
 pub struct Mine {
-    pub timer: f32,
+    /// Time before the mine detonates.
+    pub timer: Timer,
+    /// Charge of the mine.
+    /// 1 => positive
+    /// 0 => neutral
+    /// -1 => negative
     pub charge: i8,
 }
 
@@ -80,7 +115,9 @@ pub struct Mine {
 /// * `pos` - position of the mine
 /// * `dir` - direction of the mine
 /// * `charge` - charge of the mine, same as asteroids
-pub fn create_mine(pos: Vec2, dir: Vec2, charge: i8) -> EntityBuilder {
+/// * `rng` - generator of the randomized rotation
+pub fn create_mine(pos: Vec2, dir: Vec2, charge: i8, rng: &mut Rng) -> EntityBuilder {
+    let stats = config::get().enemies.mine;
     let texture = match charge {
         1 => MINE_TEX_POSITIVE,
         -1 => MINE_TEX_NEGATIVE,
@@ -93,18 +130,18 @@ pub fn create_mine(pos: Vec2, dir: Vec2, charge: i8) -> EntityBuilder {
     builder.add_bundle((
         Enemy,
         Mine {
-            timer: MINE_DETONATION_TIMER,
+            timer: Timer::new(MINE_DETONATION_TIMER, false),
             charge,
         },
         Position { x: pos.x, y: pos.y },
         Rotation {
-            angle: fastrand::f32() * 2.0 * PI,
+            angle: rng.f32() * 2.0 * PI,
         },
         LinearTorgue {
-            speed: fastrand::f32() * 1.0 - 0.50,
+            speed: rng.f32() * 1.0 - 0.50,
         },
         PhysicsMotion {
-            vel: dir * MINE_SPEED,
+            vel: dir * stats.speed,
             mass: MINE_MASS,
         },
         Sprite {
@@ -113,6 +150,16 @@ pub fn create_mine(pos: Vec2, dir: Vec2, charge: i8) -> EntityBuilder {
             color: WHITE,
             z_index: 0,
         },
+        AnimatedSprite {
+            texture: MINE_LIGHT_TEX,
+            frame_size: MINE_LIGHT_FRAME,
+            frames: MINE_LIGHT_FRAMES,
+            fps: MINE_LIGHT_MIN_FPS,
+            time: 0.0,
+            scale: 1.0,
+            color: WHITE,
+            z_index: 1,
+        },
         HitBox {
             radius: MINE_SIZE / 2.0,
         },
@@ -122,10 +169,10 @@ pub fn create_mine(pos: Vec2, dir: Vec2, charge: i8) -> EntityBuilder {
             radius: MINE_SIZE / 2.0,
         },
         Health {
-            max_hp: MINE_HEALTH,
-            hp: MINE_HEALTH,
+            max_hp: stats.health,
+            hp: stats.health,
         },
-        DamageDealer { dmg: MINE_DMG },
+        DamageDealer { dmg: stats.dmg },
         Team::Enemy,
         DeleteOnWarp,
         ChargeSender {
@@ -141,7 +188,7 @@ pub fn create_mine(pos: Vec2, dir: Vec2, charge: i8) -> EntityBuilder {
         },
         BurstXpOnDeath { amount: MINE_XP },
         MaxVelocity {
-            max_velocity: MINE_SPEED * 2.0,
+            max_velocity: stats.speed * 2.0,
         },
     ));
     builder
@@ -153,35 +200,88 @@ pub fn create_mine(pos: Vec2, dir: Vec2, charge: i8) -> EntityBuilder {
 
 /// Handles mines' detonations and makes them dead when timer ran out.
 pub fn mine_ai(world: &mut World, dt: f32) {
-    for (_, (health, mine)) in world.query_mut::<(&mut Health, &mut Mine)>() {
+    for (_, (health, mine)) in world
+        .query_mut::<(&mut Health, &mut Mine)>()
+        .without::<&Stunned>()
+    {
         //bring detonation timer closer to death
-        mine.timer -= dt;
+        mine.timer.tick(dt);
         //if timer dead, explode imediately
-        if mine.timer <= 0.0 {
+        if mine.timer.finished() {
             health.hp = -69.0;
         }
     }
 }
 
+/// Blinks mines' lights faster as the timer gets closer to detonation.
 /// Grows mines when the timer is close to detonation.
-pub fn mine_fx(world: &mut World) {
-    for (_, (mine, sprite)) in world.query_mut::<(&Mine, &mut Sprite)>() {
-        if mine.timer <= MINE_DETONATION_GROWING_TIMER {
-            sprite.scale = (MINE_SIZE / 512.0) * (2.0 - mine.timer / MINE_DETONATION_GROWING_TIMER);
-            sprite.color.g = mine.timer / MINE_DETONATION_GROWING_TIMER;
-            sprite.color.b = mine.timer / MINE_DETONATION_GROWING_TIMER;
+/// Also rumbles when a growing mine is close to the player.
+///
+/// With `reduce_flashing` the lights blink slowly and growing mines get a steady red tint.
+pub fn mine_fx(world: &mut World, reduce_flashing: bool) {
+    //get player's position
+    let player_pos = world
+        .query_mut::<&Position>()
+        .with::<&Player>()
+        .into_iter()
+        .next()
+        .map(|(_, pos)| vec2(pos.x, pos.y));
+    let mut rumble_strength: f32 = 0.0;
+
+    for (_, (mine, sprite, light, pos)) in
+        world.query_mut::<(&Mine, &mut Sprite, &mut AnimatedSprite, &Position)>()
+    {
+        let progress = mine.timer.elapsed / mine.timer.duration;
+        light.fps = if reduce_flashing {
+            MINE_LIGHT_MIN_FPS
+        } else {
+            MINE_LIGHT_MIN_FPS + (MINE_LIGHT_MAX_FPS - MINE_LIGHT_MIN_FPS) * progress
+        };
+        if mine.timer.remaining() <= MINE_DETONATION_GROWING_TIMER {
+            //rising rumble when close
+            if let Some(player_pos) = player_pos {
+                if player_pos.distance(vec2(pos.x, pos.y)) <= MINE_RUMBLE_RADIUS {
+                    rumble_strength = rumble_strength
+                        .max(0.6 * (1.0 - mine.timer.remaining() / MINE_DETONATION_GROWING_TIMER));
+                }
+            }
+            sprite.scale = (MINE_SIZE / 512.0)
+                * (2.0 - mine.timer.remaining() / MINE_DETONATION_GROWING_TIMER);
+            let tint = if reduce_flashing {
+                MINE_STEADY_TINT
+            } else {
+                mine.timer.remaining() / MINE_DETONATION_GROWING_TIMER
+            };
+            sprite.color.g = tint;
+            sprite.color.b = tint;
         }
     }
+
+    for (_, rumble) in world.query_mut::<&mut Rumble>() {
+        rumble.hold(rumble_strength);
+    }
 }
 
 /// Spawns projectiles when the mine is dead.
-/// Also handles particles spawned on death.
-pub fn mine_death(world: &mut World, cmd: &mut CommandBuffer, fx: &mut FxManager) {
+/// Also handles particles spawned on death,
+/// colored by the colorblind-safe palette with `colorblind`.
+pub fn mine_death(
+    world: &mut World,
+    cmd: &mut CommandBuffer,
+    fx: &mut FxManager,
+    colorblind: bool,
+) {
     for (_, (health, pos, mine)) in world.query::<(&Health, &Position, &Mine)>().into_iter() {
         //check if it is dead
         if health.hp <= 0.0 {
+            //thin the slower inner ring first when there are too many projectiles
+            let granted = crate::projectile::take_enemy_projectiles(world, 16);
+            let outer = granted.min(8);
+            let inner = granted - outer;
+            let ring = (0..outer).map(|k| k * 8 / outer);
+            let inner_ring = (0..inner).map(|k| 8 + k * 8 / inner);
             //spawn many smaller projectiles of the same charge
-            for i in 0..16 {
+            for i in ring.chain(inner_ring) {
                 let dir =
                     Vec2::from_angle(PI / 4.0 * (i as f32) + if i >= 8 { PI / 8.0 } else { 0.0 })
                         .rotate(Vec2::X);
@@ -191,15 +291,22 @@ pub fn mine_death(world: &mut World, cmd: &mut CommandBuffer, fx: &mut FxManager
                     _ => unreachable!(),
                 };
 
-                cmd.spawn(crate::projectile::create_projectile(
-                    vec2(pos.x, pos.y),
-                    dir * speed,
-                    MINE_PROJ_DMG,
-                    Team::Enemy,
-                    ProjectileType::Medium {
-                        charge: mine.charge,
-                    },
-                ));
+                crate::projectile::queue_projectile(
+                    world,
+                    cmd,
+                    crate::projectile::with_lifetime(
+                        crate::projectile::create_projectile(
+                            vec2(pos.x, pos.y),
+                            dir * speed,
+                            MINE_PROJ_DMG,
+                            Team::Enemy,
+                            ProjectileType::Medium {
+                                charge: mine.charge,
+                            },
+                        ),
+                        MINE_PROJ_LIFETIME,
+                    ),
+                );
             }
             //spawn random particles on destroy
             for i in 1..5 {
@@ -211,16 +318,8 @@ pub fn mine_death(world: &mut World, cmd: &mut CommandBuffer, fx: &mut FxManager
                         max_life: 1.0,
                         min_size: 0.0,
                         max_size: 5.0,
-                        color: match mine.charge {
-                            1 => RED,
-                            0 => GREEN,
-                            -1 => Color::new(0.0, 1.0, 1.0, 1.0),
-                            _ => {
-                                unimplemented!(
-                                    "Followers do not support charges different than 0,1,-1"
-                                )
-                            }
-                        },
+                        color: render::charge_color(mine.charge, colorblind),
+                        ..Default::default()
                     },
                     5.0,
                     2.0 * PI,
@@ -230,3 +329,59 @@ pub fn mine_death(world: &mut World, cmd: &mut CommandBuffer, fx: &mut FxManager
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        basic::motion::PhysicsMotion,
+        projectile::{self, Projectile, ProjectileBatch, ProjectileBudget, ENEMY_PROJECTILE_CAP},
+    };
+
+    #[test]
+    fn simultaneous_detonations_respect_cap() {
+        let mut world = World::new();
+        let mut cmd = CommandBuffer::new();
+        let mut fx = FxManager::new(1000);
+        let mut rng = Rng::with_seed(1500);
+        world.spawn((ProjectileBudget::default(),));
+        world.spawn((ProjectileBatch::default(),));
+        //enemy projectiles already flying
+        let flying = 10;
+        for _ in 0..flying {
+            world.spawn(projectile::create_projectile(
+                Vec2::ZERO,
+                Vec2::X,
+                1.0,
+                Team::Enemy,
+                ProjectileType::Medium { charge: 0 },
+            ));
+        }
+        //three mines detonating in the same frame
+        for charge in [-1, 0, 1] {
+            world.spawn(create_mine(vec2(400.0, 300.0), Vec2::X, charge, &mut rng).build());
+        }
+
+        projectile::count_enemy_projectiles(&mut world);
+        mine_ai(&mut world, MINE_DETONATION_TIMER);
+        mine_death(&mut world, &mut cmd, &mut fx, false);
+        cmd.run_on(&mut world);
+        projectile::spawn_projectiles(&mut world);
+
+        let speeds: Vec<_> = world
+            .query_mut::<&PhysicsMotion>()
+            .with::<&Projectile>()
+            .into_iter()
+            .map(|(_, physics)| physics.vel.length())
+            .collect();
+        assert_eq!(speeds.len() as u32, ENEMY_PROJECTILE_CAP);
+        //the slower inner ring of the last mine is thinned first
+        let fragments = ENEMY_PROJECTILE_CAP - flying;
+        let inner = speeds
+            .iter()
+            .filter(|speed| (**speed - MINE_PROJ_SPEED / 2.0).abs() < 0.01)
+            .count() as u32;
+        assert_eq!(inner, 16);
+        assert_eq!(fragments - inner, 8 + 8 + 6);
+    }
+}
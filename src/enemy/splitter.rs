@@ -0,0 +1,195 @@
+//! Splitter asteroid logic.
+use std::f32::consts::PI;
+
+use fastrand::Rng;
+use hecs::{CommandBuffer, EntityBuilder, World};
+use macroquad::prelude::*;
+
+use crate::{
+    basic::{
+        fx::{FxManager, Particle},
+        motion::{KnockbackDealer, LinearTorgue, MaxVelocity, PhysicsMotion},
+        render::Sprite,
+        DamageDealer, DeleteOnWarp, Health, HitBox, HurtBox, Position, Rotation, SpawnGrace, Team,
+    },
+    config,
+    game::run_rng,
+    xp::BurstXpOnDeath,
+};
+
+use super::{Enemy, ASTEROID_TEX_NEUTRAL};
+
+/// Health of a splitter asteroid of any generation.
+pub(crate) const SPLITTER_HEALTH: f32 = 1.0;
+/// Speed of a splitter asteroid.
+pub(crate) const SPLITTER_SPEED: f32 = 55.0;
+/// Mass of the first generation of a splitter asteroid.
+const SPLITTER_MASS: f32 = 24.0;
+
+/// Size of the first generation of a splitter asteroid.
+/// Every next generation is half the size.
+/// Also affects Hit/HurtBox sizes.
+const SPLITTER_SIZE: f32 = 100.0;
+
+/// Dmg the first generation of a splitter asteroid does while hitting something.
+pub(crate) const SPLITTER_DMG: f32 = 2.0;
+
+/// Knockback dealt by the first generation of a splitter asteroid.
+const SPLITTER_KNOCKBACK: f32 = 600.0;
+
+/// Amount of generations, the last one dies instead of splitting.
+const SPLITTER_GENERATIONS: u8 = 3;
+/// Speed of the sideways kick given to split children.
+const SPLITTER_KICK: f32 = 60.0;
+
+/// Xp dropped by a splitter asteroid of the last generation.
+const SPLITTER_XP: u32 = 4;
+
+/// Handles splitter asteroid's logic.
+#[derive(Clone, Copy, Debug)]
+pub struct SplitterAsteroid {
+    /// Generation of the asteroid, starting at 0.
+    pub generation: u8,
+}
+
+/// Scale of a splitter asteroid of generation `generation`,
+/// relative to the first generation.
+#[inline]
+fn generation_scale(generation: u8) -> f32 {
+    0.5f32.powi(generation as i32)
+}
+
+//------------------------------------------------------------------------------
+//ENTITY CREATION
+//------------------------------------------------------------------------------
+
+/// Creates a splitter asteroid.
+/// # Arguments
+/// * `pos` - position of the asteroid
+/// * `dir` - velocity of the asteroid relative to its speed
+/// * `generation` - generation of the asteroid, 0 is the largest one
+/// * `rng` - generator of the randomized rotation
+pub fn create_splitter_asteroid(
+    pos: Vec2,
+    dir: Vec2,
+    generation: u8,
+    rng: &mut Rng,
+) -> EntityBuilder {
+    let stats = config::get().enemies.splitter;
+    let scale = generation_scale(generation);
+    let size = SPLITTER_SIZE * scale;
+
+    let mut builder = EntityBuilder::new();
+    builder.add_bundle((
+        Enemy,
+        SplitterAsteroid { generation },
+        Position { x: pos.x, y: pos.y },
+        Rotation {
+            angle: rng.f32() * 2.0 * PI,
+        },
+        LinearTorgue {
+            speed: rng.f32() * 1.0 - 0.50,
+        },
+        PhysicsMotion {
+            vel: dir * stats.speed,
+            mass: SPLITTER_MASS * scale,
+        },
+        Sprite {
+            texture: ASTEROID_TEX_NEUTRAL,
+            scale: size / 512.0,
+            color: WHITE,
+            z_index: 0,
+        },
+        HitBox {
+            radius: size / 2.0 * 0.85,
+        },
+        HurtBox {
+            radius: size / 2.0 * 0.85,
+        },
+        Health {
+            max_hp: stats.health,
+            hp: stats.health,
+        },
+        DamageDealer {
+            dmg: stats.dmg * scale,
+        },
+        Team::Enemy,
+        DeleteOnWarp,
+    ));
+    builder.add_bundle((
+        KnockbackDealer {
+            force: SPLITTER_KNOCKBACK * scale,
+        },
+        MaxVelocity {
+            max_velocity: stats.speed * 3.0,
+        },
+    ));
+    //only the last generation drops xp
+    if generation + 1 >= SPLITTER_GENERATIONS {
+        builder.add(BurstXpOnDeath {
+            amount: SPLITTER_XP,
+        });
+    }
+    builder
+}
+
+//------------------------------------------------------------------------------
+//SYSTEM PART
+//------------------------------------------------------------------------------
+
+/// Splits dead splitter asteroids into two children of the next generation
+/// and spawns particles on their death.
+///
+/// Children inherit the parent's velocity and are kicked apart perpendicular to it.
+pub fn splitter_death(world: &mut World, cmd: &mut CommandBuffer, fx: &mut FxManager) {
+    let stats = config::get().enemies.splitter;
+    let mut rng = run_rng(world);
+    for (_, (splitter, health, pos, phys)) in
+        world.query_mut::<(&SplitterAsteroid, &Health, &Position, &PhysicsMotion)>()
+    {
+        //check if it is dead
+        if health.hp > 0.0 {
+            continue;
+        }
+        let center = vec2(pos.x, pos.y);
+        let scale = generation_scale(splitter.generation);
+        //split if not the last generation
+        if splitter.generation + 1 < SPLITTER_GENERATIONS {
+            let side = phys
+                .vel
+                .perp()
+                .try_normalize()
+                .unwrap_or_else(|| Vec2::from_angle(rng.f32() * 2.0 * PI));
+            let offset = side * SPLITTER_SIZE * scale / 4.0;
+            for sign in [-1.0, 1.0] {
+                let vel = phys.vel + side * sign * SPLITTER_KICK;
+                cmd.spawn(
+                    create_splitter_asteroid(
+                        center + offset * sign,
+                        vel / stats.speed,
+                        splitter.generation + 1,
+                        &mut rng,
+                    )
+                    .add(SpawnGrace::default())
+                    .build(),
+                );
+            }
+        }
+        //spawn random particles on destroy
+        fx.burst_particles(
+            Particle {
+                pos: center,
+                vel: vec2(40.0, 0.0),
+                life: 0.8,
+                max_life: 0.8,
+                min_size: 0.0,
+                max_size: 16.0 * scale,
+                color: LIGHTGRAY,
+                ..Default::default()
+            },
+            20.0,
+            2.0 * PI,
+            12,
+        );
+    }
+}
@@ -0,0 +1,314 @@
+//! Turret logic.
+use std::f32::consts::PI;
+
+use fastrand::Rng;
+use hecs::{CommandBuffer, Entity, EntityBuilder, World};
+use macroquad::prelude::*;
+
+use crate::{
+    basic::{
+        fx::{FxManager, Particle},
+        motion::{KnockbackDealer, LinearTorgue, MaxVelocity, PhysicsDamping, PhysicsMotion},
+        render::Sprite,
+        DamageDealer, DeleteOnWarp, Health, HitBox, HurtBox, Position, Rotation, SpawnGrace, Team,
+        Timer,
+    },
+    config,
+    player::Player,
+    projectile::{self, ProjectileType},
+    xp::BurstXpOnDeath,
+};
+
+use super::{charged::ASTEROID_OUTLINE_TEX, Enemy, Stunned, ASTEROID_TEX_NEUTRAL};
+
+/// Health of a turret.
+pub(crate) const TURRET_HEALTH: f32 = 1.5;
+/// Speed of a turret flying to its anchor.
+pub(crate) const TURRET_SPEED: f32 = 80.0;
+/// Mass of a turret.
+const TURRET_MASS: f32 = 40.0;
+
+/// Size of a turret.
+/// Also affects Hit/HurtBox sizes.
+const TURRET_SIZE: f32 = 60.0;
+/// Scale of the texture of a turret.
+const TURRET_SCALE: f32 = TURRET_SIZE / 512.0;
+/// Scale of the texture of a turret's outline.
+const TURRET_OUTLINE_SCALE: f32 = TURRET_SIZE / 544.0;
+
+/// Dmg a turret does while hitting something.
+pub(crate) const TURRET_DMG: f32 = 1.0;
+/// Knockback dealt by a turret collision.
+const TURRET_KNOCKBACK: f32 = 400.0;
+
+/// Distance from the anchor at which the turret starts braking.
+const TURRET_BRAKE_DISTANCE: f32 = 30.0;
+/// Factor the turret's velocity is multiplied by every second while braking.
+const TURRET_BRAKE_FACTOR: f32 = 0.05;
+/// Min distance of the anchor from the edges of the arena.
+pub const TURRET_ANCHOR_MARGIN: f32 = 150.0;
+
+/// Time between bursts.
+const TURRET_BURST_COOLDOWN: f32 = 3.5;
+/// Time before a burst during which the outline lights up.
+const TURRET_TELEGRAPH_TIME: f32 = 1.0;
+/// Amount of shots in a burst.
+const TURRET_BURST_SHOTS: u8 = 3;
+/// Time between shots of a burst.
+const TURRET_SHOT_INTERVAL: f32 = 0.15;
+/// Damage of projectiles from a turret.
+const TURRET_PROJ_DMG: f32 = 1.5;
+/// Speed of projectiles from a turret.
+const TURRET_PROJ_SPEED: f32 = 220.0;
+
+/// Xp dropped by a turret on death.
+const TURRET_XP: u32 = 20;
+
+/// Handles all of the turret's logic.
+#[derive(Clone, Copy, Debug)]
+pub struct Turret {
+    /// Point the turret anchors at.
+    pub anchor: Vec2,
+    /// Has the turret reached its anchor?
+    pub anchored: bool,
+    /// Time before the next burst.
+    pub burst_timer: Timer,
+    /// Shots left in the current burst.
+    pub shots_left: u8,
+    /// Time before the next shot of the current burst.
+    pub shot_timer: f32,
+    /// Reference to the entity making the outline.
+    pub outline: Entity,
+}
+
+//-----------------------------------------------------------------------------
+//ENTITY CREATION
+//-----------------------------------------------------------------------------
+
+/// Returns a function that can be used to spawn a turret.
+/// # Arguments
+/// * `pos` - position of the turret
+/// * `anchor` - point the turret flies to and anchors at
+/// * `rng` - generator of the randomized rotation
+pub fn create_turret(
+    pos: Vec2,
+    anchor: Vec2,
+    rng: &mut Rng,
+) -> impl FnOnce(&World, &mut CommandBuffer) {
+    let stats = config::get().enemies.turret;
+    let angle = rng.f32() * 2.0 * PI;
+    let dir = (anchor - pos).normalize_or_zero();
+
+    let mut builder = EntityBuilder::default();
+    builder.add_bundle((
+        Enemy,
+        Position { x: pos.x, y: pos.y },
+        Rotation { angle },
+        LinearTorgue {
+            speed: rng.f32() * 1.0 - 0.50,
+        },
+        PhysicsMotion {
+            vel: dir * stats.speed,
+            mass: TURRET_MASS,
+        },
+        Sprite {
+            texture: ASTEROID_TEX_NEUTRAL,
+            scale: TURRET_SCALE,
+            color: WHITE,
+            z_index: 0,
+        },
+        HitBox {
+            radius: TURRET_SIZE / 2.0,
+        },
+        HurtBox {
+            radius: TURRET_SIZE / 2.0,
+        },
+        Health {
+            max_hp: stats.health,
+            hp: stats.health,
+        },
+        DamageDealer { dmg: stats.dmg },
+        Team::Enemy,
+        DeleteOnWarp,
+    ));
+    builder.add_bundle((
+        KnockbackDealer {
+            force: TURRET_KNOCKBACK,
+        },
+        BurstXpOnDeath { amount: TURRET_XP },
+        MaxVelocity {
+            max_velocity: stats.speed * 2.0,
+        },
+        SpawnGrace::default(),
+    ));
+
+    move |world, cmd| {
+        //get outline entity
+        let outline_id = world.reserve_entity();
+        //embed into turret
+        builder.add(Turret {
+            anchor,
+            anchored: false,
+            burst_timer: Timer::new(TURRET_BURST_COOLDOWN, true),
+            shots_left: 0,
+            shot_timer: 0.0,
+            outline: outline_id,
+        });
+        //spawn outline
+        cmd.insert(
+            outline_id,
+            (
+                Sprite {
+                    texture: ASTEROID_OUTLINE_TEX,
+                    scale: TURRET_OUTLINE_SCALE,
+                    color: BLACK,
+                    z_index: 1,
+                },
+                Position { x: pos.x, y: pos.y },
+                Rotation { angle },
+            ),
+        );
+        //spawn turret
+        cmd.spawn(builder.build());
+    }
+}
+
+//-----------------------------------------------------------------------------
+//SYSTEM PART
+//-----------------------------------------------------------------------------
+
+/// AI of turrets.
+///
+/// Brakes them at their anchor, then fires bursts at the player's predicted position.
+pub fn turret_ai(world: &mut World, cmd: &mut CommandBuffer, dt: f32) {
+    //get player's position and velocity
+    let Some((player_pos, player_vel)) = world
+        .query_mut::<(&Position, &PhysicsMotion)>()
+        .with::<&Player>()
+        .into_iter()
+        .next()
+        .map(|(_, (pos, phys))| (vec2(pos.x, pos.y), phys.vel))
+    else {
+        return;
+    };
+
+    for (id, (turret, pos)) in world
+        .query::<(&mut Turret, &Position)>()
+        .without::<&Stunned>()
+        .into_iter()
+    {
+        let pos = vec2(pos.x, pos.y);
+        //brake at the anchor
+        if !turret.anchored {
+            if pos.distance(turret.anchor) <= TURRET_BRAKE_DISTANCE {
+                turret.anchored = true;
+                cmd.insert_one(
+                    id,
+                    PhysicsDamping {
+                        mul_factor: TURRET_BRAKE_FACTOR,
+                        flat_factor: 1.0,
+                    },
+                );
+            }
+            continue;
+        }
+        //wait for the next burst
+        if turret.shots_left == 0 {
+            turret.burst_timer.tick(dt);
+            if turret.burst_timer.just_finished() {
+                turret.shots_left = TURRET_BURST_SHOTS;
+                turret.shot_timer = 0.0;
+            }
+            continue;
+        }
+        //fire the burst
+        turret.shot_timer -= dt;
+        if turret.shot_timer > 0.0 {
+            continue;
+        }
+        turret.shot_timer = TURRET_SHOT_INTERVAL;
+        turret.shots_left -= 1;
+        //skip the shot when there are too many projectiles
+        if projectile::take_enemy_projectiles(world, 1) == 0 {
+            continue;
+        }
+        //lead the target
+        let flight_time = pos.distance(player_pos) / TURRET_PROJ_SPEED;
+        let predicted = player_pos + player_vel * flight_time;
+        let dir = (predicted - pos).normalize_or_zero();
+
+        projectile::queue_projectile(
+            world,
+            cmd,
+            projectile::create_projectile(
+                pos,
+                dir * TURRET_PROJ_SPEED,
+                TURRET_PROJ_DMG,
+                Team::Enemy,
+                ProjectileType::Medium { charge: 0 },
+            ),
+        );
+    }
+}
+
+/// Makes sure to despawn outlines of dead turrets and spawns particles on their death.
+pub fn turret_death(world: &mut World, cmd: &mut CommandBuffer, fx: &mut FxManager) {
+    for (_, (turret, health, pos)) in world.query_mut::<(&Turret, &Health, &Position)>() {
+        if health.hp > 0.0 {
+            continue;
+        }
+        cmd.despawn(turret.outline);
+        //spawn random particles on destroy
+        fx.burst_particles(
+            Particle {
+                pos: vec2(pos.x, pos.y),
+                vel: vec2(50.0, 0.0),
+                life: 1.0,
+                max_life: 1.0,
+                min_size: 0.0,
+                max_size: 14.0,
+                color: ORANGE,
+                ..Default::default()
+            },
+            25.0,
+            2.0 * PI,
+            16,
+        );
+    }
+}
+
+/// Synchronizes outlines with turrets.
+/// The outline lights up before each burst.
+pub fn turret_visual(world: &mut World) {
+    for (_, (turret, pos, angle)) in world.query::<(&Turret, &Position, &Rotation)>().into_iter() {
+        //get your outline
+        let mut outline = world
+            .query::<(&mut Position, &mut Rotation, &mut Sprite)>()
+            .without::<&Turret>();
+        let mut outline = outline.view();
+        let Some((outline_pos, outline_angle, outline_sprite)) = outline.get_mut(turret.outline)
+        else {
+            continue;
+        };
+
+        outline_pos.x = pos.x;
+        outline_pos.y = pos.y;
+
+        outline_angle.angle = angle.angle;
+
+        //ramp up the telegraph, fully lit during the burst
+        let color_unit = if !turret.anchored {
+            0.0
+        } else if turret.shots_left > 0 {
+            1.0
+        } else {
+            (1.0 - turret.burst_timer.remaining() / TURRET_TELEGRAPH_TIME).max(0.0)
+        };
+        outline_sprite.color = Color {
+            r: color_unit,
+            g: color_unit * 0.6,
+            b: 0.0,
+            a: 1.0,
+        };
+    }
+}
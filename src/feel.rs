@@ -0,0 +1,291 @@
+//! Feel tests, guarding how the ship handles against unnoticed changes.
+//!
+//! Every fixture in `src/feel` is a script of inputs replayed against the player's systems
+//! at the fixed timestep, without a window:
+//! ```text
+//! # comment
+//! <seconds> [thrust] [fire] [flip] [dash] [aim=x,y]
+//! asteroid <dx>,<dy>
+//! ```
+//! `flip` and `dash` are pressed on the first step of their line only,
+//! the aim is kept until another line changes it.
+//! `asteroid` spawns a standard asteroid at the offset from the player, heading at it.
+//!
+//! Outcomes of a script are compared with its `.expected` file.
+//! Run the tests with `FEEL_REGENERATE=1` to rewrite them after an intended tuning change.
+use hecs::{CommandBuffer, Entity, World};
+use macroquad::prelude::*;
+
+use crate::basic::arena::Arena;
+use crate::basic::clock::FIXED_DT;
+use crate::basic::motion::{self, ChargeReceiver, PhysicsMotion};
+use crate::basic::render::AssetManager;
+use crate::basic::{self, Position, Team};
+use crate::enemy::create_asteroid;
+use crate::input::{Aim, PlayerInput};
+use crate::locale::Locale;
+use crate::persist::Settings;
+use crate::player;
+use crate::projectile::{self, Projectile};
+
+/// Directory with the fixtures and their expected outcomes.
+const FIXTURE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/feel");
+/// Environment variable that makes the tests rewrite the expected outcomes.
+const REGENERATE_VAR: &str = "FEEL_REGENERATE";
+/// Relative tolerance of the measured values, absolute below one.
+const TOLERANCE: f32 = 0.01;
+
+/// Single line of a fixture.
+#[derive(Clone, Copy, Debug)]
+enum Line {
+    /// Holds the input for the given number of seconds.
+    Hold {
+        seconds: f32,
+        thrust: bool,
+        fire: bool,
+        flip: bool,
+        dash: bool,
+        aim: Option<Vec2>,
+    },
+    /// Spawns an asteroid at the offset from the player.
+    Asteroid(Vec2),
+}
+
+/// Parses `x,y` into a vector.
+fn parse_vec(text: &str) -> Vec2 {
+    let (x, y) = text.split_once(',').expect("vector is not `x,y`");
+    vec2(x.trim().parse().unwrap(), y.trim().parse().unwrap())
+}
+
+/// Parses the lines of a fixture, skipping empty lines and comments.
+fn parse_script(text: &str) -> Vec<Line> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut words = line.split_whitespace();
+            let first = words.next().unwrap();
+            if first == "asteroid" {
+                return Line::Asteroid(parse_vec(words.next().expect("asteroid has no offset")));
+            }
+            let words = words.collect::<Vec<_>>();
+            Line::Hold {
+                seconds: first.parse().expect("line does not start with seconds"),
+                thrust: words.contains(&"thrust"),
+                fire: words.contains(&"fire"),
+                flip: words.contains(&"flip"),
+                dash: words.contains(&"dash"),
+                aim: words
+                    .iter()
+                    .find_map(|word| word.strip_prefix("aim="))
+                    .map(parse_vec),
+            }
+        })
+        .collect()
+}
+
+/// Runs one fixed step of the player's systems,
+/// in the order [game_update](crate::game::state) runs them.
+fn step(world: &mut World, events: &mut World, assets: &AssetManager, input: &PlayerInput) {
+    let mut cmd = CommandBuffer::new();
+    events.clear();
+    player::weapons(world, &mut cmd, input, FIXED_DT);
+    player::motion_update(world, input, FIXED_DT);
+    player::dash(world, &mut cmd, input, FIXED_DT);
+    motion::apply_physics(world, FIXED_DT, None);
+    motion::apply_motion(world, &Settings::default(), FIXED_DT);
+    basic::ensure_damage(world, events, FIXED_DT);
+    motion::resolve_body_collisions(world);
+    motion::age_knockbacks(world, &mut cmd, FIXED_DT);
+    motion::apply_knockback(world, events, assets);
+    cmd.run_on(world);
+    projectile::spawn_projectiles(world);
+}
+
+/// Measured outcomes of a fixture.
+#[derive(Clone, Debug, Default)]
+struct Outcomes {
+    /// Length of the path the ship travelled.
+    distance: f32,
+    /// Highest speed of the ship.
+    top_speed: f32,
+    /// Length of the path after the thrust was last released.
+    stopping_distance: f32,
+    /// Where the ship ended, relative to where it started.
+    displacement: Vec2,
+    /// Times of every shot the ship fired.
+    shot_times: Vec<f32>,
+    /// Sign of the ship's polarity at the end.
+    polarity: f32,
+}
+
+impl Outcomes {
+    /// Writes the outcomes as `key = value` lines, the format of the expected files.
+    fn to_text(&self) -> String {
+        let times = self
+            .shot_times
+            .iter()
+            .map(|time| format!("{time:.4}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            "distance = {:.2}\ntop_speed = {:.2}\nstopping_distance = {:.2}\n\
+             displacement = {:.2}, {:.2}\nshots = {}\nshot_times = {times}\npolarity = {}\n",
+            self.distance,
+            self.top_speed,
+            self.stopping_distance,
+            self.displacement.x,
+            self.displacement.y,
+            self.shot_times.len(),
+            self.polarity,
+        )
+    }
+}
+
+/// Replays the script against a fresh standard arena with only the player.
+fn play(script: &[Line]) -> Outcomes {
+    let mut world = World::new();
+    let mut events = World::new();
+    let assets = AssetManager::default();
+    let player = world.spawn(player::new_entity(&Arena::STANDARD));
+    let position = |world: &World, id: Entity| {
+        let pos = world.get::<&Position>(id).unwrap();
+        vec2(pos.x, pos.y)
+    };
+    let shots = |world: &mut World| {
+        world
+            .query_mut::<&Team>()
+            .with::<&Projectile>()
+            .into_iter()
+            .filter(|(_, team)| **team == Team::Player)
+            .count()
+    };
+
+    let start = position(&world, player);
+    let mut outcomes = Outcomes::default();
+    let mut aim = Vec2::X;
+    let mut time = 0.0;
+    for line in script {
+        match *line {
+            Line::Asteroid(offset) => {
+                let pos = position(&world, player) + offset;
+                world.spawn(create_asteroid(pos, -offset.normalize()).build());
+            }
+            Line::Hold {
+                seconds,
+                thrust,
+                fire,
+                flip,
+                dash,
+                aim: new_aim,
+            } => {
+                aim = new_aim.unwrap_or(aim);
+                for i in 0..(seconds / FIXED_DT).round() as u32 {
+                    let input = PlayerInput {
+                        thrust,
+                        aim: Aim::Direction(aim),
+                        fire,
+                        flip_polarity: flip && i == 0,
+                        dash: dash && i == 0,
+                    };
+                    let before = position(&world, player);
+                    let fired = shots(&mut world);
+                    step(&mut world, &mut events, &assets, &input);
+                    time += FIXED_DT;
+
+                    let travelled = before.distance(position(&world, player));
+                    outcomes.distance += travelled;
+                    outcomes.stopping_distance = if thrust {
+                        0.0
+                    } else {
+                        outcomes.stopping_distance + travelled
+                    };
+                    let speed = world.get::<&PhysicsMotion>(player).unwrap().vel.length();
+                    outcomes.top_speed = outcomes.top_speed.max(speed);
+                    let new_shots = shots(&mut world).saturating_sub(fired);
+                    outcomes.shot_times.extend((0..new_shots).map(|_| time));
+                }
+            }
+        }
+    }
+    outcomes.displacement = position(&world, player) - start;
+    outcomes.polarity = world
+        .get::<&ChargeReceiver>(player)
+        .unwrap()
+        .multiplier
+        .signum();
+    outcomes
+}
+
+/// Parses comma separated numbers of an outcome.
+fn parse_values(text: &str) -> Vec<f32> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| value.parse().unwrap())
+        .collect()
+}
+
+/// Replays the fixture and compares its outcomes with the expected ones,
+/// or rewrites them while regenerating.
+fn check(name: &str) {
+    let script = std::fs::read_to_string(format!("{FIXTURE_DIR}/{name}.feel")).unwrap();
+    let actual = play(&parse_script(&script)).to_text();
+    let expected_path = format!("{FIXTURE_DIR}/{name}.expected");
+    if std::env::var_os(REGENERATE_VAR).is_some() {
+        std::fs::write(&expected_path, &actual).unwrap();
+        return;
+    }
+
+    let expected = Locale::parse(&std::fs::read_to_string(&expected_path).unwrap());
+    let actual = Locale::parse(&actual);
+    let mut keys = expected.keys().chain(actual.keys()).collect::<Vec<_>>();
+    keys.sort();
+    keys.dedup();
+    let mut mismatches = Vec::new();
+    for key in keys {
+        let (Some(want), Some(got)) = (expected.get(key), actual.get(key)) else {
+            mismatches.push(format!("{key} is missing"));
+            continue;
+        };
+        let (want_values, got_values) = (parse_values(want), parse_values(got));
+        let matches = want_values.len() == got_values.len()
+            && want_values
+                .iter()
+                .zip(&got_values)
+                .all(|(want, got)| (want - got).abs() <= TOLERANCE * want.abs().max(1.0));
+        if !matches {
+            mismatches.push(format!("{key}: expected {want}, got {got}"));
+        }
+    }
+    assert!(
+        mismatches.is_empty(),
+        "`{name}` does not feel the same, rerun with {REGENERATE_VAR}=1 if intended:\n{}",
+        mismatches.join("\n")
+    );
+}
+
+#[test]
+fn thrust_for_a_second() {
+    check("thrust");
+}
+
+#[test]
+fn turn_while_thrusting() {
+    check("turn");
+}
+
+#[test]
+fn fire_five_shots() {
+    check("fire");
+}
+
+#[test]
+fn flip_polarity() {
+    check("flip");
+}
+
+#[test]
+fn knockback_from_asteroid() {
+    check("knockback");
+}
@@ -0,0 +1,101 @@
+//! Overlay showing the charge fields and the charge force acting on the player.
+
+use std::f32::consts::PI;
+
+use hecs::World;
+use macroquad::prelude::*;
+
+use crate::{
+    basic::{
+        motion::{ChargeSender, NetForce},
+        Position,
+    },
+    input::Binding,
+    player::Player,
+};
+
+/// Key showing and hiding the overlay.
+const FIELDS_KEY: KeyCode = KeyCode::Tab;
+/// Color of positively charged fields.
+const POSITIVE_COLOR: Color = Color::new(1.0, 0.3, 0.3, 0.35);
+/// Color of negatively charged fields.
+const NEGATIVE_COLOR: Color = Color::new(0.0, 1.0, 1.0, 0.35);
+/// Thickness of the ring at the full force radius.
+const FULL_RING_THICKNESS: f32 = 2.0;
+/// Thickness of the ring at the zero force radius.
+const ZERO_RING_THICKNESS: f32 = 1.0;
+/// Length of the force arrow per unit of force.
+const ARROW_SCALE: f32 = 0.5;
+/// Max length of the force arrow.
+const ARROW_MAX_LENGTH: f32 = 80.0;
+/// Length of the sides of the arrow's head.
+const ARROW_HEAD: f32 = 8.0;
+/// Color of the force arrow.
+const ARROW_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.8);
+
+/// Resource remembering whether the overlay is shown.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FieldOverlay {
+    /// Is the overlay shown?
+    pub shown: bool,
+}
+
+//-----------------------------------------------------------------------------
+//SYSTEM PART
+//-----------------------------------------------------------------------------
+
+/// Shows and hides the [FieldOverlay] when its key is pressed.
+pub fn toggle_fields(world: &mut World) {
+    if !Binding::key(FIELDS_KEY).is_pressed() {
+        return;
+    }
+    for (_, overlay) in world.query_mut::<&mut FieldOverlay>() {
+        overlay.shown = !overlay.shown;
+    }
+}
+
+/// Renders rings of every [ChargeSender] at its full and zero force radius,
+/// and an arrow of the [NetForce] acting on the player, when the [FieldOverlay] is shown.
+/// Must be rendered with the world camera.
+pub fn render_fields(world: &mut World) {
+    let shown = world
+        .query_mut::<&FieldOverlay>()
+        .into_iter()
+        .any(|(_, overlay)| overlay.shown);
+    if !shown {
+        return;
+    }
+
+    //rings of the fields
+    for (_, (sender, pos)) in world.query_mut::<(&ChargeSender, &Position)>() {
+        if sender.force == 0.0 {
+            continue;
+        }
+        let color = if sender.force > 0.0 {
+            POSITIVE_COLOR
+        } else {
+            NEGATIVE_COLOR
+        };
+        draw_circle_lines(pos.x, pos.y, sender.full_radius, FULL_RING_THICKNESS, color);
+        draw_circle_lines(pos.x, pos.y, sender.no_radius, ZERO_RING_THICKNESS, color);
+    }
+
+    //force acting on the player
+    for (_, (net, pos)) in world
+        .query_mut::<(&NetForce, &Position)>()
+        .with::<&Player>()
+    {
+        let length = (net.force.length() * ARROW_SCALE).min(ARROW_MAX_LENGTH);
+        if length < 1.0 {
+            continue;
+        }
+        let dir = net.force.normalize();
+        let start = vec2(pos.x, pos.y);
+        let end = start + dir * length;
+        draw_line(start.x, start.y, end.x, end.y, 2.0, ARROW_COLOR);
+        for side in [-1.0, 1.0] {
+            let head = end - Vec2::from_angle(side * PI / 6.0).rotate(dir) * ARROW_HEAD;
+            draw_line(end.x, end.y, head.x, head.y, 2.0, ARROW_COLOR);
+        }
+    }
+}
@@ -2,13 +2,30 @@
 
 use std::f32::consts::PI;
 
-use hecs::{CommandBuffer, World};
-use macroquad::math::{vec2, Vec2};
+use fastrand::Rng;
+use fnv::FnvHashSet;
+use hecs::{CommandBuffer, Entity, World};
+use macroquad::{
+    color::{GOLD, WHITE},
+    math::{vec2, Vec2},
+};
 
-use crate::{basic::Position, enemy::Enemy, player::Player};
+use crate::{
+    banner,
+    basic::{
+        arena::{self, Arena},
+        DamageDealer, Health, Position,
+    },
+    config::{self, SpawnTuning, SpawnerConfig},
+    enemy::{boss::Boss, Enemy},
+    locale::tr,
+    menu::UiOwner,
+    player::Player,
+};
 
-use self::wave::WavePreamble;
+use self::wave::{WavePreamble, WaveStep};
 
+pub mod death;
 pub mod init;
 pub mod state;
 mod wave;
@@ -38,8 +55,34 @@ const MIN_SPAWNS_BEFORE_BREAK: u32 = 4;
 /// Maximal amount of spawns before a wave ends.
 const MAX_SPAWNS_BEFORE_BREAK: u32 = 7;
 
-/// Max amount of enemy entities that can be at once.
-const MAX_ENTITIES: usize = 15;
+/// Amount of completed waves between boss fights.
+const BOSS_WAVE_INTERVAL: u32 = 5;
+/// Break time after the boss dies.
+const BOSS_BREAK_COOLDOWN: f32 = 45.0;
+
+/// Max sum of the threat of enemies alive at once.
+/// An ordinary enemy poses a threat of [DEFAULT_THREAT].
+const MAX_ACTIVE_THREAT: f32 = 15.0;
+/// Threat posed by enemies not spawned by the spawn table, e.g. pieces of a splitter.
+const DEFAULT_THREAT: f32 = 1.0;
+/// How many times in a row a spawn can be chosen.
+const MAX_REPEATS: u32 = 2;
+
+/// Time it takes the threat level to rise by one.
+const THREAT_STEP_TIME: f32 = 60.0;
+/// Max threat level.
+/// Every multiplier stops growing there, so that the game stays survivable.
+const MAX_THREAT: f32 = 10.0;
+/// Increase of the credits per second per threat level.
+const THREAT_CREDITS: f32 = 0.1;
+/// Additional active threat allowed per threat level.
+const THREAT_ACTIVE: f32 = 1.0;
+/// Decrease of spawn cooldowns per threat level.
+const THREAT_COOLDOWN: f32 = 0.04;
+/// Increase of enemies' health per threat level.
+const THREAT_HEALTH: f32 = 0.1;
+/// Increase of enemies' damage per threat level.
+const THREAT_DAMAGE: f32 = 0.05;
 
 /// Chance to spawn an enemy twice.
 const DOUBLE_CHANCE: f32 = 0.33;
@@ -50,6 +93,8 @@ const TRIPLE_CHANCE: f32 = 0.5;
 /// Defines a wave that can be spawned.
 #[derive(Clone, Copy)]
 struct EnemySpawns {
+    /// Name of the spawn in the config.
+    name: &'static str,
     /// Cost of spawning this enemy.
     /// It must be payed when spawned.
     cost: f32,
@@ -59,58 +104,149 @@ struct EnemySpawns {
     /// Weight of this spawn.
     /// The higher the weight the higher the chance to choose this spawn.
     weight: u32,
-    /// Function that spawns the enemy.
-    spawn: &'static dyn Fn(&mut WavePreamble),
-}
-
-/// Multiplier that takes a enemy spawning function and returns a fuction that runs it `count` times.
-const fn wave_mult(
-    fun: impl Fn(&mut WavePreamble),
-    count: usize,
-) -> impl Fn(&mut WavePreamble<'_>) {
-    move |preamble: &mut WavePreamble<'_>| {
-        for _ in 0..count {
-            fun(preamble)
-        }
-    }
+    /// Weight added while the player camps a corner.
+    camp_weight: u32,
+    /// Threat each enemy of this spawn poses while alive.
+    threat: f32,
+    /// Script spawning the enemies over time.
+    script: &'static [WaveStep],
 }
 
 /// List of all possible enemy spawns.
-const ENEMY_SPAWNS: [EnemySpawns; 5] = [
+const ENEMY_SPAWNS: [EnemySpawns; 13] = [
     //spawn 4 asteroids
     EnemySpawns {
+        name: "asteroids",
         cost: 10.0,
         gain: 20.0,
         weight: 15,
-        spawn: &wave_mult(wave::asteroid, 4),
+        camp_weight: 0,
+        threat: 1.0,
+        script: &[WaveStep::new(0.0, &wave::asteroid, 4)],
     },
     //spawn 3 supercharged asteroids
     EnemySpawns {
+        name: "charged_asteroids",
         cost: 15.0,
         gain: 20.0,
         weight: 20,
-        spawn: &wave_mult(wave::charged_asteroid, 3),
+        camp_weight: 0,
+        threat: 1.5,
+        script: &[WaveStep::new(0.0, &wave::charged_asteroid, 3)],
     },
     //spawn 1 big asteroid
     EnemySpawns {
+        name: "big_asteroid",
         cost: 40.0,
         gain: 10.0,
         weight: 30,
-        spawn: &wave::big_asteroid,
+        camp_weight: 0,
+        threat: 3.0,
+        script: &[WaveStep::new(0.0, &wave::big_asteroid, 1)],
     },
     //spawn 3 saw blades
     EnemySpawns {
+        name: "followers",
         cost: 30.0,
         gain: 10.0,
         weight: 30,
-        spawn: &wave_mult(wave::follower, 3),
+        camp_weight: 0,
+        threat: 2.0,
+        script: &[WaveStep::new(0.0, &wave::follower, 3)],
     },
     //spawn 2 mines
     EnemySpawns {
+        name: "mines",
         cost: 40.0,
         gain: 10.0,
         weight: 30,
-        spawn: &wave_mult(wave::mine, 2),
+        camp_weight: 10,
+        threat: 0.5,
+        script: &[WaveStep::new(0.0, &wave::mine, 2)],
+    },
+    //spawn 1 splitter asteroid
+    EnemySpawns {
+        name: "splitter",
+        cost: 25.0,
+        gain: 15.0,
+        weight: 20,
+        camp_weight: 0,
+        threat: 2.0,
+        script: &[WaveStep::new(0.0, &wave::splitter_asteroid, 1)],
+    },
+    //spawn 1 turret
+    EnemySpawns {
+        name: "turret",
+        cost: 35.0,
+        gain: 10.0,
+        weight: 20,
+        camp_weight: 0,
+        threat: 2.0,
+        script: &[WaveStep::new(0.0, &wave::turret, 1)],
+    },
+    //spawn 2 flippers
+    EnemySpawns {
+        name: "flippers",
+        cost: 30.0,
+        gain: 15.0,
+        weight: 20,
+        camp_weight: 0,
+        threat: 1.5,
+        script: &[WaveStep::new(0.0, &wave::flipper, 2)],
+    },
+    //8 charged asteroids crunching into the center
+    EnemySpawns {
+        name: "center_crunch",
+        cost: 35.0,
+        gain: 15.0,
+        weight: 10,
+        camp_weight: 0,
+        threat: 1.0,
+        script: &[WaveStep::new(0.0, &wave::center_crunch, 1)],
+    },
+    //3 volleys of 3 charged asteroids from the side opposite the player
+    EnemySpawns {
+        name: "tripleshot",
+        cost: 35.0,
+        gain: 15.0,
+        weight: 10,
+        camp_weight: 0,
+        threat: 1.0,
+        script: &[
+            WaveStep::new(0.0, &wave::tripleshot, 1),
+            WaveStep::new(0.67, &wave::tripleshot, 1),
+            WaveStep::new(0.68, &wave::tripleshot, 1),
+        ],
+    },
+    //a positive and a negative asteroid orbiting each other
+    EnemySpawns {
+        name: "dipole_pair",
+        cost: 15.0,
+        gain: 15.0,
+        weight: 15,
+        camp_weight: 0,
+        threat: 1.0,
+        script: &[WaveStep::new(0.0, &wave::dipole_pair, 1)],
+    },
+    //5 mines in a line across an edge
+    EnemySpawns {
+        name: "mine_wall",
+        cost: 70.0,
+        gain: 10.0,
+        weight: 10,
+        camp_weight: 10,
+        threat: 0.5,
+        script: &[WaveStep::new(0.0, &wave::mine_wall, 1)],
+    },
+    //2 oppositely charged big asteroids from opposite edges
+    EnemySpawns {
+        name: "sandwich",
+        cost: 70.0,
+        gain: 10.0,
+        weight: 10,
+        camp_weight: 0,
+        threat: 3.0,
+        script: &[WaveStep::new(0.0, &wave::sandwich, 1)],
     },
 ];
 
@@ -119,9 +255,28 @@ const ENEMY_SPAWNS: [EnemySpawns; 5] = [
 const SPAWN_MARGIN: f32 = 20.0;
 /// How fat the enemy should be "pushed back" to its edge to not be visible.
 const SPAWN_PUSHBACK: f32 = 10.0;
+/// Distance from the player the enemies should spawn farther than.
+const SPAWN_SAFE_DISTANCE: f32 = 250.0;
+/// How many spawn positions are rolled before settling for the farthest one.
+const SPAWN_ATTEMPTS: u32 = 8;
+/// Max offset along the edge of enemies spawned together.
+const SPAWN_JITTER: f32 = 40.0;
+
+/// Time the player has to stay near a corner to be considered camping.
+const CAMP_TIME: f32 = 6.0;
+/// Max distance from a corner considered camping.
+const CAMP_RADIUS: f32 = 200.0;
+/// Amount of positions averaged, one is sampled every second.
+const CAMP_SAMPLES: usize = 10;
+/// Chance a spawn comes from one of the sides next to the camped corner.
+const CAMP_SIDE_CHANCE: f32 = 0.6;
+
+/// Estimated time it takes to survive one wave.
+/// Used to fast-forward the spawner.
+const SECS_PER_WAVE: f32 = 45.0;
 
 /// Enemy Spawner struct, handles all of the wave logic.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct EnemySpawner {
     /// How many enemy spawns should we spawn before a break.
     pub before_break: u32,
@@ -130,19 +285,252 @@ pub struct EnemySpawner {
     /// How long it waits before it either spawns another enemy or
     /// starts another wave.
     pub cooldown: f32,
+    /// Number of the current wave, starting at 1.
+    pub wave: u32,
+    /// Time elapsed since the start of the run.
+    pub elapsed: f32,
+    /// Detection of the player camping a corner.
+    pub camp: CampTracker,
+    /// Is a boss alive?
+    /// Regular spawns are suppressed until it dies.
+    pub boss: bool,
+    /// Difficulty at the current time of the run.
+    pub difficulty: Difficulty,
+    /// Should the current wave be announced once its spawns start?
+    pub announce: bool,
+    /// Seed of the run, runs with the same seed spawn the same enemies.
+    pub seed: u64,
+    /// Generator of the spawned enemies, seeded by `seed`.
+    pub rng: Rng,
+    /// Generator of the rest of the gameplay, e.g. enemy deaths and upgrade offers.
+    /// Kept apart, so that the spawns do not depend on what happens in the run.
+    pub events_rng: Rng,
+    /// Script of the current spawn, while it runs.
+    /// The next cooldown starts once it is finished.
+    pub script: Option<ScriptRun>,
+    /// Index of the last chosen spawn.
+    pub last_spawn: Option<usize>,
+    /// How many times in a row the last spawn was chosen.
+    pub repeats: u32,
+}
+
+/// Spawn of the spawn table an enemy was spawned by.
+#[derive(Clone, Copy, Debug)]
+pub struct SpawnEntry {
+    /// Index of the spawn in the spawn table.
+    pub index: usize,
+}
+
+/// Progress of a running wave script.
+#[derive(Clone, Copy, Debug)]
+pub struct ScriptRun {
+    /// Index of the spawn whose script runs.
+    spawn: usize,
+    /// Index of the next step.
+    step: usize,
+    /// Time since the previous step.
+    timer: f32,
+    /// How many times every step is run.
+    times: u32,
+}
+
+/// Difficulty of the run, rising with the elapsed time.
+#[derive(Clone, Copy, Debug)]
+pub struct Difficulty {
+    /// Threat level, from 1 to [MAX_THREAT].
+    pub threat: f32,
+}
+
+impl Difficulty {
+    /// Returns the difficulty after `elapsed` seconds of a run.
+    pub fn at(elapsed: f32) -> Self {
+        Self {
+            threat: (1.0 + elapsed / THREAT_STEP_TIME).min(MAX_THREAT),
+        }
+    }
+
+    /// Threat levels gained since the start of the run.
+    #[inline]
+    fn gained(&self) -> f32 {
+        self.threat - 1.0
+    }
+
+    /// Returns the multiplier of credits per second.
+    pub fn credits_mult(&self) -> f32 {
+        1.0 + THREAT_CREDITS * self.gained()
+    }
+
+    /// Returns the max sum of the threat of enemies alive at once.
+    pub fn max_active_threat(&self) -> f32 {
+        config::get().spawner.max_active_threat + THREAT_ACTIVE * self.gained()
+    }
+
+    /// Returns a random cooldown between individual enemy spawns.
+    pub fn spawn_cooldown(&self, rng: &mut Rng) -> f32 {
+        let tuning = config::get().spawner;
+        let cooldown = (tuning.max_spawn_cooldown - tuning.min_spawn_cooldown) * rng.f32()
+            + tuning.min_spawn_cooldown;
+        cooldown * (1.0 - THREAT_COOLDOWN * self.gained())
+    }
+
+    /// Returns the multiplier of enemies' health.
+    pub fn health_mult(&self) -> f32 {
+        1.0 + THREAT_HEALTH * self.gained()
+    }
+
+    /// Returns the multiplier of enemies' damage.
+    pub fn damage_mult(&self) -> f32 {
+        1.0 + THREAT_DAMAGE * self.gained()
+    }
+}
+
+/// Marker of enemies whose stats were scaled by the [Difficulty].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DifficultyScaled;
+
+/// Detects the player camping in a corner of the arena.
+///
+/// The player camps when they stay near a corner for [CAMP_TIME]
+/// and their average position over the last [CAMP_SAMPLES] seconds is near it too.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CampTracker {
+    /// Positions of the player sampled every second.
+    samples: [Vec2; CAMP_SAMPLES],
+    /// Total amount of samples taken.
+    sampled: usize,
+    /// Time since the last sample.
+    sample_timer: f32,
+    /// Time the player has continuously been near a corner.
+    pub corner_time: f32,
+}
+
+impl CampTracker {
+    /// Tracks the player's position `pos` in `arena`.
+    pub fn update(&mut self, pos: Vec2, arena: &Arena, dt: f32) {
+        //sample the position
+        self.sample_timer += dt;
+        if self.sample_timer >= 1.0 || self.sampled == 0 {
+            self.sample_timer = 0.0;
+            self.samples[self.sampled % CAMP_SAMPLES] = pos;
+            self.sampled += 1;
+        }
+        //time near a corner
+        if pos.distance(nearest_corner(pos, arena)) <= CAMP_RADIUS {
+            self.corner_time += dt;
+        } else {
+            self.corner_time = 0.0;
+        }
+    }
+
+    /// Returns the average position of the player over the sampled period.
+    pub fn average(&self) -> Vec2 {
+        let count = self.sampled.clamp(1, CAMP_SAMPLES);
+        self.samples[..count].iter().sum::<Vec2>() / count as f32
+    }
+
+    /// Returns the camped corner, if the player is camping.
+    pub fn camped_corner(&self, arena: &Arena) -> Option<Vec2> {
+        let average = self.average();
+        let corner = nearest_corner(average, arena);
+        (self.corner_time > CAMP_TIME && average.distance(corner) <= CAMP_RADIUS).then_some(corner)
+    }
+
+    /// Returns the two sides next to the camped corner, if the player is camping.
+    fn camped_sides(&self, arena: &Arena) -> Option<[u8; 2]> {
+        let corner = self.camped_corner(arena)?;
+        let vertical = if corner.y <= 0.0 { 0 } else { 1 };
+        let horizontal = if corner.x <= 0.0 { 2 } else { 3 };
+        Some([vertical, horizontal])
+    }
+}
+
+/// Returns the corner of `arena` nearest to `pos`.
+fn nearest_corner(pos: Vec2, arena: &Arena) -> Vec2 {
+    vec2(
+        if pos.x < arena.width / 2.0 {
+            0.0
+        } else {
+            arena.width
+        },
+        if pos.y < arena.height / 2.0 {
+            0.0
+        } else {
+            arena.height
+        },
+    )
 }
 
 impl EnemySpawner {
-    /// Create a default [EnemySpawner]
+    /// Create a default [EnemySpawner] with a random seed.
     pub fn new() -> Self {
+        Self::with_seed(fastrand::u64(..))
+    }
+
+    /// Create a default [EnemySpawner] whose run is generated from `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        let tuning = config::get().spawner;
+        Self {
+            before_break: tuning.min_spawns_before_break,
+            credits: tuning.init_credits,
+            cooldown: tuning.init_cooldown,
+            wave: 1,
+            elapsed: 0.0,
+            camp: CampTracker::default(),
+            boss: false,
+            difficulty: Difficulty::at(0.0),
+            announce: true,
+            seed,
+            rng: Rng::with_seed(seed),
+            events_rng: Rng::with_seed(!seed),
+            script: None,
+            last_spawn: None,
+            repeats: 0,
+        }
+    }
+
+    /// Create an [EnemySpawner] fast-forwarded to the start of wave `wave`,
+    /// as if the player had survived all the previous waves.
+    pub fn at_wave(wave: u32) -> Self {
+        let wave = wave.max(1);
+        let elapsed = (wave - 1) as f32 * SECS_PER_WAVE;
+        let seed = fastrand::u64(..);
+        let mut rng = Rng::with_seed(seed);
+        let tuning = config::get().spawner;
         Self {
-            before_break: MIN_SPAWNS_BEFORE_BREAK,
-            credits: INIT_CREDITS,
-            cooldown: INIT_COOLDOWN,
+            before_break: rng.u32(tuning.min_spawns_before_break..=tuning.max_spawns_before_break),
+            credits: credits_at(elapsed),
+            cooldown: tuning.init_cooldown,
+            wave,
+            elapsed,
+            camp: CampTracker::default(),
+            boss: false,
+            difficulty: Difficulty::at(elapsed),
+            announce: true,
+            seed,
+            rng,
+            events_rng: Rng::with_seed(!seed),
+            script: None,
+            last_spawn: None,
+            repeats: 0,
         }
     }
 }
 
+/// Credits the spawner is expected to have after `elapsed` seconds.
+///
+/// It is the difficulty curve of the spawner,
+/// credits per second grow with the [Difficulty] until it is capped.
+#[inline]
+fn credits_at(elapsed: f32) -> f32 {
+    //time the threat level grows for
+    let growing = elapsed.min((MAX_THREAT - 1.0) * THREAT_STEP_TIME);
+    let capped = elapsed - growing;
+    let gained = growing + THREAT_CREDITS * growing.powi(2) / (2.0 * THREAT_STEP_TIME);
+    let tuning = config::get().spawner;
+    tuning.init_credits
+        + tuning.credits_per_sec * (gained + Difficulty::at(elapsed).credits_mult() * capped)
+}
+
 impl Default for EnemySpawner {
     /// Create a default [EnemySpawner]
     fn default() -> Self {
@@ -155,66 +543,201 @@ impl Default for EnemySpawner {
 //------------------------------------------------------------------------------
 
 /// Handles the spawning of enemies and wave logic.
+///
+/// Enemies spawned by the spawn table are tagged with their [SpawnEntry].
 pub fn enemy_spawning(world: &mut World, cmd: &mut CommandBuffer, dt: f32) {
-    //count enemies
+    let mut spawns = CommandBuffer::new();
+    let entry = spawn_enemies(world, &mut spawns, cmd, dt);
+
+    //spawn right away, so that the new enemies can be told apart from the known ones
+    let known: FnvHashSet<Entity> = world
+        .query_mut::<&Enemy>()
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect();
+    spawns.run_on(world);
+    let Some(index) = entry else {
+        return;
+    };
+    let fresh: Vec<_> = world
+        .query_mut::<&Enemy>()
+        .into_iter()
+        .map(|(id, _)| id)
+        .filter(|id| !known.contains(id))
+        .collect();
+    for id in fresh {
+        let _ = world.insert_one(id, SpawnEntry { index });
+    }
+}
+
+/// Runs the spawner, spawning the enemies into `spawns`.
+/// Returns the index of the spawn whose enemies were spawned, if any.
+fn spawn_enemies(
+    world: &mut World,
+    spawns: &mut CommandBuffer,
+    cmd: &mut CommandBuffer,
+    dt: f32,
+) -> Option<usize> {
+    let config = config::get();
+    //count enemies and their threat
     let enemy_count = world.query_mut::<&Enemy>().into_iter().count();
+    let active_threat: f32 = world
+        .query_mut::<Option<&SpawnEntry>>()
+        .with::<&Enemy>()
+        .into_iter()
+        .map(|(_, entry)| {
+            entry
+                .and_then(|entry| config.spawns.get(entry.index))
+                .map_or(DEFAULT_THREAT, |wave| wave.threat)
+        })
+        .sum();
+    let boss_alive = world.query_mut::<&Boss>().into_iter().next().is_some();
     //get position of player
     let (_, &player_pos) = world
         .query_mut::<&Position>()
         .with::<&Player>()
         .into_iter()
-        .next()
-        .unwrap();
+        .next()?;
+    //get play space
+    let arena = arena::arena(world);
     //get spawner
     let spawner_query = &mut world.query::<&mut EnemySpawner>();
-    let (_, spawner) = spawner_query.into_iter().next().unwrap();
+    let (_, spawner) = spawner_query.into_iter().next()?;
     //give credits
-    spawner.credits += CREDITS_PER_SEC * dt;
+    spawner.credits += credits_at(spawner.elapsed + dt) - credits_at(spawner.elapsed);
+    spawner.elapsed += dt;
+    spawner.difficulty = Difficulty::at(spawner.elapsed);
+    //watch out for campers
+    spawner
+        .camp
+        .update(vec2(player_pos.x, player_pos.y), &arena, dt);
+    let camp_sides = spawner.camp.camped_sides(&arena);
+    let weight = |wave: &SpawnTuning| {
+        if camp_sides.is_some() {
+            wave.weight + wave.camp_weight
+        } else {
+            wave.weight
+        }
+    };
+    //long break after the boss dies
+    if spawner.boss && !boss_alive {
+        spawner.boss = false;
+        spawner.cooldown = BOSS_BREAK_COOLDOWN;
+        banner::push_fading_title(
+            cmd,
+            tr!("wave.boss_defeated", seconds = BOSS_BREAK_COOLDOWN as u32),
+            GOLD,
+            UiOwner::Always,
+        );
+    }
     //is break over due to lack of enemies
     if spawner.before_break == 0 && enemy_count == 0 {
         spawner.cooldown = NO_ENEMIES_BREAK_COOLDOWN;
+        spawner.wave += 1;
+        spawner.announce = true;
         //new before break
-        spawner.before_break = fastrand::u32(MIN_SPAWNS_BEFORE_BREAK..=MAX_SPAWNS_BEFORE_BREAK);
+        spawner.before_break = spawner
+            .rng
+            .u32(config.spawner.min_spawns_before_break..=config.spawner.max_spawns_before_break);
+        //boss fight after every few waves
+        let boss = (spawner.wave - 1) % BOSS_WAVE_INTERVAL == 0;
+        let respite = if boss {
+            tr!("wave.boss_incoming")
+        } else {
+            tr!("wave.respite", seconds = NO_ENEMIES_BREAK_COOLDOWN as u32)
+        };
+        banner::push_fading_title(
+            cmd,
+            tr!("wave.cleared", wave = spawner.wave - 1, respite = respite),
+            WHITE,
+            UiOwner::Always,
+        );
+        if boss {
+            spawner.boss = true;
+            wave::boss(&mut WavePreamble {
+                world,
+                cmd,
+                player_pos: &player_pos,
+                rng: &mut spawner.rng,
+                arena,
+                jitter: 0.0,
+                camp_sides,
+            });
+        }
+    }
+    //no regular spawns during the boss fight
+    if spawner.boss {
+        return None;
+    }
+    //finish the running script first
+    if spawner.script.is_some() {
+        return advance_script(spawner, world, spawns, &player_pos, arena, camp_sides, dt);
     }
     //advance state
     spawner.cooldown -= dt;
     if spawner.cooldown > 0.0 || spawner.before_break == 0 {
-        return;
+        return None;
     }
     //TOO MANY ENEMIES
-    if enemy_count >= MAX_ENTITIES {
+    if active_threat >= spawner.difficulty.max_active_threat() {
         //set new cooldown
-        spawner.cooldown =
-            (MAX_SPAWN_COOLDOWN - MIN_SPAWN_COOLDOWN) * fastrand::f32() + MIN_SPAWN_COOLDOWN;
-        return;
+        spawner.cooldown = spawner.difficulty.spawn_cooldown(&mut spawner.rng);
+        return None;
     }
+    //announce the wave with its first spawn
+    if spawner.announce {
+        spawner.announce = false;
+        banner::push_fading_title(
+            cmd,
+            tr!("wave.start", wave = spawner.wave),
+            WHITE,
+            UiOwner::Always,
+        );
+    }
+    //affordable spawns, not chosen too many times in a row
+    let (credits, last_spawn, repeats) = (spawner.credits, spawner.last_spawn, spawner.repeats);
+    let eligible = |index: usize, wave: &SpawnTuning| {
+        wave.cost <= credits && (last_spawn != Some(index) || repeats < MAX_REPEATS)
+    };
     //get weight sum
-    let weight_sum = ENEMY_SPAWNS
+    let weight_sum = config
+        .spawns
         .iter()
-        .filter(|wave| wave.cost <= spawner.credits)
-        .fold(0, |acc, wave| acc + wave.weight);
+        .enumerate()
+        .filter(|(index, wave)| eligible(*index, wave))
+        .fold(0, |acc, (_, wave)| acc + weight(wave));
     //cannot afford any
     if weight_sum == 0 {
         //set new cooldown
-        spawner.cooldown =
-            (MAX_SPAWN_COOLDOWN - MIN_SPAWN_COOLDOWN) * fastrand::f32() + MIN_SPAWN_COOLDOWN;
-        return;
+        spawner.cooldown = spawner.difficulty.spawn_cooldown(&mut spawner.rng);
+        return None;
     }
     //randomly choose wave
-    let mut value = fastrand::u32(0..weight_sum);
-    let wave = 'outer: {
-        for wave in ENEMY_SPAWNS {
-            if wave.weight <= value {
-                value -= wave.weight
+    let mut value = spawner.rng.u32(0..weight_sum);
+    let index = 'outer: {
+        for (index, wave) in config.spawns.iter().enumerate() {
+            if !eligible(index, wave) {
+                continue;
+            }
+            if weight(wave) <= value {
+                value -= weight(wave)
             } else {
-                break 'outer wave;
+                break 'outer index;
             };
         }
-        ENEMY_SPAWNS[0]
+        0
     };
-    //how many times?
-    let double = fastrand::f32() <= DOUBLE_CHANCE;
-    let triple = fastrand::f32() <= TRIPLE_CHANCE;
+    //remember repeated spawns
+    if spawner.last_spawn == Some(index) {
+        spawner.repeats += 1;
+    } else {
+        spawner.last_spawn = Some(index);
+        spawner.repeats = 1;
+    }
+    let wave = config.spawns[index];
+    //how many times are the steps run?
+    let double = spawner.rng.f32() <= DOUBLE_CHANCE;
+    let triple = spawner.rng.f32() <= TRIPLE_CHANCE;
     let times = match (double, triple) {
         (true, true) => 3,
         (true, false) => 2,
@@ -228,23 +751,141 @@ pub fn enemy_spawning(world: &mut World, cmd: &mut CommandBuffer, dt: f32) {
         spawner.credits = 0.0;
     }
     //SPAWN!!
-    for _ in 0..times {
-        (wave.spawn)(&mut WavePreamble {
-            world,
-            cmd,
-            player_pos: &player_pos,
-        })
+    spawner.script = Some(ScriptRun {
+        spawn: index,
+        step: 0,
+        timer: 0.0,
+        times,
+    });
+    advance_script(spawner, world, spawns, &player_pos, arena, camp_sides, 0.0)
+}
+
+/// Advances the running script of `spawner` by `dt` and runs the steps that are due.
+///
+/// Once the script is finished, the next cooldown starts,
+/// a break after the last spawn of a wave.
+/// Returns the index of the spawn, if any of its steps ran.
+fn advance_script(
+    spawner: &mut EnemySpawner,
+    world: &World,
+    cmd: &mut CommandBuffer,
+    player_pos: &Position,
+    arena: Arena,
+    camp_sides: Option<[u8; 2]>,
+    dt: f32,
+) -> Option<usize> {
+    let run = spawner.script.as_mut()?;
+    let script = ENEMY_SPAWNS[run.spawn].script;
+    let spawn = run.spawn;
+    let mut ran = false;
+    run.timer += dt;
+    while let Some(step) = script.get(run.step) {
+        if run.timer < step.delay {
+            return ran.then_some(spawn);
+        }
+        run.timer -= step.delay;
+        run.step += 1;
+        ran = true;
+        for _ in 0..run.times {
+            step.run(&mut WavePreamble {
+                world,
+                cmd,
+                player_pos,
+                rng: &mut spawner.rng,
+                arena,
+                jitter: 0.0,
+                camp_sides,
+            });
+        }
     }
+    spawner.script = None;
+
     //break time????
     if spawner.before_break == 1 {
         spawner.before_break = 0;
         //set new cooldown
-        spawner.cooldown =
-            (MAX_BREAK_COOLDOWN - MIN_BREAK_COOLDOWN) * fastrand::f32() + MIN_BREAK_COOLDOWN;
-        return;
+        let tuning = config::get().spawner;
+        spawner.cooldown = (tuning.max_break_cooldown - tuning.min_break_cooldown)
+            * spawner.rng.f32()
+            + tuning.min_break_cooldown;
+        return ran.then_some(spawn);
     }
     spawner.before_break -= 1;
     //set new cooldown
-    spawner.cooldown =
-        (MAX_SPAWN_COOLDOWN - MIN_SPAWN_COOLDOWN) * fastrand::f32() + MIN_SPAWN_COOLDOWN;
+    spawner.cooldown = spawner.difficulty.spawn_cooldown(&mut spawner.rng);
+    ran.then_some(spawn)
+}
+
+/// Returns the spawner tuning of the compiled constants.
+pub(crate) fn default_spawner_config() -> SpawnerConfig {
+    SpawnerConfig {
+        init_credits: INIT_CREDITS,
+        credits_per_sec: CREDITS_PER_SEC,
+        init_cooldown: INIT_COOLDOWN,
+        min_spawn_cooldown: MIN_SPAWN_COOLDOWN,
+        max_spawn_cooldown: MAX_SPAWN_COOLDOWN,
+        min_break_cooldown: MIN_BREAK_COOLDOWN,
+        max_break_cooldown: MAX_BREAK_COOLDOWN,
+        min_spawns_before_break: MIN_SPAWNS_BEFORE_BREAK,
+        max_spawns_before_break: MAX_SPAWNS_BEFORE_BREAK,
+        max_active_threat: MAX_ACTIVE_THREAT,
+    }
+}
+
+/// Returns the tuning of every spawn of the spawn table, in its order.
+pub(crate) fn default_spawn_tunings() -> Vec<SpawnTuning> {
+    ENEMY_SPAWNS
+        .iter()
+        .map(|wave| SpawnTuning {
+            name: wave.name,
+            cost: wave.cost,
+            gain: wave.gain,
+            weight: wave.weight,
+            camp_weight: wave.camp_weight,
+            threat: wave.threat,
+        })
+        .collect()
+}
+
+/// Returns a generator for gameplay randomness outside of enemy spawning.
+///
+/// It is forked from the [EnemySpawner], so that seeded runs stay deterministic.
+/// Outside of runs a randomly seeded one is returned.
+pub fn run_rng(world: &mut World) -> Rng {
+    world
+        .query_mut::<&mut EnemySpawner>()
+        .into_iter()
+        .next()
+        .map(|(_, spawner)| spawner.events_rng.fork())
+        .unwrap_or_default()
+}
+
+/// Scales health and damage of newly spawned enemies by the current [Difficulty].
+/// Every enemy is scaled once, marked by [DifficultyScaled].
+pub fn scale_enemies(world: &mut World) {
+    let Some(difficulty) = world
+        .query_mut::<&EnemySpawner>()
+        .into_iter()
+        .next()
+        .map(|(_, spawner)| spawner.difficulty)
+    else {
+        return;
+    };
+
+    let mut scaled = Vec::new();
+    for (id, (health, damage)) in world
+        .query_mut::<(&mut Health, Option<&mut DamageDealer>)>()
+        .with::<&Enemy>()
+        .without::<&DifficultyScaled>()
+    {
+        health.max_hp *= difficulty.health_mult();
+        health.hp *= difficulty.health_mult();
+        if let Some(damage) = damage {
+            damage.dmg *= difficulty.damage_mult();
+        }
+        scaled.push(id);
+    }
+    for id in scaled {
+        let _ = world.insert_one(id, DifficultyScaled);
+    }
 }
@@ -0,0 +1,137 @@
+//! Slow motion finish of a run after the player's death.
+
+use hecs::World;
+use macroquad::prelude::*;
+
+use crate::{
+    basic::{
+        arena::{self, CameraOverride},
+        Position, Timer,
+    },
+    player::{Dead, Player},
+    SPACE_HEIGHT, SPACE_WIDTH,
+};
+
+/// Time scale of the world during the slow motion.
+const SLOW_MO_SCALE: f32 = 0.25;
+/// Real time the slow motion lasts before the game over screen.
+const SLOW_MO_TIME: f32 = 1.5;
+/// Zoom the camera reaches at the end of the slow motion.
+const DEATH_ZOOM: f32 = 2.0;
+
+//-----------------------------------------------------------------------------
+//COMPONENT PART
+//-----------------------------------------------------------------------------
+
+/// Resource slowing down the time of the world.
+/// [GameState::update](super::state::GameState::update) multiplies its `dt` by `scale`.
+#[derive(Clone, Copy, Debug)]
+pub struct TimeScale {
+    /// Multiplier of the time, 1.0 is the normal speed.
+    pub scale: f32,
+}
+
+/// Resource driving the slow motion after the player's death.
+#[derive(Clone, Copy, Debug)]
+pub struct DeathSlowMo {
+    /// Real time since the death.
+    timer: Timer,
+    /// View the camera zooms from.
+    from: Rect,
+    /// View around the player's last position the camera zooms into.
+    to: Rect,
+}
+
+//-----------------------------------------------------------------------------
+//SYSTEM PART
+//-----------------------------------------------------------------------------
+
+/// Returns the current time scale of the world, 1.0 without a [TimeScale].
+pub fn time_scale(world: &World) -> f32 {
+    world
+        .query::<&TimeScale>()
+        .iter()
+        .next()
+        .map(|(_, time)| time.scale)
+        .unwrap_or(1.0)
+}
+
+/// Plays the slow motion after the player's death.
+///
+/// The first call slows the world down and starts zooming towards the [Dead] player,
+/// the zoom is skipped when `reduced_motion` is on.
+/// Returns whether the slow motion is over, the time scale is back to normal then,
+/// but the zoomed [CameraOverride] is kept for the game over screen to take over.
+/// # Arguments
+/// * `dt` - scaled time of the step
+/// * `reduced_motion` - does the user prefer reduced motion?
+pub fn death_slow_mo(world: &mut World, dt: f32, reduced_motion: bool) -> bool {
+    //start the slow motion
+    if world
+        .query_mut::<&DeathSlowMo>()
+        .into_iter()
+        .next()
+        .is_none()
+    {
+        let Some((_, &pos)) = world
+            .query_mut::<&Position>()
+            .with::<(&Player, &Dead)>()
+            .into_iter()
+            .next()
+        else {
+            return true;
+        };
+        let arena = arena::arena(world);
+        let from = arena::view_rect(world);
+        //never look outside of the arena
+        let size = vec2(SPACE_WIDTH, SPACE_HEIGHT) / DEATH_ZOOM;
+        let corner = (vec2(pos.x, pos.y) - size / 2.0).clamp(
+            Vec2::ZERO,
+            (vec2(arena.width, arena.height) - size).max(Vec2::ZERO),
+        );
+        let to = if reduced_motion {
+            from
+        } else {
+            Rect::new(corner.x, corner.y, size.x, size.y)
+        };
+        world.spawn((
+            DeathSlowMo {
+                timer: Timer::new(SLOW_MO_TIME, false),
+                from,
+                to,
+            },
+            TimeScale {
+                scale: SLOW_MO_SCALE,
+            },
+            CameraOverride { rect: from },
+        ));
+        return false;
+    }
+
+    //advance in real time
+    let real_dt = dt / time_scale(world);
+    let Some((id, (slow_mo, view))) = world
+        .query_mut::<(&mut DeathSlowMo, &mut CameraOverride)>()
+        .into_iter()
+        .next()
+    else {
+        return true;
+    };
+    slow_mo.timer.tick(real_dt);
+    //ease the zoom in and out
+    let t = (slow_mo.timer.elapsed / slow_mo.timer.duration).min(1.0);
+    let t = t * t * (3.0 - 2.0 * t);
+    view.rect = Rect::new(
+        slow_mo.from.x + (slow_mo.to.x - slow_mo.from.x) * t,
+        slow_mo.from.y + (slow_mo.to.y - slow_mo.from.y) * t,
+        slow_mo.from.w + (slow_mo.to.w - slow_mo.from.w) * t,
+        slow_mo.from.h + (slow_mo.to.h - slow_mo.from.h) * t,
+    );
+    if !slow_mo.timer.finished() {
+        return false;
+    }
+
+    //back to normal time
+    let _ = world.remove::<(DeathSlowMo, TimeScale)>(id);
+    true
+}
@@ -1,26 +1,75 @@
 //! Game state initialising functions.
-use hecs::{CommandBuffer, World};
+use hecs::{CommandBuffer, Component, World};
 use macroquad::prelude::*;
 
 use crate::{
-    basic::{HealthDisplay, Position},
-    menu::{Button, StartButton, Title},
-    player, score, SPACE_HEIGHT, SPACE_WIDTH,
+    basic::{
+        arena::{self, CameraOverride},
+        motion::{BodyCollisions, KnockbackMemory},
+        rumble::Rumble,
+        CollisionDebug, ContactMemory, DamageGhost, FadeInOnSpawn, HealthDisplay, Lifetime,
+        Position,
+    },
+    changelog::ChangelogPanel,
+    config,
+    daily::{self, DailyRun},
+    debug::SystemTimings,
+    enemy::{RunStats, StaggerConfig},
+    fields::FieldOverlay,
+    ghost::{GhostPlayback, GhostRecorder},
+    heatmap::ForceHeatmap,
+    input::{Action, KeyBindings},
+    leaderboard,
+    locale::tr,
+    menu::{
+        self, ArenaButton, BackButton, Button, ChangelogButton, ControlsButton, DailyButton,
+        LanguageButton, LeaderboardButton, PauseMenu, PracticeButton, QuitAnswerButton, QuitButton,
+        QuitConfirm, RebindButton, RenderScaleButton, ResumeButton, RetryButton, SeedInput,
+        SeededRunButton, SettingsButton, Slider, SliderSetting, StartButton, StatsButton, Title,
+        ToggleButton, TutorialButton, UiOwner, WaveSelector, WaveStepButton,
+    },
+    persist::{Persistent, Settings},
+    pickup::HealthDrops,
+    player::{self, DashDisplay, ForceDisplay},
+    projectile::{ProjectileBatch, ProjectileBudget},
+    score::{self, Combo},
+    spectate::Spectate,
+    tutorial,
+    upgrade::{Upgrade, UpgradeButton},
+    SPACE_HEIGHT, SPACE_WIDTH,
 };
 
 use super::{
-    state::{GameOverTimer, Pause},
+    state::{GameOverTimer, GameState, PracticeRun, FULL_FADE_TIME},
     EnemySpawner,
 };
 
+/// Xp the player gets for every wave skipped in a practice run.
+const PRACTICE_XP_PER_WAVE: u32 = 30;
+/// Max width of long texts, wrapped when translations get longer.
+const TEXT_MAX_WIDTH: f32 = SPACE_WIDTH - 160.0;
+
 /// Initialises the play state.
 /// After this function the world is ready to be played by the player.
 pub fn init_game(world: &mut World) {
+    init_run(world, false);
+}
+
+/// Initialises the play state of a run.
+/// # Arguments
+/// * `world` - world to play in
+/// * `shared` - is the run shared between players, so that it ignores the config file
+fn init_run(world: &mut World, shared: bool) {
+    config::use_defaults_only(shared);
+    //keep the chosen play space
+    let arena = arena::arena(world);
     //clear remains of the previous state
     world.clear();
     //add entities required to play the game
+    //add play space
+    world.spawn((arena,));
     //add player
-    let player_id = world.spawn(player::new_entity());
+    let player_id = world.spawn(player::new_entity(&arena));
 
     //add player health display
     world.spawn((
@@ -39,20 +88,232 @@ pub fn init_game(world: &mut World) {
                 b: 0.0,
                 a: 1.0,
             },
+            ghost: DamageGhost::default(),
+        },
+        UiOwner::Always,
+    ));
+
+    //add player dash cooldown display
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: SPACE_HEIGHT - 16.0,
+        },
+        DashDisplay {
+            max_width: 120.0,
+            height: 4.0,
+            color: SKYBLUE,
+            charging_color: DARKGRAY,
+        },
+        UiOwner::Always,
+    ));
+
+    //add charge force display right of the health display
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0 + 180.0,
+            y: SPACE_HEIGHT - 20.0,
+        },
+        ForceDisplay {
+            radius: 14.0,
+            dial_color: DARKGRAY,
+            color: WHITE,
+            strong_color: ORANGE,
         },
+        UiOwner::Always,
     ));
 
     //add player's score display
-    world.spawn(score::create_score_display(vec2(SPACE_WIDTH / 2.0, 20.0), player_id).build());
+    world.spawn(
+        score::create_score_display(vec2(SPACE_WIDTH / 2.0, 20.0), player_id)
+            .add(UiOwner::Always)
+            .build(),
+    );
+    //add threat level display next to it
+    world.spawn(
+        score::create_threat_display(vec2(SPACE_WIDTH / 2.0 + 160.0, 20.0))
+            .add(UiOwner::Always)
+            .build(),
+    );
+    //add run timer left of the minimap
+    world.spawn(
+        score::create_run_timer_display(vec2(SPACE_WIDTH - 280.0, 20.0))
+            .add(UiOwner::Always)
+            .build(),
+    );
 
     //add enemy spawner
     world.spawn((EnemySpawner::default(),));
+
+    //add AI staggering
+    world.spawn((StaggerConfig::default(),));
+
+    //add rumble feedback
+    world.spawn((Rumble::default(),));
+
+    //add enemy projectile limit
+    world.spawn((ProjectileBudget::default(),));
+    world.spawn((ProjectileBatch::default(),));
+
+    //add physics body collisions
+    world.spawn((BodyCollisions::default(),));
+
+    //add collision pair memory
+    world.spawn((ContactMemory::default(),));
+    world.spawn((KnockbackMemory::default(),));
+
+    //add health drops
+    world.spawn((HealthDrops::default(),));
+
+    //add charge field overlay, hidden until toggled
+    world.spawn((FieldOverlay::default(),));
+
+    //add collision debug drawing, hidden until toggled
+    world.spawn((CollisionDebug::default(),));
+
+    //add system timings for the debug overlay
+    world.spawn((SystemTimings::default(),));
+
+    //add run statistics
+    world.spawn((RunStats::default(),));
+
+    //add kill combo
+    world.spawn((Combo::default(),));
+
+    //add force heatmap for balancing, only in debug builds
+    if cfg!(debug_assertions) {
+        world.spawn((ForceHeatmap::new(player_id, &arena),));
+    }
+
+    //add ghost of the best run
+    world.spawn((GhostRecorder::default(),));
+    world.spawn((
+        GhostPlayback::default(),
+        Position {
+            x: arena.center().x,
+            y: arena.center().y,
+        },
+    ));
+}
+
+/// Initialises the tutorial.
+/// Plays like a run without enemies, with no score saved.
+pub fn init_tutorial(world: &mut World, bindings: &KeyBindings) {
+    init_game(world);
+
+    //enemies only come from the tutorial script
+    let spawners: Vec<_> = world
+        .query_mut::<&EnemySpawner>()
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect();
+    for id in spawners {
+        let _ = world.despawn(id);
+    }
+
+    //mark the run, so that no score is saved
+    world.spawn((PracticeRun,));
+    tutorial::spawn_tutorial(world, bindings);
+}
+
+/// Initialises a practice run starting at wave `wave`.
+/// High score is not saved in practice runs.
+pub fn init_practice(world: &mut World, wave: u32) {
+    init_game(world);
+
+    //fast-forward the spawner
+    for (_, spawner) in world.query_mut::<&mut EnemySpawner>() {
+        *spawner = EnemySpawner::at_wave(wave);
+    }
+    //give the player xp proportional to the skipped waves
+    for (_, player) in world.query_mut::<&mut player::Player>() {
+        player.xp = (wave - 1) * PRACTICE_XP_PER_WAVE;
+    }
+
+    //mark the run
+    world.spawn((
+        Position { x: 90.0, y: 20.0 },
+        Title {
+            text: tr!("run.practice", wave = wave),
+            font: "main_font",
+            size: 20.0,
+            color: YELLOW,
+            max_width: None,
+        },
+        UiOwner::Always,
+        PracticeRun,
+    ));
+}
+
+/// Initialises a run generated from `seed`.
+/// Runs with the same seed spawn the same enemies.
+pub fn init_seeded(world: &mut World, seed: u64) {
+    init_game(world);
+
+    for (_, spawner) in world.query_mut::<&mut EnemySpawner>() {
+        *spawner = EnemySpawner::with_seed(seed);
+    }
+
+    //mark the run
+    world.spawn((
+        Position { x: 90.0, y: 20.0 },
+        Title {
+            text: tr!("run.seed", seed = format!("{seed:X}")),
+            font: "main_font",
+            size: 20.0,
+            color: YELLOW,
+            max_width: None,
+        },
+        UiOwner::Always,
+    ));
+}
+
+/// Initialises today's daily challenge.
+/// Everyone plays the same run on the standard [Arena](arena::Arena)
+/// with the compiled tuning that day, further attempts are marked as improvement attempts.
+pub fn init_daily(world: &mut World, persist: &Persistent) {
+    for (_, arena) in world.query_mut::<&mut arena::Arena>() {
+        *arena = arena::Arena::STANDARD;
+    }
+    //the compiled tuning, so that local config files do not change the run
+    init_run(world, true);
+
+    let date = daily::today();
+    for (_, spawner) in world.query_mut::<&mut EnemySpawner>() {
+        *spawner = EnemySpawner::with_seed(daily::daily_seed(&date));
+    }
+
+    //mark the run
+    let improvement = persist
+        .daily
+        .get(&date)
+        .is_some_and(|record| record.attempts > 0);
+    world.spawn((
+        Position { x: 200.0, y: 20.0 },
+        Title {
+            text: if improvement {
+                tr!("run.daily_improvement")
+            } else {
+                tr!("run.daily")
+            },
+            font: "main_font",
+            size: 20.0,
+            color: YELLOW,
+            max_width: None,
+        },
+        UiOwner::Always,
+        DailyRun { date, improvement },
+    ));
 }
 
 /// Initialises the main menu of the game.
-pub fn init_main_menu(world: &mut World) {
-    //clear remains of the previous state
+/// The quick-stats strip is taken from `persist`.
+pub fn init_main_menu(world: &mut World, persist: &Persistent) {
+    //keep the chosen play space
+    let arena = arena::arena(world);
+    //clear remains of the previous state, a whole run when coming from the game over
     world.clear();
+    world.spawn((arena,));
 
     //add game title
     world.spawn((
@@ -61,13 +322,35 @@ pub fn init_main_menu(world: &mut World) {
             y: 120.0,
         },
         Title {
-            text: "MAGNET FURY".into(),
+            text: tr!("menu.title"),
             font: "main_font",
             size: 100.0,
             color: WHITE,
+            max_width: None,
         },
+        UiOwner::State(GameState::MainMenu),
     ));
 
+    //add quick-stats strip
+    let segments = menu::quick_stats(persist);
+    let spacing = SPACE_WIDTH / (segments.len() as f32 + 1.0);
+    for (i, text) in segments.into_iter().enumerate() {
+        world.spawn((
+            Position {
+                x: spacing * (i as f32 + 1.0),
+                y: 200.0,
+            },
+            Title {
+                text,
+                font: "main_font",
+                size: 24.0,
+                color: LIGHTGRAY,
+                max_width: None,
+            },
+            UiOwner::State(GameState::MainMenu),
+        ));
+    }
+
     //add start game button
     world.spawn((
         Position {
@@ -75,11 +358,13 @@ pub fn init_main_menu(world: &mut World) {
             y: 280.0,
         },
         Title {
-            text: "START".into(),
+            text: tr!("menu.start"),
             font: "main_font",
             size: 50.0,
             color: WHITE,
+            max_width: None,
         },
+        UiOwner::State(GameState::MainMenu),
         Button {
             width: 160.0,
             height: 50.0,
@@ -90,64 +375,1395 @@ pub fn init_main_menu(world: &mut World) {
         },
         StartButton,
     ));
-}
 
-/// Initialises pause screen.
-pub fn init_pause(world: &mut World) {
+    //point new players to the tutorial
+    if !persist.tutorial_done {
+        world.spawn((
+            Position {
+                x: SPACE_WIDTH / 2.0,
+                y: 320.0,
+            },
+            Title {
+                text: tr!("menu.tutorial_hint"),
+                font: "main_font",
+                size: 20.0,
+                color: YELLOW,
+                max_width: None,
+            },
+            UiOwner::State(GameState::MainMenu),
+        ));
+    }
+
+    //add practice and daily challenge buttons side by side
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0 - 150.0,
+            y: 360.0,
+        },
+        Title {
+            text: tr!("menu.practice"),
+            font: "main_font",
+            size: 50.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::MainMenu),
+        Button {
+            width: 260.0,
+            height: 50.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        PracticeButton,
+    ));
+
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0 + 150.0,
+            y: 360.0,
+        },
+        Title {
+            text: tr!("menu.daily"),
+            font: "main_font",
+            size: 50.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::MainMenu),
+        Button {
+            width: 180.0,
+            height: 50.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        DailyButton,
+    ));
+
+    //add arena size button
     world.spawn((
         Position {
             x: SPACE_WIDTH / 2.0,
-            y: SPACE_HEIGHT / 2.0,
+            y: 440.0,
         },
         Title {
-            text: "PAUSED".into(),
+            text: menu::arena_text(&arena),
             font: "main_font",
-            size: 40.0,
+            size: 30.0,
             color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::MainMenu),
+        Button {
+            width: 360.0,
+            height: 40.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
         },
-        Pause,
+        ArenaButton,
     ));
-}
 
-/// Clears the pause screen.
-pub fn clear_pause(world: &mut World) {
-    let mut cmd = CommandBuffer::new();
-    for (entity, _) in world.query_mut::<&Pause>() {
-        cmd.despawn(entity)
-    }
-    cmd.run_on(world);
-}
+    //add changelog button
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: 500.0,
+        },
+        Title {
+            text: tr!("menu.whats_new"),
+            font: "main_font",
+            size: 30.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::MainMenu),
+        Button {
+            width: 220.0,
+            height: 40.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        ChangelogButton,
+    ));
 
-/// Initialises game over screen.
-pub fn init_game_over(world: &mut World) {
-    world.spawn((GameOverTimer { time: 0.0 },));
+    //add controls and tutorial buttons side by side
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0 - 130.0,
+            y: 555.0,
+        },
+        Title {
+            text: tr!("menu.controls"),
+            font: "main_font",
+            size: 30.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::MainMenu),
+        Button {
+            width: 200.0,
+            height: 40.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        ControlsButton,
+    ));
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0 + 130.0,
+            y: 555.0,
+        },
+        Title {
+            text: tr!("menu.how_to_play"),
+            font: "main_font",
+            size: 30.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::MainMenu),
+        Button {
+            width: 200.0,
+            height: 40.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        TutorialButton,
+    ));
 
+    //add settings button
     world.spawn((
         Position {
             x: SPACE_WIDTH / 2.0,
-            y: SPACE_HEIGHT / 2.0,
+            y: 610.0,
         },
         Title {
-            text: "GAME OVER".into(),
+            text: tr!("menu.settings"),
             font: "main_font",
-            size: 60.0,
+            size: 30.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::MainMenu),
+        Button {
+            width: 200.0,
+            height: 40.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        SettingsButton,
+    ));
+
+    //add stats and leaderboard buttons side by side
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0 - 130.0,
+            y: 665.0,
+        },
+        Title {
+            text: tr!("menu.stats"),
+            font: "main_font",
+            size: 30.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::MainMenu),
+        Button {
+            width: 200.0,
+            height: 40.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        StatsButton,
+    ));
+
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0 + 130.0,
+            y: 665.0,
+        },
+        Title {
+            text: tr!("menu.leaderboard"),
+            font: "main_font",
+            size: 30.0,
             color: WHITE,
+            max_width: None,
         },
+        UiOwner::State(GameState::MainMenu),
+        Button {
+            width: 260.0,
+            height: 40.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        LeaderboardButton,
     ));
+}
 
+/// Initialises the settings screen showing the current `settings`.
+pub fn init_settings(world: &mut World, settings: &Settings) {
+    //add screen title
     world.spawn((
         Position {
             x: SPACE_WIDTH / 2.0,
-            y: SPACE_HEIGHT / 2.0 + 60.0,
+            y: 120.0,
         },
         Title {
-            text: "Press escape to return to main menu".into(),
+            text: tr!("menu.settings"),
             font: "main_font",
-            size: 40.0,
+            size: 60.0,
             color: WHITE,
+            max_width: None,
         },
+        UiOwner::State(GameState::Settings),
     ));
 
-    //add highscore
-    world.spawn(score::create_highscore_display(vec2(SPACE_WIDTH / 2.0, 45.0)).build());
+    //add a slider for every volume and the rumble
+    for (i, (name, setting)) in [
+        ("settings.master_volume", SliderSetting::MasterVolume),
+        ("settings.sfx_volume", SliderSetting::SfxVolume),
+        ("settings.music_volume", SliderSetting::MusicVolume),
+        ("settings.rumble_intensity", SliderSetting::RumbleIntensity),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let y = 190.0 + i as f32 * 50.0;
+        world.spawn((
+            Position {
+                x: SPACE_WIDTH / 2.0 - 180.0,
+                y,
+            },
+            Title {
+                text: tr!(name),
+                font: "main_font",
+                size: 30.0,
+                color: WHITE,
+                max_width: None,
+            },
+            UiOwner::State(GameState::Settings),
+        ));
+        world.spawn((
+            Position {
+                x: SPACE_WIDTH / 2.0 + 180.0,
+                y,
+            },
+            Slider {
+                width: 260.0,
+                height: 8.0,
+                value: setting.get(settings),
+                neutral_color: DARKGRAY,
+                active_color: WHITE,
+                dragging: false,
+            },
+            setting,
+            UiOwner::State(GameState::Settings),
+        ));
+    }
+
+    //buttons are laid out in two columns
+    let left = SPACE_WIDTH / 2.0 - 220.0;
+    let right = SPACE_WIDTH / 2.0 + 220.0;
+
+    //add language selector
+    world.spawn((
+        Position { x: left, y: 400.0 },
+        Title {
+            text: menu::language_text(settings.language),
+            font: "main_font",
+            size: 30.0,
+            color: WHITE,
+            max_width: Some(400.0),
+        },
+        UiOwner::State(GameState::Settings),
+        Button {
+            width: 420.0,
+            height: 45.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        LanguageButton,
+    ));
+
+    //add toggles
+    spawn_toggle(world, "settings.crt", settings.crt, vec2(left, 455.0));
+    spawn_toggle(
+        world,
+        "settings.flashing",
+        settings.reduce_flashing,
+        vec2(left, 510.0),
+    );
+    spawn_toggle(
+        world,
+        "settings.colorblind",
+        settings.colorblind,
+        vec2(left, 565.0),
+    );
+    spawn_toggle(
+        world,
+        "settings.kill_banners",
+        settings.kill_banners,
+        vec2(right, 400.0),
+    );
+    spawn_toggle(world, "settings.ghost", settings.ghost, vec2(right, 455.0));
+    spawn_toggle(
+        world,
+        "settings.reduced_motion",
+        settings.reduced_motion,
+        vec2(right, 510.0),
+    );
+
+    //add render scale selector
+    world.spawn((
+        Position { x: right, y: 565.0 },
+        Title {
+            text: menu::render_scale_text(settings.render_scale),
+            font: "main_font",
+            size: 30.0,
+            color: WHITE,
+            max_width: Some(400.0),
+        },
+        UiOwner::State(GameState::Settings),
+        Button {
+            width: 420.0,
+            height: 45.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        RenderScaleButton,
+    ));
+    spawn_toggle(
+        world,
+        "settings.rumble",
+        settings.rumble,
+        vec2(right, 620.0),
+    );
+
+    //add back button
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: 680.0,
+        },
+        Title {
+            text: tr!("menu.back"),
+            font: "main_font",
+            size: 40.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::Settings),
+        Button {
+            width: 140.0,
+            height: 45.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        BackButton,
+    ));
+}
+
+/// Spawns a [ToggleButton] of the settings screen.
+/// # Arguments
+/// * `label` - locale key of the button's label
+/// * `on` - current value of the toggled setting
+/// * `position` - position of the button
+fn spawn_toggle(world: &mut World, label: &'static str, on: bool, position: Vec2) {
+    world.spawn((
+        Position {
+            x: position.x,
+            y: position.y,
+        },
+        Title {
+            text: menu::toggle_text(label, on),
+            font: "main_font",
+            size: 30.0,
+            color: WHITE,
+            max_width: Some(400.0),
+        },
+        UiOwner::State(GameState::Settings),
+        Button {
+            width: 420.0,
+            height: 45.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        ToggleButton { label },
+    ));
+}
+
+/// Initialises the controls screen listing the current `bindings`.
+pub fn init_controls(world: &mut World, bindings: &KeyBindings) {
+    //add screen title
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: 120.0,
+        },
+        Title {
+            text: tr!("menu.controls"),
+            font: "main_font",
+            size: 60.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::Controls),
+    ));
+
+    //add a row for every action
+    for (i, action) in Action::ALL.into_iter().enumerate() {
+        let y = 210.0 + i as f32 * 65.0;
+        world.spawn((
+            Position {
+                x: SPACE_WIDTH / 2.0 - 150.0,
+                y,
+            },
+            Title {
+                text: action.name(),
+                font: "main_font",
+                size: 35.0,
+                color: WHITE,
+                max_width: None,
+            },
+            UiOwner::State(GameState::Controls),
+        ));
+        world.spawn((
+            Position {
+                x: SPACE_WIDTH / 2.0 + 150.0,
+                y,
+            },
+            Title {
+                text: bindings.get(action).name(),
+                font: "main_font",
+                size: 35.0,
+                color: WHITE,
+                max_width: None,
+            },
+            UiOwner::State(GameState::Controls),
+            Button {
+                width: 260.0,
+                height: 45.0,
+                neutral_color: WHITE,
+                hover_color: LIGHTGRAY,
+                active_color: GRAY,
+                clicked: false,
+            },
+            RebindButton {
+                action,
+                capturing: false,
+            },
+        ));
+    }
+
+    //add back button
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: 600.0,
+        },
+        Title {
+            text: tr!("menu.back"),
+            font: "main_font",
+            size: 40.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::Controls),
+        Button {
+            width: 140.0,
+            height: 45.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        BackButton,
+    ));
+}
+
+/// Initialises the screen listing the lifetime statistics from `persist`.
+pub fn init_stats(world: &mut World, persist: &Persistent) {
+    //add screen title
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: 100.0,
+        },
+        Title {
+            text: tr!("menu.stats"),
+            font: "main_font",
+            size: 60.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::Stats),
+    ));
+
+    //add a column of general statistics and one of kills
+    let (general, kills) = menu::lifetime_stats(persist);
+    for (x, lines) in [
+        (SPACE_WIDTH / 2.0 - 220.0, general),
+        (SPACE_WIDTH / 2.0 + 220.0, kills),
+    ] {
+        for (i, text) in lines.into_iter().enumerate() {
+            world.spawn((
+                Position {
+                    x,
+                    y: 180.0 + i as f32 * 45.0,
+                },
+                Title {
+                    text,
+                    font: "main_font",
+                    size: 28.0,
+                    color: WHITE,
+                    max_width: None,
+                },
+                UiOwner::State(GameState::Stats),
+            ));
+        }
+    }
+
+    //add back button
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: 650.0,
+        },
+        Title {
+            text: tr!("menu.back"),
+            font: "main_font",
+            size: 40.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::Stats),
+        Button {
+            width: 140.0,
+            height: 45.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        BackButton,
+    ));
+}
+
+/// Initialises the leaderboard screen.
+pub fn init_leaderboard(world: &mut World, persist: &Persistent) {
+    //add screen title
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: 100.0,
+        },
+        Title {
+            text: tr!("menu.leaderboard"),
+            font: "main_font",
+            size: 60.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::Leaderboard),
+    ));
+
+    //add a row for every run, columns of names, scores and durations
+    let rows = menu::leaderboard_rows(persist);
+    if rows.is_empty() {
+        world.spawn((
+            Position {
+                x: SPACE_WIDTH / 2.0,
+                y: 300.0,
+            },
+            Title {
+                text: tr!("leaderboard.empty"),
+                font: "main_font",
+                size: 28.0,
+                color: WHITE,
+                max_width: None,
+            },
+            UiOwner::State(GameState::Leaderboard),
+        ));
+    }
+    for (i, row) in rows.into_iter().enumerate() {
+        let columns = [-220.0, 40.0, 240.0].map(|offset| SPACE_WIDTH / 2.0 + offset);
+        for (x, text) in columns.into_iter().zip(row) {
+            world.spawn((
+                Position {
+                    x,
+                    y: 170.0 + i as f32 * 40.0,
+                },
+                Title {
+                    text,
+                    font: "main_font",
+                    size: 28.0,
+                    color: WHITE,
+                    max_width: None,
+                },
+                UiOwner::State(GameState::Leaderboard),
+            ));
+        }
+    }
+
+    //add back button
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: 650.0,
+        },
+        Title {
+            text: tr!("menu.back"),
+            font: "main_font",
+            size: 40.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::Leaderboard),
+        Button {
+            width: 140.0,
+            height: 45.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        BackButton,
+    ));
+}
+
+/// Initialises the changelog screen.
+pub fn init_changelog(world: &mut World) {
+    //add screen title
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: 100.0,
+        },
+        Title {
+            text: tr!("menu.whats_new"),
+            font: "main_font",
+            size: 60.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::Changelog),
+    ));
+
+    //add the panel
+    world.spawn((
+        ChangelogPanel::default(),
+        UiOwner::State(GameState::Changelog),
+    ));
+
+    //add back button
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: 650.0,
+        },
+        Title {
+            text: tr!("menu.back"),
+            font: "main_font",
+            size: 40.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::Changelog),
+        Button {
+            width: 140.0,
+            height: 45.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        BackButton,
+    ));
+}
+
+/// Initialises the practice menu with its wave selector.
+pub fn init_practice_menu(world: &mut World) {
+    //add menu title
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: 120.0,
+        },
+        Title {
+            text: tr!("menu.practice"),
+            font: "main_font",
+            size: 80.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::PracticeMenu),
+    ));
+
+    //add wave selector
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: 260.0,
+        },
+        Title {
+            text: tr!("practice.wave", wave = 1),
+            font: "main_font",
+            size: 50.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::PracticeMenu),
+        WaveSelector { wave: 1 },
+    ));
+    for (text, x, step) in [("<", -180.0, -1), (">", 180.0, 1)] {
+        world.spawn((
+            Position {
+                x: SPACE_WIDTH / 2.0 + x,
+                y: 260.0,
+            },
+            Title {
+                text: text.into(),
+                font: "main_font",
+                size: 50.0,
+                color: WHITE,
+                max_width: None,
+            },
+            UiOwner::State(GameState::PracticeMenu),
+            Button {
+                width: 50.0,
+                height: 50.0,
+                neutral_color: WHITE,
+                hover_color: LIGHTGRAY,
+                active_color: GRAY,
+                clicked: false,
+            },
+            WaveStepButton { step },
+        ));
+    }
+
+    //add start practice button
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: 360.0,
+        },
+        Title {
+            text: tr!("menu.start"),
+            font: "main_font",
+            size: 50.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::PracticeMenu),
+        Button {
+            width: 160.0,
+            height: 50.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        StartButton,
+    ));
+
+    //add back button
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: 440.0,
+        },
+        Title {
+            text: tr!("menu.back"),
+            font: "main_font",
+            size: 50.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::PracticeMenu),
+        Button {
+            width: 160.0,
+            height: 50.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        BackButton,
+    ));
+
+    //add seeded run button
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: 540.0,
+        },
+        Title {
+            text: tr!("practice.seeded_run"),
+            font: "main_font",
+            size: 30.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::PracticeMenu),
+        Button {
+            width: 240.0,
+            height: 40.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        SeededRunButton,
+    ));
+}
+
+/// Initialises the seeded run menu.
+pub fn init_seed_menu(world: &mut World) {
+    //add menu title
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: 120.0,
+        },
+        Title {
+            text: tr!("practice.seeded_run"),
+            font: "main_font",
+            size: 80.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::SeedMenu),
+    ));
+
+    //add instructions
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: 200.0,
+        },
+        Title {
+            text: tr!("seed.instructions"),
+            font: "main_font",
+            size: 24.0,
+            color: LIGHTGRAY,
+            max_width: Some(TEXT_MAX_WIDTH),
+        },
+        UiOwner::State(GameState::SeedMenu),
+    ));
+
+    //add seed input
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: 270.0,
+        },
+        Title {
+            text: tr!("seed.random"),
+            font: "main_font",
+            size: 50.0,
+            color: YELLOW,
+            max_width: None,
+        },
+        UiOwner::State(GameState::SeedMenu),
+        SeedInput::default(),
+    ));
+
+    //add start button
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: 360.0,
+        },
+        Title {
+            text: tr!("menu.start"),
+            font: "main_font",
+            size: 50.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::SeedMenu),
+        Button {
+            width: 160.0,
+            height: 50.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        StartButton,
+    ));
+
+    //add back button
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: 440.0,
+        },
+        Title {
+            text: tr!("menu.back"),
+            font: "main_font",
+            size: 50.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::SeedMenu),
+        Button {
+            width: 160.0,
+            height: 50.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        BackButton,
+    ));
+}
+
+/// Initialises pause screen.
+pub fn init_pause(world: &mut World) {
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: SPACE_HEIGHT / 2.0,
+        },
+        Title {
+            text: tr!("run.paused"),
+            font: "main_font",
+            size: 40.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::Paused),
+        PauseMenu,
+    ));
+
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: SPACE_HEIGHT / 2.0 + 70.0,
+        },
+        Title {
+            text: tr!("pause.resume"),
+            font: "main_font",
+            size: 30.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::Paused),
+        Button {
+            width: 300.0,
+            height: 40.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        PauseMenu,
+        ResumeButton,
+    ));
+
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: SPACE_HEIGHT / 2.0 + 120.0,
+        },
+        Title {
+            text: tr!("pause.quit"),
+            font: "main_font",
+            size: 30.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::Paused),
+        Button {
+            width: 300.0,
+            height: 40.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        PauseMenu,
+        QuitButton,
+    ));
+}
+
+/// Swaps the pause screen for the dialog confirming quitting the run.
+pub fn init_quit_confirm(world: &mut World) {
+    despawn_marked::<PauseMenu>(world);
+
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: SPACE_HEIGHT / 2.0,
+        },
+        Title {
+            text: tr!("pause.abandon"),
+            font: "main_font",
+            size: 40.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::Paused),
+        QuitConfirm,
+    ));
+
+    //add the answers side by side
+    for (text, abandon, offset) in [("pause.yes", true, -100.0), ("pause.no", false, 100.0)] {
+        world.spawn((
+            Position {
+                x: SPACE_WIDTH / 2.0 + offset,
+                y: SPACE_HEIGHT / 2.0 + 80.0,
+            },
+            Title {
+                text: tr!(text),
+                font: "main_font",
+                size: 30.0,
+                color: WHITE,
+                max_width: None,
+            },
+            UiOwner::State(GameState::Paused),
+            Button {
+                width: 140.0,
+                height: 40.0,
+                neutral_color: WHITE,
+                hover_color: LIGHTGRAY,
+                active_color: GRAY,
+                clicked: false,
+            },
+            QuitConfirm,
+            QuitAnswerButton { abandon },
+        ));
+    }
+}
+
+/// Closes the dialog confirming quitting the run and restores the pause screen.
+pub fn close_quit_confirm(world: &mut World) {
+    despawn_marked::<QuitConfirm>(world);
+    init_pause(world);
+}
+
+/// Despawns all entities marked by `T`.
+fn despawn_marked<T: Component>(world: &mut World) {
+    let marked: Vec<_> = world
+        .query_mut::<()>()
+        .with::<&T>()
+        .into_iter()
+        .map(|(entity, _)| entity)
+        .collect();
+    for entity in marked {
+        let _ = world.despawn(entity);
+    }
+}
+
+/// Initialises the level up screen offering `choices` as cards.
+pub fn init_level_up(world: &mut World, choices: &[Upgrade]) {
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: SPACE_HEIGHT / 2.0 - 100.0,
+        },
+        Title {
+            text: tr!("run.level_up"),
+            font: "main_font",
+            size: 50.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::LevelUp),
+    ));
+
+    //add a card for every choice
+    let spacing = SPACE_WIDTH / (choices.len() as f32 + 1.0);
+    for (i, &upgrade) in choices.iter().enumerate() {
+        world.spawn((
+            Position {
+                x: spacing * (i as f32 + 1.0),
+                y: SPACE_HEIGHT / 2.0,
+            },
+            Title {
+                text: upgrade.name(),
+                font: "main_font",
+                size: 30.0,
+                color: WHITE,
+                max_width: None,
+            },
+            UiOwner::State(GameState::LevelUp),
+            Button {
+                width: 300.0,
+                height: 60.0,
+                neutral_color: WHITE,
+                hover_color: LIGHTGRAY,
+                active_color: GRAY,
+                clicked: false,
+            },
+            UpgradeButton { upgrade },
+        ));
+    }
+}
+
+/// Despawns the UI owned by `state`.
+/// Shared [UiOwner::Always] pieces are kept.
+pub fn despawn_ui(world: &mut World, state: GameState) {
+    let mut cmd = CommandBuffer::new();
+    for (entity, owner) in world.query_mut::<&UiOwner>() {
+        if *owner == UiOwner::State(state) {
+            cmd.despawn(entity)
+        }
+    }
+    cmd.run_on(world);
+}
+
+/// Initialises game over screen.
+/// Spectating starts right away, unless reduced motion is on.
+/// Runs which made it onto the leaderboard at `rank` get to enter initials.
+pub fn init_game_over(world: &mut World, persist: &Persistent, rank: Option<usize>) {
+    world.spawn((GameOverTimer, Lifetime::forever()));
+    //continue from the view the run ended with
+    let views: Vec<_> = world
+        .query_mut::<&CameraOverride>()
+        .into_iter()
+        .map(|(id, view)| (id, view.rect))
+        .collect();
+    let view = views
+        .first()
+        .map(|(_, rect)| *rect)
+        .unwrap_or_else(|| arena::arena(world).view_rect());
+    for (id, _) in views {
+        let _ = world.despawn(id);
+    }
+    world.spawn((
+        Spectate::from_view(!persist.settings.reduced_motion, view),
+        CameraOverride { rect: view },
+    ));
+
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: SPACE_HEIGHT / 2.0,
+        },
+        Title {
+            text: tr!("game_over.title"),
+            font: "main_font",
+            size: 60.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::GameOver),
+    ));
+
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: SPACE_HEIGHT / 2.0 + 60.0,
+        },
+        Title {
+            text: tr!("game_over.escape"),
+            font: "main_font",
+            size: 40.0,
+            color: WHITE,
+            max_width: Some(TEXT_MAX_WIDTH),
+        },
+        UiOwner::State(GameState::GameOver),
+    ));
+
+    //add highscore
+    world.spawn(
+        score::create_highscore_display(vec2(SPACE_WIDTH / 2.0, 45.0))
+            .add(UiOwner::State(GameState::GameOver))
+            .build(),
+    );
+
+    //add initials entry
+    if let Some(rank) = rank {
+        leaderboard::spawn_name_entry(world, rank, SPACE_HEIGHT / 2.0 - 150.0);
+    }
+
+    //add kill breakdown
+    if let Some((_, &stats)) = world.query_mut::<&RunStats>().into_iter().next() {
+        world.spawn((
+            Position {
+                x: SPACE_WIDTH / 2.0,
+                y: SPACE_HEIGHT / 2.0 + 120.0,
+            },
+            Title {
+                text: tr!(
+                    "game_over.kills",
+                    shot = stats.projectile_kills,
+                    rammed = stats.collision_kills,
+                    engineered = stats.engineered_kills,
+                    other = stats.environment_kills,
+                ),
+                font: "main_font",
+                size: 24.0,
+                color: WHITE,
+                max_width: Some(TEXT_MAX_WIDTH),
+            },
+            UiOwner::State(GameState::GameOver),
+        ));
+
+        //add survival time next to the best one
+        world.spawn((
+            Position {
+                x: SPACE_WIDTH / 2.0,
+                y: SPACE_HEIGHT / 2.0 + 200.0,
+            },
+            Title {
+                text: tr!(
+                    "game_over.survived",
+                    time = menu::format_time(stats.time),
+                    best = menu::format_time(persist.stats.best_time.max(stats.time)),
+                ),
+                font: "main_font",
+                size: 24.0,
+                color: WHITE,
+                max_width: None,
+            },
+            UiOwner::State(GameState::GameOver),
+        ));
+    }
+
+    //add daily challenge results
+    if let Some(daily) = daily::daily_run(world) {
+        let record = persist.daily.get(&daily.date).copied().unwrap_or_default();
+        world.spawn((
+            Position {
+                x: SPACE_WIDTH / 2.0,
+                y: SPACE_HEIGHT / 2.0 + 265.0,
+            },
+            Title {
+                text: tr!(
+                    if daily.improvement {
+                        "game_over.daily_improvement"
+                    } else {
+                        "game_over.daily"
+                    },
+                    best = record.best * 10,
+                    attempts = record.attempts,
+                    all_time = persist.daily_best() * 10,
+                ),
+                font: "main_font",
+                size: 24.0,
+                color: YELLOW,
+                max_width: Some(TEXT_MAX_WIDTH),
+            },
+            UiOwner::State(GameState::GameOver),
+        ));
+    }
+
+    //add seed, so that the run can be shared, practice runs cannot be replayed
+    let practice = world
+        .query_mut::<&PracticeRun>()
+        .into_iter()
+        .next()
+        .is_some();
+    let seed = world
+        .query_mut::<&EnemySpawner>()
+        .into_iter()
+        .next()
+        .map(|(_, spawner)| spawner.seed);
+    if let Some(seed) = seed.filter(|_| !practice) {
+        world.spawn((
+            Position {
+                x: SPACE_WIDTH / 2.0,
+                y: SPACE_HEIGHT / 2.0 + 235.0,
+            },
+            Title {
+                text: tr!("game_over.seed", seed = format!("{seed:X}")),
+                font: "main_font",
+                size: 20.0,
+                color: LIGHTGRAY,
+                max_width: None,
+            },
+            UiOwner::State(GameState::GameOver),
+        ));
+    }
+
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: SPACE_HEIGHT / 2.0 + 160.0,
+        },
+        Title {
+            text: tr!("game_over.spectate"),
+            font: "main_font",
+            size: 24.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::GameOver),
+    ));
+
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: SPACE_HEIGHT - 45.0,
+        },
+        Title {
+            text: tr!("game_over.retry"),
+            font: "main_font",
+            size: 30.0,
+            color: WHITE,
+            max_width: None,
+        },
+        UiOwner::State(GameState::GameOver),
+        Button {
+            width: 220.0,
+            height: 40.0,
+            neutral_color: WHITE,
+            hover_color: LIGHTGRAY,
+            active_color: GRAY,
+            clicked: false,
+        },
+        RetryButton,
+    ));
+
+    //fade the screen in, unless reduced motion is on
+    if !persist.settings.reduced_motion {
+        let fading: Vec<_> = world
+            .query_mut::<(&UiOwner, &mut Title)>()
+            .without::<&Lifetime>()
+            .into_iter()
+            .filter(|(_, (owner, _))| **owner == UiOwner::State(GameState::GameOver))
+            .map(|(id, (_, title))| {
+                title.color.a = 0.0;
+                id
+            })
+            .collect();
+        for id in fading {
+            let _ = world.insert(
+                id,
+                (
+                    Lifetime::forever(),
+                    FadeInOnSpawn {
+                        seconds: FULL_FADE_TIME,
+                    },
+                ),
+            );
+        }
+    }
 }
@@ -4,40 +4,85 @@ use hecs::{CommandBuffer, World};
 use macroquad::prelude::*;
 
 use crate::{
-    basic::{self, fx::FxManager, render::AssetManager, Health},
-    enemy,
-    menu::{self, Title},
+    banner,
+    basic::{
+        self,
+        fx::FxManager,
+        render::{AssetManager, WorldCanvas},
+    },
+    changelog, daily,
+    debug::SystemTimings,
+    enemy, fields, ghost,
+    heatmap::{self, ForceHeatmap},
+    hints, indicator,
+    input::{GamepadState, PlayerInput},
+    leaderboard::{self, ScoreEntry},
+    loading,
+    locale::tr,
+    menu::{self, QuitButton, QuitConfirm, ResumeButton, RetryButton, UiOwner, WaveSelector},
+    minimap,
     persist::Persistent,
-    player::{self, Player},
-    projectile, score, xp,
+    pickup,
+    player::{self, Dead, Player},
+    projectile, score,
+    spectate::{self, Spectate},
+    tutorial, upgrade, xp, SPACE_HEIGHT, SPACE_WIDTH,
 };
 
 /// Represents the current state the game is in.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GameState {
-    /// Main Menu, first state when the game starts.
+    /// Loading of the assets, first state when the game starts.
+    Loading,
+    /// Main Menu, shown once the assets are loaded.
     MainMenu,
+    /// Menu choosing the wave a practice run starts at.
+    PracticeMenu,
+    /// Menu entering the seed of a seeded run.
+    SeedMenu,
+    /// Screen listing what changed in recent versions.
+    Changelog,
+    /// Screen rebinding the controls.
+    Controls,
+    /// Screen changing the user's preferences.
+    Settings,
+    /// Screen listing the lifetime statistics.
+    Stats,
+    /// Screen listing the best runs.
+    Leaderboard,
     /// When the game is playable and the player plays.
     Running,
+    /// Scripted run teaching the controls.
+    Tutorial,
     /// When the game is paused.
     Paused,
+    /// When the game is paused to choose an upgrade.
+    LevelUp,
     /// After death of the player to show informations.
     GameOver,
 }
 
-/// Marker of entites created in the pause state.
+/// Marker of practice runs.
+/// High score is not saved while it exists.
 #[derive(Clone, Copy, Debug, Default)]
-pub struct Pause;
+pub struct PracticeRun;
 
-/// Timer used by the gameover state.
-/// It is used to implement fading.
+/// Idle time after which the game over screen returns to the main menu.
+const GAME_OVER_IDLE_TIME: f32 = 60.0;
+/// Idle time after which the main menu throttles the frame rate.
+const MENU_IDLE_TIME: f32 = 300.0;
+
+/// Highest wave a practice run can start at.
+const MAX_PRACTICE_WAVE: u32 = 30;
+
+/// Marker of the entity whose [Lifetime](basic::Lifetime) times the game over screen.
+/// It is used to fade in the dim over the world.
 #[derive(Clone, Copy, Debug, Default)]
-pub struct GameOverTimer {
-    pub(crate) time: f32,
-}
+pub struct GameOverTimer;
 
 impl GameState {
     /// Updates the current game state
+    #[allow(clippy::too_many_arguments)]
     pub fn update(
         &mut self,
         world: &mut World,
@@ -46,33 +91,136 @@ impl GameState {
         dt: f32,
         fx: &mut FxManager,
         persist: &mut Persistent,
+        gamepad: &GamepadState,
     ) {
+        //slow motion slows down everything
+        let dt = dt * super::death::time_scale(world);
         let new_state = match self {
-            GameState::MainMenu => main_menu_update(world),
-            GameState::Running => game_update(world, events, assets, dt, fx, persist),
-            GameState::Paused => pause_update(world),
-            GameState::GameOver => game_over_update(world, dt),
+            //driven by the main loop, as the assets load asynchronously
+            GameState::Loading => None,
+            GameState::MainMenu => main_menu_update(world, gamepad, persist),
+            GameState::Controls => controls_update(world, persist),
+            GameState::Settings => settings_update(world, persist),
+            GameState::PracticeMenu => practice_menu_update(world, gamepad, persist),
+            GameState::SeedMenu => seed_menu_update(world, persist),
+            GameState::Changelog => changelog_update(world, persist, dt),
+            GameState::Stats | GameState::Leaderboard => stats_update(world, persist),
+            GameState::Running => game_update(world, events, assets, dt, fx, persist, gamepad),
+            GameState::Tutorial => tutorial_update(world, events, assets, dt, fx, persist, gamepad),
+            GameState::Paused => pause_update(world, persist),
+            GameState::LevelUp => level_up_update(world),
+            GameState::GameOver => game_over_update(world, persist, dt),
         };
         if let Some(state) = new_state {
-            *self = state;
+            self.transition(state, world, fx);
+        }
+    }
+
+    /// Switches to the `new` state.
+    ///
+    /// Despawns the UI owned by the departing state.
+    /// Clears leftover particles when a run starts or a menu is entered,
+    /// but not when pausing, unpausing or dying.
+    fn transition(&mut self, new: GameState, world: &mut World, fx: &mut FxManager) {
+        if new != *self {
+            super::init::despawn_ui(world, *self);
+        }
+        let clear = matches!(
+            (*self, new),
+            (
+                GameState::MainMenu
+                    | GameState::PracticeMenu
+                    | GameState::SeedMenu
+                    | GameState::GameOver,
+                GameState::Running | GameState::Tutorial
+            ) | (
+                _,
+                GameState::MainMenu
+                    | GameState::PracticeMenu
+                    | GameState::SeedMenu
+                    | GameState::Changelog
+                    | GameState::Controls
+                    | GameState::Settings
+                    | GameState::Stats
+                    | GameState::Leaderboard
+            )
+        );
+        if clear {
+            fx.clear_particles();
+        }
+        *self = new;
+    }
+
+    /// Is the world played in this state?
+    /// Such states are simulated in fixed steps.
+    pub fn is_played(&self) -> bool {
+        matches!(self, GameState::Running | GameState::Tutorial)
+    }
+
+    /// Pauses a running game, e.g. when the window gets minimized or loses focus.
+    /// The game stays paused until the player unpauses it.
+    pub fn auto_pause(&mut self, world: &mut World, fx: &mut FxManager, assets: &AssetManager) {
+        if *self == GameState::Running {
+            player::stop_sounds(world, assets);
+            super::init::init_pause(world);
+            self.transition(GameState::Paused, world, fx);
+        }
+    }
+
+    /// Handles automatic actions when the user is idle.
+    ///
+    /// Returns whether the frame rate should be throttled.
+    /// The [Running](GameState::Running) state is exempt from any auto-action.
+    /// # Arguments
+    /// * `idle_time` - time since the last input
+    /// * `persist` - persistent data shown in the main menu
+    pub fn idle(
+        &mut self,
+        world: &mut World,
+        fx: &mut FxManager,
+        idle_time: f32,
+        persist: &Persistent,
+    ) -> bool {
+        match self {
+            GameState::GameOver if idle_time >= GAME_OVER_IDLE_TIME => {
+                //keep the score of a walked away player, under the initials entered so far
+                leaderboard::finish_name_entry(world, persist);
+                super::init::init_main_menu(world, persist);
+                self.transition(GameState::MainMenu, world, fx);
+                false
+            }
+            GameState::MainMenu => idle_time >= MENU_IDLE_TIME,
+            _ => false,
         }
     }
 
     /// Renders the current game state
+    #[allow(clippy::too_many_arguments)]
     pub fn render(
         &self,
         world: &mut World,
-        _events: &mut World,
+        events: &mut World,
         assets: &AssetManager,
         _dt: f32,
         fx: &mut FxManager,
         persist: &Persistent,
+        canvas: &mut WorldCanvas,
     ) {
         match self {
-            GameState::MainMenu => main_menu_render(world, assets),
-            GameState::Running => game_render(world, fx, assets, persist),
-            GameState::Paused => pause_render(world, fx, assets, persist),
-            GameState::GameOver => game_over_render(world, fx, assets, persist),
+            GameState::Loading => loading::render_loading(world, assets),
+            GameState::MainMenu
+            | GameState::PracticeMenu
+            | GameState::SeedMenu
+            | GameState::Controls
+            | GameState::Stats
+            | GameState::Leaderboard => main_menu_render(world, assets, *self),
+            GameState::Changelog => changelog_render(world, assets),
+            GameState::Settings => settings_render(world, assets),
+            GameState::Running => game_render(world, events, fx, assets, persist, canvas),
+            GameState::Tutorial => tutorial_render(world, events, fx, assets, persist, canvas),
+            GameState::Paused => pause_render(world, events, fx, assets, persist, canvas),
+            GameState::LevelUp => level_up_render(world, events, fx, assets, persist, canvas),
+            GameState::GameOver => game_over_render(world, events, fx, assets, persist, canvas),
         }
     }
 }
@@ -82,20 +230,212 @@ impl GameState {
 //-----------------------------------------------------------------------------
 
 /// Updates Main Menu state
-fn main_menu_update(world: &mut World) -> Option<GameState> {
+fn main_menu_update(
+    world: &mut World,
+    gamepad: &GamepadState,
+    persist: &Persistent,
+) -> Option<GameState> {
+    menu::arena_button(world);
+    menu::gamepad_confirm(world, gamepad);
+    if menu::daily_button(world) {
+        super::init::init_daily(world, persist);
+        return Some(GameState::Running);
+    }
     let new_state = menu::handle_buttons(world);
 
-    if matches!(new_state, Some(GameState::Running)) {
-        super::init::init_game(world);
+    match new_state {
+        Some(GameState::Running) => super::init::init_game(world),
+        Some(GameState::Tutorial) => super::init::init_tutorial(world, &persist.bindings),
+        Some(GameState::PracticeMenu) => super::init::init_practice_menu(world),
+        Some(GameState::Changelog) => super::init::init_changelog(world),
+        Some(GameState::Controls) => super::init::init_controls(world, &persist.bindings),
+        Some(GameState::Settings) => super::init::init_settings(world, &persist.settings),
+        Some(GameState::Stats) => super::init::init_stats(world, persist),
+        Some(GameState::Leaderboard) => super::init::init_leaderboard(world, persist),
+        _ => {}
+    }
+
+    new_state
+}
+
+/// Updates Controls state
+fn controls_update(world: &mut World, persist: &mut Persistent) -> Option<GameState> {
+    //rebinding captures every key, escape included
+    let before = persist.bindings.clone();
+    if menu::rebind_buttons(world, &mut persist.bindings) {
+        if persist.bindings != before {
+            let _ = persist.save();
+        }
+        return None;
+    }
+
+    //escape back to the main menu
+    let new_state = if is_key_pressed(KeyCode::Escape) {
+        Some(GameState::MainMenu)
+    } else {
+        menu::handle_buttons(world)
+    };
+
+    if let Some(GameState::MainMenu) = new_state {
+        super::init::init_main_menu(world, persist);
+    }
+
+    new_state
+}
+
+/// Updates Settings state
+fn settings_update(world: &mut World, persist: &mut Persistent) -> Option<GameState> {
+    //apply the chosen values, save them once chosen
+    let released = menu::sliders(world);
+    menu::slider_settings(world, &mut persist.settings);
+    let settings = &mut persist.settings;
+    let toggled = menu::toggle_button(world, "settings.crt", &mut settings.crt)
+        | menu::toggle_button(world, "settings.flashing", &mut settings.reduce_flashing)
+        | menu::toggle_button(world, "settings.colorblind", &mut settings.colorblind)
+        | menu::toggle_button(world, "settings.kill_banners", &mut settings.kill_banners)
+        | menu::toggle_button(world, "settings.ghost", &mut settings.ghost)
+        | menu::toggle_button(
+            world,
+            "settings.reduced_motion",
+            &mut settings.reduced_motion,
+        )
+        | menu::toggle_button(world, "settings.rumble", &mut settings.rumble)
+        | menu::render_scale_button(world, settings);
+    let switched = menu::language_button(world, settings);
+    if released || toggled || switched {
+        let _ = persist.save();
+    }
+    //show the screen in the new language
+    if switched {
+        super::init::despawn_ui(world, GameState::Settings);
+        super::init::init_settings(world, &persist.settings);
+    }
+
+    //escape back to the main menu
+    let new_state = if is_key_pressed(KeyCode::Escape) {
+        Some(GameState::MainMenu)
+    } else {
+        menu::handle_buttons(world)
+    };
+
+    if let Some(GameState::MainMenu) = new_state {
+        super::init::init_main_menu(world, persist);
+    }
+
+    new_state
+}
+
+/// Renders Settings state
+fn settings_render(world: &mut World, assets: &AssetManager) {
+    menu::button_colors(world, assets);
+    menu::render_sliders(world);
+    menu::render_title(world, assets, UiOwner::State(GameState::Settings));
+}
+
+/// Updates Stats state
+/// Also updates the Leaderboard state, both only return to the main menu.
+fn stats_update(world: &mut World, persist: &Persistent) -> Option<GameState> {
+    //escape back to the main menu
+    let new_state = if is_key_pressed(KeyCode::Escape) {
+        Some(GameState::MainMenu)
+    } else {
+        menu::handle_buttons(world)
+    };
+
+    if let Some(GameState::MainMenu) = new_state {
+        super::init::init_main_menu(world, persist);
+    }
+
+    new_state
+}
+
+/// Updates Changelog state
+fn changelog_update(world: &mut World, persist: &Persistent, dt: f32) -> Option<GameState> {
+    changelog::scroll_changelog(world, dt);
+
+    //escape back to the main menu
+    let new_state = if is_key_pressed(KeyCode::Escape) {
+        Some(GameState::MainMenu)
+    } else {
+        menu::handle_buttons(world)
+    };
+
+    if let Some(GameState::MainMenu) = new_state {
+        super::init::init_main_menu(world, persist);
+    }
+
+    new_state
+}
+
+/// Renders Changelog state
+fn changelog_render(world: &mut World, assets: &AssetManager) {
+    menu::button_colors(world, assets);
+    changelog::render_changelog(world, assets);
+    menu::render_title(world, assets, UiOwner::State(GameState::Changelog));
+}
+
+/// Updates Practice Menu state
+fn practice_menu_update(
+    world: &mut World,
+    gamepad: &GamepadState,
+    persist: &Persistent,
+) -> Option<GameState> {
+    menu::wave_selector(world, MAX_PRACTICE_WAVE);
+    menu::gamepad_confirm(world, gamepad);
+
+    //escape back to the main menu
+    let new_state = if is_key_pressed(KeyCode::Escape) {
+        Some(GameState::MainMenu)
+    } else {
+        menu::handle_buttons(world)
+    };
+
+    match new_state {
+        Some(GameState::Running) => {
+            let wave = world
+                .query_mut::<&WaveSelector>()
+                .into_iter()
+                .next()
+                .map(|(_, selector)| selector.wave)
+                .unwrap_or(1);
+            super::init::init_practice(world, wave);
+        }
+        Some(GameState::MainMenu) => super::init::init_main_menu(world, persist),
+        Some(GameState::SeedMenu) => super::init::init_seed_menu(world),
+        _ => {}
+    }
+
+    new_state
+}
+
+/// Updates Seed Menu state
+fn seed_menu_update(world: &mut World, persist: &Persistent) -> Option<GameState> {
+    menu::seed_input(world);
+
+    //escape back to the main menu
+    let new_state = if is_key_pressed(KeyCode::Escape) {
+        Some(GameState::MainMenu)
+    } else {
+        menu::handle_buttons(world)
+    };
+
+    match new_state {
+        Some(GameState::Running) => {
+            let seed = menu::typed_seed(world).unwrap_or_else(|| fastrand::u64(..));
+            super::init::init_seeded(world, seed);
+        }
+        Some(GameState::MainMenu) => super::init::init_main_menu(world, persist),
+        _ => {}
     }
 
     new_state
 }
 
 /// Renders Main Menu state
-fn main_menu_render(world: &mut World, assets: &AssetManager) {
-    menu::button_colors(world);
-    menu::render_title(world, assets);
+/// Also renders the other simple menus, `state` is the one being rendered.
+fn main_menu_render(world: &mut World, assets: &AssetManager, state: GameState) {
+    menu::button_colors(world, assets);
+    menu::render_title(world, assets, UiOwner::State(state));
 }
 
 //-----------------------------------------------------------------------------
@@ -110,104 +450,321 @@ fn game_update(
     dt: f32,
     fx: &mut FxManager,
     persist: &mut Persistent,
+    gamepad: &GamepadState,
 ) -> Option<GameState> {
+    //time the systems for the debug overlay
+    let mut timings = SystemTimings::start();
     //Command buffer
     let mut cmd = CommandBuffer::new();
+    //remember where everything was, rendering interpolates from there
+    basic::motion::remember_transforms(world, &mut cmd);
+    //gather input once per step
+    let input = PlayerInput::read(
+        basic::arena::arena(world).mouse_pos(),
+        gamepad,
+        &persist.bindings,
+    );
     //PLAYER
-    player::weapons(world, &mut cmd, dt);
-    player::motion_update(world, dt);
+    player::weapons(world, &mut cmd, &input, dt);
+    player::motion_update(world, &input, dt);
+    player::dash(world, &mut cmd, &input, dt);
+    timings.lap("player");
 
     //ENEMY AI
+    projectile::count_enemy_projectiles(world);
     enemy::big_asteroid_ai(world, dt);
     enemy::charged::supercharged_asteroid_ai(world, &mut cmd, dt);
     enemy::follower::follower_ai(world, dt);
     enemy::mine::mine_ai(world, dt);
+    enemy::turret::turret_ai(world, &mut cmd, dt);
+    enemy::flipper::flipper_ai(world, dt);
+    enemy::boss::boss_ai(world, &mut cmd, dt);
+    enemy::advance_stagger(world);
+    timings.lap("enemy ai");
 
+    ghost::record_ghost(world, dt);
+    ghost::ghost_playback(world, persist, dt);
+    timings.lap("ghost");
+
+    xp::grapple(world, dt);
     xp::xp_attraction(world, dt);
+    timings.lap("xp");
 
     //GLOBAL SYSTEMS
-    basic::motion::apply_physics(world, dt);
-    basic::motion::apply_motion(world, dt);
+    //borrow the heatmap out of the world while physics run
+    let mut heatmap = world
+        .query_mut::<&mut ForceHeatmap>()
+        .into_iter()
+        .next()
+        .map(|(_, heatmap)| std::mem::take(heatmap));
+    basic::motion::apply_physics(world, dt, heatmap.as_mut());
+    if let (Some(heatmap), Some((_, slot))) = (
+        heatmap,
+        world.query_mut::<&mut ForceHeatmap>().into_iter().next(),
+    ) {
+        *slot = heatmap;
+    }
+    enemy::stunned(world);
+    basic::motion::apply_motion(world, &persist.settings, dt);
+    timings.lap("physics");
 
     basic::ensure_wrapping(world, &mut cmd, assets);
-    basic::ensure_damage(world, events);
+    basic::tick_lifetimes(world, &mut cmd, dt);
+    basic::render::advance_animations(world, dt);
+    basic::render::tick_hit_flashes(world, &mut cmd, dt);
+    basic::arena::follow_camera(world);
+    basic::spawn_grace(world, &mut cmd, dt);
+    basic::ensure_damage(world, events, dt);
+    basic::motion::resolve_body_collisions(world);
+    basic::motion::age_knockbacks(world, &mut cmd, dt);
     basic::motion::apply_knockback(world, events, assets);
+    timings.lap("collisions");
 
     //AFTER EFFECTS
-    player::health(world, events, dt);
-    enemy::health(world, events, &mut cmd);
+    player::health(world, events, &mut cmd, dt);
+    player::death(world);
+    player::low_health_warning(world, &mut cmd, assets, dt);
+    enemy::health(world, events, &mut cmd, dt);
+    enemy::kill_attribution(world);
+    score::combo_decay(world, dt);
     projectile::on_hurt(world, events, &mut cmd);
 
     xp::xp_absorbtion(world, events, &mut cmd);
+    pickup::health_pickups(world, events, &mut cmd);
+    timings.lap("after effects");
 
     //PRE DEATH EFFECTS
+    enemy::death_cues(world, assets);
+    enemy::big_asteroid_chips(world, events, &mut cmd, fx, assets);
     enemy::charged::supercharged_asteroid_death(world, &mut cmd);
 
     enemy::asteroid_death(world, fx);
     enemy::big_asteroid_death(world, &mut cmd, fx);
     enemy::follower::follower_death(world, fx);
-    enemy::mine::mine_death(world, &mut cmd, fx);
+    enemy::mine::mine_death(world, &mut cmd, fx, persist.settings.colorblind);
+    enemy::splitter::splitter_death(world, &mut cmd, fx);
+    enemy::turret::turret_death(world, &mut cmd, fx);
+    enemy::flipper::flipper_death(world, fx);
+    enemy::boss::boss_death(world, fx);
     xp::xp_bursts(world, &mut cmd);
+    banner::kill_banners(world, &mut cmd, persist);
+    hints::hints(world, &mut cmd, persist);
+    timings.lap("death effects");
+
+    //UI
+    banner::banners(world, &mut cmd, dt);
+    basic::health::update_displays(world, dt);
+    basic::health::boss_bars(world, &mut cmd, fx, dt);
+    timings.lap("ui");
 
     //spawn enemies
     super::enemy_spawning(world, &mut cmd, dt);
+    timings.lap("spawning");
 
     //Apply commands
     cmd.run_on(world);
+    projectile::spawn_projectiles(world);
+    //newly spawned enemies get tougher over time
+    super::scale_enemies(world);
+    timings.lap("commands");
+    timings.store(world);
+
+    //minimap toggling
+    minimap::toggle_minimap(persist);
+    //charge field overlay toggling
+    fields::toggle_fields(world);
+    //collision debug toggling
+    basic::health::toggle_collision_debug(world);
 
     //pausing
-    if is_key_pressed(KeyCode::Escape) {
+    if persist.bindings.pause.is_pressed() {
+        player::stop_sounds(world, assets);
         super::init::init_pause(world);
         return Some(GameState::Paused);
     }
 
     //check for game over
-    let (_, (player_hp, player)) = world
-        .query_mut::<(&Health, &Player)>()
+    let dead = world
+        .query_mut::<&Player>()
+        .with::<&Dead>()
         .into_iter()
         .next()
-        .unwrap();
+        .map(|(_, player)| player.xp);
 
-    if player_hp.hp <= 0.0 {
-        //save high score
-        persist.high_score = persist.high_score.max(player.xp);
-        let _ = persist.save();
+    if let Some(score) = dead {
+        //let the death sink in first
+        if !super::death::death_slow_mo(world, dt, persist.settings.reduced_motion) {
+            return None;
+        }
+        //save high score, unless practicing
+        let mut saved = true;
+        let mut rank = None;
+        if world
+            .query_mut::<&PracticeRun>()
+            .into_iter()
+            .next()
+            .is_none()
+        {
+            //daily challenges have their own records
+            if let Some(daily) = daily::daily_run(world) {
+                persist.record_daily(&daily.date, score);
+            }
+            //keep the path of the best run
+            if score > persist.high_score {
+                ghost::save_ghost(world, persist);
+            }
+            persist.high_score = persist.high_score.max(score);
+            //add the run to lifetime statistics and the leaderboard
+            if let Some((_, stats)) = world.query_mut::<&enemy::RunStats>().into_iter().next() {
+                persist.stats.record(stats, score);
+                rank = leaderboard::insert_score(
+                    &mut persist.leaderboard,
+                    ScoreEntry::new(score, stats.time),
+                );
+            }
+            saved = persist.save().is_ok();
+        }
+        //keep the force heatmap for balancing
+        heatmap::save_heatmap(world);
         //show game over screen
-        super::init::init_game_over(world);
+        super::init::init_game_over(world, persist, rank);
+        if !saved {
+            banner::push_fading_title(
+                &mut cmd,
+                tr!("run.save_failed"),
+                RED,
+                UiOwner::State(GameState::GameOver),
+            );
+            cmd.run_on(world);
+        }
         return Some(GameState::GameOver);
     }
 
+    //choose an upgrade
+    if let Some(choices) = upgrade::level_up(world) {
+        super::init::init_level_up(world, &choices);
+        return Some(GameState::LevelUp);
+    }
+
     None
 }
 
 /// Renders game state
-fn game_render(world: &mut World, fx: &mut FxManager, assets: &AssetManager, persist: &Persistent) {
+fn game_render(
+    world: &mut World,
+    events: &World,
+    fx: &mut FxManager,
+    assets: &AssetManager,
+    persist: &Persistent,
+    canvas: &mut WorldCanvas,
+) {
     player::audio_visuals(world, fx, assets);
     score::score_display(world, persist);
-    enemy::charged::supercharged_asteroid_visual(world, fx);
-    enemy::follower::follower_fx(world, fx);
-    enemy::mine::mine_fx(world);
+    enemy::charged::supercharged_asteroid_visual(world, fx, persist.settings.colorblind);
+    enemy::follower::follower_fx(world, fx, persist.settings.colorblind);
+    enemy::mine::mine_fx(world, persist.settings.reduce_flashing);
+    enemy::turret::turret_visual(world);
+    enemy::flipper::flipper_visual(world, fx);
 
     //actually render
+    //world is rendered at the chosen scale
+    let view = basic::arena::view_rect(world);
+    canvas.begin(persist.settings.render_scale, persist.settings.crt, view);
 
+    indicator::render_offscreen_indicators(world);
     basic::render::render_all(world, assets);
+    if persist.settings.colorblind {
+        basic::render::render_charge_glyphs(world);
+    }
+    fields::render_fields(world);
+    ghost::render_ghost(world, persist);
+    xp::grapple_visual(world);
 
     fx.render_particles();
+    player::render_reticle(world);
+    basic::health::debug_draw_collision(world, events);
 
+    //UI is rendered at native resolution
+    canvas.end();
+    player::render_low_health_vignette(world);
     basic::health::render_displays(world);
-    menu::render_title(world, assets);
+    player::render_dash_display(world);
+    player::render_force_display(world);
+    basic::health::render_boss_bars(world, assets);
+    menu::render_title(world, assets, UiOwner::Always);
+    minimap::render_minimap(world, persist);
+}
+
+//-----------------------------------------------------------------------------
+//TUTORIAL
+//-----------------------------------------------------------------------------
+
+/// Updates tutorial state.
+/// Plays like a run without enemy spawning, escape skips the tutorial.
+fn tutorial_update(
+    world: &mut World,
+    events: &mut World,
+    assets: &AssetManager,
+    dt: f32,
+    fx: &mut FxManager,
+    persist: &mut Persistent,
+    gamepad: &GamepadState,
+) -> Option<GameState> {
+    if persist.bindings.pause.is_pressed() {
+        tutorial::finish_tutorial(persist);
+        super::init::init_main_menu(world, persist);
+        return Some(GameState::MainMenu);
+    }
+    //only death leaves the tutorial by itself
+    let new_state = game_update(world, events, assets, dt, fx, persist, gamepad);
+    tutorial::tutorial(world, persist);
+    new_state
+}
+
+/// Renders tutorial state.
+fn tutorial_render(
+    world: &mut World,
+    events: &World,
+    fx: &mut FxManager,
+    assets: &AssetManager,
+    persist: &Persistent,
+    canvas: &mut WorldCanvas,
+) {
+    game_render(world, events, fx, assets, persist, canvas);
+    menu::render_title(world, assets, UiOwner::State(GameState::Tutorial));
 }
 
 //-----------------------------------------------------------------------------
 //PAUSE
 //-----------------------------------------------------------------------------
 
-/// Updates when paused
-fn pause_update(world: &mut World) -> Option<GameState> {
-    if is_key_pressed(KeyCode::Escape) {
-        super::init::clear_pause(world);
+/// Updates when paused.
+///
+/// Quitting the run has to be confirmed,
+/// escaping the confirmation returns to the pause screen.
+fn pause_update(world: &mut World, persist: &Persistent) -> Option<GameState> {
+    let confirming = world
+        .query_mut::<&QuitConfirm>()
+        .into_iter()
+        .next()
+        .is_some();
+    if confirming {
+        match menu::quit_answer(world) {
+            Some(true) => {
+                super::init::init_main_menu(world, persist);
+                return Some(GameState::MainMenu);
+            }
+            Some(false) => super::init::close_quit_confirm(world),
+            None if persist.bindings.pause.is_pressed() => super::init::close_quit_confirm(world),
+            None => (),
+        }
+        None
+    } else if persist.bindings.pause.is_pressed() || menu::marked_clicked::<ResumeButton>(world) {
         Some(GameState::Running)
     } else {
+        if menu::marked_clicked::<QuitButton>(world) {
+            super::init::init_quit_confirm(world);
+        }
         None
     }
 }
@@ -215,18 +772,20 @@ fn pause_update(world: &mut World) -> Option<GameState> {
 /// Renders when paused
 fn pause_render(
     world: &mut World,
+    events: &World,
     fx: &mut FxManager,
     assets: &AssetManager,
     persist: &Persistent,
+    canvas: &mut WorldCanvas,
 ) {
     //first render the game
-    game_render(world, fx, assets, persist);
+    game_render(world, events, fx, assets, persist, canvas);
     //overlap with transparent black
     draw_rectangle(
         0.0,
         0.0,
-        screen_width(),
-        screen_height(),
+        SPACE_WIDTH,
+        SPACE_HEIGHT,
         Color {
             r: 0.0,
             g: 0.0,
@@ -234,8 +793,52 @@ fn pause_render(
             a: 0.3,
         },
     );
-    //draw pause text
-    menu::render_title(world, assets);
+    //draw pause text and buttons
+    menu::button_colors(world, assets);
+    menu::render_title(world, assets, UiOwner::State(GameState::Paused));
+}
+
+//-----------------------------------------------------------------------------
+//LEVEL UP
+//-----------------------------------------------------------------------------
+
+/// Updates when choosing an upgrade.
+/// The run, enemy spawning included, waits until one is chosen.
+fn level_up_update(world: &mut World) -> Option<GameState> {
+    if upgrade::upgrade_picker(world) {
+        Some(GameState::Running)
+    } else {
+        None
+    }
+}
+
+/// Renders when choosing an upgrade.
+fn level_up_render(
+    world: &mut World,
+    events: &World,
+    fx: &mut FxManager,
+    assets: &AssetManager,
+    persist: &Persistent,
+    canvas: &mut WorldCanvas,
+) {
+    //first render the game
+    game_render(world, events, fx, assets, persist, canvas);
+    //overlap with transparent black
+    draw_rectangle(
+        0.0,
+        0.0,
+        SPACE_WIDTH,
+        SPACE_HEIGHT,
+        Color {
+            r: 0.0,
+            g: 0.0,
+            b: 0.0,
+            a: 0.5,
+        },
+    );
+    //draw the upgrade cards
+    menu::button_colors(world, assets);
+    menu::render_title(world, assets, UiOwner::State(GameState::LevelUp));
 }
 
 //-----------------------------------------------------------------------------
@@ -243,18 +846,38 @@ fn pause_render(
 //-----------------------------------------------------------------------------
 
 /// Time before the game over screen becomes fully visible.
-const FULL_FADE_TIME: f32 = 1.0;
+pub(crate) const FULL_FADE_TIME: f32 = 1.0;
 
 /// Updates game over state.
-fn game_over_update(world: &mut World, dt: f32) -> Option<GameState> {
-    //move timer
-    for (_, timer) in world.query_mut::<&mut GameOverTimer>() {
-        timer.time += dt;
+fn game_over_update(world: &mut World, persist: &mut Persistent, dt: f32) -> Option<GameState> {
+    heatmap::toggle_heatmap(world);
+    //the world keeps drifting while spectated
+    spectate::toggle_spectate(world);
+    let spectating = world
+        .query_mut::<&Spectate>()
+        .into_iter()
+        .next()
+        .is_some_and(|(_, spectate)| spectate.active);
+    if spectating {
+        basic::motion::apply_physics(world, dt, None);
+        basic::motion::apply_motion(world, &persist.settings, dt);
     }
-    //escape to safety when in gameover
-    if is_key_pressed(KeyCode::Escape) {
-        super::init::init_main_menu(world);
+    spectate::spectate(world, dt);
+    //fade the screen in, fade out toasts and banners left from the run
+    let mut cmd = CommandBuffer::new();
+    basic::tick_lifetimes(world, &mut cmd, dt);
+    cmd.run_on(world);
+    //enter initials for the leaderboard
+    leaderboard::name_entry(world, persist);
+    //escape to safety when in gameover, keeping the initials entered so far
+    if persist.bindings.pause.is_pressed() {
+        leaderboard::finish_name_entry(world, persist);
+        super::init::init_main_menu(world, persist);
         Some(GameState::MainMenu)
+    } else if menu::marked_clicked::<RetryButton>(world) {
+        leaderboard::finish_name_entry(world, persist);
+        super::init::init_game(world);
+        Some(GameState::Running)
     } else {
         None
     }
@@ -263,26 +886,31 @@ fn game_over_update(world: &mut World, dt: f32) -> Option<GameState> {
 /// Renders game over state.
 fn game_over_render(
     world: &mut World,
+    events: &World,
     fx: &mut FxManager,
     assets: &AssetManager,
     persist: &Persistent,
+    canvas: &mut WorldCanvas,
 ) {
-    //get time
-    let time = world
-        .query_mut::<&GameOverTimer>()
-        .into_iter()
-        .next()
-        .unwrap()
-        .1
-        .time;
+    //get time, skip the fade when reduced motion is on
+    let time = if persist.settings.reduced_motion {
+        FULL_FADE_TIME
+    } else {
+        world
+            .query_mut::<&basic::Lifetime>()
+            .with::<&GameOverTimer>()
+            .into_iter()
+            .next()
+            .map_or(FULL_FADE_TIME, |(_, lifetime)| lifetime.elapsed)
+    };
     //first render the game
-    game_render(world, fx, assets, persist);
+    game_render(world, events, fx, assets, persist, canvas);
     //overlap with transparent black
     draw_rectangle(
         0.0,
         0.0,
-        screen_width(),
-        screen_height(),
+        SPACE_WIDTH,
+        SPACE_HEIGHT,
         Color {
             r: 0.0,
             g: 0.0,
@@ -290,10 +918,9 @@ fn game_over_render(
             a: 0.5 * (time / FULL_FADE_TIME).min(1.0),
         },
     );
-    //fade in the texts as well
-    for (_, title) in world.query_mut::<&mut Title>() {
-        title.color.a = (time / FULL_FADE_TIME).min(1.0);
-    }
+    //force heatmap over the dim, under the texts
+    heatmap::render_heatmap(world);
+    menu::button_colors(world, assets);
     //draw game over text
-    menu::render_title(world, assets);
+    menu::render_title(world, assets, UiOwner::State(GameState::GameOver));
 }
@@ -1,11 +1,21 @@
 //! Enemy spawns used to compose a wave.
+use fastrand::Rng;
 use hecs::CommandBuffer;
 
 use super::*;
 
 use macroquad::prelude::*;
 
-use crate::{enemy, SPACE_HEIGHT, SPACE_WIDTH};
+use crate::{
+    basic::{arena::Arena, SpawnGrace},
+    config, enemy,
+};
+
+/// Distance between the asteroids of a dipole pair.
+/// They stay within each other's full force radius.
+const DIPOLE_SEPARATION: f32 = 120.0;
+/// Amount of mines in a mine wall.
+const WALL_MINES: usize = 5;
 
 /// Collection of useful structures that are commonly used to
 /// implement wave spawning.
@@ -17,6 +27,79 @@ pub struct WavePreamble<'a> {
     /// Current position of the [Player] so that some
     /// enemies can target it.
     pub player_pos: &'a Position,
+    /// Generator of the spawns' randomness.
+    pub rng: &'a mut Rng,
+    /// Play space the enemies are spawned around.
+    pub arena: Arena,
+    /// Offset along the spawning edge.
+    /// Used to keep enemies spawned together apart.
+    pub jitter: f32,
+    /// Sides next to the corner the player camps in, if they do.
+    pub camp_sides: Option<[u8; 2]>,
+}
+
+impl WavePreamble<'_> {
+    /// Returns a random side, favouring the sides next to a camped corner.
+    fn side(&mut self) -> u8 {
+        match self.camp_sides {
+            Some(sides) if self.rng.f32() < CAMP_SIDE_CHANCE => sides[self.rng.usize(0..2)],
+            _ => get_side(self.rng),
+        }
+    }
+
+    /// Returns a random spawn position on side `side`, away from the player.
+    fn spawn_pos(&mut self, side: u8) -> Vec2 {
+        get_safe_spawn_pos(
+            side,
+            &self.arena,
+            vec2(self.player_pos.x, self.player_pos.y),
+            self.rng,
+        )
+    }
+
+    /// Returns a random charge, either -1 or 1.
+    fn charge(&mut self) -> i8 {
+        self.rng.i8(0..=1) * 2 - 1
+    }
+}
+
+/// Step of a wave script, spawning enemies after a delay.
+///
+/// A wave script is a list of steps run one after another,
+/// so that a spawn can be choreographed over several seconds.
+#[derive(Clone, Copy)]
+pub(super) struct WaveStep {
+    /// Time waited after the previous step, or after the start of the script.
+    pub delay: f32,
+    /// Function spawning the enemies, a single enemy or a whole formation.
+    pub spawn: &'static dyn Fn(&mut WavePreamble),
+    /// How many times `spawn` is run.
+    pub count: usize,
+}
+
+impl WaveStep {
+    /// Creates a step running `spawn` `count` times after `delay`.
+    pub const fn new(delay: f32, spawn: &'static dyn Fn(&mut WavePreamble), count: usize) -> Self {
+        Self {
+            delay,
+            spawn,
+            count,
+        }
+    }
+
+    /// Runs the step.
+    ///
+    /// When the step spawns more than once, every spawn is nudged along its edge
+    /// by a random jitter so that they do not overlap.
+    pub fn run(&self, preamble: &mut WavePreamble) {
+        for _ in 0..self.count {
+            if self.count > 1 {
+                preamble.jitter = (preamble.rng.f32() * 2.0 - 1.0) * SPAWN_JITTER;
+            }
+            (self.spawn)(preamble);
+        }
+        preamble.jitter = 0.0;
+    }
 }
 
 //
@@ -27,141 +110,160 @@ pub struct WavePreamble<'a> {
 ///
 /// Charges of asteroids in corners are opposite from charges from the asteroids
 /// which come from the edges.
-#[allow(dead_code)]
-pub(super) fn center_crunch(cmd: &mut CommandBuffer) {
-    //center crunch attack
-    let charge = fastrand::i8(0..=1) * 2 - 1;
-    //spawn them
-    cmd.spawn(
-        enemy::create_charged_asteroid(
-            vec2(-SPAWN_PUSHBACK, SPACE_HEIGHT / 2.0),
-            vec2(1.0, 0.0),
-            charge,
-        )
-        .build(),
-    );
-    cmd.spawn(
-        enemy::create_charged_asteroid(
-            vec2(SPACE_WIDTH + SPAWN_PUSHBACK, SPACE_HEIGHT / 2.0),
+pub(super) fn center_crunch(preamble: &mut WavePreamble) {
+    let arena = preamble.arena;
+    let charge = preamble.charge();
+    //edges and corners with their directions into the center
+    let edges = [
+        (vec2(-SPAWN_PUSHBACK, arena.height / 2.0), vec2(1.0, 0.0)),
+        (
+            vec2(arena.width + SPAWN_PUSHBACK, arena.height / 2.0),
             vec2(-1.0, 0.0),
-            charge,
-        )
-        .build(),
-    );
-    cmd.spawn(
-        enemy::create_charged_asteroid(
-            vec2(SPACE_WIDTH / 2.0, -SPAWN_PUSHBACK),
-            vec2(0.0, 1.0),
-            charge,
-        )
-        .build(),
-    );
-    cmd.spawn(
-        enemy::create_charged_asteroid(
-            vec2(SPACE_WIDTH / 2.0, SPACE_HEIGHT + SPAWN_PUSHBACK),
+        ),
+        (vec2(arena.width / 2.0, -SPAWN_PUSHBACK), vec2(0.0, 1.0)),
+        (
+            vec2(arena.width / 2.0, arena.height + SPAWN_PUSHBACK),
             vec2(0.0, -1.0),
-            charge,
-        )
-        .build(),
-    );
-    //spawn opposite charged corners
-    cmd.spawn(
-        enemy::create_charged_asteroid(
-            vec2(-SPAWN_PUSHBACK, -SPAWN_PUSHBACK),
-            vec2(1.0, 1.0),
-            -charge,
-        )
-        .build(),
-    );
-    cmd.spawn(
-        enemy::create_charged_asteroid(
-            vec2(SPACE_WIDTH + SPAWN_PUSHBACK, -SPAWN_PUSHBACK),
+        ),
+    ];
+    let corners = [
+        (vec2(-SPAWN_PUSHBACK, -SPAWN_PUSHBACK), vec2(1.0, 1.0)),
+        (
+            vec2(arena.width + SPAWN_PUSHBACK, -SPAWN_PUSHBACK),
             vec2(-1.0, 1.0),
-            -charge,
-        )
-        .build(),
-    );
+        ),
+        (
+            vec2(-SPAWN_PUSHBACK, arena.height + SPAWN_PUSHBACK),
+            vec2(1.0, -1.0),
+        ),
+        (
+            vec2(arena.width + SPAWN_PUSHBACK, arena.height + SPAWN_PUSHBACK),
+            vec2(-1.0, -1.0),
+        ),
+    ];
+    //spawn them, corners with the opposite charge
+    for (pos, dir, charge) in edges
+        .into_iter()
+        .map(|(pos, dir)| (pos, dir, charge))
+        .chain(corners.into_iter().map(|(pos, dir)| (pos, dir, -charge)))
+    {
+        preamble
+            .cmd
+            .spawn(enemy::create_charged_asteroid(pos, dir, charge, preamble.rng).build());
+    }
+}
+
+/// Shoots a set of three charged asteroids, a volley of the tripleshot.
+///
+/// The asteroids are slightly spread.
+/// They come from the side opposite the one the player is closest to.
+pub(super) fn tripleshot(preamble: &mut WavePreamble) {
+    //get side
+    let player_pos = vec2(preamble.player_pos.x, preamble.player_pos.y);
+    let side = get_opposite_side(get_nearest_side(player_pos, &preamble.arena));
+    let center = get_center_pos(side, &preamble.arena);
+    let dir = get_dir(side);
+    let charge = preamble.charge();
+    //shoot
+    let cmd = &mut *preamble.cmd;
+    let rng = &mut *preamble.rng;
+    cmd.spawn(enemy::create_charged_asteroid(center, dir * 1.6, charge, rng).build());
     cmd.spawn(
         enemy::create_charged_asteroid(
-            vec2(-SPAWN_PUSHBACK, SPACE_HEIGHT + SPAWN_PUSHBACK),
-            vec2(1.0, -1.0),
+            center + dir.perp() * 50.0,
+            Vec2::from_angle(PI / 6.0).rotate(dir) * 1.3,
             -charge,
+            rng,
         )
         .build(),
     );
     cmd.spawn(
         enemy::create_charged_asteroid(
-            vec2(SPACE_WIDTH + SPAWN_PUSHBACK, SPACE_HEIGHT + SPAWN_PUSHBACK),
-            vec2(-1.0, -1.0),
+            center - dir.perp() * 50.0,
+            Vec2::from_angle(-PI / 6.0).rotate(dir) * 1.3,
             -charge,
+            rng,
         )
         .build(),
     );
 }
 
-/// Initialised tripleshot timer.
-///
-/// Should be ran before `tripleshot`.
-#[inline]
-#[allow(dead_code)]
-pub(super) fn tripleshot_init(timer: &mut f32) {
-    *timer = 2.0;
+/// Spawns a dipole pair, a positive and a negative charged asteroid
+/// orbiting their shared center while drifting in from a random edge.
+pub(super) fn dipole_pair(preamble: &mut WavePreamble) {
+    let side = preamble.side();
+    let dir = get_dir(side);
+    let center = preamble.spawn_pos(side) - dir * 120.0 + dir.perp() * preamble.jitter;
+    let speed = config::get().enemies.asteroid.speed;
+    let orbit = dipole_orbit_speed(
+        enemy::asteroid::ASTEROID_FORCE,
+        enemy::asteroid::ASTEROID_MASS,
+        DIPOLE_SEPARATION,
+    );
+    let charge = preamble.charge();
+    //one ahead of the center, one behind it, both circling sideways
+    for (offset, charge) in [(1.0, charge), (-1.0, -charge)] {
+        let pos = center + dir * offset * DIPOLE_SEPARATION / 2.0;
+        let vel = dir * speed + dir.perp() * offset * orbit;
+        preamble.cmd.spawn(
+            enemy::create_charged_asteroid(pos, vel / speed, charge, preamble.rng)
+                .add(SpawnGrace::default())
+                .build(),
+        );
+    }
 }
 
-/// Shoots three sets of three charged asteroids.
+/// Returns the speed two equally heavy, oppositely charged bodies
+/// circle their shared center at, so that their attraction holds them on the orbit.
 ///
-/// The asteroids are slightly spread.
-#[allow(dead_code)]
-pub(super) fn tripleshot(cmd: &mut CommandBuffer, timer: &f32, data: &mut u8) {
-    //get side
-    let side = get_side();
-    let center = get_center_pos(side);
+/// The attraction is the centripetal force, `force = mass * speed^2 / radius`,
+/// with the radius being half the separation.
+/// Assumes the bodies are within each other's full force radius.
+/// # Arguments
+/// * `force` - charge force of each body
+/// * `mass` - mass of each body
+/// * `separation` - distance between the bodies
+pub(super) fn dipole_orbit_speed(force: f32, mass: f32, separation: f32) -> f32 {
+    (force.abs() * separation / 2.0 / mass).sqrt()
+}
+
+/// Spawns a wall of mines in a line across a random edge, all sharing a charge.
+pub(super) fn mine_wall(preamble: &mut WavePreamble) {
+    let side = preamble.side();
     let dir = get_dir(side);
-    let charge = fastrand::i8(0..=1) * 2 - 1;
-    //genarate triple shot function
-    let mut shoot = || {
-        cmd.spawn(enemy::create_charged_asteroid(center, dir * 1.6, charge).build());
-        cmd.spawn(
-            enemy::create_charged_asteroid(
-                center + dir.perp() * 50.0,
-                Vec2::from_angle(PI / 6.0).rotate(dir) * 1.3,
-                -charge,
-            )
-            .build(),
-        );
-        cmd.spawn(
-            enemy::create_charged_asteroid(
-                center - dir.perp() * 50.0,
-                Vec2::from_angle(-PI / 6.0).rotate(dir) * 1.3,
-                -charge,
-            )
-            .build(),
+    let center = get_center_pos(side, &preamble.arena);
+    let length = if dir.x == 0.0 {
+        preamble.arena.width
+    } else {
+        preamble.arena.height
+    } - 2.0 * SPAWN_MARGIN;
+    let charge = preamble.rng.i8(-1..=1);
+    for i in 0..WALL_MINES {
+        let along = (i as f32 / (WALL_MINES - 1) as f32 - 0.5) * length;
+        let pos = center - dir * SPAWN_PUSHBACK + dir.perp() * along;
+        preamble.cmd.spawn(
+            enemy::mine::create_mine(pos, dir, charge, preamble.rng)
+                .add(SpawnGrace::default())
+                .build(),
         );
-    };
-    //get state
-    *data = match *data & 0x03 {
-        0 => {
-            shoot();
-            1
-        }
-        1 => {
-            if *timer <= 1.33 {
-                shoot();
-                2
-            } else {
-                1
-            }
-        }
+    }
+}
 
-        2 => {
-            if *timer <= 0.65 {
-                shoot();
-                3
-            } else {
-                2
-            }
-        }
-        x => x,
+/// Spawns a sandwich, two oppositely charged big asteroids
+/// entering from opposite edges at mirrored positions.
+pub(super) fn sandwich(preamble: &mut WavePreamble) {
+    let side = preamble.side();
+    let dir = get_dir(side);
+    let pos = preamble.spawn_pos(side) - dir * 120.0;
+    //mirror through the center of the arena
+    let mirrored = preamble.arena.center() * 2.0 - pos;
+    let charge = preamble.charge();
+    for (pos, dir, charge) in [(pos, dir, charge), (mirrored, -dir, -charge)] {
+        preamble.cmd.spawn(
+            enemy::create_big_asteroid(pos, dir, charge, preamble.rng)
+                .add(SpawnGrace::default())
+                .build(),
+        );
     }
 }
 
@@ -174,55 +276,114 @@ pub(super) fn salvo_init(timer: &mut f32, base_time: f32) {
 
 /// Spawns an asteroid from a random edge.
 pub(super) fn asteroid(preamble: &mut WavePreamble) {
-    let side = get_side();
+    let side = preamble.side();
     let dir = get_dir(side);
-    let pos = get_spawn_pos(side) - dir * 120.0;
-    let charge = fastrand::i8(0..=1) * 2 - 1;
-    preamble
-        .cmd
-        .spawn(enemy::create_charged_asteroid(pos, dir, charge).build());
+    let pos = preamble.spawn_pos(side) - dir * 120.0 + dir.perp() * preamble.jitter;
+    let charge = preamble.charge();
+    preamble.cmd.spawn(
+        enemy::create_charged_asteroid(pos, dir, charge, preamble.rng)
+            .add(SpawnGrace::default())
+            .build(),
+    );
 }
 
 /// Spawns a big asteroid from a random edge.
 pub(super) fn big_asteroid(preamble: &mut WavePreamble) {
-    let side = get_side();
+    let side = preamble.side();
+    let dir = get_dir(side);
+    let pos = preamble.spawn_pos(side) - dir * 120.0 + dir.perp() * preamble.jitter;
+    let charge = preamble.charge();
+    preamble.cmd.spawn(
+        enemy::create_big_asteroid(pos, dir, charge, preamble.rng)
+            .add(SpawnGrace::default())
+            .build(),
+    );
+}
+
+/// Spawns a splitter asteroid from a random edge.
+pub(super) fn splitter_asteroid(preamble: &mut WavePreamble) {
+    let side = preamble.side();
     let dir = get_dir(side);
-    let pos = get_spawn_pos(side) - dir * 120.0;
-    let charge = fastrand::i8(0..=1) * 2 - 1;
-    preamble
-        .cmd
-        .spawn(enemy::create_big_asteroid(pos, dir, charge).build());
+    let pos = preamble.spawn_pos(side) - dir * 120.0 + dir.perp() * preamble.jitter;
+    preamble.cmd.spawn(
+        enemy::splitter::create_splitter_asteroid(pos, dir, 0, preamble.rng)
+            .add(SpawnGrace::default())
+            .build(),
+    );
+}
+
+/// Spawns a turret from a random edge, anchoring at a random point of the arena.
+pub(super) fn turret(preamble: &mut WavePreamble) {
+    let side = preamble.side();
+    let dir = get_dir(side);
+    let pos = preamble.spawn_pos(side) - dir * SPAWN_PUSHBACK + dir.perp() * preamble.jitter;
+    let margin = enemy::turret::TURRET_ANCHOR_MARGIN;
+    let anchor = vec2(
+        preamble.rng.f32() * (preamble.arena.width - 2.0 * margin) + margin,
+        preamble.rng.f32() * (preamble.arena.height - 2.0 * margin) + margin,
+    );
+    enemy::turret::create_turret(pos, anchor, preamble.rng)(preamble.world, preamble.cmd);
+}
+
+/// Spawns a flipper from a random edge.
+pub(super) fn flipper(preamble: &mut WavePreamble) {
+    let side = preamble.side();
+    let dir = get_dir(side);
+    let pos = preamble.spawn_pos(side) - dir * SPAWN_PUSHBACK + dir.perp() * preamble.jitter;
+    let charge = preamble.charge();
+    preamble.cmd.spawn(
+        enemy::flipper::create_flipper(pos, dir, charge, preamble.rng)
+            .add(SpawnGrace::default())
+            .build(),
+    );
+}
+
+/// Spawns the boss just inside a random edge.
+/// It wraps around the arena, so it cannot spawn outside of it.
+pub(super) fn boss(preamble: &mut WavePreamble) {
+    let side = preamble.side();
+    let dir = get_dir(side);
+    let pos = preamble.spawn_pos(side) + dir * enemy::boss::BOSS_SIZE / 2.0;
+    let charge = preamble.charge();
+    enemy::boss::create_boss(pos, dir, charge, preamble.rng)(preamble.world, preamble.cmd);
 }
 
 /// Spawns a charged asteroid from a random edge.
 pub(super) fn charged_asteroid(preamble: &mut WavePreamble) {
-    let side = get_side();
+    let side = preamble.side();
     let dir = get_dir(side);
-    let pos = get_spawn_pos(side) - dir * SPAWN_PUSHBACK;
-    let charge = fastrand::i8(0..=1) * 2 - 1;
-    enemy::charged::create_supercharged_asteroid(pos, dir, charge)(preamble.world, preamble.cmd);
+    let pos = preamble.spawn_pos(side) - dir * SPAWN_PUSHBACK + dir.perp() * preamble.jitter;
+    let charge = preamble.charge();
+    enemy::charged::create_supercharged_asteroid(pos, dir, charge, preamble.rng)(
+        preamble.world,
+        preamble.cmd,
+    );
 }
 
 /// Spawns a sawblade from a random edge.
 pub(super) fn follower(preamble: &mut WavePreamble) {
-    let side = get_side();
+    let side = preamble.side();
     let dir = get_dir(side);
-    let pos = get_spawn_pos(side) - dir * SPAWN_PUSHBACK;
-    let charge = fastrand::i8(-1..=1);
-    preamble
-        .cmd
-        .spawn(enemy::follower::create_follower(pos, dir, charge).build())
+    let pos = preamble.spawn_pos(side) - dir * SPAWN_PUSHBACK + dir.perp() * preamble.jitter;
+    let charge = preamble.rng.i8(-1..=1);
+    preamble.cmd.spawn(
+        enemy::follower::create_follower(pos, dir, charge, preamble.rng)
+            .add(SpawnGrace::default())
+            .build(),
+    )
 }
 
 /// Spawns a mine from a random edge.
 pub(super) fn mine(preamble: &mut WavePreamble) {
-    let side = get_side();
+    let side = preamble.side();
     let dir = get_dir(side);
-    let pos = get_spawn_pos(side) - dir * SPAWN_PUSHBACK;
-    let charge = fastrand::i8(-1..=1);
-    preamble
-        .cmd
-        .spawn(enemy::mine::create_mine(pos, dir, charge).build())
+    let pos = preamble.spawn_pos(side) - dir * SPAWN_PUSHBACK + dir.perp() * preamble.jitter;
+    let charge = preamble.rng.i8(-1..=1);
+    preamble.cmd.spawn(
+        enemy::mine::create_mine(pos, dir, charge, preamble.rng)
+            .add(SpawnGrace::default())
+            .build(),
+    )
 }
 
 //------------------------------------------------------------------------------
@@ -236,13 +397,12 @@ pub(super) fn mine(preamble: &mut WavePreamble) {
 /// * 2 = LEFT
 /// * 3 = RIGHT
 #[inline]
-fn get_side() -> u8 {
-    fastrand::u8(0..4)
+fn get_side(rng: &mut Rng) -> u8 {
+    rng.u8(0..4)
 }
 
 /// Returns a number representing the opposite side from `side`.
 #[inline]
-#[allow(dead_code)]
 fn get_opposite_side(side: u8) -> u8 {
     match side {
         0 => 1,
@@ -256,59 +416,93 @@ fn get_opposite_side(side: u8) -> u8 {
 /// Returns a random valid position resprecting `SPAWN_MARGIN` and `SPAWN_PUSHBACK` so that
 /// the enemy is spawned on side `side`.
 #[inline]
-fn get_spawn_pos(side: u8) -> Vec2 {
+fn get_spawn_pos(side: u8, arena: &Arena, rng: &mut Rng) -> Vec2 {
+    get_side_pos(side, arena, rng.f32())
+}
+
+/// Returns the valid spawn position at portion `t` (0.0 to 1.0) along side `side`.
+fn get_side_pos(side: u8, arena: &Arena, t: f32) -> Vec2 {
     match side {
         0 => {
             //TOP
             vec2(
-                fastrand::f32() * (SPACE_WIDTH - 2.0 * SPAWN_MARGIN) + SPAWN_MARGIN,
+                t * (arena.width - 2.0 * SPAWN_MARGIN) + SPAWN_MARGIN,
                 -SPAWN_PUSHBACK,
             )
         }
         1 => {
             //BOTTOM
             vec2(
-                fastrand::f32() * (SPACE_WIDTH - 2.0 * SPAWN_MARGIN) + SPAWN_MARGIN,
-                SPACE_HEIGHT + SPAWN_PUSHBACK,
+                t * (arena.width - 2.0 * SPAWN_MARGIN) + SPAWN_MARGIN,
+                arena.height + SPAWN_PUSHBACK,
             )
         }
         2 => {
             //LEFT
             vec2(
                 -SPAWN_PUSHBACK,
-                fastrand::f32() * (SPACE_HEIGHT - 2.0 * SPAWN_MARGIN) + SPAWN_MARGIN,
+                t * (arena.height - 2.0 * SPAWN_MARGIN) + SPAWN_MARGIN,
             )
         }
         3 => {
             //RIGHT
             vec2(
-                SPACE_WIDTH + SPAWN_PUSHBACK,
-                fastrand::f32() * (SPACE_HEIGHT - 2.0 * SPAWN_MARGIN) + SPAWN_MARGIN,
+                arena.width + SPAWN_PUSHBACK,
+                t * (arena.height - 2.0 * SPAWN_MARGIN) + SPAWN_MARGIN,
             )
         }
         _ => unreachable!("Random number should not exceed range 0..4"),
     }
 }
 
+/// Returns a random spawn position on side `side`,
+/// farther than `SPAWN_SAFE_DISTANCE` from `player_pos` when possible.
+///
+/// Positions are rolled up to `SPAWN_ATTEMPTS` times,
+/// the farther end of the side is used when none is far enough.
+fn get_safe_spawn_pos(side: u8, arena: &Arena, player_pos: Vec2, rng: &mut Rng) -> Vec2 {
+    for _ in 0..SPAWN_ATTEMPTS {
+        let pos = get_spawn_pos(side, arena, rng);
+        if pos.distance(player_pos) >= SPAWN_SAFE_DISTANCE {
+            return pos;
+        }
+    }
+    //no position on the side is farther than its ends
+    let [start, end] = [0.0, 1.0].map(|t| get_side_pos(side, arena, t));
+    if start.distance(player_pos) > end.distance(player_pos) {
+        start
+    } else {
+        end
+    }
+}
+
+/// Returns the side `pos` is the closest to.
+fn get_nearest_side(pos: Vec2, arena: &Arena) -> u8 {
+    let distances = [pos.y, arena.height - pos.y, pos.x, arena.width - pos.x];
+    (0..4)
+        .min_by(|&a, &b| distances[a as usize].total_cmp(&distances[b as usize]))
+        .unwrap()
+}
+
 /// Returns the center of side `side`.
 #[inline]
-fn get_center_pos(side: u8) -> Vec2 {
+fn get_center_pos(side: u8, arena: &Arena) -> Vec2 {
     match side {
         0 => {
             //TOP
-            vec2(SPACE_WIDTH / 2.0, -SPAWN_PUSHBACK)
+            vec2(arena.width / 2.0, -SPAWN_PUSHBACK)
         }
         1 => {
             //BOTTOM
-            vec2(SPACE_WIDTH / 2.0, SPACE_HEIGHT + SPAWN_PUSHBACK)
+            vec2(arena.width / 2.0, arena.height + SPAWN_PUSHBACK)
         }
         2 => {
             //LEFT
-            vec2(-SPAWN_PUSHBACK, SPACE_HEIGHT / 2.0)
+            vec2(-SPAWN_PUSHBACK, arena.height / 2.0)
         }
         3 => {
             //RIGHT
-            vec2(SPACE_WIDTH + SPAWN_PUSHBACK, SPACE_HEIGHT / 2.0)
+            vec2(arena.width + SPAWN_PUSHBACK, arena.height / 2.0)
         }
         _ => unreachable!("Random number should not exceed range 0..4"),
     }
@@ -326,3 +520,109 @@ fn get_dir(side: u8) -> Vec2 {
         _ => unreachable!("Random number should not exceed range 0..4"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        basic::{
+            clock::FIXED_DT,
+            motion::{self, PhysicsMotion},
+        },
+        persist::Settings,
+    };
+
+    #[test]
+    fn dipole_orbit_speed_balances_attraction() {
+        //centripetal force of the speed equals the attraction
+        let (force, mass, separation) = (200.0, 4.0, 100.0);
+        let speed = dipole_orbit_speed(force, mass, separation);
+        assert_eq!(speed, 50.0);
+        assert_eq!(mass * speed * speed / (separation / 2.0), force);
+        //the sign of the charge does not matter
+        assert_eq!(dipole_orbit_speed(-force, mass, separation), speed);
+    }
+
+    #[test]
+    fn dipole_pair_keeps_separation() {
+        let mut world = World::new();
+        let mut rng = Rng::with_seed(1555);
+        let speed = config::get().enemies.asteroid.speed;
+        let orbit = dipole_orbit_speed(
+            enemy::asteroid::ASTEROID_FORCE,
+            enemy::asteroid::ASTEROID_MASS,
+            DIPOLE_SEPARATION,
+        );
+        //circling the center without drifting
+        let center = vec2(600.0, 400.0);
+        let asteroids = [(1.0, 1), (-1.0, -1)].map(|(offset, charge)| {
+            let pos = center + Vec2::X * offset * DIPOLE_SEPARATION / 2.0;
+            let vel = Vec2::Y * offset * orbit;
+            world.spawn(enemy::create_charged_asteroid(pos, vel / speed, charge, &mut rng).build())
+        });
+
+        //simulate a whole orbit
+        let period = std::f32::consts::TAU * DIPOLE_SEPARATION / 2.0 / orbit;
+        for _ in 0..(period / FIXED_DT) as u32 {
+            motion::apply_physics(&mut world, FIXED_DT, None);
+            motion::apply_motion(&mut world, &Settings::default(), FIXED_DT);
+
+            let [a, b] = asteroids.map(|id| {
+                let pos = world.get::<&Position>(id).unwrap();
+                vec2(pos.x, pos.y)
+            });
+            let separation = a.distance(b);
+            assert!(
+                (separation - DIPOLE_SEPARATION).abs() < DIPOLE_SEPARATION * 0.1,
+                "asteroids drifted {separation} apart"
+            );
+        }
+        //still moving at the orbit speed
+        for id in asteroids {
+            let vel = world.get::<&PhysicsMotion>(id).unwrap().vel;
+            assert!((vel.length() - orbit).abs() < orbit * 0.1);
+        }
+    }
+
+    #[test]
+    fn safe_spawns_avoid_player() {
+        let mut rng = Rng::with_seed(1536);
+        for arena in [Arena::STANDARD, Arena::LARGE] {
+            for _ in 0..2000 {
+                let player_pos = vec2(rng.f32() * arena.width, rng.f32() * arena.height);
+                let side = get_side(&mut rng);
+                let pos = get_safe_spawn_pos(side, &arena, player_pos, &mut rng);
+
+                //on the side, within its margins
+                assert_eq!(get_nearest_side(pos, &arena), side);
+                let along = if side < 2 { pos.x } else { pos.y };
+                let length = if side < 2 { arena.width } else { arena.height };
+                assert!((SPAWN_MARGIN..=length - SPAWN_MARGIN).contains(&along));
+
+                //outside the exclusion radius, unless the whole side is inside it
+                let far_end = [0.0, 1.0]
+                    .map(|t| get_side_pos(side, &arena, t).distance(player_pos))
+                    .into_iter()
+                    .fold(0.0, f32::max);
+                if far_end >= SPAWN_SAFE_DISTANCE {
+                    assert!(
+                        pos.distance(player_pos) >= SPAWN_SAFE_DISTANCE,
+                        "{pos} spawned too close to the player at {player_pos}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn safe_spawns_next_to_player_on_the_side() {
+        let mut rng = Rng::with_seed(1536);
+        let arena = Arena::STANDARD;
+        //hugging the middle of the left wall leaves only its ends far enough
+        let player_pos = vec2(0.0, arena.height / 2.0);
+        for _ in 0..2000 {
+            let pos = get_safe_spawn_pos(2, &arena, player_pos, &mut rng);
+            assert!(pos.distance(player_pos) >= SPAWN_SAFE_DISTANCE);
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Gamepad support of desktop platforms, backed by gilrs.
+
+use gilrs::{
+    ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Repeat, Replay, Ticks},
+    Axis, Button, Event, EventType, GamepadId, Gilrs,
+};
+use macroquad::prelude::*;
+
+use crate::{
+    basic::rumble::RumbleBackend,
+    input::{GamepadBackend, GamepadState},
+};
+
+/// Buttons held to fire.
+const FIRE_BUTTONS: [Button; 2] = [Button::RightTrigger2, Button::West];
+/// Buttons flipping the polarity when pressed.
+const POLARITY_BUTTONS: [Button; 2] = [Button::LeftTrigger, Button::RightTrigger];
+/// Buttons confirming menus when pressed.
+const CONFIRM_BUTTONS: [Button; 2] = [Button::South, Button::Start];
+/// Length of one period of the rumble effect, repeated until stopped.
+const RUMBLE_PERIOD_MS: u32 = 50;
+
+/// Backend reading and rumbling gamepads through gilrs.
+/// The gamepad used last is the one read and rumbled.
+pub struct GilrsBackend {
+    /// Gamepad library, [None] if the platform is not supported.
+    gilrs: Option<Gilrs>,
+    /// Gamepad used last, [None] if there is none connected.
+    active: Option<GamepadId>,
+    /// Gamepad the rumble effect was created for.
+    rumble_pad: Option<GamepadId>,
+    /// Rumble effect of full strength scaled by its gain,
+    /// [None] if the gamepad cannot rumble.
+    rumble: Option<Effect>,
+    /// Strength the gamepad rumbles at.
+    rumble_strength: f32,
+}
+
+impl GilrsBackend {
+    /// Creates the backend.
+    /// Without gamepad support the backend reports no gamepad.
+    pub fn new() -> Self {
+        let gilrs = match Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(err) => {
+                eprintln!("Gamepads are not supported: {err}");
+                None
+            }
+        };
+        //start with any connected gamepad
+        let active = gilrs
+            .as_ref()
+            .and_then(|gilrs| gilrs.gamepads().next())
+            .map(|(id, _)| id);
+        Self {
+            gilrs,
+            active,
+            rumble_pad: None,
+            rumble: None,
+            rumble_strength: 0.0,
+        }
+    }
+}
+
+impl Default for GilrsBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GamepadBackend for GilrsBackend {
+    fn poll(&mut self) -> GamepadState {
+        let Some(gilrs) = &mut self.gilrs else {
+            return GamepadState::default();
+        };
+        let mut state = GamepadState::default();
+
+        //collect presses of this frame
+        while let Some(Event { id, event, .. }) = gilrs.next_event() {
+            match event {
+                EventType::Disconnected => {
+                    if self.active == Some(id) {
+                        self.active = gilrs.gamepads().next().map(|(id, _)| id);
+                    }
+                }
+                EventType::Dropped => {}
+                event => {
+                    //switch to the gamepad used last
+                    self.active = Some(id);
+                    if let EventType::ButtonPressed(button, _) = event {
+                        state.polarity_pressed |= POLARITY_BUTTONS.contains(&button);
+                        state.confirm_pressed |= CONFIRM_BUTTONS.contains(&button);
+                    }
+                }
+            }
+        }
+
+        //read held buttons and the stick
+        let Some(gamepad) = self.active.and_then(|id| gilrs.connected_gamepad(id)) else {
+            return state;
+        };
+        state.connected = true;
+        state.fire = FIRE_BUTTONS
+            .into_iter()
+            .any(|button| gamepad.is_pressed(button));
+        //the stick's y axis points up, the world's down
+        state.left_stick = vec2(
+            gamepad.value(Axis::LeftStickX),
+            -gamepad.value(Axis::LeftStickY),
+        );
+        state
+    }
+}
+
+impl RumbleBackend for GilrsBackend {
+    fn set_rumble(&mut self, strength: f32) {
+        let Some(gilrs) = &mut self.gilrs else {
+            return;
+        };
+        //rumble the gamepad used last
+        if self.rumble_pad != self.active {
+            if let Some(effect) = &self.rumble {
+                let _ = effect.stop();
+            }
+            self.rumble_pad = self.active;
+            self.rumble = self.active.and_then(|id| rumble_effect(gilrs, id));
+            self.rumble_strength = 0.0;
+        }
+        let Some(effect) = &self.rumble else {
+            return;
+        };
+        if strength == self.rumble_strength {
+            return;
+        }
+
+        let result = if strength > 0.0 {
+            effect.set_gain(strength).and_then(|_| {
+                if self.rumble_strength > 0.0 {
+                    Ok(())
+                } else {
+                    effect.play()
+                }
+            })
+        } else {
+            effect.stop()
+        };
+        if result.is_ok() {
+            self.rumble_strength = strength;
+        }
+    }
+}
+
+/// Creates an effect rumbling both motors of the gamepad `id` at full strength until stopped.
+///
+/// Returns [None] if the gamepad does not support rumble.
+fn rumble_effect(gilrs: &mut Gilrs, id: GamepadId) -> Option<Effect> {
+    let scheduling = Replay {
+        play_for: Ticks::from_ms(RUMBLE_PERIOD_MS),
+        ..Default::default()
+    };
+    EffectBuilder::new()
+        .add_effect(BaseEffect {
+            kind: BaseEffectType::Strong {
+                magnitude: u16::MAX,
+            },
+            scheduling,
+            ..Default::default()
+        })
+        .add_effect(BaseEffect {
+            kind: BaseEffectType::Weak {
+                magnitude: u16::MAX,
+            },
+            scheduling,
+            ..Default::default()
+        })
+        .gamepads(&[id])
+        .repeat(Repeat::Infinitely)
+        .finish(gilrs)
+        .ok()
+}
@@ -0,0 +1,177 @@
+//! Ghost of the best run's path, shown like a racing ghost.
+
+use hecs::World;
+use macroquad::prelude::*;
+use nanoserde::{DeBin, SerBin};
+
+use crate::{
+    basic::{Position, Timer},
+    persist::Persistent,
+    player::Player,
+    SPACE_WIDTH,
+};
+
+/// Version of the [GhostPath] format.
+/// Paths of other versions are not played back.
+pub const GHOST_VERSION: u8 = 1;
+/// Time between two recorded samples.
+const GHOST_SAMPLE_INTERVAL: f32 = 0.1;
+/// Max amount of recorded samples, about 10 minutes.
+const GHOST_MAX_SAMPLES: usize = 6000;
+/// Radius of the ghost marker.
+const GHOST_RADIUS: f32 = 12.0;
+/// Color of the ghost marker.
+const GHOST_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.3);
+
+/// Path of the player compressed as deltas between samples.
+#[derive(Clone, Debug, Default, DeBin, SerBin)]
+pub struct GhostPath {
+    /// Version of the format, see [GHOST_VERSION].
+    pub version: u8,
+    /// Interleaved x and y deltas in whole units.
+    /// The first delta is relative to the origin.
+    pub deltas: Vec<i16>,
+}
+
+impl GhostPath {
+    /// Compresses `samples` into a path.
+    pub fn from_samples(samples: &[Vec2]) -> Self {
+        let mut deltas = Vec::with_capacity(samples.len() * 2);
+        let mut last = (0, 0);
+        for sample in samples {
+            //deltas of rounded positions do not drift
+            let current = (sample.x.round() as i16, sample.y.round() as i16);
+            deltas.push(current.0 - last.0);
+            deltas.push(current.1 - last.1);
+            last = current;
+        }
+
+        Self {
+            version: GHOST_VERSION,
+            deltas,
+        }
+    }
+
+    /// Decompresses the path into samples.
+    /// Returns no samples for paths of a different version.
+    pub fn samples(&self) -> Vec<Vec2> {
+        if self.version != GHOST_VERSION {
+            return Vec::new();
+        }
+        let mut samples = Vec::with_capacity(self.deltas.len() / 2);
+        let mut last = Vec2::ZERO;
+        for delta in self.deltas.chunks_exact(2) {
+            last += vec2(delta[0] as f32, delta[1] as f32);
+            samples.push(last);
+        }
+        samples
+    }
+}
+
+/// Resource recording the player's path during a run.
+#[derive(Clone, Debug)]
+pub struct GhostRecorder {
+    /// Recorded positions of the player.
+    pub samples: Vec<Vec2>,
+    /// Time before the next sample.
+    pub timer: Timer,
+}
+
+impl Default for GhostRecorder {
+    fn default() -> Self {
+        Self {
+            samples: Vec::new(),
+            timer: Timer::new(GHOST_SAMPLE_INTERVAL, true),
+        }
+    }
+}
+
+/// Render-only entity following the best run's path.
+/// It never takes part in physics nor collisions.
+#[derive(Clone, Debug, Default)]
+pub struct GhostPlayback {
+    /// Time since the start of the run.
+    pub elapsed: f32,
+    /// Decompressed samples of the path.
+    pub samples: Vec<Vec2>,
+    /// Were the samples already loaded from [Persistent]?
+    pub loaded: bool,
+}
+
+//-----------------------------------------------------------------------------
+//SYSTEM PART
+//-----------------------------------------------------------------------------
+
+/// Records the player's position into [GhostRecorder] every sample interval.
+/// Stops recording once the recorder is full.
+pub fn record_ghost(world: &mut World, dt: f32) {
+    let Some((_, &pos)) = world
+        .query_mut::<&Position>()
+        .with::<&Player>()
+        .into_iter()
+        .next()
+    else {
+        return;
+    };
+
+    for (_, recorder) in world.query_mut::<&mut GhostRecorder>() {
+        if recorder.samples.len() >= GHOST_MAX_SAMPLES {
+            continue;
+        }
+        recorder.timer.tick(dt);
+        if recorder.samples.is_empty() || recorder.timer.just_finished() {
+            recorder.samples.push(vec2(pos.x, pos.y));
+        }
+    }
+}
+
+/// Saves the recorded path as the best run's path.
+pub fn save_ghost(world: &mut World, persist: &mut Persistent) {
+    if let Some((_, recorder)) = world.query_mut::<&GhostRecorder>().into_iter().next() {
+        persist.ghost = GhostPath::from_samples(&recorder.samples);
+    }
+}
+
+/// Moves the ghost along the best run's path.
+/// The ghost stays at its last position after the path ends.
+pub fn ghost_playback(world: &mut World, persist: &Persistent, dt: f32) {
+    for (_, (ghost, pos)) in world.query_mut::<(&mut GhostPlayback, &mut Position)>() {
+        //decompress the path only once
+        if !ghost.loaded {
+            ghost.samples = persist.ghost.samples();
+            ghost.loaded = true;
+        }
+        ghost.elapsed += dt;
+
+        let index = ghost.elapsed / GHOST_SAMPLE_INTERVAL;
+        let (Some(from), Some(to)) = (
+            ghost.samples.get(index as usize),
+            ghost.samples.get(index as usize + 1),
+        ) else {
+            continue;
+        };
+        //do not interpolate across the edge of the space
+        let point = if from.distance(*to) > SPACE_WIDTH / 2.0 {
+            *from
+        } else {
+            from.lerp(*to, index.fract())
+        };
+        pos.x = point.x;
+        pos.y = point.y;
+    }
+}
+
+/// Renders the ghost as a faint marker, when enabled in settings.
+pub fn render_ghost(world: &mut World, persist: &Persistent) {
+    if !persist.settings.ghost {
+        return;
+    }
+    for (_, (ghost, pos)) in world.query_mut::<(&GhostPlayback, &Position)>() {
+        //render only while the path lasts
+        let index = (ghost.elapsed / GHOST_SAMPLE_INTERVAL) as usize;
+        if index + 1 >= ghost.samples.len() {
+            continue;
+        }
+        draw_circle_lines(pos.x, pos.y, GHOST_RADIUS, 2.0, GHOST_COLOR);
+    }
+}
@@ -0,0 +1,127 @@
+//! Heatmap of charge forces applied to the player, used for balancing.
+//! Only recorded in debug builds.
+
+use hecs::{Entity, World};
+use macroquad::prelude::*;
+
+use crate::basic::arena::{self, Arena};
+
+/// Amount of columns of the heatmap.
+const HEATMAP_COLUMNS: usize = 32;
+/// Amount of rows of the heatmap.
+const HEATMAP_ROWS: usize = 18;
+/// Key showing and hiding the heatmap on the game over screen.
+const HEATMAP_KEY: KeyCode = KeyCode::H;
+/// File the heatmap is written into at game over.
+const HEATMAP_FILE: &str = "force_heatmap.csv";
+
+/// Resource summing magnitudes of charge forces applied to an entity,
+/// per cell of a coarse grid over the arena.
+#[derive(Clone, Debug, Default)]
+pub struct ForceHeatmap {
+    /// Entity whose received forces are recorded.
+    pub target: Option<Entity>,
+    /// Width of the recorded space.
+    width: f32,
+    /// Height of the recorded space.
+    height: f32,
+    /// Summed force magnitudes, row by row.
+    cells: Vec<f32>,
+    /// Is the heatmap shown?
+    pub shown: bool,
+}
+
+impl ForceHeatmap {
+    /// Creates an empty heatmap.
+    /// # Arguments
+    /// * `target` - entity whose received forces are recorded
+    /// * `arena` - play space the grid covers
+    pub fn new(target: Entity, arena: &Arena) -> Self {
+        Self {
+            target: Some(target),
+            width: arena.width,
+            height: arena.height,
+            cells: vec![0.0; HEATMAP_COLUMNS * HEATMAP_ROWS],
+            shown: false,
+        }
+    }
+
+    /// Records a force of magnitude `force` applied at `pos`.
+    /// Positions outside of the arena are recorded into its border cells.
+    pub fn record(&mut self, pos: Vec2, force: f32) {
+        if self.cells.is_empty() {
+            return;
+        }
+        let column = ((pos.x / self.width * HEATMAP_COLUMNS as f32).max(0.0) as usize)
+            .min(HEATMAP_COLUMNS - 1);
+        let row =
+            ((pos.y / self.height * HEATMAP_ROWS as f32).max(0.0) as usize).min(HEATMAP_ROWS - 1);
+        self.cells[row * HEATMAP_COLUMNS + column] += force.abs();
+    }
+
+    /// Formats the heatmap as CSV, one line per row.
+    pub fn to_csv(&self) -> String {
+        self.cells
+            .chunks(HEATMAP_COLUMNS)
+            .map(|row| {
+                row.iter()
+                    .map(|cell| format!("{cell:.1}"))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+//-----------------------------------------------------------------------------
+//SYSTEM PART
+//-----------------------------------------------------------------------------
+
+/// Writes the [ForceHeatmap] of the run into [HEATMAP_FILE].
+pub fn save_heatmap(world: &mut World) {
+    for (_, heatmap) in world.query_mut::<&ForceHeatmap>() {
+        let _ = std::fs::write(HEATMAP_FILE, heatmap.to_csv());
+    }
+}
+
+/// Shows and hides the [ForceHeatmap] when its key is pressed.
+pub fn toggle_heatmap(world: &mut World) {
+    if !is_key_pressed(HEATMAP_KEY) {
+        return;
+    }
+    for (_, heatmap) in world.query_mut::<&mut ForceHeatmap>() {
+        heatmap.shown = !heatmap.shown;
+    }
+}
+
+/// Renders the shown [ForceHeatmap] as a translucent overlay over the arena.
+/// The strongest cell is the most opaque.
+pub fn render_heatmap(world: &mut World) {
+    let camera = arena::view_camera(world);
+    for (_, heatmap) in world.query_mut::<&ForceHeatmap>() {
+        if !heatmap.shown {
+            continue;
+        }
+        let max = heatmap.cells.iter().copied().fold(0.0, f32::max);
+        if max <= 0.0 {
+            continue;
+        }
+        let cell_width = heatmap.width / HEATMAP_COLUMNS as f32;
+        let cell_height = heatmap.height / HEATMAP_ROWS as f32;
+
+        set_camera(&camera);
+        for (i, cell) in heatmap.cells.iter().enumerate() {
+            let x = (i % HEATMAP_COLUMNS) as f32 * cell_width;
+            let y = (i / HEATMAP_COLUMNS) as f32 * cell_height;
+            draw_rectangle(
+                x,
+                y,
+                cell_width,
+                cell_height,
+                Color::new(1.0, 0.0, 0.0, 0.6 * cell / max),
+            );
+        }
+        set_camera(&crate::world_camera());
+    }
+}
@@ -0,0 +1,125 @@
+//! Onboarding hints shown during the first run.
+
+use hecs::{CommandBuffer, World};
+use macroquad::prelude::*;
+use nanoserde::{DeBin, SerBin};
+
+use crate::{
+    banner,
+    basic::{
+        motion::{ChargeReceiver, ChargeSender},
+        Position,
+    },
+    enemy::{mine::Mine, Enemy},
+    locale::tr,
+    persist::Persistent,
+    player::Player,
+};
+
+/// Distance of a charged enemy from the player that shows [Hint::FlipPolarity].
+const HINT_CHARGED_DISTANCE: f32 = 300.0;
+/// Time a hint is shown for.
+const HINT_TIME: f32 = 5.0;
+/// Color of hint banners.
+const HINT_COLOR: Color = SKYBLUE;
+
+/// Hint explaining a mechanic of the game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hint {
+    /// Shown when a charged enemy gets close for the first time.
+    FlipPolarity,
+    /// Shown when the player is pulled by a charge field for the first time.
+    Attraction,
+    /// Shown when the first mine spawns.
+    Mines,
+}
+
+impl Hint {
+    /// Returns the bit of the hint in [HintsShown].
+    fn bit(self) -> u8 {
+        match self {
+            Hint::FlipPolarity => 1,
+            Hint::Attraction => 1 << 1,
+            Hint::Mines => 1 << 2,
+        }
+    }
+}
+
+/// Bitfield of hints that were already shown.
+#[derive(Clone, Copy, Default, Debug, DeBin, SerBin)]
+pub struct HintsShown {
+    /// One bit per [Hint].
+    bits: u8,
+}
+
+impl HintsShown {
+    /// Was `hint` already shown?
+    pub fn shown(&self, hint: Hint) -> bool {
+        self.bits & hint.bit() != 0
+    }
+
+    /// Remembers that `hint` was shown.
+    pub fn mark(&mut self, hint: Hint) {
+        self.bits |= hint.bit();
+    }
+}
+
+//-----------------------------------------------------------------------------
+//SYSTEM PART
+//-----------------------------------------------------------------------------
+
+/// Shows hints whose situations occured for the first time.
+/// Only runs during the first run on a fresh save, every hint is shown once.
+pub fn hints(world: &mut World, cmd: &mut CommandBuffer, persist: &mut Persistent) {
+    if !persist.first_run() {
+        return;
+    }
+    let Some((player_id, (player_pos, receiver))) = world
+        .query_mut::<(&Position, &ChargeReceiver)>()
+        .with::<&Player>()
+        .into_iter()
+        .next()
+        .map(|(id, (pos, receiver))| (id, (vec2(pos.x, pos.y), *receiver)))
+    else {
+        return;
+    };
+
+    //detect the situations
+    let mut charged_near = false;
+    let mut pulled = false;
+    for (id, (sender, pos, enemy)) in
+        world.query_mut::<(&ChargeSender, &Position, Option<&Enemy>)>()
+    {
+        if id == player_id {
+            continue;
+        }
+        let distance = player_pos.distance(vec2(pos.x, pos.y));
+        if enemy.is_some() && sender.force != 0.0 && distance <= HINT_CHARGED_DISTANCE {
+            charged_near = true;
+        }
+        //opposite charges attract
+        if distance < sender.no_radius && sender.force * receiver.multiplier < 0.0 {
+            pulled = true;
+        }
+    }
+    let mine_spawned = world.query_mut::<&Mine>().into_iter().next().is_some();
+
+    //show the hints
+    let mut slots = banner::banner_slots(world);
+    for (hint, occured, text) in [
+        (
+            Hint::FlipPolarity,
+            charged_near,
+            tr!("hint.flip_polarity", key = persist.bindings.polarity.name()),
+        ),
+        (Hint::Attraction, pulled, tr!("hint.attraction")),
+        (Hint::Mines, mine_spawned, tr!("hint.mines")),
+    ] {
+        if !occured || persist.hints.shown(hint) {
+            continue;
+        }
+        banner::push_banner(world, cmd, &mut slots, text, HINT_COLOR, HINT_TIME);
+        persist.hints.mark(hint);
+        let _ = persist.save();
+    }
+}
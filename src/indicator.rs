@@ -0,0 +1,71 @@
+//! Indicators of enemies outside of the visible space.
+
+use hecs::World;
+use macroquad::prelude::*;
+
+use crate::{
+    basic::{arena, motion::ChargeSender, Position},
+    enemy::Enemy,
+    projectile::Projectile,
+    xp::XpOrb,
+};
+
+/// Distance of indicators from the edge of the screen.
+const INDICATOR_MARGIN: f32 = 12.0;
+/// Size of indicators of far away enemies.
+const INDICATOR_MIN_SIZE: f32 = 4.0;
+/// Size of indicators of enemies right behind the edge.
+const INDICATOR_MAX_SIZE: f32 = 10.0;
+/// Distance from the edge at which indicators are the smallest.
+const INDICATOR_RANGE: f32 = 400.0;
+/// Color of indicators of positively charged enemies.
+const INDICATOR_POSITIVE: Color = Color::new(1.0, 0.2, 0.2, 0.8);
+/// Color of indicators of negatively charged enemies.
+const INDICATOR_NEGATIVE: Color = Color::new(0.0, 1.0, 1.0, 0.8);
+/// Color of indicators of neutral enemies.
+const INDICATOR_NEUTRAL: Color = Color::new(0.8, 0.8, 0.8, 0.8);
+
+/// Renders arrows at the edge of the screen pointing at enemies outside of it.
+/// Their color shows the enemy's charge, their size how close it is.
+/// Must be rendered with the camera of the world.
+pub fn render_offscreen_indicators(world: &mut World) {
+    let view = arena::view_rect(world);
+    let min = view.point() + Vec2::splat(INDICATOR_MARGIN);
+    let max = view.point() + view.size() - Vec2::splat(INDICATOR_MARGIN);
+
+    for (_, (pos, charge)) in world
+        .query_mut::<(&Position, Option<&ChargeSender>)>()
+        .with::<&Enemy>()
+        .without::<&Projectile>()
+        .without::<&XpOrb>()
+    {
+        let pos = vec2(pos.x, pos.y);
+        if view.contains(pos) {
+            continue;
+        }
+        //point at the edge closest to the enemy
+        let point = pos.clamp(min, max);
+        let Some(dir) = (pos - point).try_normalize() else {
+            continue;
+        };
+        //closer enemies get bigger arrows
+        let proximity = (1.0 - pos.distance(point) / INDICATOR_RANGE).clamp(0.0, 1.0);
+        let size = INDICATOR_MIN_SIZE + (INDICATOR_MAX_SIZE - INDICATOR_MIN_SIZE) * proximity;
+        let force = charge.map(|charge| charge.force).unwrap_or(0.0);
+        let color = if force > 0.0 {
+            INDICATOR_POSITIVE
+        } else if force < 0.0 {
+            INDICATOR_NEGATIVE
+        } else {
+            INDICATOR_NEUTRAL
+        };
+
+        let side = dir.perp() * size * 0.6;
+        draw_triangle(
+            point + dir * size,
+            point - dir * size * 0.5 + side,
+            point - dir * size * 0.5 - side,
+            color,
+        );
+    }
+}
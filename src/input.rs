@@ -0,0 +1,371 @@
+//! Input handling shared by all game states.
+
+use std::cell::Cell;
+
+use macroquad::prelude::*;
+use nanoserde::{DeBin, SerBin};
+
+use crate::locale::tr;
+
+/// Stick deflection below which the stick is considered centered.
+const STICK_DEADZONE: f32 = 0.25;
+
+thread_local! {
+    /// Were the presses of this frame already handled by a simulation step?
+    static PRESSES_HANDLED: Cell<bool> = const { Cell::new(false) };
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Keys pressed by [press_key] in the current frame.
+    static TEST_PRESSES: std::cell::RefCell<Vec<KeyCode>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+/// Returns the keys pressed this frame.
+#[cfg(not(test))]
+fn keys_pressed() -> Vec<KeyCode> {
+    get_keys_pressed().into_iter().collect()
+}
+
+/// Returns the keys pressed this frame.
+/// Tests have no window, their presses come from [press_key].
+#[cfg(test)]
+fn keys_pressed() -> Vec<KeyCode> {
+    TEST_PRESSES.with_borrow(Clone::clone)
+}
+
+/// Presses `key` in the current frame, until the next [begin_frame].
+#[cfg(test)]
+pub fn press_key(key: KeyCode) {
+    TEST_PRESSES.with_borrow_mut(|presses| presses.push(key));
+}
+
+/// Starts a new frame, its presses are reported by [Binding::is_pressed].
+pub fn begin_frame() {
+    PRESSES_HANDLED.set(false);
+    #[cfg(test)]
+    TEST_PRESSES.with_borrow_mut(Vec::clear);
+}
+
+/// Marks the presses of this frame as handled,
+/// so that further simulation steps of the frame do not handle them again.
+pub fn mark_presses_handled() {
+    PRESSES_HANDLED.set(true);
+}
+
+/// Was any key or mouse button pressed this frame?
+pub fn any_pressed() -> bool {
+    !get_keys_pressed().is_empty()
+        || [MouseButton::Left, MouseButton::Right, MouseButton::Middle]
+            .into_iter()
+            .any(is_mouse_button_pressed)
+}
+
+/// Snapshot of the gamepad's state in the current frame.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GamepadState {
+    /// Is any gamepad connected?
+    pub connected: bool,
+    /// Deflection of the left stick, each axis -1.0 to 1.0.
+    pub left_stick: Vec2,
+    /// Is the fire button (right trigger or a face button) held?
+    pub fire: bool,
+    /// Was the polarity button (shoulder) pressed this frame?
+    pub polarity_pressed: bool,
+    /// Was the confirm button pressed this frame?
+    pub confirm_pressed: bool,
+}
+
+impl GamepadState {
+    /// Was there any input from the gamepad this frame?
+    pub fn any_input(&self) -> bool {
+        self.connected
+            && (self.left_stick.length() > STICK_DEADZONE
+                || self.fire
+                || self.polarity_pressed
+                || self.confirm_pressed)
+    }
+}
+
+/// Platform layer able to read gamepads.
+pub trait GamepadBackend {
+    /// Reads the state of the gamepad.
+    /// Should be called exactly once per frame.
+    fn poll(&mut self) -> GamepadState;
+}
+
+/// Backend of platforms without gamepad support.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoGamepad;
+
+impl GamepadBackend for NoGamepad {
+    fn poll(&mut self) -> GamepadState {
+        GamepadState::default()
+    }
+}
+
+/// Key or mouse button bound to an action.
+#[derive(Clone, Debug, PartialEq, DeBin, SerBin)]
+pub enum Binding {
+    /// Keyboard key.
+    Key {
+        /// Discriminant of the [KeyCode].
+        code: u16,
+        /// Name of the key shown to the user.
+        name: String,
+    },
+    /// Mouse button.
+    /// 0 => left
+    /// 1 => right
+    /// 2 => middle
+    Mouse(u8),
+}
+
+impl Binding {
+    /// Creates a binding of `key`.
+    pub fn key(key: KeyCode) -> Self {
+        Self::Key {
+            code: key as u16,
+            name: format!("{key:?}"),
+        }
+    }
+
+    /// Creates a binding of the mouse `button`.
+    pub fn mouse(button: MouseButton) -> Self {
+        Self::Mouse(match button {
+            MouseButton::Right => 1,
+            MouseButton::Middle => 2,
+            _ => 0,
+        })
+    }
+
+    /// Returns the mouse button of a mouse binding.
+    fn mouse_button(index: u8) -> MouseButton {
+        match index {
+            1 => MouseButton::Right,
+            2 => MouseButton::Middle,
+            _ => MouseButton::Left,
+        }
+    }
+
+    /// Is the bound key or button held?
+    pub fn is_down(&self) -> bool {
+        match self {
+            Self::Key { code, .. } => get_keys_down().iter().any(|key| *key as u16 == *code),
+            Self::Mouse(index) => is_mouse_button_down(Self::mouse_button(*index)),
+        }
+    }
+
+    /// Was the bound key or button pressed this frame?
+    /// Presses are reported only until they are marked as handled.
+    pub fn is_pressed(&self) -> bool {
+        if PRESSES_HANDLED.get() {
+            return false;
+        }
+        match self {
+            Self::Key { code, .. } => keys_pressed().iter().any(|key| *key as u16 == *code),
+            Self::Mouse(index) => is_mouse_button_pressed(Self::mouse_button(*index)),
+        }
+    }
+
+    /// Returns the name of the binding shown to the user.
+    pub fn name(&self) -> String {
+        match self {
+            Self::Key { name, .. } => name.to_uppercase(),
+            Self::Mouse(index) => match Self::mouse_button(*index) {
+                MouseButton::Right => tr!("input.right_mouse"),
+                MouseButton::Middle => tr!("input.middle_mouse"),
+                _ => tr!("input.left_mouse"),
+            },
+        }
+    }
+
+    /// Returns a binding of the key or mouse button pressed this frame, if any.
+    pub fn capture() -> Option<Self> {
+        if let Some(key) = get_last_key_pressed() {
+            return Some(Self::key(key));
+        }
+        [MouseButton::Left, MouseButton::Right, MouseButton::Middle]
+            .into_iter()
+            .find(|button| is_mouse_button_pressed(*button))
+            .map(Self::mouse)
+    }
+}
+
+/// Action of the player that can be rebound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Fire,
+    Thrust,
+    Polarity,
+    Pause,
+    Minimap,
+    Dash,
+}
+
+impl Action {
+    /// All rebindable actions in the order they are listed.
+    pub const ALL: [Action; 6] = [
+        Action::Fire,
+        Action::Thrust,
+        Action::Polarity,
+        Action::Dash,
+        Action::Pause,
+        Action::Minimap,
+    ];
+
+    /// Returns the name of the action shown to the user.
+    pub fn name(&self) -> String {
+        tr!(match self {
+            Action::Fire => "action.fire",
+            Action::Thrust => "action.thrust",
+            Action::Polarity => "action.polarity",
+            Action::Pause => "action.pause",
+            Action::Minimap => "action.minimap",
+            Action::Dash => "action.dash",
+        })
+    }
+}
+
+/// Bindings of the player's actions.
+#[derive(Clone, Debug, PartialEq, DeBin, SerBin)]
+pub struct KeyBindings {
+    /// Fires the weapon while held.
+    pub fire: Binding,
+    /// Turns the thrusters on while held.
+    pub thrust: Binding,
+    /// Flips the polarity when pressed.
+    pub polarity: Binding,
+    /// Pauses and unpauses the game when pressed.
+    pub pause: Binding,
+    /// Shows and hides the minimap when pressed.
+    pub minimap: Binding,
+    /// Dashes in the aim direction when pressed.
+    pub dash: Binding,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            fire: Binding::mouse(MouseButton::Right),
+            thrust: Binding::mouse(MouseButton::Left),
+            polarity: Binding::key(KeyCode::A),
+            pause: Binding::key(KeyCode::Escape),
+            minimap: Binding::key(KeyCode::M),
+            //space is taken by the grapple
+            dash: Binding::key(KeyCode::LeftShift),
+        }
+    }
+}
+
+impl KeyBindings {
+    /// Returns the binding of `action`.
+    pub fn get(&self, action: Action) -> &Binding {
+        match action {
+            Action::Fire => &self.fire,
+            Action::Thrust => &self.thrust,
+            Action::Polarity => &self.polarity,
+            Action::Pause => &self.pause,
+            Action::Minimap => &self.minimap,
+            Action::Dash => &self.dash,
+        }
+    }
+
+    /// Returns the mutable binding of `action`.
+    pub fn get_mut(&mut self, action: Action) -> &mut Binding {
+        match action {
+            Action::Fire => &mut self.fire,
+            Action::Thrust => &mut self.thrust,
+            Action::Polarity => &mut self.polarity,
+            Action::Pause => &mut self.pause,
+            Action::Minimap => &mut self.minimap,
+            Action::Dash => &mut self.dash,
+        }
+    }
+}
+
+/// Where the player wants to aim.
+#[derive(Clone, Copy, Debug)]
+pub enum Aim {
+    /// Aim at a point in the world, used by the mouse.
+    Point(Vec2),
+    /// Aim in a direction, used by the gamepad's stick.
+    Direction(Vec2),
+}
+
+/// Player's input in the current frame, independent of the input device.
+#[derive(Clone, Copy, Debug)]
+pub struct PlayerInput {
+    /// Should the thrusters be on?
+    pub thrust: bool,
+    /// Where the player aims.
+    pub aim: Aim,
+    /// Should the weapon fire?
+    pub fire: bool,
+    /// Should the polarity flip?
+    pub flip_polarity: bool,
+    /// Should the player dash?
+    pub dash: bool,
+}
+
+impl PlayerInput {
+    /// Builds the input from the mouse, keyboard and the gamepad.
+    /// The gamepad's stick takes precedence over the mouse while deflected.
+    /// # Arguments
+    /// * `mouse_pos` - position of the mouse in world coordinates
+    /// * `gamepad` - state of the gamepad
+    /// * `bindings` - user's bindings of the keyboard and mouse
+    pub fn read(mouse_pos: Vec2, gamepad: &GamepadState, bindings: &KeyBindings) -> Self {
+        let stick = if gamepad.connected && gamepad.left_stick.length() > STICK_DEADZONE {
+            Some(gamepad.left_stick)
+        } else {
+            None
+        };
+
+        Self {
+            thrust: bindings.thrust.is_down() || stick.is_some(),
+            aim: match stick {
+                Some(stick) => Aim::Direction(stick),
+                None => Aim::Point(mouse_pos),
+            },
+            fire: bindings.fire.is_down() || gamepad.fire,
+            flip_polarity: bindings.polarity.is_pressed() || gamepad.polarity_pressed,
+            dash: bindings.dash.is_pressed(),
+        }
+    }
+}
+
+/// Was there any input from the user this frame?
+///
+/// Must be called at most once per frame, as it consumes the mouse movement.
+pub fn any_input() -> bool {
+    get_last_key_pressed().is_some()
+        || !get_keys_down().is_empty()
+        || is_mouse_button_down(MouseButton::Left)
+        || is_mouse_button_down(MouseButton::Right)
+        || is_mouse_button_down(MouseButton::Middle)
+        || mouse_delta_position() != Vec2::ZERO
+        || mouse_wheel() != (0.0, 0.0)
+}
+
+/// Tracks for how long the user has not given any input.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IdleTracker {
+    /// Time since the last input.
+    pub idle_time: f32,
+}
+
+impl IdleTracker {
+    /// Advances the tracker, resetting it on any input.
+    /// Should be called exactly once per frame.
+    /// # Arguments
+    /// * `gamepad` - state of the gamepad
+    /// * `dt` - delta time
+    pub fn update(&mut self, gamepad: &GamepadState, dt: f32) {
+        if any_input() || gamepad.any_input() {
+            self.idle_time = 0.0;
+        } else {
+            self.idle_time += dt;
+        }
+    }
+}
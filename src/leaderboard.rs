@@ -0,0 +1,360 @@
+//! Table of the best runs and entering initials into it.
+
+use hecs::{Entity, World};
+use macroquad::prelude::*;
+use nanoserde::{DeBin, SerBin};
+
+use crate::{
+    basic::Position,
+    game::state::GameState,
+    locale::tr,
+    menu::{Button, MenuCursor, Title, UiOwner},
+    persist::Persistent,
+    SPACE_WIDTH,
+};
+
+/// Amount of runs kept on the leaderboard.
+pub const LEADERBOARD_SIZE: usize = 10;
+/// Initials runs are entered with before the player changes them.
+const DEFAULT_INITIALS: [u8; 3] = *b"AAA";
+/// Color of the initial being changed.
+const SELECTED_COLOR: Color = YELLOW;
+
+/// Run on the leaderboard.
+#[derive(Clone, Debug, DeBin, SerBin)]
+pub struct ScoreEntry {
+    /// Three letter initials of the player.
+    pub name: String,
+    /// Xp collected during the run.
+    pub score: u32,
+    /// Time the run lasted.
+    pub duration: f32,
+}
+
+impl ScoreEntry {
+    /// Creates an entry with the [DEFAULT_INITIALS].
+    pub fn new(score: u32, duration: f32) -> Self {
+        Self {
+            name: String::from_utf8_lossy(&DEFAULT_INITIALS).into_owned(),
+            score,
+            duration,
+        }
+    }
+}
+
+/// Inserts `entry` into the `board` sorted from the highest score.
+/// Entries tied with older ones rank below them.
+/// The board is kept at most [LEADERBOARD_SIZE] long.
+///
+/// Returns the rank (index) of the entry, [None] when it did not qualify.
+pub fn insert_score(board: &mut Vec<ScoreEntry>, entry: ScoreEntry) -> Option<usize> {
+    if entry.score == 0 {
+        return None;
+    }
+    let rank = board
+        .iter()
+        .position(|other| entry.score > other.score)
+        .unwrap_or(board.len());
+    if rank >= LEADERBOARD_SIZE {
+        return None;
+    }
+    board.insert(rank, entry);
+    board.truncate(LEADERBOARD_SIZE);
+    Some(rank)
+}
+
+//-----------------------------------------------------------------------------
+//COMPONENT PART
+//-----------------------------------------------------------------------------
+
+/// Initials being entered for the leaderboard entry at `rank`.
+#[derive(Clone, Copy, Debug)]
+pub struct NameEntry {
+    /// Rank (index) of the entry on the leaderboard.
+    pub rank: usize,
+    /// Currently entered initials.
+    pub letters: [u8; 3],
+    /// Index of the initial being changed.
+    pub slot: usize,
+}
+
+/// Shows a single initial of the [NameEntry].
+#[derive(Clone, Copy, Debug)]
+pub struct LetterSlot {
+    /// Index of the shown initial.
+    pub slot: usize,
+}
+
+/// Button cycling an initial of the [NameEntry].
+#[derive(Clone, Copy, Debug)]
+pub struct LetterButton {
+    /// Index of the cycled initial.
+    pub slot: usize,
+    /// By how many letters the initial changes.
+    pub step: i8,
+}
+
+/// Marker of the button confirming the [NameEntry].
+#[derive(Clone, Copy, Debug)]
+pub struct ConfirmNameButton;
+
+/// Marker of all parts of the name entry widget.
+#[derive(Clone, Copy, Debug)]
+pub struct NameEntryPart;
+
+//-----------------------------------------------------------------------------
+//ENTITY CREATION
+//-----------------------------------------------------------------------------
+
+/// Spawns the widget entering initials for the leaderboard entry at `rank`.
+/// # Arguments
+/// * `world` - world to spawn the widget into
+/// * `rank` - rank (index) of the entry on the leaderboard
+/// * `y` - vertical position of the initials
+pub fn spawn_name_entry(world: &mut World, rank: usize, y: f32) {
+    let owner = UiOwner::State(GameState::GameOver);
+    let button = Button {
+        width: 50.0,
+        height: 40.0,
+        neutral_color: WHITE,
+        hover_color: LIGHTGRAY,
+        active_color: GRAY,
+        clicked: false,
+    };
+
+    //prompt
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0,
+            y: y - 90.0,
+        },
+        Title {
+            text: tr!("leaderboard.prompt", rank = rank + 1),
+            font: "main_font",
+            size: 30.0,
+            color: WHITE,
+            max_width: None,
+        },
+        owner,
+        NameEntry {
+            rank,
+            letters: DEFAULT_INITIALS,
+            slot: 0,
+        },
+        NameEntryPart,
+    ));
+
+    //initials with their arrows
+    for slot in 0..3 {
+        let x = SPACE_WIDTH / 2.0 + (slot as f32 - 1.0) * 60.0 - 50.0;
+        world.spawn((
+            Position { x, y },
+            Title {
+                text: "A".into(),
+                font: "main_font",
+                size: 50.0,
+                color: WHITE,
+                max_width: None,
+            },
+            owner,
+            LetterSlot { slot },
+            NameEntryPart,
+        ));
+        for (step, text, offset) in [(1, "+", -45.0), (-1, "-", 45.0)] {
+            world.spawn((
+                Position { x, y: y + offset },
+                Title {
+                    text: text.into(),
+                    font: "main_font",
+                    size: 40.0,
+                    color: WHITE,
+                    max_width: None,
+                },
+                owner,
+                button,
+                LetterButton { slot, step },
+                NameEntryPart,
+            ));
+        }
+    }
+
+    //confirmation
+    world.spawn((
+        Position {
+            x: SPACE_WIDTH / 2.0 + 130.0,
+            y,
+        },
+        Title {
+            text: tr!("leaderboard.ok"),
+            font: "main_font",
+            size: 40.0,
+            color: WHITE,
+            max_width: None,
+        },
+        owner,
+        Button {
+            width: 80.0,
+            ..button
+        },
+        ConfirmNameButton,
+        NameEntryPart,
+    ));
+}
+
+//-----------------------------------------------------------------------------
+//SYSTEM PART
+//-----------------------------------------------------------------------------
+
+/// Lets the player enter initials into the [NameEntry].
+///
+/// Left and right choose the initial, up and down cycle it and enter confirms,
+/// the widget's buttons do the same with the mouse.
+/// Every change is written into `persist`, confirming saves it and despawns the widget.
+pub fn name_entry(world: &mut World, persist: &mut Persistent) {
+    //gather clicks
+    let steps: Vec<(usize, i8)> = world
+        .query_mut::<(&Button, &LetterButton)>()
+        .into_iter()
+        .filter(|(_, (button, _))| button.clicked)
+        .map(|(_, (_, letter))| (letter.slot, letter.step))
+        .collect();
+    let confirmed = is_key_pressed(KeyCode::Enter)
+        || world
+            .query_mut::<&Button>()
+            .with::<&ConfirmNameButton>()
+            .into_iter()
+            .any(|(_, button)| button.clicked);
+
+    let Some((_, entry)) = world.query_mut::<&mut NameEntry>().into_iter().next() else {
+        return;
+    };
+
+    //choose the initial
+    if is_key_pressed(KeyCode::Left) {
+        entry.slot = (entry.slot + 2) % 3;
+    }
+    if is_key_pressed(KeyCode::Right) {
+        entry.slot = (entry.slot + 1) % 3;
+    }
+    //cycle it
+    let mut changes = steps;
+    if is_key_pressed(KeyCode::Up) {
+        changes.push((entry.slot, 1));
+    }
+    if is_key_pressed(KeyCode::Down) {
+        changes.push((entry.slot, -1));
+    }
+    for (slot, step) in changes {
+        let letter = (entry.letters[slot] - b'A') as i8 + step;
+        entry.letters[slot] = b'A' + letter.rem_euclid(26) as u8;
+        entry.slot = slot;
+    }
+
+    let entry = *entry;
+    if let Some(score) = persist.leaderboard.get_mut(entry.rank) {
+        score.name = String::from_utf8_lossy(&entry.letters).into_owned();
+    }
+
+    //synchronize the shown initials, keeping their fade
+    for (_, (title, letter)) in world.query_mut::<(&mut Title, &LetterSlot)>() {
+        title.text = (entry.letters[letter.slot] as char).to_string();
+        let color = if letter.slot == entry.slot {
+            SELECTED_COLOR
+        } else {
+            WHITE
+        };
+        title.color = Color {
+            a: title.color.a,
+            ..color
+        };
+    }
+
+    if confirmed {
+        finish_name_entry(world, persist);
+    }
+}
+
+/// Saves the entered initials and despawns the name entry widget.
+/// Does nothing when no initials are being entered.
+pub fn finish_name_entry(world: &mut World, persist: &Persistent) {
+    let parts: Vec<Entity> = world
+        .query_mut::<&NameEntryPart>()
+        .into_iter()
+        .map(|(entity, _)| entity)
+        .collect();
+    if parts.is_empty() {
+        return;
+    }
+    for entity in parts {
+        let _ = world.despawn(entity);
+    }
+    //the key confirming the initials must not press the focused button as well
+    for (_, cursor) in world.query_mut::<&mut MenuCursor>() {
+        cursor.focused = None;
+    }
+    let _ = persist.save();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the scores of the `board` in order.
+    fn scores(board: &[ScoreEntry]) -> Vec<u32> {
+        board.iter().map(|entry| entry.score).collect()
+    }
+
+    /// Returns a board full of entries scoring 10 to [LEADERBOARD_SIZE] * 10.
+    fn full_board() -> Vec<ScoreEntry> {
+        (1..=LEADERBOARD_SIZE as u32)
+            .rev()
+            .map(|i| ScoreEntry::new(i * 10, 0.0))
+            .collect()
+    }
+
+    #[test]
+    fn sorts_from_highest() {
+        let mut board = Vec::new();
+        assert_eq!(insert_score(&mut board, ScoreEntry::new(20, 0.0)), Some(0));
+        assert_eq!(insert_score(&mut board, ScoreEntry::new(50, 0.0)), Some(0));
+        assert_eq!(insert_score(&mut board, ScoreEntry::new(10, 0.0)), Some(2));
+        assert_eq!(insert_score(&mut board, ScoreEntry::new(30, 0.0)), Some(1));
+        assert_eq!(scores(&board), [50, 30, 20, 10]);
+    }
+
+    #[test]
+    fn ties_rank_below_older() {
+        let mut board = Vec::new();
+        insert_score(&mut board, ScoreEntry::new(30, 1.0));
+        insert_score(&mut board, ScoreEntry::new(10, 1.0));
+        assert_eq!(insert_score(&mut board, ScoreEntry::new(30, 2.0)), Some(1));
+        assert_eq!(scores(&board), [30, 30, 10]);
+        //the older entry stays on top
+        assert_eq!(board[0].duration, 1.0);
+        assert_eq!(board[1].duration, 2.0);
+    }
+
+    #[test]
+    fn full_board_drops_lowest() {
+        let mut board = full_board();
+        assert_eq!(insert_score(&mut board, ScoreEntry::new(55, 0.0)), Some(5));
+        assert_eq!(board.len(), LEADERBOARD_SIZE);
+        assert_eq!(board.last().unwrap().score, 20);
+        assert_eq!(board[5].score, 55);
+    }
+
+    #[test]
+    fn not_qualifying() {
+        let mut board = full_board();
+        //lower than the last entry
+        assert_eq!(insert_score(&mut board, ScoreEntry::new(5, 0.0)), None);
+        //tied with the last entry
+        assert_eq!(insert_score(&mut board, ScoreEntry::new(10, 0.0)), None);
+        assert_eq!(scores(&board), scores(&full_board()));
+
+        //runs without any score never qualify
+        let mut empty = Vec::new();
+        assert_eq!(insert_score(&mut empty, ScoreEntry::new(0, 10.0)), None);
+        assert!(empty.is_empty());
+    }
+}
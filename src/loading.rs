@@ -0,0 +1,176 @@
+//! Loading of the assets one per frame, so that the progress can be shown meanwhile.
+
+use std::collections::VecDeque;
+
+use hecs::World;
+use macroquad::prelude::*;
+
+use crate::{basic::render::AssetManager, locale::tr, SPACE_HEIGHT, SPACE_WIDTH};
+
+/// Width of the progress bar.
+const BAR_WIDTH: f32 = 400.0;
+/// Height of the progress bar.
+const BAR_HEIGHT: f32 = 8.0;
+/// Color of the loaded part of the progress bar.
+const BAR_COLOR: Color = WHITE;
+/// Color of the part of the progress bar still loading.
+const BAR_BACKGROUND_COLOR: Color = DARKGRAY;
+/// Size of the game title above the progress bar.
+const TITLE_SIZE: f32 = 60.0;
+/// Size of the lines listing the assets which failed to load.
+const ERROR_SIZE: f32 = 20.0;
+
+/// Kind of an asset, deciding how it is loaded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AssetKind {
+    /// Texture of sprites.
+    Texture,
+    /// Sound effect.
+    Sound,
+    /// Music track, skipped when missing.
+    Music,
+    /// Font of texts.
+    Font,
+}
+
+/// Asset waiting to be loaded.
+#[derive(Clone, Copy, Debug)]
+pub struct AssetRequest {
+    /// Kind of the asset.
+    pub kind: AssetKind,
+    /// Id the asset is stored under in the [AssetManager].
+    pub id: &'static str,
+    /// Path of the asset's file.
+    pub path: &'static str,
+}
+
+/// Resource of the loading screen, loading the requested assets one by one.
+#[derive(Clone, Debug)]
+pub struct AssetLoader {
+    /// Assets still waiting to be loaded.
+    queue: VecDeque<AssetRequest>,
+    /// Amount of all the requested assets.
+    total: usize,
+    /// Paths of the assets which could not be loaded.
+    failed: Vec<&'static str>,
+}
+
+impl AssetLoader {
+    /// Creates a loader of all the `requests`, loaded in their order.
+    pub fn new(requests: impl IntoIterator<Item = AssetRequest>) -> Self {
+        let queue: VecDeque<_> = requests.into_iter().collect();
+        Self {
+            total: queue.len(),
+            queue,
+            failed: Vec::new(),
+        }
+    }
+
+    /// Returns the fraction of the assets loaded so far, 0.0 to 1.0.
+    pub fn progress(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            1.0 - self.queue.len() as f32 / self.total as f32
+        }
+    }
+
+    /// Loads the next asset into `assets`.
+    /// Assets which fail to load are remembered, except for music which is skipped.
+    ///
+    /// Returns whether all the assets are loaded successfully.
+    pub async fn load_next(&mut self, assets: &mut AssetManager) -> bool {
+        if let Some(request) = self.queue.pop_front() {
+            let result = match request.kind {
+                AssetKind::Texture => assets.load_texture(request.id, request.path).await,
+                AssetKind::Sound | AssetKind::Music => {
+                    assets.load_sound(request.id, request.path).await
+                }
+                AssetKind::Font => assets.load_font(request.id, request.path).await,
+            };
+            if result.is_err() {
+                if request.kind == AssetKind::Music {
+                    eprintln!(
+                        "Music `{}` could not be loaded, it is skipped!",
+                        request.path
+                    );
+                } else {
+                    eprintln!("Asset `{}` could not be loaded!", request.path);
+                    self.failed.push(request.path);
+                }
+            }
+        }
+        self.queue.is_empty() && self.failed.is_empty()
+    }
+}
+
+//-----------------------------------------------------------------------------
+//SYSTEM PART
+//-----------------------------------------------------------------------------
+
+/// Loads the next asset of the [AssetLoader] in the world.
+///
+/// Returns whether all the assets are loaded successfully.
+/// It never happens while some of them failed, the loading screen lists them instead.
+pub async fn load_next(world: &mut World, assets: &mut AssetManager) -> bool {
+    let Some((_, loader)) = world.query_mut::<&mut AssetLoader>().into_iter().next() else {
+        return false;
+    };
+    loader.load_next(assets).await
+}
+
+/// Renders the game title, the progress of the [AssetLoader]
+/// and the paths of the assets which failed to load.
+pub fn render_loading(world: &mut World, assets: &AssetManager) {
+    let Some((_, loader)) = world.query_mut::<&AssetLoader>().into_iter().next() else {
+        return;
+    };
+    //the font may not be loaded yet
+    let font = assets.get_font("main_font");
+    let center = vec2(SPACE_WIDTH / 2.0, SPACE_HEIGHT / 2.0);
+
+    //draw title
+    let title = tr!("menu.title");
+    let dimensions = measure_text(&title, font, TITLE_SIZE as u16, 1.0);
+    draw_text_ex(
+        &title,
+        center.x - dimensions.width / 2.0,
+        center.y - 40.0,
+        TextParams {
+            font,
+            font_size: TITLE_SIZE as u16 * 2,
+            font_scale: 0.5,
+            color: WHITE,
+            ..Default::default()
+        },
+    );
+
+    //draw progress bar
+    let left = center.x - BAR_WIDTH / 2.0;
+    draw_rectangle(left, center.y, BAR_WIDTH, BAR_HEIGHT, BAR_BACKGROUND_COLOR);
+    draw_rectangle(
+        left,
+        center.y,
+        BAR_WIDTH * loader.progress(),
+        BAR_HEIGHT,
+        BAR_COLOR,
+    );
+
+    //list the failed assets
+    for (i, path) in loader.failed.iter().enumerate() {
+        let text = tr!("loading.failed", path = path);
+        let dimensions = measure_text(&text, font, ERROR_SIZE as u16, 1.0);
+        draw_text_ex(
+            &text,
+            center.x - dimensions.width / 2.0,
+            center.y + 50.0 + i as f32 * ERROR_SIZE * 1.2,
+            TextParams {
+                font,
+                font_size: ERROR_SIZE as u16 * 2,
+                font_scale: 0.5,
+                color: RED,
+                ..Default::default()
+            },
+        );
+    }
+}
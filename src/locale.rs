@@ -0,0 +1,133 @@
+//! Translations of the UI strings.
+//!
+//! Every language has a file in `res/locale` with one string per line, e.g.
+//! ```text
+//! # comment
+//! menu.start = START
+//! score.value = Score: {score}
+//! ```
+//! Keys missing in the chosen language fall back to English,
+//! keys missing even there are shown as they are.
+
+use std::{cell::RefCell, collections::HashMap};
+
+use macroquad::prelude::*;
+
+/// Languages the user can choose from, by their codes and names shown in the settings.
+/// English comes first, it is the fallback of the others.
+pub const LANGUAGES: [(&str, &str); 2] = [("en", "ENGLISH"), ("cs", "ČEŠTINA")];
+
+/// Directory of the translation files.
+const LOCALE_DIR: &str = "res/locale";
+
+thread_local! {
+    /// Translations of all languages and the chosen one.
+    static LOCALE: RefCell<Locale> = RefCell::new(Locale::default());
+}
+
+/// Translates `key` into the chosen language.
+///
+/// Placeholders in braces are replaced by the named arguments, e.g.
+/// `tr!("score.value", score = 420)` fills in `{score}`.
+macro_rules! tr {
+    ($key:expr) => {
+        $crate::locale::translate($key, &[])
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::locale::translate($key, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+
+pub(crate) use tr;
+
+/// Strings of every language, by their keys.
+#[derive(Clone, Debug, Default)]
+pub struct Locale {
+    /// Strings of the languages, in the order of [LANGUAGES].
+    strings: Vec<HashMap<String, String>>,
+    /// Index of the chosen language in [LANGUAGES].
+    language: usize,
+}
+
+impl Locale {
+    /// Parses the `key = value` lines of a translation file.
+    /// Empty lines and lines starting with `#` are skipped.
+    pub fn parse(text: &str) -> HashMap<String, String> {
+        text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let Some((key, value)) = line.split_once('=') else {
+                    eprintln!("Locale line `{line}` is not `key = value`, it is skipped!");
+                    return None;
+                };
+                Some((key.trim().to_owned(), value.trim().to_owned()))
+            })
+            .collect()
+    }
+
+    /// Returns the string of `key` in the chosen language,
+    /// falling back to English and then to the key itself.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        [self.language, 0]
+            .into_iter()
+            .find_map(|language| self.strings.get(language)?.get(key))
+            .map_or(key, String::as_str)
+    }
+}
+
+//-----------------------------------------------------------------------------
+//SYSTEM PART
+//-----------------------------------------------------------------------------
+
+/// Translates `key` into the chosen language and fills in the placeholders.
+/// Used by [tr].
+/// # Arguments
+/// * `key` - key of the string
+/// * `args` - names of the placeholders and their values
+pub fn translate(key: &str, args: &[(&str, String)]) -> String {
+    LOCALE.with_borrow(|locale| {
+        let mut text = locale.get(key).to_owned();
+        for (name, value) in args {
+            text = text.replace(&format!("{{{name}}}"), value);
+        }
+        text
+    })
+}
+
+/// Returns the name of the language at `index` of [LANGUAGES], shown in the settings.
+pub fn language_name(index: usize) -> &'static str {
+    LANGUAGES
+        .get(index)
+        .map_or(LANGUAGES[0].1, |(_, name)| name)
+}
+
+/// Chooses the language at `index` of [LANGUAGES].
+/// Unknown languages fall back to English.
+pub fn set_language(index: usize) {
+    LOCALE.with_borrow_mut(|locale| {
+        locale.language = if index < LANGUAGES.len() { index } else { 0 };
+    });
+}
+
+/// Loads the translation files of all languages and chooses the one at `language`.
+///
+/// Missing files leave their language empty, so it falls back to English.
+pub async fn load(language: usize) {
+    let mut strings = Vec::with_capacity(LANGUAGES.len());
+    for (code, _) in LANGUAGES {
+        let path = format!("{LOCALE_DIR}/{code}.txt");
+        strings.push(match load_string(&path).await {
+            Ok(text) => Locale::parse(&text),
+            Err(_) => {
+                eprintln!("Locale `{path}` could not be loaded, it falls back to English!");
+                HashMap::new()
+            }
+        });
+    }
+    LOCALE.set(Locale {
+        strings,
+        language: 0,
+    });
+    set_language(language);
+}
@@ -3,32 +3,77 @@
 //! It also handles the main loop, update and render of Gamestates.
 //!
 
+pub mod banner;
 pub mod basic;
+pub mod changelog;
+mod config;
+mod cursor;
+mod daily;
+pub mod debug;
 pub mod enemy;
+#[cfg(test)]
+mod feel;
+mod fields;
 pub mod game;
+#[cfg(not(target_arch = "wasm32"))]
+mod gamepad;
+pub mod ghost;
+pub mod heatmap;
+mod hints;
+mod indicator;
+pub mod input;
+pub mod leaderboard;
+mod loading;
+mod locale;
 pub mod menu;
+mod minimap;
+mod music;
 pub mod persist;
+pub mod pickup;
 mod player;
 pub mod projectile;
 pub mod score;
+mod spectate;
+mod tutorial;
+mod upgrade;
+mod visibility;
+mod window;
 pub mod xp;
 
-use basic::{fx::FxManager, render::AssetManager};
+#[cfg(target_arch = "wasm32")]
+use basic::rumble::NoRumble;
+use basic::{
+    audio,
+    clock::{FixedClock, FIXED_DT},
+    fx::FxManager,
+    motion,
+    render::{render_letterbox, AssetManager, WorldCanvas, BACKGROUND_COLOR},
+    rumble,
+};
+use cursor::Cursor;
+use debug::DebugOverlay;
 use enemy::{
+    boss::BOSS_TEX,
     charged::ASTEROID_OUTLINE_TEX,
     follower::{FOLLOWER_TEX_NEGATIVE, FOLLOWER_TEX_NEUTRAL, FOLLOWER_TEX_POSITIVE},
-    mine::{MINE_TEX_NEGATIVE, MINE_TEX_NEUTRAL, MINE_TEX_POSITIVE},
+    mine::{MINE_LIGHT_TEX, MINE_TEX_NEGATIVE, MINE_TEX_NEUTRAL, MINE_TEX_POSITIVE},
     ASTEROID_TEX_NEGATIVE, ASTEROID_TEX_NEUTRAL, ASTEROID_TEX_POSITIVE, BIG_ASTEROID_TEX_NEGATIVE,
     BIG_ASTEROID_TEX_POSITIVE,
 };
 use game::state::GameState;
+#[cfg(target_arch = "wasm32")]
+use input::NoGamepad;
+use input::{GamepadBackend, IdleTracker};
+use loading::{AssetKind, AssetLoader, AssetRequest};
 use macroquad::prelude::*;
+use music::{MusicController, COMBAT_TRACK, GAME_OVER_STING, MENU_TRACK};
 use persist::Persistent;
-use player::{PLAYER_TEX_NEGATIVE, PLAYER_TEX_POSITIVE};
+use player::{PLAYER_FLAME_TEX, PLAYER_TEX_NEGATIVE, PLAYER_TEX_POSITIVE};
 use projectile::{
     PROJ_MED_TEX_NEG, PROJ_MED_TEX_NEUTRAL, PROJ_MED_TEX_POS, PROJ_SMALL_TEX_NEG,
     PROJ_SMALL_TEX_POS,
 };
+use visibility::Visibility;
 
 /// Internal logical space width.
 /// Values outside this range are not rendered.
@@ -37,20 +82,24 @@ pub const SPACE_WIDTH: f32 = 1280.0;
 /// Values outside this range are not rendered.
 pub const SPACE_HEIGHT: f32 = 720.0;
 
+/// Extra time each frame takes when the game is throttled due to idling.
+/// Reduces the frame rate to about 10 FPS.
+const IDLE_FRAME_MILLIS: u64 = 100;
+
+/// Returns the camera that maps the logical space onto the screen.
+/// The space is letterboxed to keep its aspect ratio.
+pub fn world_camera() -> Camera2D {
+    basic::arena::rect_camera(Rect::new(0.0, 0.0, SPACE_WIDTH, SPACE_HEIGHT))
+}
+
 /// Returns the position of the mouse in world coordinates.
 pub fn world_mouse_pos() -> Vec2 {
     let (mx, my) = mouse_position();
-    let camera = &Camera2D::from_display_rect(Rect {
-        x: 0.0,
-        y: SPACE_HEIGHT,
-        w: SPACE_WIDTH,
-        h: -SPACE_HEIGHT,
-    });
-    camera.screen_to_world(vec2(mx, my))
+    world_camera().screen_to_world(vec2(mx, my))
 }
 
 /// Texture assets id, location, lookup table.
-const TEXTURES: [(&str, &str); 19] = [
+const TEXTURES: [(&str, &str); 22] = [
     (ASTEROID_TEX_NEUTRAL, "res/asteroid.png"),
     (ASTEROID_TEX_POSITIVE, "res/asteroid_plus.png"),
     (ASTEROID_TEX_NEGATIVE, "res/asteroid_minus.png"),
@@ -59,6 +108,7 @@ const TEXTURES: [(&str, &str); 19] = [
     (BIG_ASTEROID_TEX_NEGATIVE, "res/asteroid_big_minus.png"),
     (PLAYER_TEX_POSITIVE, "res/player_plus.png"),
     (PLAYER_TEX_NEGATIVE, "res/player_minus.png"),
+    (PLAYER_FLAME_TEX, "res/thruster_flame.png"),
     (PROJ_SMALL_TEX_NEG, "res/smal_proj_minus.png"),
     (PROJ_SMALL_TEX_POS, "res/smal_proj_plus.png"),
     (PROJ_MED_TEX_NEUTRAL, "res/medium_proj_neutral.png"),
@@ -70,89 +120,407 @@ const TEXTURES: [(&str, &str); 19] = [
     (MINE_TEX_NEUTRAL, "res/mine_neutral.png"),
     (MINE_TEX_POSITIVE, "res/mine_plus.png"),
     (MINE_TEX_NEGATIVE, "res/mine_minus.png"),
+    (MINE_LIGHT_TEX, "res/mine_light.png"),
+    (BOSS_TEX, "res/asteroid_big.png"),
+];
+
+/// Texture IDs referenced by [Sprite](basic::render::Sprite)s of all entities.
+/// Every one of them must be present in [TEXTURES].
+const SPRITE_TEXTURES: [&[&str]; 7] = [
+    &enemy::ASTEROID_TEXTURES,
+    &enemy::boss::BOSS_TEXTURES,
+    &enemy::charged::CHARGED_TEXTURES,
+    &enemy::follower::FOLLOWER_TEXTURES,
+    &enemy::mine::MINE_TEXTURES,
+    &player::PLAYER_TEXTURES,
+    &projectile::PROJECTILE_TEXTURES,
 ];
 
 /// Sound assets id, location, lookup table.
-const SOUNDS: [(&str, &str); 3] = [
+const SOUNDS: [(&str, &str); 4] = [
     ("player_jet", "res/sound/movement.wav"),
     ("knockback", "res/sound/boing.wav"),
     ("pew_pew", "res/sound/pew_pew.wav"),
+    ("heartbeat", "res/sound/heartbeat.wav"),
+];
+
+/// Music assets id, location, lookup table.
+/// Missing tracks are skipped, the game plays without them.
+const MUSIC: [(&str, &str); 3] = [
+    (MENU_TRACK, "res/music/menu.wav"),
+    (COMBAT_TRACK, "res/music/combat.wav"),
+    (GAME_OVER_STING, "res/music/game_over.wav"),
+];
+
+/// Fonts of the texts, by their ids and paths.
+const FONTS: [(&str, &str); 1] = [
+    ("main_font", "res/NotoSans-Regular.ttf"),
+    //("main_font", "res/ShantellSans-Medium.ttf"),
 ];
 
 /// Returns requested properties of the window.
-/// It sets the title and window size.
+/// It sets the title and the window size and mode preferred by the user.
 fn conf() -> Conf {
+    let window = Persistent::peek()
+        .map(|persist| persist.window)
+        .unwrap_or_default();
     Conf {
-        window_title: "Magnet fury".to_owned(),
-        window_width: SPACE_WIDTH as i32,
-        window_height: SPACE_HEIGHT as i32,
+        window_title: format!("Magnet fury {}", changelog::CURRENT_VERSION),
+        window_width: window.width as i32,
+        window_height: window.height as i32,
+        fullscreen: window.fullscreen,
+        icon: window_icon(),
         ..Default::default()
     }
 }
 
+/// Creates the window icon from the embedded player texture.
+fn window_icon() -> Option<miniquad::conf::Icon> {
+    let image = Image::from_file_with_format(
+        include_bytes!("../res/player_plus.png"),
+        Some(ImageFormat::Png),
+    )
+    .ok()?;
+    Some(miniquad::conf::Icon {
+        small: icon_data(&image),
+        medium: icon_data(&image),
+        big: icon_data(&image),
+    })
+}
+
+/// Samples `image` into a square RGBA icon of `BYTES` bytes, nearest neighbour.
+fn icon_data<const BYTES: usize>(image: &Image) -> [u8; BYTES] {
+    let size = ((BYTES / 4) as f32).sqrt() as usize;
+    let mut data = [0; BYTES];
+    for y in 0..size {
+        for x in 0..size {
+            let src_x = x * image.width as usize / size;
+            let src_y = y * image.height as usize / size;
+            let src = (src_y * image.width as usize + src_x) * 4;
+            let dst = (y * size + x) * 4;
+            data[dst..dst + 4].copy_from_slice(&image.bytes[src..src + 4]);
+        }
+    }
+    data
+}
+
 /// Entry point of the application.
 #[macroquad::main(conf)]
 async fn main() {
     //load persitent as a resource
-    let mut persist = Persistent::load().await.unwrap_or_default();
+    let mut persist = Persistent::load().unwrap_or_default();
 
-    //load assets to render
-    let mut assets = AssetManager::default();
-    for (asset_id, asset_path) in TEXTURES {
-        assets.load_texture(asset_id, asset_path).await.unwrap();
-    }
-    for (asset_id, asset_path) in SOUNDS {
-        assets.load_sound(asset_id, asset_path).await.unwrap();
-    }
+    //load tuning values
+    config::load().await;
+    locale::load(persist.settings.language as usize).await;
 
-    //load font
-    assets
-        .load_font("main_font", "res/NotoSans-Regular.ttf")
-        //.load_font("main_font", "res/ShantellSans-Medium.ttf")
-        .await
-        .unwrap();
+    //assets to render, loaded one per frame by the loading screen
+    let mut assets = AssetManager::default();
 
     //init particle system
     let mut fx = FxManager::new(1024);
+    //init world canvas
+    let mut canvas = WorldCanvas::default();
 
     //init world
     let mut world = hecs::World::default();
     //init events
     let mut events = hecs::World::default();
     //init game state
-    let mut state = GameState::MainMenu;
+    let mut state = GameState::Loading;
+    //init idle detection
+    let mut idle = IdleTracker::default();
+    //init debug overlay, hidden until toggled
+    let mut debug_overlay = DebugOverlay::default();
+    //init gamepad, it plays the rumble too, the web has no backend yet
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut gamepad_backend = gamepad::GilrsBackend::new();
+    #[cfg(target_arch = "wasm32")]
+    let mut gamepad_backend = NoGamepad;
+    //init minimize detection
+    let mut visibility = Visibility::default();
+    //init game cursor, replacing the OS one
+    let mut cursor = Cursor::default();
+    //init music, following the game state
+    let mut music = MusicController::default();
+    //init fixed timestep of runs
+    let mut clock = FixedClock::default();
 
-    //init game
-    game::init::init_main_menu(&mut world);
+    //init loading screen, the font comes first so that the progress is readable
+    world.spawn((AssetLoader::new(
+        FONTS
+            .into_iter()
+            .map(|(id, path)| (AssetKind::Font, id, path))
+            .chain(TEXTURES.map(|(id, path)| (AssetKind::Texture, id, path)))
+            .chain(SOUNDS.map(|(id, path)| (AssetKind::Sound, id, path)))
+            .chain(MUSIC.map(|(id, path)| (AssetKind::Music, id, path)))
+            .map(|(kind, id, path)| AssetRequest { kind, id, path }),
+    ),));
 
     loop {
         let dt = get_frame_time();
+
+        // load the next asset, entering the main menu once all of them are loaded
+        if state == GameState::Loading && loading::load_next(&mut world, &mut assets).await {
+            assets.load_flash_material();
+            //verify every referenced texture can be resolved
+            for missing in assets.missing_textures(SPRITE_TEXTURES.into_iter().flatten().copied()) {
+                eprintln!("Texture `{missing}` is used by a sprite, but it was never loaded!");
+            }
+
+            //init game
+            game::init::init_main_menu(&mut world, &persist);
+            state = GameState::MainMenu;
+
+            //show what's new once after an update
+            if changelog::is_unseen(&persist) {
+                changelog::mark_seen(&mut persist);
+                game::init::init_changelog(&mut world);
+                game::init::despawn_ui(&mut world, state);
+                state = GameState::Changelog;
+            }
+        }
+
+        // pause when minimized or suspended, before the long frame is simulated
+        let suspended = visibility.update(dt);
+        if suspended {
+            state.auto_pause(&mut world, &mut fx, &assets);
+        }
+
         //UPDATE WORLD
 
+        // read the gamepad once per frame
+        let gamepad = gamepad_backend.poll();
+        input::begin_frame();
+
+        // apply particle preferences
+        fx.apply_settings(&persist.settings);
+        // apply volume preferences
+        assets.apply_settings(&persist.settings);
+        // hear the world from the player
+        audio::place_listener(&mut world, assets.audio());
+
+        // reload changed textures
+        assets.reload_dirty(config::RELOAD_KEY).await;
+
         // update current game state
-        state.update(&mut world, &mut events, &assets, dt, &mut fx, &mut persist);
+        // runs are simulated in fixed steps, so that they play the same at any frame rate
+        if state.is_played() {
+            let pressed =
+                input::any_pressed() || gamepad.polarity_pressed || gamepad.confirm_pressed;
+            let steps = clock.advance(dt, pressed);
+            let mut gamepad = gamepad;
+            let played = state;
+            for _ in 0..steps {
+                // events of the last step are kept for rendering
+                events.clear();
+                state.update(
+                    &mut world,
+                    &mut events,
+                    &assets,
+                    FIXED_DT,
+                    &mut fx,
+                    &mut persist,
+                    &gamepad,
+                );
+                debug_overlay.record_events(&events);
+                // presses are handled by the first step only
+                input::mark_presses_handled();
+                gamepad.polarity_pressed = false;
+                gamepad.confirm_pressed = false;
+                if state != played {
+                    clock.reset();
+                    break;
+                }
+            }
+        } else {
+            clock.reset();
+            state.update(
+                &mut world,
+                &mut events,
+                &assets,
+                dt,
+                &mut fx,
+                &mut persist,
+                &gamepad,
+            );
+            debug_overlay.record_events(&events);
+        }
 
-        //CLEAR ALL EVENTS
-        events.clear();
+        // switch between fullscreen and windowed
+        window::toggle_fullscreen(&mut persist);
+
+        // show stats and inspect entities
+        debug_overlay.update(&mut world);
+        // reload tuning values
+        config::hot_reload().await;
+
+        // show the OS cursor when the game one is not wanted
+        cursor.update(state, suspended);
+        // crossfade the music of the current state
+        music.update(state, &assets, &persist.settings, dt);
+
+        // handle idle user
+        idle.update(&gamepad, dt);
+        let throttle = state.idle(&mut world, &mut fx, idle.idle_time, &persist);
 
         //RENDERING PHASE
-        clear_background(Color::new(0.0, 0.05, 0.1, 1.0));
+        // nothing to render into when minimized
+        if visibility.visible {
+            clear_background(BACKGROUND_COLOR);
 
-        //UPDATE VISUALS
-        // set camera so that we have consistent space size
-        set_camera(&Camera2D::from_display_rect(Rect {
-            x: 0.0,
-            y: SPACE_HEIGHT,
-            w: SPACE_WIDTH,
-            h: -SPACE_HEIGHT,
-        }));
+            //UPDATE VISUALS
+            // set camera so that we have consistent space size
+            set_camera(&world_camera());
 
-        // step particle simulation
-        fx.update_particles(dt);
+            // step particle simulation, slowed down with the world
+            fx.update_particles(dt * game::death::time_scale(&world));
 
-        // render current state
-        state.render(&mut world, &mut events, &assets, dt, &mut fx, &persist);
+            // render current state, runs between their last two steps
+            let alpha = if state.is_played() {
+                clock.alpha()
+            } else {
+                1.0
+            };
+            let stash = motion::interpolate_transforms(&mut world, alpha);
+            state.render(
+                &mut world,
+                &mut events,
+                &assets,
+                dt,
+                &mut fx,
+                &persist,
+                &mut canvas,
+            );
+            motion::restore_transforms(&mut world, stash);
+            debug_overlay.render(&mut world, &assets, &fx, &persist.settings);
+            // hide everything outside of the space
+            render_letterbox();
+            // cursor goes above everything
+            set_camera(&world_camera());
+            cursor.render(&mut world);
+        }
+
+        //CLEAR ALL EVENTS
+        // after rendering, so that the debug drawing sees them
+        events.clear();
+
+        // play requested rumble
+        #[cfg(not(target_arch = "wasm32"))]
+        let rumble_backend = &mut gamepad_backend;
+        #[cfg(target_arch = "wasm32")]
+        let rumble_backend = &mut NoRumble;
+        rumble::play_rumble(&mut world, rumble_backend, &persist.settings, dt);
+
+        // save resources when nobody is watching
+        if throttle || !visibility.visible {
+            #[cfg(not(target_arch = "wasm32"))]
+            std::thread::sleep(std::time::Duration::from_millis(IDLE_FRAME_MILLIS));
+        }
 
         next_frame().await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use hecs::{CommandBuffer, World};
+
+    use super::*;
+    use crate::{
+        basic::{
+            arena::Arena,
+            render::{AnimatedSprite, Sprite},
+            Team,
+        },
+        enemy::{
+            boss, charged, create_asteroid, create_big_asteroid, create_charged_asteroid, flipper,
+            follower, mine, splitter, turret,
+        },
+        projectile::ProjectileType,
+    };
+
+    /// Is the texture `id` loaded at startup?
+    fn loaded(id: &str) -> bool {
+        TEXTURES.iter().any(|(texture, _)| *texture == id)
+    }
+
+    #[test]
+    fn sprite_textures_are_loaded() {
+        for id in SPRITE_TEXTURES.into_iter().flatten() {
+            assert!(loaded(id), "texture `{id}` is not in TEXTURES");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "embed-assets")]
+    fn startup_assets_are_embedded() {
+        let paths = TEXTURES.iter().chain(&SOUNDS).chain(&MUSIC).chain(&FONTS);
+        for (_, path) in paths {
+            let embedded =
+                basic::embed::get(path).unwrap_or_else(|| panic!("`{path}` is not embedded"));
+            assert_eq!(
+                embedded,
+                std::fs::read(path).unwrap(),
+                "embedded `{path}` differs from the file"
+            );
+        }
+    }
+
+    #[test]
+    fn created_entities_use_loaded_textures() {
+        let mut world = World::new();
+        let mut cmd = CommandBuffer::new();
+        let mut rng = fastrand::Rng::with_seed(1485);
+        let (pos, dir) = (vec2(100.0, 100.0), Vec2::X);
+
+        world.spawn(player::new_entity(&Arena::STANDARD));
+        world.spawn(create_asteroid(pos, dir).build());
+        for charge in [-1, 1] {
+            world.spawn(create_charged_asteroid(pos, dir, charge, &mut rng).build());
+            world.spawn(create_big_asteroid(pos, dir, charge, &mut rng).build());
+            world.spawn(flipper::create_flipper(pos, dir, charge, &mut rng).build());
+            charged::create_supercharged_asteroid(pos, dir, charge, &mut rng)(&world, &mut cmd);
+            boss::create_boss(pos, dir, charge, &mut rng)(&world, &mut cmd);
+            world.spawn(projectile::create_projectile(
+                pos,
+                dir,
+                1.0,
+                Team::Player,
+                ProjectileType::Small { charge },
+            ));
+        }
+        for charge in [-1, 0, 1] {
+            world.spawn(follower::create_follower(pos, dir, charge, &mut rng).build());
+            world.spawn(mine::create_mine(pos, dir, charge, &mut rng).build());
+            world.spawn(projectile::create_projectile(
+                pos,
+                dir,
+                1.0,
+                Team::Enemy,
+                ProjectileType::Medium { charge },
+            ));
+        }
+        for generation in 0..3 {
+            world.spawn(splitter::create_splitter_asteroid(pos, dir, generation, &mut rng).build());
+        }
+        turret::create_turret(pos, Vec2::ZERO, &mut rng)(&world, &mut cmd);
+        cmd.run_on(&mut world);
+
+        let mut textures: Vec<_> = world
+            .query_mut::<&Sprite>()
+            .into_iter()
+            .map(|(_, sprite)| sprite.texture)
+            .collect();
+        textures.extend(
+            world
+                .query_mut::<&AnimatedSprite>()
+                .into_iter()
+                .map(|(_, sprite)| sprite.texture),
+        );
+        assert!(!textures.is_empty());
+        for id in textures {
+            assert!(loaded(id), "texture `{id}` is not in TEXTURES");
+        }
+    }
+}
@@ -1,11 +1,16 @@
 //! Contains components required to render UI.
 
-use hecs::World;
+use hecs::{Component, Entity, World};
 use macroquad::prelude::*;
 
 use crate::{
-    basic::{render::AssetManager, Position},
+    basic::{arena::Arena, render::AssetManager, Position},
     game::state::GameState,
+    input::GamepadState,
+    input::{Action, Binding, KeyBindings},
+    leaderboard::NameEntry,
+    locale::{self, tr, LANGUAGES},
+    persist::{Persistent, Settings, RENDER_SCALES},
     world_mouse_pos,
 };
 
@@ -21,6 +26,62 @@ pub struct Title {
     pub size: f32,
     /// Color of the text.
     pub color: Color,
+    /// Max width of the text, longer text is wrapped into more lines.
+    /// The text is shrunk if even a single word does not fit.
+    /// Single line of any width if [None].
+    pub max_width: Option<f32>,
+}
+
+impl Title {
+    /// Splits the text into the lines it is rendered as.
+    ///
+    /// Returns the lines, the size they are rendered with and their bounding box.
+    /// # Arguments
+    /// * `font` - font the title is rendered with
+    pub fn layout(&self, font: Option<&Font>) -> (Vec<String>, f32, Vec2) {
+        let Some(max_width) = self.max_width else {
+            let dimensions = measure_text(&self.text, font, self.size as u16, 1.0);
+            return (
+                vec![self.text.clone()],
+                self.size,
+                vec2(dimensions.width, dimensions.height),
+            );
+        };
+        //shrink the text until its widest word fits
+        let widest = self
+            .text
+            .split_whitespace()
+            .map(|word| measure_text(word, font, self.size as u16, 1.0).width)
+            .fold(0.0, f32::max);
+        let size = if widest > max_width {
+            (self.size * max_width / widest).floor().max(1.0)
+        } else {
+            self.size
+        };
+        let lines = wrap_text(&self.text, font, size, max_width);
+        let width = lines
+            .iter()
+            .map(|line| measure_text(line, font, size as u16, 1.0).width)
+            .fold(0.0, f32::max);
+        let height = size * TITLE_LINE_SPACING * lines.len() as f32;
+        (lines, size, vec2(width, height))
+    }
+}
+
+/// Height of a line of wrapped [Title]s, relative to their size.
+const TITLE_LINE_SPACING: f32 = 1.2;
+
+/// Game state owning a UI entity.
+///
+/// [Title]s are only rendered for their owner and
+/// state transitions despawn the UI owned by the departing state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UiOwner {
+    /// Owned by a single game state.
+    State(GameState),
+    /// HUD pieces valid in all states of a run,
+    /// [Running](GameState::Running), [Paused](GameState::Paused) and [GameOver](GameState::GameOver).
+    Always,
 }
 
 /// Detects mouse interactions (hovering and activation) and changes color
@@ -41,67 +102,817 @@ pub struct Button {
     pub clicked: bool,
 }
 
+/// Resource tracking the [Button] focused by keyboard navigation.
+/// Spawned by [button_colors] when missing.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MenuCursor {
+    /// Focused button, if any.
+    pub focused: Option<Entity>,
+    /// Position of the mouse in the previous frame.
+    /// Focus follows the mouse only while it moves.
+    last_mouse: Vec2,
+}
+
+/// Keys moving the focus to the previous [Button].
+const FOCUS_PREV_KEYS: [KeyCode; 2] = [KeyCode::Up, KeyCode::W];
+/// Keys moving the focus to the next [Button].
+const FOCUS_NEXT_KEYS: [KeyCode; 2] = [KeyCode::Down, KeyCode::S];
+/// Keys activating the focused [Button].
+const ACTIVATE_KEYS: [KeyCode; 2] = [KeyCode::Enter, KeyCode::KpEnter];
+/// Thickness of the line underlining the focused [Button].
+const FOCUS_LINE_THICKNESS: f32 = 2.0;
+/// Gap between the focused [Button]'s text and its underline.
+const FOCUS_LINE_GAP: f32 = 4.0;
+
 /// Marker of the button which starts the game.
 #[derive(Clone, Copy, Debug)]
 pub struct StartButton;
+
+/// Marker of the button which opens the practice menu.
+#[derive(Clone, Copy, Debug)]
+pub struct PracticeButton;
+
+/// Marker of the button which starts the daily challenge.
+#[derive(Clone, Copy, Debug)]
+pub struct DailyButton;
+
+/// Marker of the button which opens the changelog.
+#[derive(Clone, Copy, Debug)]
+pub struct ChangelogButton;
+
+/// Marker of the button which opens the controls screen.
+#[derive(Clone, Copy, Debug)]
+pub struct ControlsButton;
+
+/// Button rebinding an [Action] to the next pressed key.
+#[derive(Clone, Copy, Debug)]
+pub struct RebindButton {
+    /// Action to rebind.
+    pub action: Action,
+    /// Is the button waiting for a key?
+    pub capturing: bool,
+}
+
+/// Marker of the button which returns to the main menu.
+#[derive(Clone, Copy, Debug)]
+pub struct BackButton;
+
+/// Marker of the UI of the pause screen hidden while [QuitConfirm] is open.
+#[derive(Clone, Copy, Debug)]
+pub struct PauseMenu;
+
+/// Marker of the button which resumes the paused run.
+#[derive(Clone, Copy, Debug)]
+pub struct ResumeButton;
+
+/// Marker of the button which asks to quit the paused run.
+#[derive(Clone, Copy, Debug)]
+pub struct QuitButton;
+
+/// Marker of the UI of the dialog confirming quitting the paused run.
+#[derive(Clone, Copy, Debug)]
+pub struct QuitConfirm;
+
+/// Button answering the [QuitConfirm] dialog.
+#[derive(Clone, Copy, Debug)]
+pub struct QuitAnswerButton {
+    /// Does the button abandon the run?
+    pub abandon: bool,
+}
+
+/// Marker of the button which starts a new run from the game over screen.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryButton;
+
+/// Marker of the button which switches between the standard and the large [Arena].
+#[derive(Clone, Copy, Debug)]
+pub struct ArenaButton;
+
+/// Marker of the button which opens the settings screen.
+#[derive(Clone, Copy, Debug)]
+pub struct SettingsButton;
+
+/// Marker of the button which starts the tutorial.
+#[derive(Clone, Copy, Debug)]
+pub struct TutorialButton;
+
+/// Marker of the button which opens the lifetime statistics screen.
+#[derive(Clone, Copy, Debug)]
+pub struct StatsButton;
+
+/// Marker of the button which opens the seeded run screen.
+#[derive(Clone, Copy, Debug)]
+pub struct SeededRunButton;
+
+/// Seed typed in for a seeded run, in hexadecimal digits.
+#[derive(Clone, Debug, Default)]
+pub struct SeedInput {
+    /// Digits typed so far.
+    pub text: String,
+}
+
+/// Marker of the button which opens the leaderboard screen.
+#[derive(Clone, Copy, Debug)]
+pub struct LeaderboardButton;
+
+/// Button which toggles a setting, labeled by its [toggle_text].
+#[derive(Clone, Copy, Debug)]
+pub struct ToggleButton {
+    /// Locale key of the label, the state is appended as `_on` or `_off`.
+    pub label: &'static str,
+}
+
+/// Marker of the button which switches to the next render scale.
+#[derive(Clone, Copy, Debug)]
+pub struct RenderScaleButton;
+
+/// Marker of the button which switches to the next language.
+#[derive(Clone, Copy, Debug)]
+pub struct LanguageButton;
+
+/// Most hexadecimal digits of a seed, as many as fit into [u64].
+const MAX_SEED_DIGITS: usize = 16;
+
+/// Lets the user choose a value from 0.0 to 1.0 by dragging it with the mouse.
+#[derive(Clone, Copy, Debug)]
+pub struct Slider {
+    /// Width of the track.
+    pub width: f32,
+    /// Height of the track.
+    pub height: f32,
+    /// Currently chosen value, 0.0 to 1.0.
+    pub value: f32,
+    /// Color of the unfilled part of the track.
+    pub neutral_color: Color,
+    /// Color of the filled part of the track and the knob.
+    pub active_color: Color,
+    /// Is the slider being dragged?
+    pub dragging: bool,
+}
+
+/// Setting changed by a [Slider].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SliderSetting {
+    /// Volume of everything.
+    MasterVolume,
+    /// Volume of sound effects.
+    SfxVolume,
+    /// Volume of the music.
+    MusicVolume,
+    /// Strength of the rumble.
+    RumbleIntensity,
+}
+
+impl SliderSetting {
+    /// Returns the value of the setting in `settings`.
+    pub fn get(&self, settings: &Settings) -> f32 {
+        match self {
+            SliderSetting::MasterVolume => settings.master_volume,
+            SliderSetting::SfxVolume => settings.sfx_volume,
+            SliderSetting::MusicVolume => settings.music_volume,
+            SliderSetting::RumbleIntensity => settings.rumble_intensity,
+        }
+    }
+
+    /// Returns the mutable value of the setting in `settings`.
+    pub fn get_mut<'a>(&self, settings: &'a mut Settings) -> &'a mut f32 {
+        match self {
+            SliderSetting::MasterVolume => &mut settings.master_volume,
+            SliderSetting::SfxVolume => &mut settings.sfx_volume,
+            SliderSetting::MusicVolume => &mut settings.music_volume,
+            SliderSetting::RumbleIntensity => &mut settings.rumble_intensity,
+        }
+    }
+}
+
+/// Shows the wave a practice run starts at.
+#[derive(Clone, Copy, Debug)]
+pub struct WaveSelector {
+    /// Wave the practice run starts at.
+    pub wave: u32,
+}
+
+/// Button changing the wave of the [WaveSelector].
+#[derive(Clone, Copy, Debug)]
+pub struct WaveStepButton {
+    /// By how many waves the button changes the selection.
+    pub step: i32,
+}
+
 //-----------------------------------------------------------------------------
 //SYSTEM PART
 //-----------------------------------------------------------------------------
 
-/// Handles rendering the texts of [Title]s.
-pub fn render_title(world: &mut World, assets: &AssetManager) {
-    for (_, (title, position)) in world.query_mut::<(&Title, &Position)>() {
+/// Handles rendering the texts of [Title]s owned by `owner`.
+pub fn render_title(world: &mut World, assets: &AssetManager, owner: UiOwner) {
+    for (_, (title, position, _)) in world
+        .query_mut::<(&Title, &Position, &UiOwner)>()
+        .into_iter()
+        .filter(|(_, (_, _, title_owner))| **title_owner == owner)
+    {
         //get font to render
         let font = assets.get_font(title.font);
-        //render it center aligned
-        let dimensions = measure_text(title.text.as_str(), font, title.size as u16, 1.0);
-        draw_text_ex(
-            title.text.as_str(),
-            position.x - dimensions.width / 2.0,
-            position.y + dimensions.offset_y / 2.0,
-            TextParams {
-                font,
-                font_size: title.size as u16 * 2,
-                font_scale: 0.5,
-                color: title.color,
-                ..Default::default()
-            },
-        )
+        //render the lines stacked, center aligned
+        let (lines, size, _) = title.layout(font);
+        let line_height = size * TITLE_LINE_SPACING;
+        let top = position.y - line_height * (lines.len() as f32 - 1.0) / 2.0;
+        for (i, line) in lines.iter().enumerate() {
+            let dimensions = measure_text(line, font, size as u16, 1.0);
+            draw_text_ex(
+                line,
+                position.x - dimensions.width / 2.0,
+                top + line_height * i as f32 + dimensions.offset_y / 2.0,
+                TextParams {
+                    font,
+                    font_size: size as u16 * 2,
+                    font_scale: 0.5,
+                    color: title.color,
+                    ..Default::default()
+                },
+            );
+        }
+    }
+}
+
+/// Splits `text` into lines no wider than `max_width`.
+/// Words longer than `max_width` are kept on their own line.
+/// # Arguments
+/// * `text` - text to split
+/// * `font` - font the text is rendered with
+/// * `size` - size of the text, same as [Title]'s
+/// * `max_width` - max width of a line
+pub fn wrap_text(text: &str, font: Option<&Font>, size: f32, max_width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if line.is_empty() {
+            word.to_string()
+        } else {
+            format!("{line} {word}")
+        };
+        if !line.is_empty() && measure_text(&candidate, font, size as u16, 1.0).width > max_width {
+            lines.push(std::mem::replace(&mut line, word.to_string()));
+        } else {
+            line = candidate;
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
     }
+    lines
 }
 
 /// Handles changing [Title]'s color depending on the [Button]'s state.
 /// Also sets [Button]'s 'clicked' variable according to its state.
-pub fn button_colors(world: &mut World) {
-    for (_, (position, button, title)) in world.query_mut::<(&Position, &mut Button, &mut Title)>()
-    {
-        //check for overlap
-        let mouse_pos = world_mouse_pos();
-        let hover = mouse_pos.x <= position.x + button.width / 2.0
-            && mouse_pos.x >= position.x - button.width / 2.0
-            && mouse_pos.y <= position.y + button.height / 2.0
-            && mouse_pos.y >= position.y - button.height / 2.0;
-        let click = hover && is_mouse_button_pressed(MouseButton::Left);
-        //set color
-        title.color = if click {
+///
+/// Buttons with wrapped [Title]s are hovered over their text's bounding box
+/// instead of their size.
+///
+/// Keyboard navigation moves the focus of the [MenuCursor] between the buttons,
+/// top to bottom and left to right, and activates the focused one.
+/// Moving the mouse focuses the hovered button. The focused button is underlined.
+pub fn button_colors(world: &mut World, assets: &AssetManager) {
+    let mouse_pos = world_mouse_pos();
+    //check for overlap
+    let mut buttons: Vec<_> = world
+        .query_mut::<(&Position, &Button, &Title)>()
+        .into_iter()
+        .map(|(id, (position, button, title))| {
+            let size = if title.max_width.is_some() {
+                title.layout(assets.get_font(title.font)).2
+            } else {
+                vec2(button.width, button.height)
+            };
+            let hover = mouse_pos.x <= position.x + size.x / 2.0
+                && mouse_pos.x >= position.x - size.x / 2.0
+                && mouse_pos.y <= position.y + size.y / 2.0
+                && mouse_pos.y >= position.y - size.y / 2.0;
+            (id, vec2(position.x, position.y), hover)
+        })
+        .collect();
+    buttons.sort_by(|(_, a, _), (_, b, _)| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+
+    //get the cursor, keeping its focus only on present buttons
+    let mut cursor = world
+        .query_mut::<&MenuCursor>()
+        .into_iter()
+        .next()
+        .map(|(_, cursor)| *cursor)
+        .unwrap_or_default();
+    let mut focused = cursor
+        .focused
+        .and_then(|focused| buttons.iter().position(|(id, _, _)| *id == focused));
+    //keys being captured for rebinding or cycling initials do not navigate
+    let capturing = world
+        .query_mut::<&RebindButton>()
+        .into_iter()
+        .any(|(_, rebind)| rebind.capturing)
+        || world.query_mut::<&NameEntry>().into_iter().next().is_some();
+    let count = buttons.len();
+    if !capturing && count > 0 {
+        if FOCUS_NEXT_KEYS.into_iter().any(is_key_pressed) {
+            focused = Some(focused.map_or(0, |i| (i + 1) % count));
+        }
+        if FOCUS_PREV_KEYS.into_iter().any(is_key_pressed) {
+            focused = Some(focused.map_or(count - 1, |i| (i + count - 1) % count));
+        }
+    }
+    if mouse_pos != cursor.last_mouse {
+        if let Some(hovered) = buttons.iter().position(|(_, _, hover)| *hover) {
+            focused = Some(hovered);
+        }
+    }
+    cursor.last_mouse = mouse_pos;
+    cursor.focused = focused.map(|i| buttons[i].0);
+    let activate = !capturing && ACTIVATE_KEYS.into_iter().any(is_key_pressed);
+
+    for (id, _, hover) in buttons {
+        let Ok((button, title)) = world.query_one_mut::<(&mut Button, &mut Title)>(id) else {
+            continue;
+        };
+        let is_focused = cursor.focused == Some(id);
+        let click =
+            (hover && is_mouse_button_pressed(MouseButton::Left)) || (is_focused && activate);
+        //set color, keeping the alpha of fading titles
+        let color = if click {
             button.active_color
-        } else if hover {
+        } else if hover || is_focused {
             button.hover_color
         } else {
             button.neutral_color
         };
+        title.color = Color {
+            a: title.color.a,
+            ..color
+        };
         //set clicked
         button.clicked = click;
     }
+
+    //underline the focused button
+    if let Some(focused) = cursor.focused {
+        if let Ok((position, title)) = world.query_one_mut::<(&Position, &Title)>(focused) {
+            let size = title.layout(assets.get_font(title.font)).2;
+            draw_rectangle(
+                position.x - size.x / 2.0,
+                position.y + size.y / 2.0 + FOCUS_LINE_GAP,
+                size.x,
+                FOCUS_LINE_THICKNESS,
+                title.color,
+            );
+        }
+    }
+
+    //store the cursor
+    match world.query_mut::<&mut MenuCursor>().into_iter().next() {
+        Some((_, stored)) => *stored = cursor,
+        None => {
+            world.spawn((cursor,));
+        }
+    }
 }
 
 /// Handle special buttons.
-/// Currently handles [StartButton] changing game state to [Running](GameState::Running).
+/// Currently handles:
+/// - [StartButton] changing game state to [Running](GameState::Running)
+/// - [PracticeButton] changing game state to [PracticeMenu](GameState::PracticeMenu)
+/// - [ChangelogButton] changing game state to [Changelog](GameState::Changelog)
+/// - [ControlsButton] changing game state to [Controls](GameState::Controls)
+/// - [SettingsButton] changing game state to [Settings](GameState::Settings)
+/// - [StatsButton] changing game state to [Stats](GameState::Stats)
+/// - [LeaderboardButton] changing game state to [Leaderboard](GameState::Leaderboard)
+/// - [SeededRunButton] changing game state to [SeedMenu](GameState::SeedMenu)
+/// - [TutorialButton] changing game state to [Tutorial](GameState::Tutorial)
+/// - [BackButton] changing game state to [MainMenu](GameState::MainMenu)
 pub fn handle_buttons(world: &mut World) -> Option<GameState> {
     for (_, button) in world.query_mut::<&Button>().with::<&StartButton>() {
         if button.clicked {
             return Some(GameState::Running);
         }
     }
+    for (_, button) in world.query_mut::<&Button>().with::<&PracticeButton>() {
+        if button.clicked {
+            return Some(GameState::PracticeMenu);
+        }
+    }
+    for (_, button) in world.query_mut::<&Button>().with::<&ChangelogButton>() {
+        if button.clicked {
+            return Some(GameState::Changelog);
+        }
+    }
+    for (_, button) in world.query_mut::<&Button>().with::<&ControlsButton>() {
+        if button.clicked {
+            return Some(GameState::Controls);
+        }
+    }
+    for (_, button) in world.query_mut::<&Button>().with::<&SettingsButton>() {
+        if button.clicked {
+            return Some(GameState::Settings);
+        }
+    }
+    for (_, button) in world.query_mut::<&Button>().with::<&StatsButton>() {
+        if button.clicked {
+            return Some(GameState::Stats);
+        }
+    }
+    for (_, button) in world.query_mut::<&Button>().with::<&LeaderboardButton>() {
+        if button.clicked {
+            return Some(GameState::Leaderboard);
+        }
+    }
+    for (_, button) in world.query_mut::<&Button>().with::<&SeededRunButton>() {
+        if button.clicked {
+            return Some(GameState::SeedMenu);
+        }
+    }
+    for (_, button) in world.query_mut::<&Button>().with::<&TutorialButton>() {
+        if button.clicked {
+            return Some(GameState::Tutorial);
+        }
+    }
+    for (_, button) in world.query_mut::<&Button>().with::<&BackButton>() {
+        if button.clicked {
+            return Some(GameState::MainMenu);
+        }
+    }
     None
 }
+
+/// Changes the [WaveSelector]'s wave by clicked [WaveStepButton]s.
+/// Also synchronizes its [Title].
+pub fn wave_selector(world: &mut World, max_wave: u32) {
+    //sum all clicked steps
+    let step: i32 = world
+        .query_mut::<(&Button, &WaveStepButton)>()
+        .into_iter()
+        .filter(|(_, (button, _))| button.clicked)
+        .map(|(_, (_, step))| step.step)
+        .sum();
+
+    for (_, (selector, title)) in world.query_mut::<(&mut WaveSelector, &mut Title)>() {
+        selector.wave = (selector.wave as i32 + step).clamp(1, max_wave as i32) as u32;
+        title.text = tr!("practice.wave", wave = selector.wave);
+    }
+}
+
+/// Types hexadecimal digits into the [SeedInput], backspace deletes them.
+/// Seeds can be pasted with Ctrl+V as well.
+/// Also synchronizes its [Title].
+pub fn seed_input(world: &mut World) {
+    let mut typed: Vec<char> = std::iter::from_fn(get_char_pressed).collect();
+    let control = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+    if control && is_key_pressed(KeyCode::V) {
+        typed.extend(
+            miniquad::window::clipboard_get()
+                .unwrap_or_default()
+                .trim()
+                .chars(),
+        );
+    }
+
+    for (_, (input, title)) in world.query_mut::<(&mut SeedInput, &mut Title)>() {
+        if is_key_pressed(KeyCode::Backspace) {
+            input.text.pop();
+        }
+        for digit in typed.iter().filter(|c| c.is_ascii_hexdigit()) {
+            if input.text.len() < MAX_SEED_DIGITS {
+                input.text.push(digit.to_ascii_uppercase());
+            }
+        }
+        title.text = if input.text.is_empty() {
+            tr!("seed.random")
+        } else {
+            input.text.clone()
+        };
+    }
+}
+
+/// Returns the seed typed into the [SeedInput].
+/// Returns [None] when there is none, the run should be random then.
+pub fn typed_seed(world: &mut World) -> Option<u64> {
+    world
+        .query_mut::<&SeedInput>()
+        .into_iter()
+        .next()
+        .and_then(|(_, input)| u64::from_str_radix(&input.text, 16).ok())
+}
+
+/// Returns whether the [DailyButton] was clicked.
+pub fn daily_button(world: &mut World) -> bool {
+    world
+        .query_mut::<&Button>()
+        .with::<&DailyButton>()
+        .into_iter()
+        .any(|(_, button)| button.clicked)
+}
+
+/// Returns the text of the [ArenaButton] describing `arena`.
+pub fn arena_text(arena: &Arena) -> String {
+    if *arena == Arena::LARGE {
+        tr!("menu.arena_large")
+    } else {
+        tr!("menu.arena_standard")
+    }
+}
+
+/// Returns the segments of the main menu's quick-stats strip.
+/// Shows a single invitation when no run has been played yet.
+pub fn quick_stats(persist: &Persistent) -> Vec<String> {
+    let stats = &persist.stats;
+    if stats.runs == 0 {
+        return vec![tr!("menu.no_runs")];
+    }
+
+    let total_time = stats.positive_time + stats.negative_time;
+    let (favorite, favorite_time) = if stats.positive_time >= stats.negative_time {
+        ("polarity.positive", stats.positive_time)
+    } else {
+        ("polarity.negative", stats.negative_time)
+    };
+    let favorite_share = if total_time > 0.0 {
+        (favorite_time / total_time * 100.0).round() as u32
+    } else {
+        100
+    };
+
+    vec![
+        tr!("menu.high_score", score = persist.high_score),
+        tr!("menu.best_time", time = format_time(stats.best_time)),
+        tr!("menu.runs", runs = stats.runs),
+        tr!(
+            "menu.favorite",
+            polarity = tr!(favorite),
+            share = favorite_share
+        ),
+    ]
+}
+
+/// Returns the lines of the lifetime statistics screen.
+/// The first column holds general statistics, the second kills by type.
+pub fn lifetime_stats(persist: &Persistent) -> (Vec<String>, Vec<String>) {
+    let stats = &persist.stats;
+    let general = vec![
+        tr!("stats.runs", runs = stats.runs),
+        tr!("stats.play_time", time = format_time(stats.play_time)),
+        tr!("stats.longest_run", time = format_time(stats.best_time)),
+        tr!("stats.xp", xp = stats.xp),
+        tr!("stats.kills", kills = stats.kills.total()),
+    ];
+    let kills = stats
+        .kills
+        .by_type()
+        .into_iter()
+        .map(|(name, kills)| format!("{} {kills}", tr!(name)))
+        .collect();
+    (general, kills)
+}
+
+/// Returns the rows of the leaderboard screen.
+/// Every row holds the rank with initials, the score and the duration of the run.
+pub fn leaderboard_rows(persist: &Persistent) -> Vec<[String; 3]> {
+    persist
+        .leaderboard
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            [
+                format!("{}. {}", i + 1, entry.name),
+                format!("{}", entry.score * 10),
+                format_time(entry.duration),
+            ]
+        })
+        .collect()
+}
+
+/// Formats `seconds` as minutes and seconds, with hours when there are any.
+pub fn format_time(seconds: f32) -> String {
+    let seconds = seconds as u32;
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+/// Switches the [Arena] between the standard and the large one
+/// when the [ArenaButton] is clicked.
+/// Also synchronizes its [Title].
+pub fn arena_button(world: &mut World) {
+    let clicked = world
+        .query_mut::<&Button>()
+        .with::<&ArenaButton>()
+        .into_iter()
+        .any(|(_, button)| button.clicked);
+    if !clicked {
+        return;
+    }
+
+    let mut current = Arena::STANDARD;
+    for (_, arena) in world.query_mut::<&mut Arena>() {
+        *arena = if *arena == Arena::LARGE {
+            Arena::STANDARD
+        } else {
+            Arena::LARGE
+        };
+        current = *arena;
+    }
+
+    for (_, title) in world.query_mut::<&mut Title>().with::<&ArenaButton>() {
+        title.text = arena_text(&current);
+    }
+}
+
+/// Clicks the [StartButton] when the gamepad's confirm button is pressed,
+/// so that a run can be started without the mouse.
+pub fn gamepad_confirm(world: &mut World, gamepad: &GamepadState) {
+    if !gamepad.confirm_pressed {
+        return;
+    }
+    for (_, button) in world.query_mut::<&mut Button>().with::<&StartButton>() {
+        button.clicked = true;
+    }
+}
+
+/// Handles [RebindButton]s.
+/// A clicked button captures the next pressed key or mouse button
+/// and binds it to its action.
+///
+/// Returns whether a button is capturing or has just captured,
+/// other input should be ignored in that case.
+pub fn rebind_buttons(world: &mut World, bindings: &mut KeyBindings) -> bool {
+    let mut busy = false;
+    for (_, (button, rebind, title)) in
+        world.query_mut::<(&Button, &mut RebindButton, &mut Title)>()
+    {
+        if rebind.capturing {
+            busy = true;
+            if let Some(binding) = Binding::capture() {
+                *bindings.get_mut(rebind.action) = binding;
+                rebind.capturing = false;
+                title.text = bindings.get(rebind.action).name();
+            }
+        } else if button.clicked {
+            busy = true;
+            rebind.capturing = true;
+            title.text = tr!("controls.press_key");
+        }
+    }
+    busy
+}
+
+/// Handles dragging of [Slider]s.
+///
+/// Returns whether a slider was released this frame,
+/// so that the chosen value can be saved.
+pub fn sliders(world: &mut World) -> bool {
+    let mouse_pos = world_mouse_pos();
+    let mut released = false;
+    for (_, (position, slider)) in world.query_mut::<(&Position, &mut Slider)>() {
+        let left = position.x - slider.width / 2.0;
+        //start dragging on click
+        let hover = mouse_pos.x <= position.x + slider.width / 2.0
+            && mouse_pos.x >= left
+            && mouse_pos.y <= position.y + slider.height
+            && mouse_pos.y >= position.y - slider.height;
+        if hover && is_mouse_button_pressed(MouseButton::Left) {
+            slider.dragging = true;
+        }
+        if !slider.dragging {
+            continue;
+        }
+        //follow the mouse
+        slider.value = ((mouse_pos.x - left) / slider.width).clamp(0.0, 1.0);
+        if !is_mouse_button_down(MouseButton::Left) {
+            slider.dragging = false;
+            released = true;
+        }
+    }
+    released
+}
+
+/// Writes the values of [Slider]s into their [SliderSetting]s.
+pub fn slider_settings(world: &mut World, settings: &mut Settings) {
+    for (_, (slider, setting)) in world.query_mut::<(&Slider, &SliderSetting)>() {
+        *setting.get_mut(settings) = slider.value;
+    }
+}
+
+/// Renders [Slider]s as a track filled up to the value with a knob.
+pub fn render_sliders(world: &mut World) {
+    for (_, (position, slider)) in world.query_mut::<(&Position, &Slider)>() {
+        let left = position.x - slider.width / 2.0;
+        let top = position.y - slider.height / 2.0;
+        let filled = slider.width * slider.value;
+        draw_rectangle(left, top, slider.width, slider.height, slider.neutral_color);
+        draw_rectangle(left, top, filled, slider.height, slider.active_color);
+        draw_circle(
+            left + filled,
+            position.y,
+            slider.height,
+            slider.active_color,
+        );
+    }
+}
+
+/// Returns the text of a [ToggleButton] with the `label` describing whether it is `on`.
+pub fn toggle_text(label: &str, on: bool) -> String {
+    tr!(&format!("{label}_{}", if on { "on" } else { "off" }))
+}
+
+/// Toggles the `value` when the [ToggleButton] with the `label` is clicked.
+/// Also synchronizes its [Title].
+///
+/// Returns whether the value was toggled.
+/// # Arguments
+/// * `label` - locale key of the button's label
+/// * `value` - setting toggled by the button
+pub fn toggle_button(world: &mut World, label: &str, value: &mut bool) -> bool {
+    let mut toggled = false;
+    for (_, (button, toggle, title)) in world.query_mut::<(&Button, &ToggleButton, &mut Title)>() {
+        if button.clicked && toggle.label == label {
+            *value = !*value;
+            title.text = toggle_text(label, *value);
+            toggled = true;
+        }
+    }
+    toggled
+}
+
+/// Returns the text of the [RenderScaleButton] showing the `scale` in percent.
+pub fn render_scale_text(scale: f32) -> String {
+    tr!("settings.render_scale", scale = (scale * 100.0).round())
+}
+
+/// Switches `settings` to the next of the [RENDER_SCALES] when the [RenderScaleButton] is clicked.
+/// Also synchronizes its [Title].
+///
+/// Returns whether the scale was switched.
+pub fn render_scale_button(world: &mut World, settings: &mut Settings) -> bool {
+    let mut switched = false;
+    for (_, (button, title)) in world
+        .query_mut::<(&Button, &mut Title)>()
+        .with::<&RenderScaleButton>()
+    {
+        if button.clicked {
+            //an unknown scale switches to the lowest one
+            let next = RENDER_SCALES
+                .iter()
+                .position(|scale| *scale == settings.render_scale)
+                .map_or(0, |i| (i + 1) % RENDER_SCALES.len());
+            settings.render_scale = RENDER_SCALES[next];
+            title.text = render_scale_text(settings.render_scale);
+            switched = true;
+        }
+    }
+    switched
+}
+
+/// Returns the text of the [LanguageButton] naming the `language`, an index of [LANGUAGES].
+pub fn language_text(language: u8) -> String {
+    tr!(
+        "settings.language",
+        language = locale::language_name(language as usize)
+    )
+}
+
+/// Switches `settings` to the next language when the [LanguageButton] is clicked.
+/// The UI has to be recreated to show the new language.
+///
+/// Returns whether the language was switched.
+pub fn language_button(world: &mut World, settings: &mut Settings) -> bool {
+    let clicked = world
+        .query_mut::<&Button>()
+        .with::<&LanguageButton>()
+        .into_iter()
+        .any(|(_, button)| button.clicked);
+    if clicked {
+        settings.language = ((settings.language as usize + 1) % LANGUAGES.len()) as u8;
+        locale::set_language(settings.language as usize);
+    }
+    clicked
+}
+
+/// Returns whether a [Button] marked by `T` is clicked,
+/// e.g. the [QuitButton], [ResumeButton] or [RetryButton].
+pub fn marked_clicked<T: Component>(world: &mut World) -> bool {
+    world
+        .query_mut::<&Button>()
+        .with::<&T>()
+        .into_iter()
+        .any(|(_, button)| button.clicked)
+}
+
+/// Returns the answer of the [QuitConfirm] dialog, whether to abandon the run,
+/// or [None] if no [QuitAnswerButton] is clicked.
+pub fn quit_answer(world: &mut World) -> Option<bool> {
+    world
+        .query_mut::<(&Button, &QuitAnswerButton)>()
+        .into_iter()
+        .find(|(_, (button, _))| button.clicked)
+        .map(|(_, (_, answer))| answer.abandon)
+}
@@ -0,0 +1,128 @@
+//! Minimap showing the whole arena in a corner of the screen.
+
+use hecs::World;
+use macroquad::prelude::*;
+
+use crate::{
+    basic::{arena, HurtBox, Position, Team},
+    enemy::{asteroid::BigAsteroid, mine::Mine, Enemy},
+    persist::Persistent,
+    player::Player,
+    xp::XpOrb,
+    SPACE_WIDTH,
+};
+
+/// Width of the minimap, its height follows the arena's aspect ratio.
+const MINIMAP_WIDTH: f32 = 200.0;
+/// Distance of the minimap from the top right corner of the screen.
+const MINIMAP_OFFSET: f32 = 10.0;
+/// Space around the arena shown on the minimap.
+/// Enemies approaching from outside appear there before they are visible.
+const MINIMAP_MARGIN: f32 = 120.0;
+/// Max amount of dots drawn, the rest is sampled out.
+const MINIMAP_MAX_DOTS: usize = 100;
+/// Radius of a regular dot.
+const DOT_RADIUS: f32 = 2.0;
+/// Radius of dots of threatening enemies.
+const THREAT_DOT_RADIUS: f32 = 4.0;
+/// Color of the minimap's background.
+const BACKGROUND_COLOR: Color = Color::new(0.0, 0.0, 0.0, 0.5);
+/// Color of xp orb dots.
+const XP_COLOR: Color = Color::new(1.0, 1.0, 0.0, 0.35);
+
+/// Toggles the minimap setting when its binding is pressed.
+pub fn toggle_minimap(persist: &mut Persistent) {
+    if persist.bindings.minimap.is_pressed() {
+        persist.settings.minimap = !persist.settings.minimap;
+        let _ = persist.save();
+    }
+}
+
+/// Renders the minimap, when enabled in settings.
+/// Must be rendered with the UI camera.
+pub fn render_minimap(world: &mut World, persist: &Persistent) {
+    if !persist.settings.minimap {
+        return;
+    }
+    let arena = arena::arena(world);
+
+    //mapped space is the arena with a margin around it
+    let origin = vec2(-MINIMAP_MARGIN, -MINIMAP_MARGIN);
+    let space = vec2(
+        arena.width + 2.0 * MINIMAP_MARGIN,
+        arena.height + 2.0 * MINIMAP_MARGIN,
+    );
+    let scale = MINIMAP_WIDTH / space.x;
+    let corner = vec2(SPACE_WIDTH - MINIMAP_WIDTH - MINIMAP_OFFSET, MINIMAP_OFFSET);
+    let to_map = |pos: &Position| {
+        let point = (vec2(pos.x, pos.y) - origin).clamp(Vec2::ZERO, space);
+        corner + point * scale
+    };
+
+    //background, arena bounds and the visible part of it
+    draw_rectangle(
+        corner.x,
+        corner.y,
+        space.x * scale,
+        space.y * scale,
+        BACKGROUND_COLOR,
+    );
+    draw_rectangle_lines(
+        corner.x + MINIMAP_MARGIN * scale,
+        corner.y + MINIMAP_MARGIN * scale,
+        arena.width * scale,
+        arena.height * scale,
+        1.0,
+        GRAY,
+    );
+    let view = arena::view_rect(world);
+    if arena.follow || view != arena.view_rect() {
+        let point = corner + (view.point() - origin) * scale;
+        draw_rectangle_lines(
+            point.x,
+            point.y,
+            view.w * scale,
+            view.h * scale,
+            1.0,
+            DARKGRAY,
+        );
+    }
+
+    //gather dots
+    let mut dots = Vec::new();
+    for (_, pos) in world.query_mut::<&Position>().with::<&XpOrb>() {
+        dots.push((to_map(pos), DOT_RADIUS, XP_COLOR));
+    }
+    for (_, (pos, team)) in world
+        .query_mut::<(&Position, &Team)>()
+        .with::<&HurtBox>()
+        .without::<&Enemy>()
+    {
+        if *team == Team::Neutral {
+            dots.push((to_map(pos), DOT_RADIUS, ORANGE));
+        }
+    }
+    for (_, (pos, big, mine)) in world
+        .query_mut::<(&Position, Option<&BigAsteroid>, Option<&Mine>)>()
+        .with::<&Enemy>()
+    {
+        let radius = if big.is_some() || mine.is_some() {
+            THREAT_DOT_RADIUS
+        } else {
+            DOT_RADIUS
+        };
+        dots.push((to_map(pos), radius, RED));
+    }
+
+    //sample when there are too many of them
+    let step = dots.len().div_ceil(MINIMAP_MAX_DOTS).max(1);
+    for (point, radius, color) in dots.into_iter().step_by(step) {
+        draw_circle(point.x, point.y, radius, color);
+    }
+
+    //player on top of everything
+    for (_, pos) in world.query_mut::<&Position>().with::<&Player>() {
+        let point = to_map(pos);
+        draw_circle(point.x, point.y, DOT_RADIUS + 1.0, WHITE);
+    }
+}
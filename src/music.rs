@@ -0,0 +1,123 @@
+//! Background music following the game state.
+
+use macroquad::{
+    audio::{play_sound, set_sound_volume, stop_sound, PlaySoundParams},
+    prelude::*,
+};
+
+use crate::{basic::render::AssetManager, game::state::GameState, persist::Settings};
+
+/// Sound ID of the track playing in menus.
+pub const MENU_TRACK: &str = "music_menu";
+/// Sound ID of the track playing during runs.
+pub const COMBAT_TRACK: &str = "music_combat";
+/// Sound ID of the short sting played once on game over.
+pub const GAME_OVER_STING: &str = "music_game_over";
+
+/// Time a track takes to fade in or out.
+const CROSSFADE_TIME: f32 = 1.5;
+/// Volume of the combat track while paused.
+const PAUSE_DUCK: f32 = 0.5;
+
+/// Track fading in or out.
+#[derive(Clone, Copy, Debug)]
+struct Fade {
+    /// Sound ID of the track.
+    track: &'static str,
+    /// Current volume of the track, before the user's volume preferences.
+    volume: f32,
+}
+
+/// Plays the track fitting the current game state,
+/// crossfading between tracks when the state changes.
+#[derive(Clone, Debug, Default)]
+pub struct MusicController {
+    /// Tracks currently playing.
+    playing: Vec<Fade>,
+    /// State of the previous update.
+    last_state: Option<GameState>,
+}
+
+impl MusicController {
+    /// Ramps the volumes of the tracks towards the ones wanted by `state`.
+    /// Should be called exactly once per frame.
+    /// # Arguments
+    /// * `state` - current game state
+    /// * `assets` - assets with the loaded tracks, missing tracks stay silent
+    /// * `settings` - user's volume preferences
+    /// * `dt` - delta time
+    pub fn update(
+        &mut self,
+        state: GameState,
+        assets: &AssetManager,
+        settings: &Settings,
+        dt: f32,
+    ) {
+        let audio = assets.audio();
+        let volume_mult = settings.master_volume * settings.music_volume;
+
+        //track and its volume wanted by the state
+        let wanted = match state {
+            GameState::Running | GameState::Tutorial | GameState::LevelUp => {
+                Some((COMBAT_TRACK, 1.0))
+            }
+            GameState::Paused => Some((COMBAT_TRACK, PAUSE_DUCK)),
+            GameState::GameOver => None,
+            _ => Some((MENU_TRACK, 1.0)),
+        };
+
+        //sting the death once
+        if state == GameState::GameOver && self.last_state != Some(GameState::GameOver) {
+            if let Some(sting) = audio.get(GAME_OVER_STING) {
+                play_sound(
+                    sting,
+                    PlaySoundParams {
+                        looped: false,
+                        volume: volume_mult,
+                    },
+                );
+            }
+        }
+        self.last_state = Some(state);
+
+        //start the wanted track silently, it fades in
+        if let Some((track, _)) = wanted {
+            if !self.playing.iter().any(|fade| fade.track == track) {
+                if let Some(sound) = audio.get(track) {
+                    play_sound(
+                        sound,
+                        PlaySoundParams {
+                            looped: true,
+                            volume: 0.0,
+                        },
+                    );
+                    self.playing.push(Fade { track, volume: 0.0 });
+                }
+            }
+        }
+
+        //crossfade
+        let step = dt / CROSSFADE_TIME;
+        self.playing.retain_mut(|fade| {
+            let target = match wanted {
+                Some((track, volume)) if track == fade.track => volume,
+                _ => 0.0,
+            };
+            fade.volume = if fade.volume < target {
+                (fade.volume + step).min(target)
+            } else {
+                (fade.volume - step).max(target)
+            };
+            let Some(sound) = audio.get(fade.track) else {
+                return false;
+            };
+            //faded out completely
+            if fade.volume <= 0.0 && target <= 0.0 {
+                stop_sound(sound);
+                return false;
+            }
+            set_sound_volume(sound, fade.volume * volume_mult);
+            true
+        });
+    }
+}
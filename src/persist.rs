@@ -1,34 +1,718 @@
 //! Persistent storage.
 
-use std::io::Write;
+use std::collections::HashMap;
 
-use macroquad::file::load_file;
 use nanoserde::{DeBin, SerBin};
 
+use crate::{
+    daily::DailyRecord,
+    enemy::{KillCounts, RunStats},
+    ghost::GhostPath,
+    hints::HintsShown,
+    input::KeyBindings,
+    leaderboard::ScoreEntry,
+    window::WindowPrefs,
+};
+
+mod storage;
+
+/// Render scales the user can choose from.
+pub const RENDER_SCALES: [f32; 3] = [0.5, 0.75, 1.0];
+
+/// Name of the save in the [storage].
+const SAVE_NAME: &str = "save.bin";
+/// Name corrupt saves are backed up under.
+const BACKUP_NAME: &str = "save.bin.bak";
+/// Bytes every versioned save starts with.
+const SAVE_MAGIC: &[u8; 4] = b"MGFY";
+/// Version of the current save format, stored right after [SAVE_MAGIC].
+/// Bump it whenever the layout of [Persistent] changes and migrate the previous one.
+const SAVE_VERSION: u8 = 8;
+
 /// Persistent data that the application can be saved and loaded.
-#[derive(Clone, Copy, Default, Debug, DeBin, SerBin)]
+#[derive(Clone, Default, Debug, DeBin, SerBin)]
 pub struct Persistent {
     /// Highest reached score across all runs.
     pub high_score: u32,
+    /// User's preferences.
+    pub settings: Settings,
+    /// Path of the player in the run with the highest score.
+    pub ghost: GhostPath,
+    /// Version whose changelog the user has already seen.
+    pub last_seen_version: String,
+    /// User's bindings of the controls.
+    pub bindings: KeyBindings,
+    /// Statistics accumulated across all runs.
+    pub stats: LifetimeStats,
+    /// Onboarding hints already shown.
+    pub hints: HintsShown,
+    /// User's preferences of the window.
+    pub window: WindowPrefs,
+    /// Best runs sorted from the highest score, practice runs excluded.
+    pub leaderboard: Vec<ScoreEntry>,
+    /// Results of daily challenges, keyed by their date.
+    pub daily: HashMap<String, DailyRecord>,
+    /// Has the tutorial been completed or skipped?
+    pub tutorial_done: bool,
+}
+
+/// Layout of [Persistent] in saves of version 1 and the last unversioned ones.
+#[derive(Clone, Default, Debug, DeBin, SerBin)]
+struct PersistentV1 {
+    high_score: u32,
+    settings: SettingsV1,
+    ghost: GhostPath,
+    last_seen_version: String,
+    bindings: KeyBindings,
+    stats: LifetimeStats,
+    hints: HintsShown,
+    window: WindowPrefs,
+}
+
+/// Layout of [Persistent] in saves of version 2.
+#[derive(Clone, Default, Debug, DeBin, SerBin)]
+struct PersistentV2 {
+    high_score: u32,
+    settings: SettingsV1,
+    ghost: GhostPath,
+    last_seen_version: String,
+    bindings: KeyBindings,
+    stats: LifetimeStats,
+    hints: HintsShown,
+    window: WindowPrefs,
+    leaderboard: Vec<ScoreEntry>,
+}
+
+impl From<PersistentV1> for PersistentV2 {
+    fn from(old: PersistentV1) -> Self {
+        Self {
+            high_score: old.high_score,
+            settings: old.settings,
+            ghost: old.ghost,
+            last_seen_version: old.last_seen_version,
+            bindings: old.bindings,
+            stats: old.stats,
+            hints: old.hints,
+            window: old.window,
+            leaderboard: Vec::new(),
+        }
+    }
+}
+
+/// Layout of [Persistent] in saves of version 3.
+#[derive(Clone, Default, Debug, DeBin, SerBin)]
+struct PersistentV3 {
+    high_score: u32,
+    settings: SettingsV1,
+    ghost: GhostPath,
+    last_seen_version: String,
+    bindings: KeyBindings,
+    stats: LifetimeStats,
+    hints: HintsShown,
+    window: WindowPrefs,
+    leaderboard: Vec<ScoreEntry>,
+    daily: HashMap<String, DailyRecord>,
+}
+
+impl From<PersistentV2> for PersistentV3 {
+    fn from(old: PersistentV2) -> Self {
+        Self {
+            high_score: old.high_score,
+            settings: old.settings,
+            ghost: old.ghost,
+            last_seen_version: old.last_seen_version,
+            bindings: old.bindings,
+            stats: old.stats,
+            hints: old.hints,
+            window: old.window,
+            leaderboard: old.leaderboard,
+            daily: HashMap::new(),
+        }
+    }
+}
+
+/// Layout of [Persistent] in saves of version 4.
+#[derive(Clone, Default, Debug, DeBin, SerBin)]
+struct PersistentV4 {
+    high_score: u32,
+    settings: SettingsV1,
+    ghost: GhostPath,
+    last_seen_version: String,
+    bindings: KeyBindings,
+    stats: LifetimeStats,
+    hints: HintsShown,
+    window: WindowPrefs,
+    leaderboard: Vec<ScoreEntry>,
+    daily: HashMap<String, DailyRecord>,
+    tutorial_done: bool,
+}
+
+/// Players with older saves are not new, they are not recommended the tutorial.
+impl From<PersistentV3> for PersistentV4 {
+    fn from(old: PersistentV3) -> Self {
+        Self {
+            high_score: old.high_score,
+            settings: old.settings,
+            ghost: old.ghost,
+            last_seen_version: old.last_seen_version,
+            bindings: old.bindings,
+            stats: old.stats,
+            hints: old.hints,
+            window: old.window,
+            leaderboard: old.leaderboard,
+            daily: old.daily,
+            tutorial_done: true,
+        }
+    }
+}
+
+impl From<PersistentV4> for Persistent {
+    fn from(old: PersistentV4) -> Self {
+        Self {
+            high_score: old.high_score,
+            settings: old.settings.into(),
+            ghost: old.ghost,
+            last_seen_version: old.last_seen_version,
+            bindings: old.bindings,
+            stats: old.stats,
+            hints: old.hints,
+            window: old.window,
+            leaderboard: old.leaderboard,
+            daily: old.daily,
+            tutorial_done: old.tutorial_done,
+        }
+    }
+}
+
+/// Layout of [Persistent] in saves of version 5.
+#[derive(Clone, Default, Debug, DeBin, SerBin)]
+struct PersistentV5 {
+    high_score: u32,
+    settings: SettingsV5,
+    ghost: GhostPath,
+    last_seen_version: String,
+    bindings: KeyBindings,
+    stats: LifetimeStats,
+    hints: HintsShown,
+    window: WindowPrefs,
+    leaderboard: Vec<ScoreEntry>,
+    daily: HashMap<String, DailyRecord>,
+    tutorial_done: bool,
+}
+
+impl From<PersistentV5> for Persistent {
+    fn from(old: PersistentV5) -> Self {
+        Self {
+            high_score: old.high_score,
+            settings: old.settings.into(),
+            ghost: old.ghost,
+            last_seen_version: old.last_seen_version,
+            bindings: old.bindings,
+            stats: old.stats,
+            hints: old.hints,
+            window: old.window,
+            leaderboard: old.leaderboard,
+            daily: old.daily,
+            tutorial_done: old.tutorial_done,
+        }
+    }
+}
+
+/// Layout of [Persistent] in saves of version 6.
+#[derive(Clone, Default, Debug, DeBin, SerBin)]
+struct PersistentV6 {
+    high_score: u32,
+    settings: SettingsV6,
+    ghost: GhostPath,
+    last_seen_version: String,
+    bindings: KeyBindings,
+    stats: LifetimeStats,
+    hints: HintsShown,
+    window: WindowPrefs,
+    leaderboard: Vec<ScoreEntry>,
+    daily: HashMap<String, DailyRecord>,
+    tutorial_done: bool,
+}
+
+impl From<PersistentV6> for Persistent {
+    fn from(old: PersistentV6) -> Self {
+        Self {
+            high_score: old.high_score,
+            settings: old.settings.into(),
+            ghost: old.ghost,
+            last_seen_version: old.last_seen_version,
+            bindings: old.bindings,
+            stats: old.stats,
+            hints: old.hints,
+            window: old.window,
+            leaderboard: old.leaderboard,
+            daily: old.daily,
+            tutorial_done: old.tutorial_done,
+        }
+    }
+}
+
+/// Layout of [Persistent] in saves of version 7.
+#[derive(Clone, Default, Debug, DeBin, SerBin)]
+struct PersistentV7 {
+    high_score: u32,
+    settings: SettingsV7,
+    ghost: GhostPath,
+    last_seen_version: String,
+    bindings: KeyBindings,
+    stats: LifetimeStats,
+    hints: HintsShown,
+    window: WindowPrefs,
+    leaderboard: Vec<ScoreEntry>,
+    daily: HashMap<String, DailyRecord>,
+    tutorial_done: bool,
+}
+
+impl From<PersistentV7> for Persistent {
+    fn from(old: PersistentV7) -> Self {
+        Self {
+            high_score: old.high_score,
+            settings: old.settings.into(),
+            ghost: old.ghost,
+            last_seen_version: old.last_seen_version,
+            bindings: old.bindings,
+            stats: old.stats,
+            hints: old.hints,
+            window: old.window,
+            leaderboard: old.leaderboard,
+            daily: old.daily,
+            tutorial_done: old.tutorial_done,
+        }
+    }
+}
+
+/// Statistics accumulated across all runs, practice runs excluded.
+#[derive(Clone, Copy, Default, Debug, DeBin, SerBin)]
+pub struct LifetimeStats {
+    /// Amount of finished runs.
+    pub runs: u32,
+    /// Longest time survived in a single run.
+    pub best_time: f32,
+    /// Total time spent with positive polarity.
+    pub positive_time: f32,
+    /// Total time spent with negative polarity.
+    pub negative_time: f32,
+    /// Total time spent in runs.
+    pub play_time: f32,
+    /// Total xp collected.
+    pub xp: u32,
+    /// Total enemies killed, by their type.
+    pub kills: KillCounts,
+}
+
+impl LifetimeStats {
+    /// Adds a finished run into the statistics.
+    /// # Arguments
+    /// * `run` - statistics of the run
+    /// * `xp` - xp collected during the run
+    pub fn record(&mut self, run: &RunStats, xp: u32) {
+        self.runs += 1;
+        self.best_time = self.best_time.max(run.time);
+        self.positive_time += run.positive_time;
+        self.negative_time += run.negative_time;
+        self.play_time += run.time;
+        self.xp += xp;
+        self.kills.add(&run.kills);
+    }
+}
+
+/// User's preferences changing the behaviour of the game.
+#[derive(Clone, Copy, Debug, DeBin, SerBin)]
+pub struct Settings {
+    /// Should kill banners be shown when significant enemies die?
+    pub kill_banners: bool,
+    /// Should the gamepad rumble?
+    pub rumble: bool,
+    /// Multiplier of the rumble strength, 0.0 to 1.0.
+    pub rumble_intensity: f32,
+    /// Scale of the resolution the world is rendered at.
+    /// One of [RENDER_SCALES].
+    pub render_scale: f32,
+    /// Should cosmetic motion (spinning, particles, fades) be reduced?
+    pub reduced_motion: bool,
+    /// Should the ghost of the best run be shown?
+    pub ghost: bool,
+    /// Should the minimap be shown during runs?
+    pub minimap: bool,
+    /// Should the world be rendered through the CRT filter?
+    pub crt: bool,
+    /// Volume of everything, 0.0 to 1.0.
+    pub master_volume: f32,
+    /// Volume of sound effects, 0.0 to 1.0.
+    pub sfx_volume: f32,
+    /// Volume of the music, 0.0 to 1.0.
+    pub music_volume: f32,
+    /// Should strobing colors be replaced by steady tints and bursts of particles capped?
+    pub reduce_flashing: bool,
+    /// Should charges be marked by glyphs and colored by a colorblind-safe palette?
+    pub colorblind: bool,
+    /// Language of the UI, an index of [crate::locale::LANGUAGES].
+    pub language: u8,
+}
+
+/// Layout of [Settings] in saves up to version 4.
+#[derive(Clone, Copy, Default, Debug, DeBin, SerBin)]
+struct SettingsV1 {
+    kill_banners: bool,
+    rumble: bool,
+    rumble_intensity: f32,
+    render_scale: f32,
+    reduced_motion: bool,
+    ghost: bool,
+    minimap: bool,
+    crt: bool,
+    master_volume: f32,
+    sfx_volume: f32,
+}
+
+impl From<SettingsV1> for Settings {
+    fn from(old: SettingsV1) -> Self {
+        Self {
+            kill_banners: old.kill_banners,
+            rumble: old.rumble,
+            rumble_intensity: old.rumble_intensity,
+            render_scale: old.render_scale,
+            reduced_motion: old.reduced_motion,
+            ghost: old.ghost,
+            minimap: old.minimap,
+            crt: old.crt,
+            master_volume: old.master_volume,
+            sfx_volume: old.sfx_volume,
+            music_volume: Settings::default().music_volume,
+            reduce_flashing: Settings::default().reduce_flashing,
+            colorblind: Settings::default().colorblind,
+            language: Settings::default().language,
+        }
+    }
+}
+
+/// Layout of [Settings] in saves of version 5.
+#[derive(Clone, Copy, Default, Debug, DeBin, SerBin)]
+struct SettingsV5 {
+    kill_banners: bool,
+    rumble: bool,
+    rumble_intensity: f32,
+    render_scale: f32,
+    reduced_motion: bool,
+    ghost: bool,
+    minimap: bool,
+    crt: bool,
+    master_volume: f32,
+    sfx_volume: f32,
+    music_volume: f32,
+}
+
+impl From<SettingsV5> for Settings {
+    fn from(old: SettingsV5) -> Self {
+        Self {
+            kill_banners: old.kill_banners,
+            rumble: old.rumble,
+            rumble_intensity: old.rumble_intensity,
+            render_scale: old.render_scale,
+            reduced_motion: old.reduced_motion,
+            ghost: old.ghost,
+            minimap: old.minimap,
+            crt: old.crt,
+            master_volume: old.master_volume,
+            sfx_volume: old.sfx_volume,
+            music_volume: old.music_volume,
+            reduce_flashing: Settings::default().reduce_flashing,
+            colorblind: Settings::default().colorblind,
+            language: Settings::default().language,
+        }
+    }
+}
+
+/// Layout of [Settings] in saves of version 6.
+#[derive(Clone, Copy, Default, Debug, DeBin, SerBin)]
+struct SettingsV6 {
+    kill_banners: bool,
+    rumble: bool,
+    rumble_intensity: f32,
+    render_scale: f32,
+    reduced_motion: bool,
+    ghost: bool,
+    minimap: bool,
+    crt: bool,
+    master_volume: f32,
+    sfx_volume: f32,
+    music_volume: f32,
+    reduce_flashing: bool,
+}
+
+impl From<SettingsV6> for Settings {
+    fn from(old: SettingsV6) -> Self {
+        Self {
+            kill_banners: old.kill_banners,
+            rumble: old.rumble,
+            rumble_intensity: old.rumble_intensity,
+            render_scale: old.render_scale,
+            reduced_motion: old.reduced_motion,
+            ghost: old.ghost,
+            minimap: old.minimap,
+            crt: old.crt,
+            master_volume: old.master_volume,
+            sfx_volume: old.sfx_volume,
+            music_volume: old.music_volume,
+            reduce_flashing: old.reduce_flashing,
+            colorblind: Settings::default().colorblind,
+            language: Settings::default().language,
+        }
+    }
+}
+
+/// Layout of [Settings] in saves of version 7.
+#[derive(Clone, Copy, Default, Debug, DeBin, SerBin)]
+struct SettingsV7 {
+    kill_banners: bool,
+    rumble: bool,
+    rumble_intensity: f32,
+    render_scale: f32,
+    reduced_motion: bool,
+    ghost: bool,
+    minimap: bool,
+    crt: bool,
+    master_volume: f32,
+    sfx_volume: f32,
+    music_volume: f32,
+    reduce_flashing: bool,
+    colorblind: bool,
+}
+
+impl From<SettingsV7> for Settings {
+    fn from(old: SettingsV7) -> Self {
+        Self {
+            kill_banners: old.kill_banners,
+            rumble: old.rumble,
+            rumble_intensity: old.rumble_intensity,
+            render_scale: old.render_scale,
+            reduced_motion: old.reduced_motion,
+            ghost: old.ghost,
+            minimap: old.minimap,
+            crt: old.crt,
+            master_volume: old.master_volume,
+            sfx_volume: old.sfx_volume,
+            music_volume: old.music_volume,
+            reduce_flashing: old.reduce_flashing,
+            colorblind: old.colorblind,
+            language: Settings::default().language,
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            kill_banners: true,
+            rumble: true,
+            rumble_intensity: 1.0,
+            render_scale: 1.0,
+            reduced_motion: false,
+            ghost: true,
+            minimap: true,
+            crt: false,
+            master_volume: 1.0,
+            sfx_volume: 1.0,
+            music_volume: 1.0,
+            reduce_flashing: false,
+            colorblind: false,
+            language: 0,
+        }
+    }
 }
 
 impl Persistent {
-    /// Load the persistent data from file.
-    pub async fn load() -> Result<Self, macroquad::Error> {
-        //load from file
-        let file = load_file("save.bin").await?;
-        let persist = DeBin::deserialize_bin(&file).unwrap_or_default();
+    /// Returns the highest score of all daily challenges.
+    pub fn daily_best(&self) -> u32 {
+        self.daily
+            .values()
+            .map(|record| record.best)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Adds a finished daily challenge attempt of `date` reaching `score`.
+    /// Only better scores replace the best one of the day.
+    pub fn record_daily(&mut self, date: &str, score: u32) {
+        let record = self.daily.entry(date.to_owned()).or_default();
+        record.attempts += 1;
+        record.best = record.best.max(score);
+    }
+
+    /// Is no run finished on this save yet?
+    pub fn first_run(&self) -> bool {
+        self.stats.runs == 0 && self.high_score == 0
+    }
+
+    /// Load the persistent data from the platform's storage.
+    ///
+    /// Returns [None] when nothing was saved yet.
+    /// Corrupt saves are backed up under [BACKUP_NAME] and replaced by defaults.
+    pub fn load() -> Option<Self> {
+        let file = storage::read(SAVE_NAME)?;
+
+        Some(Self::from_bytes(&file).unwrap_or_else(|| {
+            //keep the corrupt save around before it is overwritten
+            let _ = storage::write(BACKUP_NAME, &file);
+            let persist = Self::default();
+            let _ = persist.save();
+            persist
+        }))
+    }
+
+    /// Load the persistent data without repairing corrupt saves.
+    ///
+    /// Used before the window is created, [Persistent::load] repairs the save later.
+    pub fn peek() -> Option<Self> {
+        let file = storage::read(SAVE_NAME)?;
+
+        Self::from_bytes(&file)
+    }
+
+    /// Deserializes the persistent data of any known save format.
+    ///
+    /// Returns [None] for corrupt or truncated saves and saves of unknown versions.
+    fn from_bytes(file: &[u8]) -> Option<Self> {
+        let Some(versioned) = file.strip_prefix(SAVE_MAGIC) else {
+            return Self::migrate_unversioned(file);
+        };
+        let (&version, payload) = versioned.split_first()?;
+        match version {
+            1 => PersistentV1::deserialize_bin(payload)
+                .ok()
+                .map(|old| PersistentV4::from(PersistentV3::from(PersistentV2::from(old))).into()),
+            2 => PersistentV2::deserialize_bin(payload)
+                .ok()
+                .map(|old| PersistentV4::from(PersistentV3::from(old)).into()),
+            3 => PersistentV3::deserialize_bin(payload)
+                .ok()
+                .map(|old| PersistentV4::from(old).into()),
+            4 => PersistentV4::deserialize_bin(payload).ok().map(Self::from),
+            5 => PersistentV5::deserialize_bin(payload).ok().map(Self::from),
+            6 => PersistentV6::deserialize_bin(payload).ok().map(Self::from),
+            7 => PersistentV7::deserialize_bin(payload).ok().map(Self::from),
+            SAVE_VERSION => DeBin::deserialize_bin(payload).ok(),
+            _ => None,
+        }
+    }
+
+    /// Migrates saves from before the format was versioned.
+    ///
+    /// Saves of the last unversioned layout, same as version 1, are read whole.
+    /// Older ones held only the high score in 4 bytes, other files are corrupt.
+    fn migrate_unversioned(file: &[u8]) -> Option<Self> {
+        if let Ok(persist) = PersistentV1::deserialize_bin(file) {
+            return Some(
+                PersistentV4::from(PersistentV3::from(PersistentV2::from(persist))).into(),
+            );
+        }
+        //they held nothing but the high score, anything else is corrupt
+        let high_score = u32::from_le_bytes(file.try_into().ok()?);
 
-        Ok(persist)
+        Some(Self {
+            high_score,
+            tutorial_done: true,
+            ..Default::default()
+        })
     }
 
-    /// Save the persistent data into a file.
+    /// Save the persistent data into the platform's storage.
+    /// The data is preceded by [SAVE_MAGIC] and [SAVE_VERSION].
+    ///
+    /// Returns an error when the storage cannot be written into.
     pub fn save(&self) -> Result<(), std::io::Error> {
-        //save into le file
-        let mut file = std::fs::File::create("save.bin")?;
+        let mut file = SAVE_MAGIC.to_vec();
+        file.push(SAVE_VERSION);
+        file.extend(self.serialize_bin());
+
+        storage::write(SAVE_NAME, &file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `file` as the save and loads it back.
+    fn load_file(file: &[u8]) -> Persistent {
+        storage::write(SAVE_NAME, file).unwrap();
+        Persistent::load().unwrap()
+    }
+
+    /// Returns a save of version 1 with a high score of `high_score`.
+    fn save_v1(high_score: u32) -> PersistentV1 {
+        PersistentV1 {
+            high_score,
+            last_seen_version: "0.1.0".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn loads_current_version() {
+        let persist = Persistent {
+            high_score: 1234,
+            last_seen_version: "1.0.0".into(),
+            ..Default::default()
+        };
+        persist.save().unwrap();
+
+        let loaded = Persistent::load().unwrap();
+        assert_eq!(loaded.high_score, 1234);
+        assert_eq!(loaded.last_seen_version, "1.0.0");
+    }
+
+    #[test]
+    fn migrates_version_1() {
+        let mut file = SAVE_MAGIC.to_vec();
+        file.push(1);
+        file.extend(save_v1(420).serialize_bin());
+
+        let persist = load_file(&file);
+        assert_eq!(persist.high_score, 420);
+        assert_eq!(persist.last_seen_version, "0.1.0");
+    }
+
+    #[test]
+    fn migrates_unversioned_version_1() {
+        let persist = load_file(&save_v1(69).serialize_bin());
+        assert_eq!(persist.high_score, 69);
+        assert_eq!(persist.last_seen_version, "0.1.0");
+    }
+
+    #[test]
+    fn migrates_high_score_only() {
+        let persist = load_file(&37u32.to_le_bytes());
+        assert_eq!(persist.high_score, 37);
+        assert!(persist.tutorial_done);
+    }
+
+    #[test]
+    fn replaces_corrupt_unversioned() {
+        for file in [&[1, 2, 3][..], &[1, 2, 3, 4, 5], b"definitely not a save"] {
+            let persist = load_file(file);
+            assert_eq!(persist.high_score, 0, "{file:?} is not corrupt");
+            assert_eq!(storage::read(BACKUP_NAME).unwrap(), file);
+        }
+    }
 
-        file.write_all(&self.serialize_bin())?;
+    #[test]
+    fn replaces_corrupt_versioned() {
+        let mut truncated = SAVE_MAGIC.to_vec();
+        truncated.push(SAVE_VERSION);
+        truncated.extend(&Persistent::default().serialize_bin()[..3]);
+        let mut unknown = SAVE_MAGIC.to_vec();
+        unknown.push(SAVE_VERSION + 1);
 
-        Ok(())
+        for file in [truncated, unknown] {
+            let persist = load_file(&file);
+            assert_eq!(persist.high_score, 0);
+            assert_eq!(storage::read(BACKUP_NAME).unwrap(), file);
+        }
     }
 }
@@ -0,0 +1,85 @@
+//! Platform storage of save files.
+//!
+//! Native builds store files in the user's data directory,
+//! web builds store them in the browser's local storage.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use native::*;
+#[cfg(target_arch = "wasm32")]
+pub use web::*;
+
+/// Storage in files of the user's data directory.
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::path::PathBuf;
+
+    /// Directory of the game inside the user's data directory.
+    const DATA_DIR: &str = "magnet-fury";
+
+    /// Returns the path of the file called `name`.
+    /// Falls back to the working directory when there is no data directory.
+    #[cfg(not(test))]
+    fn path(name: &str) -> PathBuf {
+        dirs::data_dir()
+            .map(|dir| dir.join(DATA_DIR))
+            .unwrap_or_default()
+            .join(name)
+    }
+
+    /// Returns the path of the file called `name`.
+    /// Tests keep their files in a temporary directory of their thread,
+    /// away from the user's saves and from each other.
+    #[cfg(test)]
+    fn path(name: &str) -> PathBuf {
+        let thread = format!("{:?}", std::thread::current().id());
+        std::env::temp_dir()
+            .join(DATA_DIR)
+            .join(thread.trim_matches(|c: char| !c.is_ascii_digit()))
+            .join(name)
+    }
+
+    /// Reads the file called `name`.
+    ///
+    /// Returns [None] when there is no such file.
+    /// Files saved into the working directory by older versions are read as well.
+    pub fn read(name: &str) -> Option<Vec<u8>> {
+        std::fs::read(path(name))
+            .or_else(|_| std::fs::read(name))
+            .ok()
+    }
+
+    /// Writes `bytes` into the file called `name`.
+    pub fn write(name: &str, bytes: &[u8]) -> Result<(), std::io::Error> {
+        let path = path(name);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, bytes)
+    }
+}
+
+/// Storage in the browser's local storage.
+/// It only holds strings, so the bytes are stored as hex.
+#[cfg(target_arch = "wasm32")]
+mod web {
+    /// Reads the entry called `name`.
+    ///
+    /// Returns [None] when there is no such entry or it is not valid hex.
+    pub fn read(name: &str) -> Option<Vec<u8>> {
+        let hex = quad_storage::STORAGE.lock().ok()?.get(name)?;
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+            .collect()
+    }
+
+    /// Writes `bytes` into the entry called `name`.
+    pub fn write(name: &str, bytes: &[u8]) -> Result<(), std::io::Error> {
+        let hex: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+        quad_storage::STORAGE
+            .lock()
+            .map_err(|_| std::io::Error::other("local storage is poisoned"))?
+            .set(name, &hex);
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+//! Health pickups dropped by enemies.
+
+use hecs::{CommandBuffer, EntityBuilder, World};
+use macroquad::prelude::*;
+
+use crate::{
+    basic::{
+        render::Circle, FadeOutOnExpiry, Health, HitEvent, HurtBox, Lifetime, Position, Team,
+        Wrapped,
+    },
+    player::Player,
+};
+
+/// Default chance of a killed enemy dropping a health pickup.
+pub const HEALTH_DROP_CHANCE: f32 = 0.03;
+/// Health restored by a single pickup.
+const PICKUP_HEAL: f32 = 3.0;
+/// Time before an uncollected pickup disappears.
+const PICKUP_LIFETIME: f32 = 15.0;
+/// Time before despawning over which a health pickup fades out.
+const PICKUP_FADE: f32 = 2.0;
+/// Distance at which the pickup is collected by the player.
+const PICKUP_RADIUS: f32 = 10.0;
+/// Radius of the pickup's circle.
+const PICKUP_SIZE: f32 = 5.0;
+
+//-----------------------------------------------------------------------------
+//COMPONENT PART
+//-----------------------------------------------------------------------------
+
+/// Pickup healing the player when collected.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HealthPickup {
+    /// Amount of health restored.
+    pub heal: f32,
+}
+
+/// Resource configuring health pickups dropped by killed enemies.
+#[derive(Clone, Copy, Debug)]
+pub struct HealthDrops {
+    /// Chance (0.0 to 1.0) of a killed enemy dropping a pickup.
+    pub chance: f32,
+}
+
+impl Default for HealthDrops {
+    fn default() -> Self {
+        Self {
+            chance: HEALTH_DROP_CHANCE,
+        }
+    }
+}
+
+//-----------------------------------------------------------------------------
+//ENTITY CREATION
+//-----------------------------------------------------------------------------
+
+/// Creates a health pickup entity.
+/// # Arguments
+/// * `pos` - position of the pickup
+pub fn create_health_pickup(pos: Vec2) -> EntityBuilder {
+    let mut builder = EntityBuilder::new();
+
+    builder.add_bundle((
+        Position { x: pos.x, y: pos.y },
+        HealthPickup { heal: PICKUP_HEAL },
+        HurtBox {
+            radius: PICKUP_RADIUS,
+        },
+        Circle {
+            radius: PICKUP_SIZE,
+            color: GREEN,
+            z_index: 0,
+        },
+        Lifetime::new(PICKUP_LIFETIME),
+        FadeOutOnExpiry {
+            seconds: PICKUP_FADE,
+        },
+        Team::Player,
+        Wrapped,
+    ));
+
+    builder
+}
+
+//-----------------------------------------------------------------------------
+//SYSTEM PART
+//-----------------------------------------------------------------------------
+
+/// Returns the chance of a killed enemy dropping a health pickup.
+/// No pickups are dropped without a [HealthDrops] resource.
+pub fn drop_chance(world: &mut World) -> f32 {
+    world
+        .query_mut::<&HealthDrops>()
+        .into_iter()
+        .next()
+        .map(|(_, drops)| drops.chance)
+        .unwrap_or(0.0)
+}
+
+/// Heals the player by health pickups they touched and despawns them.
+pub fn health_pickups(world: &mut World, events: &mut World, cmd: &mut CommandBuffer) {
+    //find player
+    let mut player_query = world.query::<&mut Health>().with::<&Player>();
+    let Some((player_id, player_hp)) = player_query.iter().next() else {
+        return;
+    };
+    //check events for collisions
+    for (_, hit_event) in events.query_mut::<&HitEvent>() {
+        //is the one hit a player?
+        if hit_event.who != player_id {
+            continue;
+        }
+        //is the one who hit a pickup?
+        let Ok(pickup) = world.get::<&HealthPickup>(hit_event.by) else {
+            continue;
+        };
+
+        player_hp.heal(pickup.heal);
+        cmd.despawn(hit_event.by);
+    }
+}
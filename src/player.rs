@@ -3,26 +3,38 @@
 use std::f32::consts::PI;
 
 use hecs::World;
-use macroquad::{audio::PlaySoundParams, prelude::*};
+use macroquad::prelude::*;
 
 use crate::{
     basic::{
+        arena::{self, Arena},
         fx::{FxManager, Particle},
-        motion::{ChargeReceiver, ChargeSender, PhysicsMotion},
-        render::{AssetManager, Sprite},
-        DamageDealer, Health, HitBox, HitEvent, Position, Rotation, Team, Wrapped,
+        motion::{
+            ChargeDisable, ChargeReceiver, ChargeSender, KnockbackReflect, NetForce, PhysicsMotion,
+            RecentKnockback, KNOCKBACK_CANCEL_WINDOW,
+        },
+        render::{AnimatedSprite, AssetManager, HitFlash, Sprite},
+        rumble, DamageDealer, Health, HealthDisplay, HitBox, HitEvent, Position, Rotation, Team,
+        Timer, Wrapped,
     },
+    config,
+    enemy::RunStats,
+    game::run_rng,
+    input::{Aim, PlayerInput},
     projectile::{self, ProjectileType},
-    world_mouse_pos, SPACE_HEIGHT, SPACE_WIDTH,
+    score::Combo,
+    upgrade::{PlayerUpgrades, SPREAD_ANGLE},
+    xp::Grapple,
+    SPACE_HEIGHT, SPACE_WIDTH,
 };
 
 /// Player's acceleration when thrusters are on.
-const PLAYER_ACCEL: f32 = 600.0;
+pub(crate) const PLAYER_ACCEL: f32 = 600.0;
 /// Player's mass for physics
-const PLAYER_MASS: f32 = 10.0;
+pub(crate) const PLAYER_MASS: f32 = 10.0;
 
 /// Force applied by Player's charge.
-const PLAYER_CHARGE_FORCE: f32 = 200.0;
+pub(crate) const PLAYER_CHARGE_FORCE: f32 = 200.0;
 /// Radius where Player's charge is at strongest.
 const PLAYER_CHARGE_FULL_RADIUS: f32 = 150.0;
 /// Radius where Player's charge is first zero.
@@ -30,41 +42,187 @@ const PLAYER_CHARGE_FULL_RADIUS: f32 = 150.0;
 const PLAYER_CHARGE_RADIUS: f32 = 300.0;
 
 /// Player's max health.
-const PLAYER_MAX_BASE_HP: f32 = 10.0;
+pub(crate) const PLAYER_MAX_BASE_HP: f32 = 10.0;
 /// Player's health regeneration.
-const PLAYER_BASE_HP_REGEN: f32 = 0.3;
+pub(crate) const PLAYER_BASE_HP_REGEN: f32 = 0.3;
 
 /// Player's cooldown between projectiles.
-const PLAYER_FIRE_COOLDOWN: f32 = 0.15;
+pub(crate) const PLAYER_FIRE_COOLDOWN: f32 = 0.15;
+/// Fraction of max health below which the Player is warned about low health.
+const LOW_HEALTH_FRACTION: f32 = 0.3;
+/// Time between heartbeats while at low health.
+const HEARTBEAT_INTERVAL: f32 = 0.8;
+/// Volume of the heartbeat.
+const HEARTBEAT_VOLUME: f32 = 0.6;
+/// Width of the low health vignette at the edges of the space.
+const VIGNETTE_WIDTH: f32 = 60.0;
+/// Amount of bands the vignette fades out in.
+const VIGNETTE_BANDS: u32 = 10;
+/// Opacity of the vignette's outermost band right on a heartbeat.
+const VIGNETTE_ALPHA: f32 = 0.5;
+/// Strength of the vignette and the health bar blink between heartbeats.
+const LOW_HEALTH_MIN_PULSE: f32 = 0.3;
+
+/// Color the Player flashes with when hit.
+const PLAYER_HIT_FLASH_COLOR: Color = RED;
 /// Player's cooldown between hits.
-const PLAYER_INVUL_COOLDOWN: f32 = 1.0;
+pub(crate) const PLAYER_INVUL_COOLDOWN: f32 = 1.0;
+/// Distance of the Player's nose from its center.
+/// Projectiles are fired from there.
+const PLAYER_MUZZLE_OFFSET: f32 = 14.0;
+/// Impulse pushing the Player back on every shot.
+pub(crate) const PLAYER_RECOIL: f32 = 80.0;
+
+/// Time the fire button must be held before the railgun starts charging.
+/// Shorter presses fire regular shots.
+const RAIL_CHARGE_DELAY: f32 = 0.3;
+/// Time of charging after which the railgun shot is the strongest.
+const RAIL_MAX_CHARGE: f32 = 1.2;
+/// Damage of a railgun shot released right after it started charging.
+const RAIL_MIN_DMG: f32 = 0.5;
+/// Damage of a fully charged railgun shot.
+const RAIL_MAX_DMG: f32 = 3.0;
+/// Speed of railgun shots.
+const RAIL_SPEED: f32 = 900.0;
+/// Time railgun shots live for.
+const RAIL_LIFETIME: f32 = 1.5;
+/// Amount of enemies a railgun shot passes through before it is spent.
+const RAIL_PIERCE: u8 = 3;
+/// Impulse pushing the Player back with a fully charged railgun shot.
+const RAIL_RECOIL: f32 = 400.0;
+
+/// Spread angle added by every shot, in radians.
+const BLOOM_PER_SHOT: f32 = 0.04;
+/// Max spread angle of the Player's shots, in radians.
+const BLOOM_MAX: f32 = 0.3;
+/// Spread angle recovered every second, in radians.
+const BLOOM_DECAY: f32 = 0.5;
+/// Radius of the aim reticle with no spread.
+const RETICLE_MIN_RADIUS: f32 = 4.0;
+/// Color of the aim reticle.
+const RETICLE_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.35);
+/// Portion of a knockback's velocity cancelled by flipping polarity right after it.
+const KNOCKBACK_CANCEL_FRACTION: f32 = 0.5;
+
+/// Impulse of the Player's dash.
+pub(crate) const PLAYER_DASH_IMPULSE: f32 = 6000.0;
+/// Duration of the Player's dash.
+/// The Player is invulnerable and ignores charges for its duration.
+pub(crate) const PLAYER_DASH_TIME: f32 = 0.3;
+/// Player's cooldown between dashes.
+pub(crate) const PLAYER_DASH_COOLDOWN: f32 = 2.0;
 
 /// Player's texture ID representing positive player.
 pub const PLAYER_TEX_POSITIVE: &str = "player_plus";
 /// Player's texture ID representing negative player.
 pub const PLAYER_TEX_NEGATIVE: &str = "player_negative";
+/// Texture ID of the atlas of the Player's thruster flame.
+pub const PLAYER_FLAME_TEX: &str = "thruster_flame";
+/// All texture IDs used by the Player's sprites.
+pub const PLAYER_TEXTURES: [&str; 3] = [PLAYER_TEX_POSITIVE, PLAYER_TEX_NEGATIVE, PLAYER_FLAME_TEX];
+
+/// Size of a single frame of the thruster flame's atlas.
+/// The frame is centered at the Player, the flame is at its back.
+const PLAYER_FLAME_FRAME: Vec2 = vec2(64.0, 32.0);
+/// Amount of frames of the thruster flame.
+const PLAYER_FLAME_FRAMES: u32 = 4;
+/// Frames per second of the thruster flame.
+const PLAYER_FLAME_FPS: f32 = 20.0;
 
 /// Size of the Player.
 /// Also influences the size of Player's Hit/HurtBox.
 const PLAYER_SIZE: f32 = 30.0;
 
+/// Net charge force above which the Player's sprite is tinted.
+/// Around the pull of a single asteroid at full strength.
+const FORCE_TINT_THRESHOLD: f32 = 150.0;
+/// Tint of the Player's sprite under a strong charge force.
+const FORCE_TINT: Color = Color::new(1.0, 0.85, 0.7, 1.0);
+/// Force at which the arrow of [ForceDisplay] reaches its full length.
+const FORCE_DISPLAY_MAX: f32 = 500.0;
+/// Forces much smaller than this are scaled linearly by [ForceDisplay],
+/// larger ones logarithmically.
+const FORCE_DISPLAY_KNEE: f32 = 10.0;
+
+/// Shows the cooldown of the Player's dash as a bar.
+#[derive(Clone, Copy, Debug)]
+pub struct DashDisplay {
+    /// Width of the bar when the dash is ready.
+    pub max_width: f32,
+    /// Height of the bar.
+    pub height: f32,
+    /// Color of the bar when the dash is ready.
+    pub color: Color,
+    /// Color of the bar while the dash recharges.
+    pub charging_color: Color,
+}
+
+/// Shows the direction and magnitude of the charge force acting on the Player.
+/// The length of the arrow is scaled logarithmically, so weak pulls stay visible.
+#[derive(Clone, Copy, Debug)]
+pub struct ForceDisplay {
+    /// Radius of the dial, the length of the arrow at [FORCE_DISPLAY_MAX].
+    pub radius: f32,
+    /// Color of the dial.
+    pub dial_color: Color,
+    /// Color of the arrow.
+    pub color: Color,
+    /// Color of the arrow when the force is strong enough to tint the Player.
+    pub strong_color: Color,
+}
+
+/// Warns the player about their low health, present on them only while it is low.
+#[derive(Clone, Copy, Debug)]
+pub struct LowHealth {
+    /// Time before the next heartbeat.
+    beat: Timer,
+}
+
+impl LowHealth {
+    /// Returns the strength of the warning's pulse, highest right on a heartbeat.
+    pub fn pulse(&self) -> f32 {
+        let fade = 1.0 - self.beat.elapsed / self.beat.duration;
+        LOW_HEALTH_MIN_PULSE + (1.0 - LOW_HEALTH_MIN_PULSE) * fade * fade
+    }
+}
+
+/// Marks the player after their death.
+/// Dead players do not act, collide nor emit charge, they stay where they died.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Dead;
+
 /// This componenet handles all of the player's logic.
 #[derive(Debug)]
 pub struct Player {
     /// Time before another shot can be fired.
-    fire_timer: f32,
+    fire_timer: Timer,
+    /// Time the fire button has been held for.
+    fire_held: f32,
+    /// Charge of the railgun, from 0.0 (not charging) to 1.0 (fully charged).
+    rail_charge: f32,
+    /// Current spread angle of the shots, grows with every shot.
+    /// Shots deviate from the aim by up to this angle to either side.
+    spread: f32,
     /// Time before another hit can be taken.
-    invul_timer: f32,
+    invul_timer: Timer,
+    /// Time before another dash can be done.
+    dash_cooldown: Timer,
+    /// Time before the current dash ends.
+    dash_timer: Timer,
     /// Charge of the player.
     /// 1 => positive
     /// -1 => negative
     polarity: i8,
     /// Has the player already exploded into particles when dead?
     dead_burst: bool,
-    /// Should the thruster's sound play?
-    jet_sound_playing: bool,
+    /// Are the thrusters on?
+    thrusting: bool,
     /// Should the shooting sound play?
     shoot_sound: bool,
+    /// Should the muzzle flash be shown?
+    muzzle_flash: bool,
+    /// Should the spark of a cancelled knockback be shown?
+    counter_spark: bool,
 
     /// Score the player got this game.
     pub xp: u32,
@@ -73,28 +231,48 @@ pub struct Player {
 impl Player {
     /// Creates a new default Player component.
     pub fn new() -> Self {
+        let tuning = config::get().player;
         Self {
-            fire_timer: 0.0,
-            invul_timer: 0.0,
+            fire_timer: Timer::new_finished(tuning.fire_cooldown),
+            fire_held: 0.0,
+            rail_charge: 0.0,
+            spread: 0.0,
+            invul_timer: Timer::new_finished(tuning.invul_cooldown),
+            dash_cooldown: Timer::new_finished(tuning.dash_cooldown),
+            dash_timer: Timer::new_finished(tuning.dash_time),
 
             polarity: 1,
 
             dead_burst: false,
 
-            jet_sound_playing: false,
+            thrusting: false,
             shoot_sound: false,
+            muzzle_flash: false,
+            counter_spark: false,
 
             xp: 0,
         }
     }
+
+    /// Returns the player's polarity.
+    /// 1 => positive
+    /// -1 => negative
+    pub fn polarity(&self) -> i8 {
+        self.polarity
+    }
+
+    /// Can the player fire right now?
+    pub fn can_fire(&self) -> bool {
+        self.fire_timer.finished()
+    }
 }
 
 //-----------------------------------------------------------------------------
 //ENTITY GEN
 //-----------------------------------------------------------------------------
 
-/// Create an entire feature complete Player.
-pub fn new_entity() -> (
+/// Components of the player, as returned by [new_entity].
+pub type PlayerBundle = (
     Player,
     Position,
     PhysicsMotion,
@@ -106,21 +284,31 @@ pub fn new_entity() -> (
     Sprite,
     ChargeReceiver,
     ChargeSender,
-) {
+    Grapple,
+    PlayerUpgrades,
+    AnimatedSprite,
+    NetForce,
+);
+
+/// Create an entire feature complete Player.
+/// # Arguments
+/// * `arena` - play space the player is placed in the center of
+pub fn new_entity(arena: &Arena) -> PlayerBundle {
+    let tuning = config::get().player;
     (
         Player::new(),
         Position {
-            x: SPACE_WIDTH / 2.0,
-            y: SPACE_HEIGHT / 2.0,
+            x: arena.center().x,
+            y: arena.center().y,
         },
         PhysicsMotion {
             vel: Vec2::ZERO,
-            mass: PLAYER_MASS,
+            mass: tuning.mass,
         },
         Rotation::default(),
         Health {
-            hp: PLAYER_MAX_BASE_HP,
-            max_hp: PLAYER_MAX_BASE_HP,
+            hp: tuning.max_hp,
+            max_hp: tuning.max_hp,
         },
         HitBox { radius: 7.0 },
         Team::Player,
@@ -133,10 +321,23 @@ pub fn new_entity() -> (
         },
         ChargeReceiver { multiplier: 0.2 },
         ChargeSender {
-            force: PLAYER_CHARGE_FORCE,
+            force: tuning.charge_force,
             full_radius: PLAYER_CHARGE_FULL_RADIUS,
             no_radius: PLAYER_CHARGE_RADIUS,
         },
+        Grapple::default(),
+        PlayerUpgrades::default(),
+        AnimatedSprite {
+            texture: PLAYER_FLAME_TEX,
+            frame_size: PLAYER_FLAME_FRAME,
+            frames: PLAYER_FLAME_FRAMES,
+            fps: PLAYER_FLAME_FPS,
+            time: 0.0,
+            scale: 1.0,
+            color: Color::new(1.0, 1.0, 1.0, 0.0),
+            z_index: -1,
+        },
+        NetForce::default(),
     )
 }
 
@@ -145,91 +346,248 @@ pub fn new_entity() -> (
 //-----------------------------------------------------------------------------
 
 /// Handles the weapon logic of the player.
-pub fn weapons(world: &mut World, cmd: &mut hecs::CommandBuffer, dt: f32) {
+pub fn weapons(world: &mut World, cmd: &mut hecs::CommandBuffer, input: &PlayerInput, dt: f32) {
+    let mut rng = run_rng(world);
     //get player
-    let (_, (player, vel, angle, pos, charge_send, charge_receive)) = world
+    let Some((
+        player_id,
+        (player, vel, angle, pos, charge_send, charge_receive, knockback, upgrades),
+    )) = world
         .query_mut::<(
             &mut Player,
-            &PhysicsMotion,
+            &mut PhysicsMotion,
             &Rotation,
             &Position,
             &mut ChargeSender,
             &mut ChargeReceiver,
+            Option<&RecentKnockback>,
+            &PlayerUpgrades,
         )>()
+        .without::<&Dead>()
         .into_iter()
         .next()
-        .unwrap();
+    else {
+        return;
+    };
+    let tuning = config::get().player;
+    let mut shots = Vec::new();
     //decrement timer
-    player.fire_timer -= dt;
+    player.fire_timer.duration = tuning.fire_cooldown * upgrades.fire_cooldown_mult();
+    player.fire_timer.tick(dt);
+    //accuracy recovers over time
+    player.spread = (player.spread - BLOOM_DECAY * dt).max(0.0);
+    //holding fire charges the railgun
+    if input.fire {
+        player.fire_held += dt;
+    }
+    let charging = player.fire_held >= RAIL_CHARGE_DELAY;
+    player.rail_charge = if charging {
+        ((player.fire_held - RAIL_CHARGE_DELAY) / RAIL_MAX_CHARGE).min(1.0)
+    } else {
+        0.0
+    };
+    //releasing it fires the railgun
+    let mut railed = false;
+    if !input.fire {
+        if charging {
+            let facing = Vec2::from_angle(angle.angle).rotate(Vec2::X);
+            let rail = projectile::with_lifetime(
+                projectile::create_projectile(
+                    vec2(pos.x, pos.y) + facing * PLAYER_MUZZLE_OFFSET,
+                    facing * RAIL_SPEED + vec2(vel.vel.x, vel.vel.y),
+                    RAIL_MIN_DMG + (RAIL_MAX_DMG - RAIL_MIN_DMG) * player.rail_charge,
+                    Team::Player,
+                    ProjectileType::Medium { charge: 0 },
+                ),
+                RAIL_LIFETIME,
+            );
+            cmd.spawn(projectile::piercing(rail, RAIL_PIERCE).build());
+            //recoil
+            vel.apply_force(-facing * RAIL_RECOIL * player.rail_charge, 1.0);
+            //schedule to play sound and flash
+            player.shoot_sound = true;
+            player.muzzle_flash = true;
+            railed = true;
+        }
+        player.fire_held = 0.0;
+        player.rail_charge = 0.0;
+    }
     //shoot
-    if player.fire_timer <= 0.0 && is_mouse_button_down(MouseButton::Right) {
+    if player.fire_timer.finished() && input.fire && !charging {
         //reset timer
-        player.fire_timer = PLAYER_FIRE_COOLDOWN;
-        //fire
-        cmd.spawn(projectile::create_projectile(
-            vec2(pos.x, pos.y),
-            Vec2::from_angle(angle.angle).rotate(Vec2::X) * 250.0 + vec2(vel.vel.x, vel.vel.y),
-            0.2,
-            Team::Player,
-            ProjectileType::Small {
-                charge: -player.polarity,
-            },
-        ));
-        //schedule to play sound
+        player.fire_timer.reset();
+        //fire from the nose
+        let facing = Vec2::from_angle(angle.angle).rotate(Vec2::X);
+        //deviate within the current spread, then spread further
+        let deviation = (rng.f32() * 2.0 - 1.0) * player.spread;
+        player.spread = (player.spread + BLOOM_PER_SHOT * upgrades.bloom_mult()).min(BLOOM_MAX);
+        //spread shots fire a fan of three
+        let spread: &[f32] = if upgrades.spread_shot {
+            &[-SPREAD_ANGLE, 0.0, SPREAD_ANGLE]
+        } else {
+            &[0.0]
+        };
+        for offset in spread {
+            let dir = Vec2::from_angle(angle.angle + offset + deviation).rotate(Vec2::X);
+            shots.push(projectile::create_projectile(
+                vec2(pos.x, pos.y) + facing * PLAYER_MUZZLE_OFFSET,
+                dir * 250.0 + vec2(vel.vel.x, vel.vel.y),
+                0.2,
+                Team::Player,
+                ProjectileType::Small {
+                    charge: -player.polarity,
+                },
+            ));
+        }
+        //recoil
+        vel.apply_force(-facing * tuning.recoil, 1.0);
+        //schedule to play sound and flash
         player.shoot_sound = true;
+        player.muzzle_flash = true;
     }
 
     //polarity switching
-    if is_key_pressed(KeyCode::A) {
+    if input.flip_polarity {
         player.polarity = -player.polarity;
         //change charge
         charge_receive.multiplier = 1.0 * player.polarity as f32;
-        charge_send.force = PLAYER_CHARGE_FORCE * player.polarity as f32;
+        charge_send.force =
+            tuning.charge_force * upgrades.charge_force_mult() * player.polarity as f32;
+        //flipping right after a knockback cancels a part of it
+        if let Some(knockback) = knockback {
+            if knockback.since <= KNOCKBACK_CANCEL_WINDOW {
+                vel.vel -= knockback.vel * KNOCKBACK_CANCEL_FRACTION;
+                cmd.remove_one::<RecentKnockback>(player_id);
+                player.counter_spark = true;
+            }
+        }
+    }
+
+    //light tick per shot
+    if !shots.is_empty() {
+        for shot in shots {
+            projectile::queue_projectile(world, cmd, shot);
+        }
+        rumble::queue_rumble(world, 0.15, 0.05);
+    }
+    //heavy kick of the railgun
+    if railed {
+        rumble::queue_rumble(world, 0.5, 0.15);
     }
 }
 
 /// Handles thruster and mouse following logic of Player.
-pub fn motion_update(world: &mut World, dt: f32) {
+pub fn motion_update(world: &mut World, input: &PlayerInput, dt: f32) {
     //get player
-    let (_, (vel, angle, pos)) = world
-        .query_mut::<(&mut PhysicsMotion, &mut Rotation, &mut Position)>()
-        .with::<&Player>()
+    let Some((_, (player, vel, angle, pos))) = world
+        .query_mut::<(
+            &mut Player,
+            &mut PhysicsMotion,
+            &mut Rotation,
+            &mut Position,
+        )>()
+        .without::<&Dead>()
         .into_iter()
         .next()
-        .unwrap();
+    else {
+        return;
+    };
+    let tuning = config::get().player;
+    player.thrusting = input.thrust;
     //motion friction
-    if is_mouse_button_down(MouseButton::Left) {
+    if input.thrust {
         vel.vel.x *= 0.7_f32.powf(dt);
         vel.vel.y *= 0.7_f32.powf(dt);
     } else {
         vel.vel.x *= 0.3_f32.powf(dt);
         vel.vel.y *= 0.3_f32.powf(dt);
     }
-    //follow mouse
-    let mouse_pos = world_mouse_pos();
-    angle.angle = (mouse_pos.y - pos.y).atan2(mouse_pos.x - pos.x);
+    //follow mouse or stick
+    angle.angle = match input.aim {
+        Aim::Point(point) => (point.y - pos.y).atan2(point.x - pos.x),
+        Aim::Direction(dir) => dir.y.atan2(dir.x),
+    };
     //input handling
-    if is_mouse_button_down(MouseButton::Left) {
-        vel.vel.x += angle.angle.cos() * PLAYER_ACCEL * dt;
-        vel.vel.y += angle.angle.sin() * PLAYER_ACCEL * dt;
+    if input.thrust {
+        vel.vel.x += angle.angle.cos() * tuning.accel * dt;
+        vel.vel.y += angle.angle.sin() * tuning.accel * dt;
     }
     //euler integration
     pos.x += vel.vel.x * dt;
     pos.y += vel.vel.y * dt;
+
+    //track time spent in each polarity
+    let polarity = player.polarity;
+    for (_, stats) in world.query_mut::<&mut RunStats>() {
+        stats.time += dt;
+        if polarity > 0 {
+            stats.positive_time += dt;
+        } else {
+            stats.negative_time += dt;
+        }
+    }
+}
+
+/// Handles the Player's dash in the aim direction.
+///
+/// While dashing the Player is invulnerable, ignores charges
+/// and reflects knockback onto whoever dealt it.
+pub fn dash(world: &mut World, cmd: &mut hecs::CommandBuffer, input: &PlayerInput, dt: f32) {
+    //get player
+    let Some((player_id, (player, vel, angle))) = world
+        .query_mut::<(&mut Player, &mut PhysicsMotion, &Rotation)>()
+        .without::<&Dead>()
+        .into_iter()
+        .next()
+    else {
+        return;
+    };
+    let tuning = config::get().player;
+    player.dash_cooldown.tick(dt);
+    player.dash_timer.tick(dt);
+    //end of the dash
+    if player.dash_timer.just_finished() {
+        cmd.remove_one::<KnockbackReflect>(player_id);
+    }
+
+    if !input.dash || !player.dash_cooldown.finished() {
+        return;
+    }
+    player.dash_cooldown.reset();
+    player.dash_timer.reset();
+    //dash where the player aims
+    let facing = Vec2::from_angle(angle.angle).rotate(Vec2::X);
+    vel.apply_force(facing * tuning.dash_impulse, 1.0);
+    //invulnerable for the dash only
+    player.invul_timer.reset();
+    player.invul_timer.elapsed = tuning.invul_cooldown - tuning.dash_time;
+    cmd.insert(
+        player_id,
+        (
+            ChargeDisable {
+                timer: Timer::new(tuning.dash_time, false),
+            },
+            KnockbackReflect,
+        ),
+    );
 }
 
 /// Handles Player damage reception and invulnerability frames.
-pub fn health(world: &mut World, events: &mut World, dt: f32) {
+pub fn health(world: &mut World, events: &mut World, cmd: &mut hecs::CommandBuffer, dt: f32) {
     //get player
-    let player_query = &mut world.query::<(&mut Player, &mut Health)>();
-    let (player_id, (player, player_hp)) = player_query.into_iter().next().unwrap();
+    let player_query = &mut world
+        .query::<(&mut Player, &mut Health)>()
+        .without::<&Dead>();
+    let Some((player_id, (player, player_hp))) = player_query.into_iter().next() else {
+        return;
+    };
     //move invul frames
-    player.invul_timer -= dt;
-    if player.invul_timer > 0.0 {
+    player.invul_timer.tick(dt);
+    if !player.invul_timer.finished() {
         return;
     }
     //health regen
-    player_hp.heal(PLAYER_BASE_HP_REGEN * dt);
+    player_hp.heal(config::get().player.hp_regen * dt);
     //get events concerning the player
     let hit_events = events
         .query_mut::<&HitEvent>()
@@ -247,18 +605,107 @@ pub fn health(world: &mut World, events: &mut World, dt: f32) {
         //apply it
         player_hp.hp -= damage.dmg;
         //set invul frames
-        player.invul_timer = PLAYER_INVUL_COOLDOWN;
+        player.invul_timer.reset();
+        cmd.insert_one(player_id, HitFlash::new(PLAYER_HIT_FLASH_COLOR));
+        //getting hit breaks the combo
+        for (_, combo) in world.query::<&mut Combo>().iter() {
+            combo.reset();
+        }
+        //strong pulse
+        rumble::queue_rumble(world, 0.8, 0.25);
+    }
+}
+
+/// Warns the Player about low health with heartbeats and a blinking health bar.
+///
+/// Stops once the health regenerates over [LOW_HEALTH_FRACTION] or the Player dies.
+pub fn low_health_warning(
+    world: &mut World,
+    cmd: &mut hecs::CommandBuffer,
+    assets: &AssetManager,
+    dt: f32,
+) {
+    let Some((player_id, (health, warning, dead))) = world
+        .query_mut::<(&Health, Option<&mut LowHealth>, Option<&Dead>)>()
+        .with::<&Player>()
+        .into_iter()
+        .next()
+    else {
+        return;
+    };
+    let low = dead.is_none() && health.hp < health.max_hp * LOW_HEALTH_FRACTION;
+    let pulse = match (low, warning) {
+        //keep beating
+        (true, Some(warning)) => {
+            warning.beat.tick(dt);
+            if warning.beat.just_finished() {
+                assets.audio().play("heartbeat", HEARTBEAT_VOLUME);
+            }
+            Some(warning.pulse())
+        }
+        //start beating right away
+        (true, None) => {
+            let warning = LowHealth {
+                beat: Timer::new(HEARTBEAT_INTERVAL, true),
+            };
+            cmd.insert_one(player_id, warning);
+            assets.audio().play("heartbeat", HEARTBEAT_VOLUME);
+            Some(warning.pulse())
+        }
+        (false, Some(_)) => {
+            cmd.remove_one::<LowHealth>(player_id);
+            None
+        }
+        (false, None) => None,
+    };
+    //blink the health bar
+    for (_, display) in world.query_mut::<&mut HealthDisplay>() {
+        if display.target == player_id {
+            display.color.a = pulse.unwrap_or(1.0);
+        }
+    }
+}
+
+/// Marks the player out of health as [Dead].
+///
+/// Stops them moving and removes their [HitBox] and charges,
+/// so that they neither collide nor push anything around.
+pub fn death(world: &mut World) {
+    let dead: Vec<_> = world
+        .query_mut::<(&Health, &mut PhysicsMotion)>()
+        .with::<&Player>()
+        .without::<&Dead>()
+        .into_iter()
+        .filter(|(_, (health, _))| health.hp <= 0.0)
+        .map(|(id, (_, physics))| {
+            physics.vel = Vec2::ZERO;
+            id
+        })
+        .collect();
+    for id in dead {
+        let _ = world.remove::<(HitBox, ChargeSender, ChargeReceiver)>(id);
+        let _ = world.insert_one(id, Dead);
     }
 }
 
 /// Handles the sound and visuals (particles) the Player makes.
 pub fn audio_visuals(world: &mut World, fx: &mut FxManager, assets: &AssetManager) {
     //get player
-    let (_, (player, pos, rotation, sprite, health)) = world
-        .query_mut::<(&mut Player, &Position, &Rotation, &mut Sprite, &Health)>()
+    let Some((_, (player, pos, rotation, sprite, flame, health, net))) = world
+        .query_mut::<(
+            &mut Player,
+            &Position,
+            &Rotation,
+            &mut Sprite,
+            &mut AnimatedSprite,
+            &Health,
+            Option<&NetForce>,
+        )>()
         .into_iter()
         .next()
-        .unwrap();
+    else {
+        return;
+    };
 
     //change texture based on polarity
     sprite.texture = if player.polarity > 0 {
@@ -267,8 +714,15 @@ pub fn audio_visuals(world: &mut World, fx: &mut FxManager, assets: &AssetManage
         PLAYER_TEX_NEGATIVE
     };
 
+    //tint when pulled hard by a charge
+    let strong = net.is_some_and(|net| net.force.length() > FORCE_TINT_THRESHOLD);
+    sprite.color = if strong { FORCE_TINT } else { WHITE };
+
+    //show the flame only when running
+    flame.color.a = if player.thrusting { 1.0 } else { 0.0 };
+
     //emit fumes if running
-    if is_mouse_button_down(MouseButton::Left) {
+    if player.thrusting {
         fx.burst_particles(
             Particle {
                 pos: vec2(pos.x, pos.y) + Vec2::from_angle(rotation.angle).rotate(-Vec2::X) * 15.0,
@@ -278,40 +732,102 @@ pub fn audio_visuals(world: &mut World, fx: &mut FxManager, assets: &AssetManage
                 min_size: 1.0,
                 max_size: 4.0,
                 color: ORANGE,
+                ..Default::default()
             },
             4.0,
             PI / 8.0,
             7,
         );
-        //jet sound
-        if !player.jet_sound_playing {
-            player.jet_sound_playing = true;
-            macroquad::audio::play_sound(
-                assets.get_sound("player_jet").unwrap(),
-                PlaySoundParams {
-                    looped: true,
-                    volume: 1.0,
-                },
-            );
-        }
+    }
+
+    //jet sound, silenced completely at zero volume
+    if player.thrusting {
+        assets.audio().start_loop("player_jet", 1.0);
     } else {
-        //anti jet sound
-        if player.jet_sound_playing {
-            player.jet_sound_playing = false;
-            macroquad::audio::stop_sound(assets.get_sound("player_jet").unwrap());
-        }
+        assets.audio().stop_loop("player_jet");
+    }
+
+    //muzzle flash lasting a single frame
+    if player.muzzle_flash {
+        player.muzzle_flash = false;
+        let facing = Vec2::from_angle(rotation.angle).rotate(Vec2::X);
+        fx.add_particle(Particle {
+            pos: vec2(pos.x, pos.y) + facing * PLAYER_MUZZLE_OFFSET,
+            vel: Vec2::ZERO,
+            life: 1.0 / 60.0,
+            max_life: 1.0 / 60.0,
+            min_size: 6.0,
+            max_size: 6.0,
+            color: WHITE,
+            ..Default::default()
+        });
+    }
+
+    //glow of the charging railgun, growing with its charge
+    if player.rail_charge > 0.0 {
+        let facing = Vec2::from_angle(rotation.angle).rotate(Vec2::X);
+        let size = 3.0 + 9.0 * player.rail_charge;
+        fx.add_particle(Particle {
+            pos: vec2(pos.x, pos.y) + facing * PLAYER_MUZZLE_OFFSET,
+            vel: Vec2::ZERO,
+            life: 1.0 / 60.0,
+            max_life: 1.0 / 60.0,
+            min_size: size,
+            max_size: size,
+            color: if player.rail_charge >= 1.0 {
+                YELLOW
+            } else {
+                SKYBLUE
+            },
+            ..Default::default()
+        });
+    }
+
+    //spark ring and chime of a cancelled knockback
+    if player.counter_spark {
+        player.counter_spark = false;
+        fx.burst_particles(
+            Particle {
+                pos: vec2(pos.x, pos.y),
+                vel: vec2(120.0, 0.0),
+                life: 0.3,
+                max_life: 0.3,
+                min_size: 0.0,
+                max_size: 3.0,
+                color: YELLOW,
+                ..Default::default()
+            },
+            10.0,
+            2.0 * PI,
+            16,
+        );
+        assets.audio().play("pew_pew", 0.8);
+    }
+
+    //trail along the dash path
+    if !player.dash_timer.finished() {
+        let facing = Vec2::from_angle(rotation.angle).rotate(Vec2::X);
+        fx.burst_particles(
+            Particle {
+                pos: vec2(pos.x, pos.y),
+                vel: -facing * 40.0,
+                life: 0.4,
+                max_life: 0.4,
+                min_size: 0.0,
+                max_size: 5.0,
+                color: SKYBLUE,
+                ..Default::default()
+            },
+            10.0,
+            PI / 4.0,
+            4,
+        );
     }
 
     //shooting sound
     if player.shoot_sound {
         player.shoot_sound = false;
-        macroquad::audio::play_sound(
-            assets.get_sound("pew_pew").unwrap(),
-            PlaySoundParams {
-                looped: false,
-                volume: 0.4,
-            },
-        );
+        assets.audio().play("pew_pew", 0.4);
     }
 
     //explode if dead
@@ -330,6 +846,7 @@ pub fn audio_visuals(world: &mut World, fx: &mut FxManager, assets: &AssetManage
                     min_size: 0.0,
                     max_size: 20.0,
                     color: RED,
+                    ..Default::default()
                 },
                 30.0,
                 2.0 * PI,
@@ -338,3 +855,211 @@ pub fn audio_visuals(world: &mut World, fx: &mut FxManager, assets: &AssetManage
         }
     }
 }
+
+/// Stops the Player's looping sounds, e.g. when the game gets paused.
+pub fn stop_sounds(world: &mut World, assets: &AssetManager) {
+    for (_, player) in world.query_mut::<&mut Player>() {
+        player.thrusting = false;
+    }
+    assets.audio().stop_loop("player_jet");
+}
+
+/// Renders [ForceDisplay]s showing the charge force acting on the Player.
+pub fn render_force_display(world: &mut World) {
+    let Some((_, net)) = world
+        .query_mut::<&NetForce>()
+        .with::<&Player>()
+        .into_iter()
+        .next()
+    else {
+        return;
+    };
+    let force = net.force;
+    for (_, (display, pos)) in world.query_mut::<(&ForceDisplay, &Position)>() {
+        draw_circle_lines(pos.x, pos.y, display.radius, 1.0, display.dial_color);
+        //log scaled length of the arrow
+        let magnitude = force.length();
+        let scale = (magnitude / FORCE_DISPLAY_KNEE).ln_1p()
+            / (FORCE_DISPLAY_MAX / FORCE_DISPLAY_KNEE).ln_1p();
+        let length = display.radius * scale.min(1.0);
+        if length < 1.0 {
+            continue;
+        }
+        let color = if magnitude > FORCE_TINT_THRESHOLD {
+            display.strong_color
+        } else {
+            display.color
+        };
+        let dir = force / magnitude;
+        let end = vec2(pos.x, pos.y) + dir * length;
+        draw_line(pos.x, pos.y, end.x, end.y, 2.0, color);
+        draw_circle(end.x, end.y, 2.5, color);
+    }
+}
+
+/// Renders the aim reticle at the mouse.
+/// It widens with the spread of the Player's shots, covering where they can land.
+pub fn render_reticle(world: &mut World) {
+    let mouse = arena::arena(world).mouse_pos();
+    let Some((_, (player, pos))) = world
+        .query_mut::<(&Player, &Position)>()
+        .without::<&Dead>()
+        .into_iter()
+        .next()
+    else {
+        return;
+    };
+    let distance = mouse.distance(vec2(pos.x, pos.y));
+    let radius = RETICLE_MIN_RADIUS + distance * player.spread.tan();
+    draw_circle_lines(mouse.x, mouse.y, radius, 1.0, RETICLE_COLOR);
+}
+
+/// Renders a red vignette pulsing with the heartbeat of the Player's [LowHealth].
+pub fn render_low_health_vignette(world: &mut World) {
+    let Some((_, warning)) = world.query_mut::<&LowHealth>().into_iter().next() else {
+        return;
+    };
+    let band = VIGNETTE_WIDTH / VIGNETTE_BANDS as f32;
+    for i in 0..VIGNETTE_BANDS {
+        let fade = 1.0 - i as f32 / VIGNETTE_BANDS as f32;
+        let color = Color::new(1.0, 0.0, 0.0, VIGNETTE_ALPHA * warning.pulse() * fade);
+        let inset = i as f32 * band;
+        let width = SPACE_WIDTH - 2.0 * inset;
+        let height = SPACE_HEIGHT - 2.0 * inset;
+        //top and bottom
+        draw_rectangle(inset, inset, width, band, color);
+        draw_rectangle(inset, SPACE_HEIGHT - inset - band, width, band, color);
+        //left and right, between the top and bottom
+        draw_rectangle(inset, inset + band, band, height - 2.0 * band, color);
+        draw_rectangle(
+            SPACE_WIDTH - inset - band,
+            inset + band,
+            band,
+            height - 2.0 * band,
+            color,
+        );
+    }
+}
+
+/// Renders [DashDisplay]s showing the recharge of the Player's dash.
+pub fn render_dash_display(world: &mut World) {
+    let Some((_, player)) = world.query_mut::<&Player>().into_iter().next() else {
+        return;
+    };
+    let charge = (player.dash_cooldown.elapsed / player.dash_cooldown.duration).min(1.0);
+    for (_, (display, pos)) in world.query_mut::<(&DashDisplay, &Position)>() {
+        let color = if charge >= 1.0 {
+            display.color
+        } else {
+            display.charging_color
+        };
+        draw_rectangle(
+            pos.x - display.max_width / 2.0,
+            pos.y - display.height / 2.0,
+            display.max_width * charge,
+            display.height,
+            color,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hecs::{CommandBuffer, Entity};
+
+    use super::*;
+
+    /// Input flipping the polarity and nothing else.
+    const FLIP: PlayerInput = PlayerInput {
+        thrust: false,
+        aim: Aim::Direction(Vec2::X),
+        fire: false,
+        flip_polarity: true,
+        dash: false,
+    };
+
+    /// Spawns a player moving at `vel`, of which `knockback` was added by a knockback `since` ago.
+    fn knocked_player(world: &mut World, vel: Vec2, knockback: Vec2, since: f32) -> Entity {
+        let player = world.spawn(new_entity(&Arena::STANDARD));
+        world.get::<&mut PhysicsMotion>(player).unwrap().vel = vel;
+        world
+            .insert_one(
+                player,
+                RecentKnockback {
+                    vel: knockback,
+                    since,
+                },
+            )
+            .unwrap();
+        player
+    }
+
+    #[test]
+    fn flip_cancels_knockback() {
+        let mut world = World::new();
+        let mut cmd = CommandBuffer::new();
+        let knockback = vec2(300.0, -120.0);
+        let player = knocked_player(&mut world, vec2(50.0, 20.0) + knockback, knockback, 0.1);
+
+        weapons(&mut world, &mut cmd, &FLIP, 1.0 / 120.0);
+        cmd.run_on(&mut world);
+
+        let vel = world.get::<&PhysicsMotion>(player).unwrap().vel;
+        assert_eq!(
+            vel,
+            vec2(50.0, 20.0) + knockback * KNOCKBACK_CANCEL_FRACTION
+        );
+        //consumed, so it is not cancelled twice
+        assert!(!world.satisfies::<&RecentKnockback>(player).unwrap());
+        assert!(world.get::<&Player>(player).unwrap().counter_spark);
+    }
+
+    #[test]
+    fn flip_at_window_end_cancels_knockback() {
+        let mut world = World::new();
+        let mut cmd = CommandBuffer::new();
+        let knockback = vec2(0.0, 200.0);
+        let player = knocked_player(&mut world, knockback, knockback, KNOCKBACK_CANCEL_WINDOW);
+
+        weapons(&mut world, &mut cmd, &FLIP, 1.0 / 120.0);
+
+        let vel = world.get::<&PhysicsMotion>(player).unwrap().vel;
+        assert_eq!(vel, knockback * (1.0 - KNOCKBACK_CANCEL_FRACTION));
+    }
+
+    #[test]
+    fn late_flip_keeps_knockback() {
+        let mut world = World::new();
+        let mut cmd = CommandBuffer::new();
+        let knockback = vec2(0.0, 200.0);
+        let player = knocked_player(
+            &mut world,
+            knockback,
+            knockback,
+            KNOCKBACK_CANCEL_WINDOW + 0.01,
+        );
+
+        weapons(&mut world, &mut cmd, &FLIP, 1.0 / 120.0);
+
+        assert_eq!(world.get::<&PhysicsMotion>(player).unwrap().vel, knockback);
+        assert!(!world.get::<&Player>(player).unwrap().counter_spark);
+    }
+
+    #[test]
+    fn no_flip_keeps_knockback() {
+        let mut world = World::new();
+        let mut cmd = CommandBuffer::new();
+        let knockback = vec2(0.0, 200.0);
+        let player = knocked_player(&mut world, knockback, knockback, 0.0);
+        let input = PlayerInput {
+            flip_polarity: false,
+            ..FLIP
+        };
+
+        weapons(&mut world, &mut cmd, &input, 1.0 / 120.0);
+        cmd.run_on(&mut world);
+
+        assert_eq!(world.get::<&PhysicsMotion>(player).unwrap().vel, knockback);
+        assert!(world.satisfies::<&RecentKnockback>(player).unwrap());
+    }
+}
@@ -3,15 +3,95 @@
 use crate::basic::{
     motion::{ChargeDisable, ChargeReceiver, MaxVelocity, PhysicsMotion},
     render::Sprite,
-    DamageDealer, HitEvent, HurtBox, Position, Team,
+    DamageDealer, DamageEvent, FadeOutOnExpiry, HitEvent, HurtBox, Lifetime, Position,
+    SweptCollider, Team, Timer,
 };
-use hecs::{CommandBuffer, World};
+use hecs::{CommandBuffer, EntityBuilder, World};
 use macroquad::prelude::*;
 
 /// Marker of projectile entities.
 #[derive(Clone, Copy, Debug)]
 pub struct Projectile;
 
+/// Lets the projectile pass through what it hurts.
+#[derive(Clone, Copy, Debug)]
+pub struct Piercing {
+    /// Amount of hurt entities the projectile can still pass through.
+    pub remaining: u8,
+}
+
+/// Default max amount of live enemy projectiles.
+pub const ENEMY_PROJECTILE_CAP: u32 = 48;
+
+/// Resource limiting the amount of live enemy projectiles.
+///
+/// Keeps bursts of many enemies dodgeable and cheap to simulate.
+#[derive(Clone, Copy, Debug)]
+pub struct ProjectileBudget {
+    /// Enemy projectiles alive or spawned this frame.
+    pub live: u32,
+    /// Max amount of live enemy projectiles.
+    pub cap: u32,
+}
+
+impl Default for ProjectileBudget {
+    fn default() -> Self {
+        Self {
+            live: 0,
+            cap: ENEMY_PROJECTILE_CAP,
+        }
+    }
+}
+
+impl ProjectileBudget {
+    /// Reserves up to `count` projectiles.
+    /// Returns how many of them can be spawned.
+    pub fn take(&mut self, count: u32) -> u32 {
+        let granted = count.min(self.cap.saturating_sub(self.live));
+        self.live += granted;
+        granted
+    }
+}
+
+/// Components of a projectile, as returned by [create_projectile].
+pub type ProjectileBundle = (
+    Projectile,
+    Position,
+    Team,
+    HurtBox,
+    DamageDealer,
+    Sprite,
+    //ChargeSender,
+    ChargeReceiver,
+    ChargeDisable,
+    PhysicsMotion,
+    MaxVelocity,
+    Lifetime,
+    FadeOutOnExpiry,
+    SweptCollider,
+);
+
+/// Amount of projectiles [ProjectileBatch] has room for without reallocating.
+/// A mine's burst and a few other shots fit in.
+const PROJECTILE_BATCH_CAPACITY: usize = 64;
+
+/// Resource collecting projectiles fired during a frame.
+///
+/// They are all spawned at once by [spawn_projectiles],
+/// instead of one by one through a [CommandBuffer].
+pub struct ProjectileBatch {
+    /// Projectiles waiting to be spawned.
+    pending: Vec<ProjectileBundle>,
+}
+
+impl Default for ProjectileBatch {
+    fn default() -> Self {
+        Self {
+            pending: Vec::with_capacity(PROJECTILE_BATCH_CAPACITY),
+        }
+    }
+}
+
 /// Defines the type of projectile to spawn.
 #[derive(Clone, Debug)]
 pub enum ProjectileType {
@@ -49,6 +129,8 @@ const PROJ_SMALL_CHARGE_MULT: f32 = 0.4;
 const PROJ_SMALL_F_RADIUS: f32 = 100.0;
 /// Small projectiles's charge zero force radius.
 const PROJ_SMALL_RADIUS: f32 = 200.0;
+/// Time small projectiles live for.
+const PROJ_SMALL_LIFETIME: f32 = 4.0;
 
 /// Texture ID of positively charged medium projectile.
 pub const PROJ_MED_TEX_POS: &str = "proj_medium_plus";
@@ -57,6 +139,15 @@ pub const PROJ_MED_TEX_NEG: &str = "proj_medium_minus";
 /// Texture ID of non-charged medium projectile.
 pub const PROJ_MED_TEX_NEUTRAL: &str = "proj_medium_neutral";
 
+/// All texture IDs used by projectiles' sprites.
+pub const PROJECTILE_TEXTURES: [&str; 5] = [
+    PROJ_SMALL_TEX_POS,
+    PROJ_SMALL_TEX_NEG,
+    PROJ_MED_TEX_POS,
+    PROJ_MED_TEX_NEG,
+    PROJ_MED_TEX_NEUTRAL,
+];
+
 /// Medium projectiles's mass.
 const PROJ_MED_MASS: f32 = 1.0;
 /// Medium projectiles's size.
@@ -70,6 +161,9 @@ const PROJ_MED_CHARGE_MULT: f32 = 0.7;
 const PROJ_MED_F_RADIUS: f32 = 120.0;
 /// Medium projectiles's charge zero force radius.
 const PROJ_MED_RADIUS: f32 = 250.0;
+/// Time medium projectiles live for.
+/// Keeps them from orbiting in charge fields forever.
+const PROJ_MED_LIFETIME: f32 = 8.0;
 
 //-----------------------------------------------------------------------------
 //CONSTRUCT ENTITY
@@ -88,19 +182,7 @@ pub fn create_projectile(
     dmg: f32,
     team: Team,
     proj_type: ProjectileType,
-) -> (
-    Projectile,
-    Position,
-    Team,
-    HurtBox,
-    DamageDealer,
-    Sprite,
-    //ChargeSender,
-    ChargeReceiver,
-    ChargeDisable,
-    PhysicsMotion,
-    MaxVelocity,
-) {
+) -> ProjectileBundle {
     //get properties from type
     let size = match proj_type {
         ProjectileType::Small { .. } => PROJ_SMALL_SIZE,
@@ -112,6 +194,11 @@ pub fn create_projectile(
         ProjectileType::Medium { .. } => PROJ_MED_MASS,
     };
 
+    let lifetime = match proj_type {
+        ProjectileType::Small { .. } => PROJ_SMALL_LIFETIME,
+        ProjectileType::Medium { .. } => PROJ_MED_LIFETIME,
+    };
+
     let texture = match proj_type {
         ProjectileType::Small { charge } => {
             if charge > 0 {
@@ -168,20 +255,113 @@ pub fn create_projectile(
                     x => x.signum(),
                 },
         },
-        ChargeDisable { timer: 0.2 },
+        ChargeDisable {
+            timer: Timer::new(0.2, false),
+        },
         PhysicsMotion { vel, mass },
         MaxVelocity {
             max_velocity: vel.length() * 2.0,
         },
+        Lifetime::new(lifetime),
+        FadeOutOnExpiry::default(),
+        SweptCollider,
     )
 }
 
+/// Overrides the lifetime of a projectile created by [create_projectile].
+/// # Arguments
+/// - `projectile` - projectile to change
+/// - `seconds` - time the projectile lives for
+pub fn with_lifetime(mut projectile: ProjectileBundle, seconds: f32) -> ProjectileBundle {
+    projectile.10 = Lifetime::new(seconds);
+    projectile
+}
+
+/// Makes a projectile created by [create_projectile] [Piercing].
+/// # Arguments
+/// - `projectile` - projectile to change
+/// - `remaining` - amount of hurt entities the projectile passes through
+pub fn piercing(projectile: ProjectileBundle, remaining: u8) -> EntityBuilder {
+    let mut builder = EntityBuilder::new();
+    builder.add_bundle(projectile).add(Piercing { remaining });
+    builder
+}
+
 //-----------------------------------------------------------------------------
 //SYSTEM PART
 //-----------------------------------------------------------------------------
+
+/// Reserves up to `count` enemy projectiles from the [ProjectileBudget].
+/// Returns how many of them can be spawned.
+/// Worlds without a budget are not limited.
+pub fn take_enemy_projectiles(world: &World, count: u32) -> u32 {
+    world
+        .query::<&mut ProjectileBudget>()
+        .iter()
+        .next()
+        .map(|(_, budget)| budget.take(count))
+        .unwrap_or(count)
+}
+
+/// Queues `projectile` into the [ProjectileBatch].
+/// Worlds without a batch spawn it through `cmd` instead.
+pub fn queue_projectile(world: &World, cmd: &mut CommandBuffer, projectile: ProjectileBundle) {
+    match world.query::<&mut ProjectileBatch>().iter().next() {
+        Some((_, batch)) => batch.pending.push(projectile),
+        None => cmd.spawn(projectile),
+    }
+}
+
+/// Spawns all projectiles queued in the [ProjectileBatch] at once.
+/// Should run once per frame, after all weapons fired.
+pub fn spawn_projectiles(world: &mut World) {
+    let Some(mut pending) = world
+        .query_mut::<&mut ProjectileBatch>()
+        .into_iter()
+        .next()
+        .map(|(_, batch)| std::mem::take(&mut batch.pending))
+    else {
+        return;
+    };
+    world.spawn_batch(pending.drain(..));
+    //give the allocation back
+    if let Some((_, batch)) = world.query_mut::<&mut ProjectileBatch>().into_iter().next() {
+        batch.pending = pending;
+    }
+}
+
+/// Recounts live enemy projectiles into the [ProjectileBudget].
+/// Should run before any enemy fires.
+pub fn count_enemy_projectiles(world: &mut World) {
+    let live = world
+        .query_mut::<&Team>()
+        .with::<&Projectile>()
+        .into_iter()
+        .filter(|(_, team)| **team == Team::Enemy)
+        .count() as u32;
+
+    for (_, budget) in world.query_mut::<&mut ProjectileBudget>() {
+        budget.live = live;
+    }
+}
+
 /// Handles deletion of projectiles on collision with something they can hurt.
+/// [Piercing] projectiles are deleted once they hurt enough enemies.
 pub fn on_hurt(world: &mut World, events: &mut World, cmd: &mut CommandBuffer) {
-    for (proj_id, _) in world.query_mut::<&Projectile>() {
+    for (proj_id, piercing) in world.query_mut::<&mut Piercing>().with::<&Projectile>() {
+        //repeated hits of the same enemy do not damage it, so count only damage
+        for (_, event) in events.query_mut::<&DamageEvent>() {
+            if event.by != proj_id {
+                continue;
+            }
+            if piercing.remaining == 0 {
+                cmd.despawn(proj_id);
+                break;
+            }
+            piercing.remaining -= 1;
+        }
+    }
+    for (proj_id, _) in world.query_mut::<&Projectile>().without::<&Piercing>() {
         for (_, event) in events.query_mut::<&HitEvent>() {
             //did it hurt?
             if !event.can_hurt {
@@ -196,3 +376,82 @@ pub fn on_hurt(world: &mut World, events: &mut World, cmd: &mut CommandBuffer) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fires `count` enemy projectiles, queued into the batch when the world has one.
+    fn fire(world: &World, cmd: &mut CommandBuffer, count: usize) {
+        for i in 0..count {
+            let dir = Vec2::from_angle(i as f32);
+            queue_projectile(
+                world,
+                cmd,
+                create_projectile(
+                    dir * 20.0,
+                    dir * 300.0,
+                    1.0,
+                    Team::Enemy,
+                    ProjectileType::Small { charge: 1 },
+                ),
+            );
+        }
+    }
+
+    #[test]
+    fn batch_spawns_every_queued_projectile() {
+        let mut world = World::new();
+        let mut cmd = CommandBuffer::new();
+        world.spawn((ProjectileBatch::default(),));
+        fire(&world, &mut cmd, 100);
+        //nothing is spawned before the batch is
+        cmd.run_on(&mut world);
+        assert_eq!(world.query_mut::<&Projectile>().into_iter().count(), 0);
+        spawn_projectiles(&mut world);
+        assert_eq!(world.query_mut::<&Projectile>().into_iter().count(), 100);
+        //the batch is emptied
+        spawn_projectiles(&mut world);
+        assert_eq!(world.query_mut::<&Projectile>().into_iter().count(), 100);
+    }
+
+    /// Times spawning a frame's projectiles through a [CommandBuffer] and through the [ProjectileBatch].
+    /// Run with `cargo test --release -- --ignored --nocapture projectile_batch_benchmark`.
+    #[test]
+    #[ignore]
+    fn projectile_batch_benchmark() {
+        const FRAMES: u32 = 2_000;
+        for per_frame in [8, 32, 128] {
+            let mut times = Vec::new();
+            for batched in [false, true] {
+                let mut world = World::new();
+                if batched {
+                    world.spawn((ProjectileBatch::default(),));
+                }
+                let start = std::time::Instant::now();
+                for _ in 0..FRAMES {
+                    let mut cmd = CommandBuffer::new();
+                    fire(&world, &mut cmd, per_frame);
+                    cmd.run_on(&mut world);
+                    spawn_projectiles(&mut world);
+                    //expire them, keeping the world the same size
+                    let fired: Vec<_> = world
+                        .query_mut::<()>()
+                        .with::<&Projectile>()
+                        .into_iter()
+                        .map(|(id, _)| id)
+                        .collect();
+                    for id in fired {
+                        world.despawn(id).unwrap();
+                    }
+                }
+                times.push(start.elapsed().as_secs_f64() / FRAMES as f64);
+            }
+            println!(
+                "{per_frame} projectiles: command buffer {:.1} us, batch {:.1} us per frame",
+                times[0] * 1e6,
+                times[1] * 1e6
+            );
+        }
+    }
+}
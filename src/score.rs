@@ -1,9 +1,60 @@
 //! Score displays.
 
 use hecs::{Entity, EntityBuilder, World};
-use macroquad::{color::WHITE, math::Vec2};
+use macroquad::{
+    color::{Color, RED, WHITE},
+    math::Vec2,
+};
+
+use crate::{
+    basic::{Position, Timer},
+    enemy::RunStats,
+    game::EnemySpawner,
+    locale::tr,
+    menu::{format_time, Title},
+    persist::Persistent,
+    player::Player,
+};
+
+/// Time without a kill after which the combo drops back to 1.
+const COMBO_TIME: f32 = 3.0;
+/// Highest combo multiplier.
+const MAX_COMBO: u32 = 10;
+
+/// Resource multiplying collected xp by the amount of recent kills.
+#[derive(Clone, Copy, Debug)]
+pub struct Combo {
+    /// Current multiplier, from 1 to [MAX_COMBO].
+    pub value: u32,
+    /// Time before the combo drops back to 1.
+    pub timer: Timer,
+}
+
+impl Default for Combo {
+    fn default() -> Self {
+        Self {
+            value: 1,
+            timer: Timer::new_finished(COMBO_TIME),
+        }
+    }
+}
+
+impl Combo {
+    /// Raises the multiplier by the amount of `kills` and restarts the timer.
+    pub fn add_kills(&mut self, kills: u32) {
+        if kills == 0 {
+            return;
+        }
+        self.value = (self.value + kills).min(MAX_COMBO);
+        self.timer.reset();
+    }
 
-use crate::{basic::Position, menu::Title, persist::Persistent, player::Player};
+    /// Drops the multiplier back to 1.
+    pub fn reset(&mut self) {
+        self.value = 1;
+        self.timer.elapsed = self.timer.duration;
+    }
+}
 
 /// Displays current score.
 #[derive(Clone, Copy, Debug)]
@@ -16,6 +67,14 @@ pub struct ScoreDisplay {
 #[derive(Clone, Copy, Debug)]
 pub struct HighScoreDisplay;
 
+/// Displays the current threat level of the run.
+#[derive(Clone, Copy, Debug)]
+pub struct ThreatDisplay;
+
+/// Displays the time the player survived in the run.
+#[derive(Clone, Copy, Debug)]
+pub struct RunTimerDisplay;
+
 //-----------------------------------------------------------------------------
 //ENTITY CREATION
 //-----------------------------------------------------------------------------
@@ -30,10 +89,11 @@ pub fn create_score_display(pos: Vec2, player: Entity) -> EntityBuilder {
     builder.add(Position { x: pos.x, y: pos.y });
 
     builder.add(Title {
-        text: "Score: 0".to_string(),
+        text: tr!("score.value", score = 0),
         font: "main_font",
         size: 24.0,
         color: WHITE,
+        max_width: None,
     });
 
     builder.add(ScoreDisplay { player });
@@ -50,10 +110,11 @@ pub fn create_highscore_display(pos: Vec2) -> EntityBuilder {
     builder.add(Position { x: pos.x, y: pos.y });
 
     builder.add(Title {
-        text: "Score: 0".to_string(),
+        text: tr!("score.value", score = 0),
         font: "main_font",
         size: 24.0,
         color: WHITE,
+        max_width: None,
     });
 
     builder.add(HighScoreDisplay);
@@ -61,18 +122,83 @@ pub fn create_highscore_display(pos: Vec2) -> EntityBuilder {
     builder
 }
 
+/// Creates a threat level display entity.
+/// # Arguments
+/// - `pos` - position of the threat display
+pub fn create_threat_display(pos: Vec2) -> EntityBuilder {
+    let mut builder = EntityBuilder::new();
+
+    builder.add(Position { x: pos.x, y: pos.y });
+
+    builder.add(Title {
+        text: tr!("score.threat", threat = 1),
+        font: "main_font",
+        size: 24.0,
+        color: WHITE,
+        max_width: None,
+    });
+
+    builder.add(ThreatDisplay);
+
+    builder
+}
+
+/// Creates a run timer display entity.
+/// # Arguments
+/// - `pos` - position of the run timer display
+pub fn create_run_timer_display(pos: Vec2) -> EntityBuilder {
+    let mut builder = EntityBuilder::new();
+
+    builder.add(Position { x: pos.x, y: pos.y });
+
+    builder.add(Title {
+        text: format_time(0.0),
+        font: "main_font",
+        size: 24.0,
+        color: WHITE,
+        max_width: None,
+    });
+
+    builder.add(RunTimerDisplay);
+
+    builder
+}
+
 //-----------------------------------------------------------------------------
 //SYSTEM PART
 //-----------------------------------------------------------------------------
 
-/// Synchronizes the titles and current score/highscores.
+/// Drops the [Combo] back to 1 when no kill came in time.
+pub fn combo_decay(world: &mut World, dt: f32) {
+    for (_, combo) in world.query_mut::<&mut Combo>() {
+        combo.timer.tick(dt);
+        if combo.timer.finished() {
+            combo.value = 1;
+        }
+    }
+}
+
+/// Synchronizes the titles and current score/highscores/threat level/run time.
 pub fn score_display(world: &mut World, persist: &Persistent) {
     //synchronize score displays
+    let combo = world
+        .query_mut::<&Combo>()
+        .into_iter()
+        .next()
+        .map(|(_, combo)| combo.value)
+        .unwrap_or(1);
     for (_, (title, display)) in world.query::<(&mut Title, &ScoreDisplay)>().into_iter() {
         //read score
         let score = world.get::<&Player>(display.player).unwrap().xp;
-        //write it
-        title.text = format!("Score: {}", score * 10);
+        //write it, with the combo when there is one
+        title.text = if combo > 1 {
+            tr!("score.combo", score = score * 10, combo = combo)
+        } else {
+            tr!("score.value", score = score * 10)
+        };
+        //redder with higher combos
+        let heat = (combo - 1) as f32 / (MAX_COMBO - 1) as f32;
+        title.color = Color::from_vec(WHITE.to_vec().lerp(RED.to_vec(), heat));
     }
 
     //synchronize highscore displays
@@ -81,7 +207,34 @@ pub fn score_display(world: &mut World, persist: &Persistent) {
         .with::<&HighScoreDisplay>()
         .into_iter()
     {
-        //write it
-        title.text = format!("High Score: {}", persist.high_score * 10);
+        //write it, with the initials of the best run when there are any
+        title.text = match persist.leaderboard.first() {
+            Some(best) => tr!("score.best_by", score = best.score * 10, name = best.name),
+            None => tr!("score.best", score = persist.high_score * 10),
+        };
+    }
+
+    //synchronize threat displays
+    let threat = world
+        .query_mut::<&EnemySpawner>()
+        .into_iter()
+        .next()
+        .map(|(_, spawner)| spawner.difficulty.threat as u32);
+    if let Some(threat) = threat {
+        for (_, title) in world.query_mut::<&mut Title>().with::<&ThreatDisplay>() {
+            title.text = tr!("score.threat", threat = threat);
+        }
+    }
+
+    //synchronize run timer displays
+    let time = world
+        .query_mut::<&RunStats>()
+        .into_iter()
+        .next()
+        .map(|(_, stats)| stats.time);
+    if let Some(time) = time {
+        for (_, title) in world.query_mut::<&mut Title>().with::<&RunTimerDisplay>() {
+            title.text = format_time(time);
+        }
     }
 }
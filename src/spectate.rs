@@ -0,0 +1,131 @@
+//! Spectating camera of the game over screen.
+//!
+//! Drifts towards the remaining enemies, so the player can watch
+//! the chaos they left behind.
+
+use hecs::World;
+use macroquad::prelude::*;
+
+use crate::{
+    basic::{
+        arena::{self, Arena, CameraOverride},
+        HitBox, Position,
+    },
+    enemy::Enemy,
+    SPACE_HEIGHT, SPACE_WIDTH,
+};
+
+/// Key switching between spectating and the static framing.
+const SPECTATE_KEY: KeyCode = KeyCode::C;
+/// Time between recomputations of the spectated point.
+const RETARGET_TIME: f32 = 1.0;
+/// Fraction of the distance to the spectated point travelled every second.
+const SPECTATE_EASE: f32 = 0.5;
+/// Zoom the spectating camera eases into.
+const SPECTATE_ZOOM: f32 = 1.4;
+
+/// Resource driving the spectating camera.
+#[derive(Clone, Copy, Debug)]
+pub struct Spectate {
+    /// Is the camera spectating, or showing the static framing?
+    pub active: bool,
+    /// Center of the view.
+    center: Vec2,
+    /// Point the view drifts towards.
+    target: Vec2,
+    /// Current zoom, 1.0 shows the static framing's area.
+    zoom: f32,
+    /// Time before the next retarget.
+    retarget: f32,
+}
+
+impl Spectate {
+    /// Creates a camera starting at the static framing of `arena`.
+    /// # Arguments
+    /// * `active` - should it start spectating?
+    /// * `arena` - arena being spectated
+    pub fn new(active: bool, arena: &Arena) -> Self {
+        Self::from_view(active, arena.view_rect())
+    }
+
+    /// Creates a camera starting at `view`, e.g. the one the run ended with.
+    /// # Arguments
+    /// * `active` - should it start spectating?
+    /// * `view` - part of the world shown at the start
+    pub fn from_view(active: bool, view: Rect) -> Self {
+        let center = view.center();
+        Self {
+            active,
+            center,
+            target: center,
+            zoom: SPACE_WIDTH / view.w,
+            retarget: 0.0,
+        }
+    }
+}
+
+//-----------------------------------------------------------------------------
+//SYSTEM PART
+//-----------------------------------------------------------------------------
+
+/// Switches between spectating and the static framing when its key is pressed.
+/// Switching back to the static framing snaps the view back immediately.
+pub fn toggle_spectate(world: &mut World) {
+    if !is_key_pressed(SPECTATE_KEY) {
+        return;
+    }
+    let arena = arena::arena(world);
+    for (_, spectate) in world.query_mut::<&mut Spectate>() {
+        *spectate = Spectate::new(!spectate.active, &arena);
+    }
+}
+
+/// Eases the spectating camera towards the remaining enemies
+/// and writes the view into the [CameraOverride].
+///
+/// The spectated point is the centroid of enemies weighted by their size.
+pub fn spectate(world: &mut World, dt: f32) {
+    let arena = arena::arena(world);
+    //find the densest cluster of enemies
+    let mut sum = Vec2::ZERO;
+    let mut weight = 0.0;
+    for (_, (pos, hitbox)) in world
+        .query_mut::<(&Position, Option<&HitBox>)>()
+        .with::<&Enemy>()
+    {
+        let size = hitbox.map(|hitbox| hitbox.radius).unwrap_or(1.0);
+        sum += vec2(pos.x, pos.y) * size;
+        weight += size;
+    }
+
+    let Some((_, spectate)) = world.query_mut::<&mut Spectate>().into_iter().next() else {
+        return;
+    };
+    let rect = if spectate.active {
+        //retarget
+        spectate.retarget -= dt;
+        if spectate.retarget <= 0.0 {
+            spectate.retarget = RETARGET_TIME;
+            if weight > 0.0 {
+                spectate.target = sum / weight;
+            }
+        }
+        //ease towards the target
+        let ease = 1.0 - (1.0 - SPECTATE_EASE).powf(dt);
+        spectate.center = spectate.center.lerp(spectate.target, ease);
+        spectate.zoom += (SPECTATE_ZOOM - spectate.zoom) * ease;
+        //never look outside of the arena
+        let size = vec2(SPACE_WIDTH, SPACE_HEIGHT) / spectate.zoom;
+        let corner = (spectate.center - size / 2.0).clamp(
+            Vec2::ZERO,
+            (vec2(arena.width, arena.height) - size).max(Vec2::ZERO),
+        );
+        Rect::new(corner.x, corner.y, size.x, size.y)
+    } else {
+        arena.view_rect()
+    };
+
+    for (_, view) in world.query_mut::<&mut CameraOverride>() {
+        view.rect = rect;
+    }
+}
@@ -0,0 +1,232 @@
+//! Tutorial walking new players through movement, polarity and shooting.
+
+use hecs::World;
+use macroquad::prelude::*;
+
+use crate::{
+    basic::{arena, render::Circle, Position},
+    enemy::create_asteroid,
+    game::state::GameState,
+    input::KeyBindings,
+    locale::tr,
+    menu::{Title, UiOwner},
+    persist::Persistent,
+    player::Player,
+    xp::{create_orb, BurstXpOnDeath},
+};
+
+/// Distance the player must get to the marker's center to reach it.
+const MARKER_RADIUS: f32 = 40.0;
+/// Color of the marker the player flies to.
+const MARKER_COLOR: Color = Color::new(0.0, 1.0, 0.3, 0.3);
+/// Offset of the marker from the center of the arena.
+const MARKER_OFFSET: Vec2 = vec2(300.0, 0.0);
+/// Offset of the dummy orb from the center of the arena.
+const ORB_OFFSET: Vec2 = vec2(100.0, 0.0);
+/// Offset of the target asteroid from the center of the arena.
+const TARGET_OFFSET: Vec2 = vec2(0.0, -200.0);
+/// Vertical position of the instructions.
+const INSTRUCTION_Y: f32 = 110.0;
+
+/// Stage of the tutorial.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TutorialStage {
+    /// Fly to the [TutorialMarker].
+    Move,
+    /// Flip the polarity to pull in the [TutorialOrb].
+    Polarity,
+    /// Shoot down the [TutorialTarget].
+    Shoot,
+    /// Everything is done.
+    Done,
+}
+
+impl TutorialStage {
+    /// Returns the stage following this one.
+    fn next(self) -> Self {
+        match self {
+            TutorialStage::Move => TutorialStage::Polarity,
+            TutorialStage::Polarity => TutorialStage::Shoot,
+            TutorialStage::Shoot | TutorialStage::Done => TutorialStage::Done,
+        }
+    }
+
+    /// Returns the instructions of the stage, naming the user's `bindings`.
+    fn instruction(self, bindings: &KeyBindings) -> String {
+        match self {
+            TutorialStage::Move => tr!("tutorial.move", key = bindings.thrust.name()),
+            TutorialStage::Polarity => tr!("tutorial.polarity", key = bindings.polarity.name()),
+            TutorialStage::Shoot => tr!("tutorial.shoot", key = bindings.fire.name()),
+            TutorialStage::Done => tr!("tutorial.done", key = bindings.pause.name()),
+        }
+    }
+
+    /// Is the stage completed?
+    fn completed(self, world: &mut World) -> bool {
+        match self {
+            TutorialStage::Move => {
+                let Some((_, &player_pos)) = world
+                    .query_mut::<&Position>()
+                    .with::<&Player>()
+                    .into_iter()
+                    .next()
+                else {
+                    return false;
+                };
+                world
+                    .query_mut::<&Position>()
+                    .with::<&TutorialMarker>()
+                    .into_iter()
+                    .any(|(_, pos)| {
+                        vec2(pos.x - player_pos.x, pos.y - player_pos.y).length() < MARKER_RADIUS
+                    })
+            }
+            TutorialStage::Polarity => world
+                .query_mut::<&TutorialOrb>()
+                .into_iter()
+                .next()
+                .is_none(),
+            TutorialStage::Shoot => world
+                .query_mut::<&TutorialTarget>()
+                .into_iter()
+                .next()
+                .is_none(),
+            TutorialStage::Done => false,
+        }
+    }
+}
+
+//-----------------------------------------------------------------------------
+//COMPONENT PART
+//-----------------------------------------------------------------------------
+
+/// Resource scripting the tutorial.
+#[derive(Clone, Copy, Debug)]
+pub struct TutorialScript {
+    /// Current stage.
+    pub stage: TutorialStage,
+}
+
+/// Marker of the title showing the current instructions.
+#[derive(Clone, Copy, Debug)]
+pub struct TutorialInstruction;
+
+/// Marker of the circle the player has to fly to.
+#[derive(Clone, Copy, Debug)]
+pub struct TutorialMarker;
+
+/// Marker of the orb the player has to pull in.
+#[derive(Clone, Copy, Debug)]
+pub struct TutorialOrb;
+
+/// Marker of the asteroid the player has to shoot down.
+#[derive(Clone, Copy, Debug)]
+pub struct TutorialTarget;
+
+//-----------------------------------------------------------------------------
+//ENTITY CREATION
+//-----------------------------------------------------------------------------
+
+/// Spawns the [TutorialScript] at its first stage with its instructions.
+/// Expects a world prepared for playing, without an enemy spawner.
+pub fn spawn_tutorial(world: &mut World, bindings: &KeyBindings) {
+    let stage = TutorialStage::Move;
+    world.spawn((TutorialScript { stage },));
+    world.spawn((
+        Position {
+            x: crate::SPACE_WIDTH / 2.0,
+            y: INSTRUCTION_Y,
+        },
+        Title {
+            text: stage.instruction(bindings),
+            font: "main_font",
+            size: 28.0,
+            color: YELLOW,
+            max_width: None,
+        },
+        UiOwner::State(GameState::Tutorial),
+        TutorialInstruction,
+    ));
+    enter_stage(world, stage);
+}
+
+/// Spawns the entities `stage` needs.
+fn enter_stage(world: &mut World, stage: TutorialStage) {
+    let center = arena::arena(world).center();
+    match stage {
+        TutorialStage::Move => {
+            let pos = center + MARKER_OFFSET;
+            world.spawn((
+                Position { x: pos.x, y: pos.y },
+                Circle {
+                    radius: MARKER_RADIUS,
+                    color: MARKER_COLOR,
+                    z_index: -2,
+                },
+                TutorialMarker,
+            ));
+        }
+        TutorialStage::Polarity => {
+            //charged like the player, so it is pushed away until the polarity flips
+            let orb = world.spawn(create_orb(center + ORB_OFFSET, Vec2::ZERO, 1, Some(1)).build());
+            let _ = world.insert_one(orb, TutorialOrb);
+        }
+        TutorialStage::Shoot => {
+            let target = world.spawn(create_asteroid(center + TARGET_OFFSET, Vec2::ZERO).build());
+            //no xp, so that the tutorial never levels up
+            let _ = world.remove_one::<BurstXpOnDeath>(target);
+            let _ = world.insert_one(target, TutorialTarget);
+        }
+        TutorialStage::Done => {}
+    }
+}
+
+//-----------------------------------------------------------------------------
+//SYSTEM PART
+//-----------------------------------------------------------------------------
+
+/// Advances the [TutorialScript] once its stage is completed.
+/// Reaching the end marks the tutorial as done in `persist`.
+pub fn tutorial(world: &mut World, persist: &mut Persistent) {
+    let Some((_, &script)) = world.query_mut::<&TutorialScript>().into_iter().next() else {
+        return;
+    };
+    if !script.stage.completed(world) {
+        return;
+    }
+
+    //leave the stage behind
+    let leftovers: Vec<_> = world
+        .query_mut::<&TutorialMarker>()
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect();
+    for id in leftovers {
+        let _ = world.despawn(id);
+    }
+
+    //enter the next one
+    let stage = script.stage.next();
+    for (_, script) in world.query_mut::<&mut TutorialScript>() {
+        script.stage = stage;
+    }
+    for (_, title) in world
+        .query_mut::<&mut Title>()
+        .with::<&TutorialInstruction>()
+    {
+        title.text = stage.instruction(&persist.bindings);
+    }
+    enter_stage(world, stage);
+
+    if stage == TutorialStage::Done {
+        finish_tutorial(persist);
+    }
+}
+
+/// Marks the tutorial as done in `persist`, completed or skipped.
+pub fn finish_tutorial(persist: &mut Persistent) {
+    if !persist.tutorial_done {
+        persist.tutorial_done = true;
+        let _ = persist.save();
+    }
+}
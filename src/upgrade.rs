@@ -0,0 +1,177 @@
+//! Player upgrades bought by collected xp.
+
+use hecs::World;
+use macroquad::prelude::*;
+
+use crate::{
+    basic::{motion::ChargeSender, Health},
+    game::run_rng,
+    locale::tr,
+    menu::Button,
+    player::Player,
+};
+
+/// Xp needed for the first level up.
+/// Every next level needs this much more than the previous one.
+const LEVEL_XP_STEP: u32 = 50;
+/// Amount of upgrades offered on a level up.
+const UPGRADE_CHOICES: usize = 3;
+
+/// Multiplier of the fire cooldown per fire rate upgrade.
+const FIRE_RATE_MULT: f32 = 0.8;
+/// Max health gained per health upgrade.
+const MAX_HEALTH_GAIN: f32 = 2.0;
+/// Multiplier of the charge force per charge upgrade.
+const CHARGE_FORCE_MULT: f32 = 1.25;
+/// Multiplier of the spread added by every shot per stability upgrade.
+const STABILITY_BLOOM_MULT: f32 = 0.6;
+/// Angle between projectiles of a spread shot.
+pub const SPREAD_ANGLE: f32 = 0.2;
+
+/// Upgrade the player can choose on a level up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Upgrade {
+    /// Shorter cooldown between shots.
+    FireRate,
+    /// More max health.
+    MaxHealth,
+    /// Stronger charge field.
+    ChargeForce,
+    /// Fires three projectiles in a fan.
+    SpreadShot,
+    /// Shots lose less accuracy.
+    Stability,
+}
+
+impl Upgrade {
+    /// All upgrades.
+    pub const ALL: [Upgrade; 5] = [
+        Upgrade::FireRate,
+        Upgrade::MaxHealth,
+        Upgrade::ChargeForce,
+        Upgrade::SpreadShot,
+        Upgrade::Stability,
+    ];
+
+    /// Returns the text shown on the upgrade's card.
+    pub fn name(&self) -> String {
+        tr!(match self {
+            Upgrade::FireRate => "upgrade.fire_rate",
+            Upgrade::MaxHealth => "upgrade.max_health",
+            Upgrade::ChargeForce => "upgrade.charge_force",
+            Upgrade::SpreadShot => "upgrade.spread_shot",
+            Upgrade::Stability => "upgrade.stability",
+        })
+    }
+}
+
+/// Upgrades chosen by the player this run.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlayerUpgrades {
+    /// Level reached so far, every level grants one upgrade.
+    pub level: u32,
+    /// Amount of fire rate upgrades.
+    pub fire_rate: u32,
+    /// Amount of health upgrades.
+    pub max_health: u32,
+    /// Amount of charge upgrades.
+    pub charge_force: u32,
+    /// Does the player fire spread shots?
+    pub spread_shot: bool,
+    /// Amount of stability upgrades.
+    pub stability: u32,
+}
+
+impl PlayerUpgrades {
+    /// Returns the xp needed for the next level up.
+    pub fn next_level_xp(&self) -> u32 {
+        let next = self.level + 1;
+        LEVEL_XP_STEP * next * (next + 1) / 2
+    }
+
+    /// Returns the multiplier of the fire cooldown.
+    pub fn fire_cooldown_mult(&self) -> f32 {
+        FIRE_RATE_MULT.powi(self.fire_rate as i32)
+    }
+
+    /// Returns the multiplier of the spread added by every shot.
+    pub fn bloom_mult(&self) -> f32 {
+        STABILITY_BLOOM_MULT.powi(self.stability as i32)
+    }
+
+    /// Returns the multiplier of the charge force.
+    pub fn charge_force_mult(&self) -> f32 {
+        CHARGE_FORCE_MULT.powi(self.charge_force as i32)
+    }
+
+    /// Returns upgrades that can still be chosen.
+    pub fn available(&self) -> Vec<Upgrade> {
+        Upgrade::ALL
+            .into_iter()
+            .filter(|upgrade| *upgrade != Upgrade::SpreadShot || !self.spread_shot)
+            .collect()
+    }
+}
+
+/// Card of the level up picker choosing an [Upgrade].
+#[derive(Clone, Copy, Debug)]
+pub struct UpgradeButton {
+    /// Upgrade chosen by the card.
+    pub upgrade: Upgrade,
+}
+
+//-----------------------------------------------------------------------------
+//SYSTEM PART
+//-----------------------------------------------------------------------------
+
+/// Checks whether the player collected enough xp for a level up.
+/// Returns upgrades to choose from if so.
+pub fn level_up(world: &mut World) -> Option<Vec<Upgrade>> {
+    let (_, (player, upgrades)) = world
+        .query_mut::<(&Player, &mut PlayerUpgrades)>()
+        .into_iter()
+        .next()?;
+    if player.xp < upgrades.next_level_xp() {
+        return None;
+    }
+    upgrades.level += 1;
+    //offer a random selection
+    let mut choices = upgrades.available();
+    run_rng(world).shuffle(&mut choices);
+    choices.truncate(UPGRADE_CHOICES);
+    Some(choices)
+}
+
+/// Applies the [Upgrade] of the clicked [UpgradeButton].
+/// Returns whether any was chosen.
+pub fn upgrade_picker(world: &mut World) -> bool {
+    let Some(upgrade) = world
+        .query_mut::<(&Button, &UpgradeButton)>()
+        .into_iter()
+        .find(|(_, (button, _))| button.clicked)
+        .map(|(_, (_, card))| card.upgrade)
+    else {
+        return false;
+    };
+
+    for (_, (upgrades, health, sender)) in world
+        .query_mut::<(&mut PlayerUpgrades, &mut Health, &mut ChargeSender)>()
+        .with::<&Player>()
+    {
+        match upgrade {
+            Upgrade::FireRate => upgrades.fire_rate += 1,
+            Upgrade::MaxHealth => {
+                upgrades.max_health += 1;
+                health.max_hp += MAX_HEALTH_GAIN;
+                health.heal(MAX_HEALTH_GAIN);
+            }
+            Upgrade::ChargeForce => {
+                upgrades.charge_force += 1;
+                sender.force *= CHARGE_FORCE_MULT;
+            }
+            Upgrade::SpreadShot => upgrades.spread_shot = true,
+            Upgrade::Stability => upgrades.stability += 1,
+        }
+    }
+    true
+}
@@ -0,0 +1,55 @@
+//! Detection of the window being minimized, hidden, suspended or losing focus.
+//!
+//! Macroquad does not report minimizing nor focus changes,
+//! so they are detected from the size of the window, the length of frames
+//! and the shortcuts switching windows.
+
+use macroquad::prelude::*;
+
+/// Frames longer than this mean the game was suspended,
+/// e.g. the window was occluded or dragged around.
+const SUSPEND_FRAME_TIME: f32 = 0.5;
+/// Keys taking the focus away from the window by themselves, e.g. opening the start menu.
+const FOCUS_KEYS: [KeyCode; 2] = [KeyCode::LeftSuper, KeyCode::RightSuper];
+/// Modifiers switching windows when held with Tab.
+const SWITCH_MODIFIERS: [KeyCode; 4] = [
+    KeyCode::LeftAlt,
+    KeyCode::RightAlt,
+    KeyCode::LeftSuper,
+    KeyCode::RightSuper,
+];
+
+/// Tracks whether the window is visible.
+#[derive(Clone, Copy, Debug)]
+pub struct Visibility {
+    /// Is the window visible and worth rendering into?
+    pub visible: bool,
+}
+
+impl Default for Visibility {
+    fn default() -> Self {
+        Self { visible: true }
+    }
+}
+
+impl Visibility {
+    /// Updates the visibility of the window.
+    ///
+    /// Returns whether the window has just been hidden, lost focus
+    /// or the game has just been suspended, a running game should be paused in that case.
+    /// # Arguments
+    /// * `dt` - length of the last frame
+    pub fn update(&mut self, dt: f32) -> bool {
+        //minimized windows have no size on most platforms
+        let visible = screen_width() >= 1.0 && screen_height() >= 1.0;
+        let hidden = (self.visible && !visible) || dt > SUSPEND_FRAME_TIME || switching_away();
+        self.visible = visible;
+        hidden
+    }
+}
+
+/// Is the user switching away from the window this frame?
+fn switching_away() -> bool {
+    FOCUS_KEYS.into_iter().any(is_key_pressed)
+        || (is_key_pressed(KeyCode::Tab) && SWITCH_MODIFIERS.into_iter().any(is_key_down))
+}
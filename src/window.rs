@@ -0,0 +1,52 @@
+//! Window mode switching between fullscreen and windowed.
+
+use macroquad::prelude::*;
+use nanoserde::{DeBin, SerBin};
+
+use crate::{persist::Persistent, SPACE_HEIGHT, SPACE_WIDTH};
+
+/// Key toggling fullscreen.
+const FULLSCREEN_KEY: KeyCode = KeyCode::F11;
+
+/// User's preferences of the window.
+#[derive(Clone, Copy, Debug, DeBin, SerBin)]
+pub struct WindowPrefs {
+    /// Should the game run in fullscreen?
+    pub fullscreen: bool,
+    /// Width of the window when not in fullscreen.
+    pub width: f32,
+    /// Height of the window when not in fullscreen.
+    pub height: f32,
+}
+
+impl Default for WindowPrefs {
+    fn default() -> Self {
+        Self {
+            fullscreen: false,
+            width: SPACE_WIDTH,
+            height: SPACE_HEIGHT,
+        }
+    }
+}
+
+/// Toggles fullscreen when [FULLSCREEN_KEY] is pressed.
+///
+/// The size of the window is remembered before going fullscreen,
+/// so that going back restores it.
+pub fn toggle_fullscreen(persist: &mut Persistent) {
+    if !is_key_pressed(FULLSCREEN_KEY) {
+        return;
+    }
+    let window = &mut persist.window;
+    window.fullscreen = !window.fullscreen;
+    if window.fullscreen {
+        //remember the windowed size
+        window.width = screen_width();
+        window.height = screen_height();
+        set_fullscreen(true);
+    } else {
+        set_fullscreen(false);
+        request_new_screen_size(window.width, window.height);
+    }
+    let _ = persist.save();
+}
@@ -2,12 +2,17 @@
 
 use std::f32::consts::PI;
 
-use hecs::{CommandBuffer, EntityBuilder, World};
+use hecs::{CommandBuffer, Entity, EntityBuilder, World};
 use macroquad::prelude::*;
 
 use crate::{
-    basic::{motion::PhysicsMotion, Health, HitEvent, HurtBox, Position, Team, Wrapped},
-    player::Player,
+    basic::{
+        motion::{ChargeReceiver, ChargeSender, PhysicsMotion},
+        Health, HitEvent, HurtBox, Position, Team, Timer, Wrapped,
+    },
+    game::run_rng,
+    player::{Dead, Player},
+    score::Combo,
 };
 
 /// Distance at which the orb is absorbed into the player.
@@ -28,6 +33,24 @@ const ATTRACTION_SPEED: f32 = 100.0;
 /// Multiplicative.
 const ATTRACTION_MULT_PER_SEC: f32 = 0.8;
 
+/// Influence of charge fields on charged orbs.
+const ORB_CHARGE_MULT: f32 = 2.0;
+/// Color of positively charged orbs.
+const ORB_COLOR_POSITIVE: Color = Color::new(1.0, 0.4, 0.3, 1.0);
+/// Color of negatively charged orbs.
+const ORB_COLOR_NEGATIVE: Color = Color::new(0.0, 1.0, 1.0, 1.0);
+
+/// Key that has to be held to grapple onto an orb.
+const GRAPPLE_KEY: KeyCode = KeyCode::Space;
+/// Max distance of an orb the player can grapple onto.
+const GRAPPLE_RADIUS: f32 = 400.0;
+/// Min amount of xp an orb must have to be grappled onto.
+const GRAPPLE_MIN_AMOUNT: u32 = 5;
+/// Force pulling the player towards the grappled orb.
+const GRAPPLE_FORCE: f32 = 4000.0;
+/// Cooldown after the grapple is released.
+const GRAPPLE_COOLDOWN: f32 = 1.5;
+
 /// Component that spawns xp orbs on entities death (hp <= 0.0).
 #[derive(Clone, Copy, Debug, Default)]
 pub struct BurstXpOnDeath {
@@ -45,6 +68,25 @@ pub struct XpOrb {
     pub follow_mult: f32,
 }
 
+/// Magnet grapple of the player.
+/// Reverses the xp attraction, pulling the player towards an orb.
+#[derive(Clone, Copy, Debug)]
+pub struct Grapple {
+    /// Orb the player is grappled onto.
+    pub anchor: Option<Entity>,
+    /// Time before the grapple can be used again.
+    pub cooldown: Timer,
+}
+
+impl Default for Grapple {
+    fn default() -> Self {
+        Self {
+            anchor: None,
+            cooldown: Timer::new_finished(GRAPPLE_COOLDOWN),
+        }
+    }
+}
+
 //-----------------------------------------------------------------------------
 //ENTITY CREATION
 //-----------------------------------------------------------------------------
@@ -54,7 +96,8 @@ pub struct XpOrb {
 /// * `pos` - position of the orb
 /// * `vel` - velocity of the orb
 /// * `amount` - how much xp is in the orb
-pub fn create_orb(pos: Vec2, vel: Vec2, amount: u32) -> EntityBuilder {
+/// * `charge` - charge of the orb, neutral orbs ignore charge fields
+pub fn create_orb(pos: Vec2, vel: Vec2, amount: u32, charge: Option<i8>) -> EntityBuilder {
     let mut builder = EntityBuilder::new();
 
     builder.add_bundle((
@@ -73,12 +116,22 @@ pub fn create_orb(pos: Vec2, vel: Vec2, amount: u32) -> EntityBuilder {
         crate::basic::render::Circle {
             radius: MIN_RADIUS
                 + (MAX_RADIUS - MIN_RADIUS) * (1.0 - 1.0 / (RADIUS_COEFF * amount as f32 + 1.0)),
-            color: YELLOW,
+            color: match charge {
+                Some(x) if x > 0 => ORB_COLOR_POSITIVE,
+                Some(x) if x < 0 => ORB_COLOR_NEGATIVE,
+                _ => YELLOW,
+            },
             z_index: 0,
         },
         Team::Player,
         Wrapped,
     ));
+    //charged orbs are pushed and pulled by charge fields
+    if let Some(charge) = charge.filter(|charge| *charge != 0) {
+        builder.add(ChargeReceiver {
+            multiplier: ORB_CHARGE_MULT * charge.signum() as f32,
+        });
+    }
 
     builder
 }
@@ -88,10 +141,18 @@ pub fn create_orb(pos: Vec2, vel: Vec2, amount: u32) -> EntityBuilder {
 //-----------------------------------------------------------------------------
 
 /// Handles xp orb spawning on death of `BurstXpOnDeath` entites.
+/// Orbs of charged entities carry the same charge.
 pub fn xp_bursts(world: &mut World, cmd: &mut CommandBuffer) {
-    for (_, (burst, pos, health)) in world.query_mut::<(&BurstXpOnDeath, &Position, &Health)>() {
+    let mut rng = run_rng(world);
+    for (_, (burst, pos, health, sender)) in
+        world.query_mut::<(&BurstXpOnDeath, &Position, &Health, Option<&ChargeSender>)>()
+    {
         //get spawning position
         let pos = vec2(pos.x, pos.y);
+        //get charge of the orbs
+        let charge = sender
+            .filter(|sender| sender.force != 0.0)
+            .map(|sender| sender.force.signum() as i8);
         //is the entity dead?
         if health.hp <= 0.0 {
             //spawn xp's if dead
@@ -105,40 +166,135 @@ pub fn xp_bursts(world: &mut World, cmd: &mut CommandBuffer) {
                 }
                 //emit large xp orbs
                 big_xp -= 10;
-                let angle = fastrand::f32() * 2.0 * PI;
-                let speed = fastrand::f32() * 20.0 + 5.0;
+                let angle = rng.f32() * 2.0 * PI;
+                let speed = rng.f32() * 20.0 + 5.0;
                 cmd.spawn(
-                    create_orb(pos, Vec2::from_angle(angle).rotate(Vec2::X) * speed, 10).build(),
+                    create_orb(
+                        pos,
+                        Vec2::from_angle(angle).rotate(Vec2::X) * speed,
+                        10,
+                        charge,
+                    )
+                    .build(),
                 );
             }
             //emit rest XP
             while rest_xp > 0 {
-                let amount = fastrand::u32(1..=5).min(rest_xp);
+                let amount = rng.u32(1..=5).min(rest_xp);
                 rest_xp -= amount;
-                let angle = fastrand::f32() * 2.0 * PI;
-                let speed = fastrand::f32() * 30.0 + 10.0;
+                let angle = rng.f32() * 2.0 * PI;
+                let speed = rng.f32() * 30.0 + 10.0;
                 cmd.spawn(
-                    create_orb(pos, Vec2::from_angle(angle).rotate(Vec2::X) * speed, amount)
-                        .build(),
+                    create_orb(
+                        pos,
+                        Vec2::from_angle(angle).rotate(Vec2::X) * speed,
+                        amount,
+                        charge,
+                    )
+                    .build(),
                 );
             }
         }
     }
 }
 
+/// Handles grappling onto orbs by holding [GRAPPLE_KEY].
+///
+/// The nearest large enough orb in range becomes the anchor until the key is released.
+pub fn grapple(world: &mut World, dt: f32) {
+    //find player
+    let Some((_, (&player_pos, grapple))) = world
+        .query_mut::<(&Position, &mut Grapple)>()
+        .with::<&Player>()
+        .without::<&Dead>()
+        .into_iter()
+        .next()
+    else {
+        return;
+    };
+    let mut grapple_state = *grapple;
+    grapple_state.cooldown.tick(dt);
+
+    //release on key up or when the anchor disappeared
+    if let Some(anchor) = grapple_state.anchor {
+        if !is_key_down(GRAPPLE_KEY) || !world.contains(anchor) {
+            grapple_state.anchor = None;
+            grapple_state.cooldown.reset();
+        }
+    }
+    //find the nearest large orb
+    else if is_key_down(GRAPPLE_KEY) && grapple_state.cooldown.finished() {
+        grapple_state.anchor = world
+            .query_mut::<(&Position, &XpOrb)>()
+            .into_iter()
+            .filter(|(_, (_, orb))| orb.amount >= GRAPPLE_MIN_AMOUNT)
+            .map(|(id, (pos, _))| {
+                (
+                    id,
+                    vec2(pos.x - player_pos.x, pos.y - player_pos.y).length(),
+                )
+            })
+            .filter(|(_, distance)| *distance <= GRAPPLE_RADIUS)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(id, _)| id);
+    }
+
+    //write back the state
+    for (_, grapple) in world.query_mut::<&mut Grapple>() {
+        *grapple = grapple_state;
+    }
+}
+
 /// Attracts `XpOrb` entites to the player, if in range.
+///
+/// Charged orbs matching the player's polarity are not attracted,
+/// the player's charge field pushes them away instead.
+/// When the player grapples onto an orb, the attraction is reversed and
+/// the player is pulled towards the orb instead.
 pub fn xp_attraction(world: &mut World, dt: f32) {
     //find player
-    let (_, &player_pos) = world
-        .query_mut::<&Position>()
+    let Some((_, (&player_pos, grapple, player_charge))) = world
+        .query_mut::<(&Position, Option<&Grapple>, Option<&ChargeSender>)>()
         .with::<&Player>()
+        .without::<&Dead>()
         .into_iter()
         .next()
-        .unwrap();
+    else {
+        return;
+    };
+    let player_force = player_charge.map(|charge| charge.force).unwrap_or(0.0);
+
+    //reversed attraction
+    if let Some(anchor) = grapple.and_then(|grapple| grapple.anchor) {
+        let anchor_pos = world
+            .get::<&Position>(anchor)
+            .map(|pos| vec2(pos.x, pos.y))
+            .ok();
+        if let Some(anchor_pos) = anchor_pos {
+            let delta = anchor_pos - vec2(player_pos.x, player_pos.y);
+            //player is pulled
+            for (_, player_vel) in world.query_mut::<&mut PhysicsMotion>().with::<&Player>() {
+                player_vel.apply_force(delta.normalize_or_zero() * GRAPPLE_FORCE, dt);
+            }
+            //orbs stay put
+            for (_, (vel, orb)) in world.query_mut::<(&mut PhysicsMotion, &mut XpOrb)>() {
+                vel.vel *= 0.7_f32.powf(dt);
+                orb.follow_mult = 0.0;
+            }
+            return;
+        }
+    }
 
-    for (_, (pos, vel, orb)) in world.query_mut::<(&Position, &mut PhysicsMotion, &mut XpOrb)>() {
+    for (_, (pos, vel, orb, receiver)) in world.query_mut::<(
+        &Position,
+        &mut PhysicsMotion,
+        &mut XpOrb,
+        Option<&ChargeReceiver>,
+    )>() {
         let delta = vec2(player_pos.x - pos.x, player_pos.y - pos.y);
-        if delta.length() <= ATTRACTION_RADIUS {
+        //like charges repel
+        let repelled = receiver.is_some_and(|receiver| receiver.multiplier * player_force > 0.0);
+        if delta.length() <= ATTRACTION_RADIUS && !repelled {
             vel.vel = ATTRACTION_SPEED * delta.normalize_or_zero() * (1.0 + orb.follow_mult);
             orb.follow_mult += dt * ATTRACTION_MULT_PER_SEC;
         } else {
@@ -154,9 +310,21 @@ pub fn xp_attraction(world: &mut World, dt: f32) {
 
 /// Absorbs the xp orbs into player when in range.
 pub fn xp_absorbtion(world: &mut World, events: &mut World, cmd: &mut CommandBuffer) {
+    //get the combo multiplying the xp
+    let combo = world
+        .query_mut::<&Combo>()
+        .into_iter()
+        .next()
+        .map(|(_, combo)| combo.value)
+        .unwrap_or(1);
     //find player
-    let mut player_query = world.query::<&mut Player>();
-    let (player_id, player) = player_query.iter().next().unwrap();
+    let mut player_query = world
+        .query::<(&mut Player, Option<&Grapple>)>()
+        .without::<&Dead>();
+    let Some((player_id, (player, grapple))) = player_query.iter().next() else {
+        return;
+    };
+    let grapple_anchor = grapple.and_then(|grapple| grapple.anchor);
     //check events for collisions
     for (_, hit_event) in events.query_mut::<&HitEvent>() {
         //is the one hit a player?
@@ -167,9 +335,26 @@ pub fn xp_absorbtion(world: &mut World, events: &mut World, cmd: &mut CommandBuf
         let Ok(orb) = world.get::<&XpOrb>(hit_event.by) else {
             continue;
         };
+        //anchors are not absorbed until released
+        if grapple_anchor == Some(hit_event.by) {
+            continue;
+        }
 
-        //add the xp and DIE
-        player.xp += orb.amount;
+        //add the multiplied xp and DIE
+        player.xp += orb.amount * combo;
         cmd.despawn(hit_event.by);
     }
 }
+
+/// Renders the tether between the player and the grappled orb.
+pub fn grapple_visual(world: &mut World) {
+    for (_, (pos, grapple)) in world.query::<(&Position, &Grapple)>().into_iter() {
+        let Some(anchor) = grapple.anchor else {
+            continue;
+        };
+        let Ok(anchor_pos) = world.get::<&Position>(anchor) else {
+            continue;
+        };
+        draw_line(pos.x, pos.y, anchor_pos.x, anchor_pos.y, 2.0, YELLOW);
+    }
+}